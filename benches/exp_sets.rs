@@ -0,0 +1,57 @@
+use std::collections::HashSet;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use flanelly::{aexp::{AExp, InternedAExp}, ast::{Prog, ProgAtom}, bexp::BExp, cfg, cfg::{Cfg, RawAnnot}, common::VarName};
+use flanelly::flow_analysis::{mfp::mfp, mfp::MfpAnnot, avail_exp::ExpSetLat};
+
+fn var(x: &str) -> Box<AExp> {
+    Box::new(AExp::Var(VarName::new(x)))
+}
+
+/// `x + y`, squared `depth` times, i.e. `(x + y)*(x + y)`, `((x + y)*(x + y))*((x + y)*(x + y))`, ... The expression contains `2^depth` copies of `x + y`, but only `depth + 3` distinct subexpressions.
+fn squares(depth: usize) -> AExp {
+    (0..depth).fold(AExp::Add(var("x"), var("y")), |a, _| AExp::Mul(Box::new(a.clone()), Box::new(a)))
+}
+
+/// A loop whose body assigns several expressions that share `squares(depth)`, which is also compared against in the guard
+fn shared(depth: usize) -> Prog {
+    let e = Box::new(squares(depth));
+    let assign = |x: &str, a: AExp| ProgAtom::Assign(VarName::new(x), Box::new(a));
+    let body = Prog::Prog(vec![
+        assign("a", AExp::Add(e.clone(), Box::new(AExp::Num(1)))),
+        assign("b", AExp::Mul(e.clone(), Box::new(AExp::Num(2)))),
+        assign("z", AExp::Add(var("z"), Box::new(AExp::Num(1))))
+    ]);
+    Prog::Prog(vec![ProgAtom::While(Box::new(BExp::LessEq(var("z"), e)), Box::new(body))])
+}
+
+fn bench_exp_sets(c: &mut Criterion) {
+    let mut group = c.benchmark_group("exp_sets");
+    for depth in &[4, 8, 12] {
+        let e = squares(*depth);
+        group.bench_with_input(BenchmarkId::new("sub_aexps", depth), &e, |b, e| {
+            b.iter(|| e.sub_aexps())
+        });
+        group.bench_with_input(BenchmarkId::new("sub_aexps_interned", depth), &e, |b, e| {
+            b.iter(|| e.interned().sub_aexps())
+        });
+        // Hashing the subexpressions: Structurally, the time depends on their size, whereas interned ones are hashed in constant time
+        let subs: Vec<AExp> = e.sub_aexps().into_iter().collect();
+        let interned: Vec<InternedAExp> = e.interned().sub_aexps();
+        group.bench_with_input(BenchmarkId::new("hash_set", depth), &subs, |b, subs| {
+            b.iter(|| subs.iter().cloned().collect::<HashSet<_>>())
+        });
+        group.bench_with_input(BenchmarkId::new("hash_set_interned", depth), &interned, |b, interned| {
+            b.iter(|| interned.iter().cloned().collect::<HashSet<_>>())
+        });
+        let cfg: Cfg<RawAnnot> = cfg::ast_to_cfg(&shared(*depth));
+        group.bench_with_input(BenchmarkId::new("avail_exp", depth), &cfg, |b, cfg| {
+            b.iter(|| { let _: Cfg<MfpAnnot<ExpSetLat>> = mfp(cfg); })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_exp_sets);
+criterion_main!(benches);
@@ -0,0 +1,92 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use flanelly::{aexp::AExp, ast::{Prog, ProgAtom}, bexp::BExp, cfg, cfg::{Cfg, RawAnnot}, common::{VarName, Int}};
+use flanelly::flow_analysis::{mfp::mfp, mfp::MfpAnnot, const_prop::MultiConstLat, avail_exp::ExpSetLat};
+
+////////////////////
+// CFG Generation //
+////////////////////
+
+// The following functions build ASTs of a given size. They are deterministic, i.e. the same size always yields the same AST.
+
+fn var(i: usize) -> Box<AExp> {
+    Box::new(AExp::Var(VarName::new(&format!("v{}", to_letters(i)))))
+}
+
+/// Variable names are alphabetical only, so encode the index in letters (0 => "a", 25 => "z", 26 => "ba", ...)
+fn to_letters(mut i: usize) -> String {
+    let mut s = vec![];
+    loop {
+        s.push((b'a' + (i % 26) as u8) as char);
+        i /= 26;
+        if i == 0 { break }
+    }
+    s.into_iter().rev().collect()
+}
+
+fn assign(i: usize, a: AExp) -> ProgAtom {
+    ProgAtom::Assign(VarName::new(&format!("v{}", to_letters(i))), Box::new(a))
+}
+
+/// `va := 1; vb := va + 1; vc := vb + 1; ...` with `n` assignments
+fn chain(n: usize) -> Prog {
+    let mut atoms = vec![assign(0, AExp::Num(1))];
+    (1..n).for_each(|i| atoms.push(assign(i, AExp::Add(var(i - 1), Box::new(AExp::Num(1))))));
+    Prog::Prog(atoms)
+}
+
+/// A balanced tree of nested conditionals of the given `depth`, with an assignment in each leaf
+fn conditionals(depth: usize) -> Prog {
+    fn build(depth: usize, leaf: &mut usize) -> Prog {
+        if depth == 0 {
+            *leaf += 1;
+            Prog::Prog(vec![assign(*leaf % 8, AExp::Mul(var(*leaf % 8), Box::new(AExp::Num(*leaf as Int))))])
+        }
+        else {
+            let guard = BExp::LessEq(var(depth % 8), Box::new(AExp::Num(depth as Int)));
+            let p_tt = build(depth - 1, leaf);
+            let p_ff = build(depth - 1, leaf);
+            Prog::Prog(vec![ProgAtom::Cond(Box::new(guard), Box::new(p_tt), Box::new(p_ff))])
+        }
+    }
+    build(depth, &mut 0)
+}
+
+/// `depth` nested while loops, each counting its own variable up
+fn loop_nest(depth: usize) -> Prog {
+    let mut body = Prog::Prog(vec![assign(depth, AExp::Add(var(depth), var(0)))]);
+    for i in (0..depth).rev() {
+        let guard = BExp::LessEq(var(i), Box::new(AExp::Var(VarName::new("x"))));
+        let Prog::Prog(mut atoms) = body;
+        atoms.push(assign(i, AExp::Add(var(i), Box::new(AExp::Num(1)))));
+        body = Prog::Prog(vec![assign(i, AExp::Num(0)), ProgAtom::While(Box::new(guard), Box::new(Prog::Prog(atoms)))]);
+    }
+    body
+}
+
+//////////////////
+// Benchmarking //
+//////////////////
+
+fn bench_shape(c: &mut Criterion, name: &str, sizes: &[usize], gen: fn(usize) -> Prog) {
+    let mut group = c.benchmark_group(name);
+    for size in sizes {
+        let cfg: Cfg<RawAnnot> = cfg::ast_to_cfg(&gen(*size));
+        group.bench_with_input(BenchmarkId::new("const_prop", size), &cfg, |b, cfg| {
+            b.iter(|| { let _: Cfg<MfpAnnot<MultiConstLat>> = mfp(cfg); })
+        });
+        group.bench_with_input(BenchmarkId::new("avail_exp", size), &cfg, |b, cfg| {
+            b.iter(|| { let _: Cfg<MfpAnnot<ExpSetLat>> = mfp(cfg); })
+        });
+    }
+    group.finish();
+}
+
+fn bench_mfp(c: &mut Criterion) {
+    bench_shape(c, "chain", &[10, 100, 500], chain);
+    bench_shape(c, "conditionals", &[2, 4, 8], conditionals);
+    bench_shape(c, "loop_nest", &[1, 3, 6], loop_nest);
+}
+
+criterion_group!(benches, bench_mfp);
+criterion_main!(benches);
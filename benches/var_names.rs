@@ -0,0 +1,75 @@
+use std::{alloc::{GlobalAlloc, Layout, System}, collections::{hash_map::DefaultHasher, HashMap}, hash::{Hash, Hasher}, sync::atomic::{AtomicUsize, Ordering}};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use flanelly::{common::VarName, flow_analysis::const_prop::{ConstLat, MultiConstLat}};
+
+/// The system allocator, counting the allocations (as `VarName`s are interned, cloning them should not allocate)
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// The number of allocations performed by `f`
+fn count_allocations<T>(f: impl FnOnce() -> T) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let result = f();
+    let after = ALLOCATIONS.load(Ordering::Relaxed);
+    drop(result);
+    after - before
+}
+
+/// `n` distinct, rather long variable names
+fn names(n: usize) -> Vec<VarName> {
+    (0..n).map(|i| VarName::new(&format!("variable{}", i))).collect()
+}
+
+fn bench_var_names(c: &mut Criterion) {
+    let mut group = c.benchmark_group("var_names");
+    for size in &[10, 100, 1000] {
+        let vars = names(*size);
+        let mem = MultiConstLat::new(vars.iter().map(|x| (x.clone(), ConstLat::Top)).collect::<HashMap<_, _>>(), ConstLat::Const(0));
+        // Only the vector itself is allocated, not its elements
+        eprintln!("var_names/{}: cloning the names takes {} allocation(s)", size, count_allocations(|| vars.clone()));
+        group.bench_with_input(BenchmarkId::new("clone", size), &vars, |b, vars| {
+            b.iter(|| vars.clone())
+        });
+        group.bench_with_input(BenchmarkId::new("compare", size), &vars, |b, vars| {
+            b.iter(|| vars.iter().zip(vars.iter().rev()).filter(|(x, y)| x == y).count())
+        });
+        group.bench_with_input(BenchmarkId::new("hash", size), &vars, |b, vars| {
+            b.iter(|| vars.iter().map(|x| {
+                let mut hasher = DefaultHasher::new();
+                x.hash(&mut hasher);
+                hasher.finish()
+            }).fold(0, u64::wrapping_add))
+        });
+        group.bench_with_input(BenchmarkId::new("sort", size), &vars, |b, vars| {
+            b.iter(|| {
+                let mut sorted = vars.clone();
+                sorted.sort();
+                sorted
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("clone_mem", size), &mem, |b, mem| {
+            b.iter(|| mem.clone())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_var_names);
+criterion_main!(benches);
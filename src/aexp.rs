@@ -1,15 +1,20 @@
-use std::{collections::HashSet, fmt::Display};
+use core::{fmt::{self, Display}, hash::{Hash, Hasher}};
+use alloc::{boxed::Box, format, string::{String, ToString}, sync::Arc, vec, vec::Vec};
 use serde::{Serialize, Deserialize};
 
 use crate::common::*;
 
 /// Arithmetic expression
-#[derive(PartialEq,Clone,Debug,Eq,Hash,Serialize,Deserialize)]
+///
+/// Expressions are totally ordered: First by their variant (`Num < Var < Add < Mul < Mod < Pow`), then lexicographically by their components (numbers numerically, variables by name).
+#[derive(PartialEq,Clone,Debug,Eq,Hash,PartialOrd,Ord,Serialize,Deserialize)]
 pub enum AExp {
-    Num(i32),
+    Num(Int),
     Var(VarName),
     Add(Box<AExp>, Box<AExp>),
-    Mul(Box<AExp>, Box<AExp>)
+    Mul(Box<AExp>, Box<AExp>),
+    Mod(Box<AExp>, Box<AExp>),
+    Pow(Box<AExp>, Box<AExp>)
 }
 
 impl AExp {
@@ -18,26 +23,126 @@ impl AExp {
         return match self {
             AExp::Num(_) => false,
             AExp::Var(name) => x.eq(name),
-            AExp::Add(left, right) | AExp::Mul(left, right) =>
+            AExp::Add(left, right) | AExp::Mul(left, right) | AExp::Mod(left, right) | AExp::Pow(left, right) =>
                 left.contains_var(x) || right.contains_var(x)
         }
     }
 
-    pub fn sub_aexps(&self) -> HashSet<AExp> {
+    /// Return the set of variables occurring in the arithmetic expression
+    pub fn vars(&self) -> Set<VarName> {
+        match self {
+            AExp::Num(_) => Set::new(),
+            AExp::Var(name) => vec![name.clone()].into_iter().collect(),
+            AExp::Add(left, right) | AExp::Mul(left, right) | AExp::Mod(left, right) | AExp::Pow(left, right) =>
+                left.vars().union(&right.vars()).cloned().collect()
+        }
+    }
+
+    /// Return the set of integer literals occurring in the arithmetic expression
+    pub fn literals(&self) -> Set<Int> {
+        match self {
+            AExp::Num(n) => vec![*n].into_iter().collect(),
+            AExp::Var(_) => Set::new(),
+            AExp::Add(left, right) | AExp::Mul(left, right) | AExp::Mod(left, right) | AExp::Pow(left, right) =>
+                left.literals().union(&right.literals()).cloned().collect()
+        }
+    }
+
+    /// Replace every occurrence of the variable `x` by the arithmetic expression `with`
+    pub fn substitute(&self, x: &VarName, with: &AExp) -> AExp {
+        match self {
+            AExp::Num(_) => self.clone(),
+            AExp::Var(name) => if x.eq(name) { with.clone() } else { self.clone() },
+            AExp::Add(left, right) => AExp::Add(Box::new(left.substitute(x, with)), Box::new(right.substitute(x, with))),
+            AExp::Mul(left, right) => AExp::Mul(Box::new(left.substitute(x, with)), Box::new(right.substitute(x, with))),
+            AExp::Mod(left, right) => AExp::Mod(Box::new(left.substitute(x, with)), Box::new(right.substitute(x, with))),
+            AExp::Pow(left, right) => AExp::Pow(Box::new(left.substitute(x, with)), Box::new(right.substitute(x, with)))
+        }
+    }
+
+    /// Return a normal form modulo commutativity: The operands of every addition and multiplication are ordered ascendingly (see `Ord`), recursively. Thus, e.g. `x + y` and `y + x` have the same normal form `x + y`, whereas `(x + y) + z` and `x + (y + z)` stay different.
+    pub fn normalized(&self) -> AExp {
+        let ordered = |a1: &AExp, a2: &AExp| {
+            let (n1, n2) = (a1.normalized(), a2.normalized());
+            if n1 <= n2 { (Box::new(n1), Box::new(n2)) } else { (Box::new(n2), Box::new(n1)) }
+        };
+        match self {
+            AExp::Num(_) | AExp::Var(_) => self.clone(),
+            AExp::Add(left, right) => { let (l, r) = ordered(left, right); AExp::Add(l, r) }
+            AExp::Mul(left, right) => { let (l, r) = ordered(left, right); AExp::Mul(l, r) }
+            AExp::Mod(left, right) => AExp::Mod(Box::new(left.normalized()), Box::new(right.normalized())),
+            AExp::Pow(left, right) => AExp::Pow(Box::new(left.normalized()), Box::new(right.normalized()))
+        }
+    }
+
+    /// Constant folding: Evaluate all operations on numbers, bottom-up, and simplify by the identities `a + 0 = a`, `a*1 = a`, `a*0 = 0`, `a % 1 = 0`, `a^0 = 1` and `a^1 = a` (and their commuted versions for `+` and `*`), e.g. `(x + 2*0)*(1 + 2)` becomes `x*3`. Operations with an undefined result (`n % 0` and `n ^ m` for `m < 0`, see `checked_mod` and `checked_pow`) are kept.
+    pub fn folded(&self) -> AExp {
+        let folded = |left: &AExp, right: &AExp| (left.folded(), right.folded());
+        match self {
+            AExp::Num(_) | AExp::Var(_) => self.clone(),
+            AExp::Add(left, right) => match folded(left, right) {
+                (AExp::Num(n), AExp::Num(m)) => AExp::Num(n.wrapping_add(m)),
+                (AExp::Num(0), a) | (a, AExp::Num(0)) => a,
+                (l, r) => AExp::Add(Box::new(l), Box::new(r))
+            },
+            AExp::Mul(left, right) => match folded(left, right) {
+                (AExp::Num(n), AExp::Num(m)) => AExp::Num(n.wrapping_mul(m)),
+                (AExp::Num(0), _) | (_, AExp::Num(0)) => AExp::Num(0),
+                (AExp::Num(1), a) | (a, AExp::Num(1)) => a,
+                (l, r) => AExp::Mul(Box::new(l), Box::new(r))
+            },
+            AExp::Mod(left, right) => match folded(left, right) {
+                (AExp::Num(n), AExp::Num(m)) if m != 0 => AExp::Num(checked_mod(n, m).unwrap()),
+                (_, AExp::Num(1)) => AExp::Num(0),
+                (l, r) => AExp::Mod(Box::new(l), Box::new(r))
+            },
+            AExp::Pow(left, right) => match folded(left, right) {
+                (AExp::Num(n), AExp::Num(m)) if m >= 0 => AExp::Num(checked_pow(n, m).unwrap()),
+                (_, AExp::Num(0)) => AExp::Num(1),
+                (a, AExp::Num(1)) => a,
+                (l, r) => AExp::Pow(Box::new(l), Box::new(r))
+            }
+        }
+    }
+
+    /// Pretty-print the expression in the concrete syntax, such that parsing the result yields the very same expression (see `parser::parse_aexp`). Unlike `fmt`, which omits parentheses e.g. in `x + (y + z)`, this inserts all parentheses that are needed to preserve the structure.
+    pub fn to_source(&self) -> String {
+        match self {
+            AExp::Num(n) => {n.to_string()}
+            AExp::Var(v) => {v.to_string()}
+            AExp::Add(left, right) => {format!("{} + {}", left.to_source(), right.source_operand(1))}
+            AExp::Mul(left, right) => {format!("{}*{}", left.source_operand(1), right.source_operand(2))}
+            AExp::Mod(left, right) => {format!("{}%{}", left.source_operand(1), right.source_operand(2))}
+            AExp::Pow(left, right) => {format!("{}^{}", left.source_operand(3), right.source_operand(2))}
+        }
+    }
+
+    /// Helper function for `to_source`: Pretty-print an operand that must bind at least as strong as `level`, parenthesizing it otherwise. The levels follow the grammar: `0` for addition, `1` for multiplication and modulo, `2` for exponentiation and `3` for atoms.
+    fn source_operand(&self, level: u8) -> String {
+        let binding = match self {
+            AExp::Add(_, _)                   => {0}
+            AExp::Mul(_, _) | AExp::Mod(_, _) => {1}
+            AExp::Pow(_, _)                   => {2}
+            AExp::Num(_) | AExp::Var(_)       => {3}
+        };
+        if binding >= level { self.to_source() } else { format!("({})", self.to_source()) }
+    }
+
+    pub fn sub_aexps(&self) -> Set<AExp> {
         match self {
             AExp::Num(_) | AExp::Var(_) => {
                 // Singleton set
-                let mut set = HashSet::new();
+                let mut set = Set::new();
                 set.insert(self.clone());
                 set
             }
-            AExp::Add(a1, a2) | AExp::Mul(a1, a2) => {
+            AExp::Add(a1, a2) | AExp::Mul(a1, a2) | AExp::Mod(a1, a2) | AExp::Pow(a1, a2) => {
                 let sub_aexps1 = a1.sub_aexps();
                 let sub_aexps2 = a2.sub_aexps();
                 // Rust Expl.: Create an iterator over `&AExp`
                 let iter = sub_aexps1.union(&sub_aexps2);
-                // Rust Expl.: `iter.cloned()` creates an iterator over `AExp` (this is possible because `AExp` implements `Clone`). `collect()` uses this iterator to fill a `HashSet`.
-                let mut set: HashSet<AExp> = iter.cloned().collect();
+                // Rust Expl.: `iter.cloned()` creates an iterator over `AExp` (this is possible because `AExp` implements `Clone`). `collect()` uses this iterator to fill a `Set`.
+                let mut set: Set<AExp> = iter.cloned().collect();
                 set.insert(self.clone());
                 set
             }
@@ -45,19 +150,37 @@ impl AExp {
     }
 
     /// This helper function pretty-prints an arithmetic expression just like `fmt`, but inserting parentheses for addition terms. It (mutually) recurses on `fmt`.
-    fn fmt_with_parens(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt_with_parens(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             AExp::Num(n) => {write!(f, "{}", n)}
             AExp::Var(v) => {write!(f, "{}", v)}
             AExp::Add(left, right) => {write!(f, "({} + {})", left, right)}
-            AExp::Mul(left, right) => {write!(f, "{}*{}", left, right)}
+            AExp::Mul(_, _) | AExp::Mod(_, _) | AExp::Pow(_, _) => {Display::fmt(self, f)}
+        }
+    }
+
+    /// This helper function pretty-prints an operand of an exponentiation, inserting parentheses for all compound terms except for a right-hand (i.e. exponent) exponentiation: `^` binds strongest and associates to the right.
+    fn fmt_pow_operand(&self, f: &mut fmt::Formatter<'_>, is_exponent: bool) -> fmt::Result {
+        match self {
+            AExp::Num(_) | AExp::Var(_)         => {Display::fmt(self, f)}
+            AExp::Pow(_, _) if is_exponent      => {Display::fmt(self, f)}
+            _                                   => {write!(f, "({})", self)}
+        }
+    }
+
+    /// This helper function pretty-prints the right operand of a multiplication or modulo term like `fmt_with_parens`, but additionally inserting parentheses for modulo terms (and for multiplication terms right of a `%`): Both operators associate to the left, and only multiplication is associative.
+    fn fmt_right_operand(&self, f: &mut fmt::Formatter<'_>, in_mod: bool) -> fmt::Result {
+        match self {
+            AExp::Mod(_, _)             => {write!(f, "({})", self)}
+            AExp::Mul(_, _) if in_mod   => {write!(f, "({})", self)}
+            _                           => {self.fmt_with_parens(f)}
         }
     }
 }
 
 impl Display for AExp {
     /// This function recurses on itself (by the `write!` macro) and it (mutually) recurses on `fmt_with_parens` in order to add parentheses when needed.
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match &self {
             AExp::Num(n) => {write!(f, "{}", n)}
             AExp::Var(v) => {write!(f, "{}", v)}
@@ -65,8 +188,162 @@ impl Display for AExp {
             AExp::Mul(left, right) => {
                 left.fmt_with_parens(f)?;
                 write!(f, "*")?;
-                right.fmt_with_parens(f)
+                right.fmt_right_operand(f, false)
+            }
+            AExp::Mod(left, right) => {
+                left.fmt_with_parens(f)?;
+                write!(f, "%")?;
+                right.fmt_right_operand(f, true)
+            }
+            AExp::Pow(left, right) => {
+                left.fmt_pow_operand(f, false)?;
+                write!(f, "^")?;
+                right.fmt_pow_operand(f, true)
             }
         }
     }
-}
\ No newline at end of file
+}
+
+/// The key of an interned arithmetic expression in the table of the `Interner`: A number, a variable (given by the index of its name) or an operation (`0` to `3` for `+`, `*`, `%` and `^`) applied to two interned expressions (given by their addresses, which are unique as long as the expressions are in use)
+#[derive(PartialEq,Eq,Hash,PartialOrd,Ord,Clone,Copy,Debug)]
+pub(crate) enum ExpKey {
+    Num(Int),
+    Var(u32),
+    Op(u8, usize, usize)
+}
+
+/// A node of an interned arithmetic expression together with its precomputed hash, see `InternedAExp`
+#[derive(Debug)]
+pub(crate) struct ExpNode {
+    exp: ExpKind,
+    hash: u64
+}
+
+/// The expression of an `ExpNode`, whose operands are interned (with the operations numbered like in `ExpKey`)
+#[derive(Debug)]
+enum ExpKind {
+    Num(Int),
+    Var(VarName),
+    Op(u8, InternedAExp, InternedAExp)
+}
+
+/// A hash-consed arithmetic expression: An interned expression is a shared pointer to a node that refers to its (interned) operands, and a global table ensures that equal expressions are the same node, so identical subexpressions are stored only once. The hash value of the expression is computed once when interning it, so equality (a comparison of pointers) and hashing take constant time, no matter how large the expression is. This pays off for large sets of expressions, see `ExpSetLat`. The table only holds weak references, so an expression is freed once it is not used anymore.
+///
+/// The hash value only depends on the structure of the expression (and the names of its variables), so it does not depend on the order in which expressions were interned.
+#[derive(Clone,Debug)]
+pub struct InternedAExp(Arc<ExpNode>);
+
+impl AExp {
+    /// Intern the expression, see `InternedAExp`. This takes time linear in the size of the expression.
+    pub fn interned(&self) -> InternedAExp {
+        intern(&mut interner(), self, true).unwrap()
+    }
+
+    /// Return the interned expression if the expression is currently interned (see `interned`), without interning it otherwise
+    pub fn find_interned(&self) -> Option<InternedAExp> {
+        intern(&mut interner(), self, false)
+    }
+}
+
+/// Helper function for `AExp::interned`: Look up the interned expression `a`, interning it (and its subexpressions) first if `insert` is set. Otherwise, `None` is returned if `a` is not interned.
+pub(crate) fn intern(interner: &mut Interner, a: &AExp, insert: bool) -> Option<InternedAExp> {
+    let mut hasher = StableHasher::default();
+    let (key, exp) = match a {
+        AExp::Num(n) => {
+            hasher.write_u8(0);
+            n.hash(&mut hasher);
+            (ExpKey::Num(*n), ExpKind::Num(*n))
+        }
+        AExp::Var(x) => {
+            hasher.write_u8(1);
+            x.hash(&mut hasher);
+            (ExpKey::Var(x.index()), ExpKind::Var(x.clone()))
+        }
+        AExp::Add(left, right) | AExp::Mul(left, right) | AExp::Mod(left, right) | AExp::Pow(left, right) => {
+            let op = match a { AExp::Add(_, _) => {0} AExp::Mul(_, _) => {1} AExp::Mod(_, _) => {2} _ => {3} };
+            let left = intern(interner, left, insert)?;
+            let right = intern(interner, right, insert)?;
+            hasher.write_u8(2 + op);
+            hasher.write_u64(left.hash_value());
+            hasher.write_u64(right.hash_value());
+            (ExpKey::Op(op, left.addr(), right.addr()), ExpKind::Op(op, left, right))
+        }
+    };
+    if let Some(node) = interner.exps.get(&key) {
+        return Some(InternedAExp(node));
+    }
+    if !insert {
+        return None;
+    }
+    let node = Arc::new(ExpNode { exp, hash: hasher.finish() });
+    interner.exps.insert(key, &node);
+    Some(InternedAExp(node))
+}
+
+impl InternedAExp {
+    /// The expression that was interned. This does not lock the `Interner`.
+    pub fn to_aexp(&self) -> AExp {
+        match &self.0.exp {
+            ExpKind::Num(n) => {AExp::Num(*n)}
+            ExpKind::Var(x) => {AExp::Var(x.clone())}
+            ExpKind::Op(0, left, right) => {AExp::Add(Box::new(left.to_aexp()), Box::new(right.to_aexp()))}
+            ExpKind::Op(1, left, right) => {AExp::Mul(Box::new(left.to_aexp()), Box::new(right.to_aexp()))}
+            ExpKind::Op(2, left, right) => {AExp::Mod(Box::new(left.to_aexp()), Box::new(right.to_aexp()))}
+            ExpKind::Op(_, left, right) => {AExp::Pow(Box::new(left.to_aexp()), Box::new(right.to_aexp()))}
+        }
+    }
+
+    /// The precomputed hash value, see `InternedAExp`
+    pub fn hash_value(&self) -> u64 {
+        self.0.hash
+    }
+
+    /// The address of the node, which identifies the expression as long as it is in use
+    pub(crate) fn addr(&self) -> usize {
+        Arc::as_ptr(&self.0) as usize
+    }
+
+    /// Return `true` if the variable `x` occurs in the expression, see `AExp::contains_var`. Every distinct subexpression is visited only once.
+    pub fn contains_var(&self, x: &VarName) -> bool {
+        distinct_sub_aexps([self]).iter().any(|a| matches!(&a.0.exp, ExpKind::Var(y) if y == x))
+    }
+
+    /// Return the distinct subexpressions (including the expression itself), see `AExp::sub_aexps`. As identical subexpressions are shared, this takes time linear in the number of distinct subexpressions, which may be much less than the size of the expression.
+    pub fn sub_aexps(&self) -> Vec<InternedAExp> {
+        distinct_sub_aexps([self])
+    }
+}
+
+/// Helper function: The distinct subexpressions of all the expressions `exps`, in depth-first order
+pub(crate) fn distinct_sub_aexps<'a, I: IntoIterator<Item = &'a InternedAExp>>(exps: I) -> Vec<InternedAExp> {
+    let mut visited = Set::new();
+    let mut stack: Vec<&InternedAExp> = exps.into_iter().collect();
+    stack.reverse();
+    let mut res = vec![];
+    while let Some(a) = stack.pop() {
+        if !visited.insert(a.addr()) {
+            continue;
+        }
+        res.push(a.clone());
+        if let ExpKind::Op(_, left, right) = &a.0.exp {
+            stack.push(right);
+            stack.push(left);
+        }
+    }
+    res
+}
+
+/// Interned expressions are equal iff they are the same node, i.e. iff the expressions are equal
+impl PartialEq for InternedAExp {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for InternedAExp {}
+
+impl Hash for InternedAExp {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.0.hash)
+    }
+}
@@ -1,30 +1,422 @@
-use std::fmt::Display;
+use core::{fmt::{self, Display}, iter};
+use alloc::{boxed::Box, collections::BTreeMap, format, string::{String, ToString}, vec, vec::Vec};
+#[cfg(feature = "std")]
+use std::path::Path;
 use serde::{Serialize, Deserialize};
 
-use crate::{common::{VarName}};
+use crate::{common::{VarName, Int, Map, Set}};
 use crate::aexp::*;
 use crate::bexp::*;
 use crate::ast::{Prog::*, ProgAtom::*};
 
 
 /// A `Prog`ram represents an AST (abstract syntax tree).
-#[derive(PartialEq,Debug,Serialize,Deserialize)]
+#[derive(PartialEq,Clone,Debug,Serialize,Deserialize)]
 pub enum Prog {
     Prog(Vec<ProgAtom>)
 }
 
 /// A `ProgAtom` ("program atom") represents atomic statements of a program
-#[derive(PartialEq,Debug,Serialize,Deserialize)]
+#[derive(PartialEq,Clone,Debug,Serialize,Deserialize)]
 pub enum ProgAtom {
     // Rust Expl.: The `Box<BExp>` type represents *references to data of the `BExp` type on the heap*. This is the mechanism used to represent arbitrarily-large syntax trees, analogously to how linked lists are implemented.
     Skip,
     Assign(VarName, Box<AExp>),
     Cond(Box<BExp>, Box<Prog>, Box<Prog>),
     While(Box<BExp>, Box<Prog>),
+    // A post-tested loop `repeat p until b end`: The body is executed at least once, and again as long as the guard does not hold.
+    Repeat(Box<Prog>, Box<BExp>),
+    // A block `let x := a in p end` with a scoped variable: `x` holds the value of `a` within `p` only, its outer value is restored afterwards. As with any other statement, procedures called within `p` see the scoped value.
+    Let(VarName, Box<AExp>, Box<Prog>),
+    // A procedure definition is only allowed at the top level of a program. It does nothing by itself but makes the procedure available to `Call`s.
+    Proc(VarName, Box<Prog>),
+    Call(VarName),
+    // A nondeterministic assignment `havoc x`, which sets `x` to an arbitrary value, e.g. to model external input in the middle of a program. The interpreter picks `0`, whereas the analyses consider every value possible.
+    Havoc(VarName),
+    // Leave the innermost enclosing loop (`while` or `repeat`). The parser rejects `break` and `continue` outside of loops.
+    Break,
+    // Skip the rest of the body of the innermost enclosing loop: A `while` loop continues with testing its guard, and so does a `repeat` loop (like `continue` in a `do`-`while` loop of C).
+    Continue,
+}
+
+/// The error type of `Prog::validate_var_kinds`
+pub type VarKindError = String;
+
+/// The kind of a variable, i.e. how its name is used in a program, see `Prog::validate_var_kinds`. So far, a name is either a scalar (holding an `Int`) or a procedure; further kinds like arrays need to be added here.
+#[derive(PartialEq,Eq,Debug,Clone,Copy)]
+pub enum VarKind {
+    Scalar,
+    Procedure
+}
+
+impl Display for VarKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VarKind::Scalar => {write!(f, "a scalar")}
+            VarKind::Procedure => {write!(f, "a procedure")}
+        }
+    }
+}
+
+/// Statistics on the size and complexity of a program (including its procedure bodies), see `Prog::stats`
+/// - `assignments`:    Number of assignments (including the initializations of `let` blocks and `havoc` statements)
+/// - `conditionals`:   Number of conditionals
+/// - `loops`:          Number of loops (`while` and `repeat`)
+/// - `max_loop_depth`: Maximal nesting depth of loops, i.e. `0` for a loop-free program and `1` if no loop contains another one
+/// - `vars`:           Number of distinct variables that are assigned or read
+#[derive(PartialEq,Eq,Debug,Clone,Default,Serialize,Deserialize)]
+pub struct ProgStats {
+    pub assignments: usize,
+    pub conditionals: usize,
+    pub loops: usize,
+    pub max_loop_depth: usize,
+    pub vars: usize
+}
+
+/// A position in the source code of a program: The `line` and the `column` (counted in bytes) are `1`-based
+#[derive(PartialEq,Eq,Debug,Clone,Copy,PartialOrd,Ord,Hash)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize
+}
+
+/// The part of the source code that a statement was parsed from, from `start` (inclusive) to `end` (exclusive), see `parser::parse_with_spans`. The implicit `skip` of a conditional without else-branch has an empty span at the `end` keyword of the conditional.
+#[derive(PartialEq,Eq,Debug,Clone,Copy,Hash)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position
+}
+
+/// The comments of a program, attached to its statements (see `parser::parse_with_comments`), which are identified by their index in the order of `Prog::atoms`. The text of a comment is everything after its `#`.
+/// - `leading`:  The comments on separate lines before a statement, in the order in which they occur
+/// - `trailing`: The comment at the end of the line on which a statement ends
+/// - `end`:      The comments after the last statement of the program
+#[derive(PartialEq,Eq,Debug,Clone,Default)]
+pub struct Comments {
+    pub leading: BTreeMap<usize, Vec<String>>,
+    pub trailing: BTreeMap<usize, String>,
+    pub end: Vec<String>
+}
+
+impl Prog {
+    /// Compute statistics on the program, see `ProgStats`
+    pub fn stats(&self) -> ProgStats {
+        let mut stats = ProgStats::default();
+        let mut vars = Set::new();
+        self.collect_stats(0, &mut stats, &mut vars);
+        stats.vars = vars.len();
+        stats
+    }
+
+    /// Pretty-print the program in the concrete syntax like `fmt`, but such that parsing the result yields the very same program (see `AExp::to_source` and `BExp::to_source`)
+    pub fn to_source(&self) -> String {
+        let Prog(ps) = self;
+        ps.iter().map(ProgAtom::to_source).collect::<Vec<_>>().join("; ")
+    }
+
+    /// Return the set of variables that are assigned or read somewhere in the program (including procedure bodies)
+    pub fn vars(&self) -> Set<VarName> {
+        let mut vars = Set::new();
+        self.collect_stats(0, &mut ProgStats::default(), &mut vars);
+        vars
+    }
+
+    /// Return the variables that are assigned somewhere in the program, but whose value is never read anywhere (e.g. bookkeeping variables that were left over). The output variable `z` is excluded. This is a whole-program check that ignores the control flow, so unlike dead stores (see `live_vars::dead_stores`), a variable that is read at all is never reported.
+    pub fn write_only_vars(&self) -> Set<VarName> {
+        let read = self.read_vars();
+        self.vars().into_iter().filter(|x| !read.contains(x) && *x != VarName::new("z")).collect()
+    }
+
+    /// Check that every name is used with a single kind throughout the program (including procedure bodies), e.g. that no name is used both as a scalar and as a procedure (which the parser accepts, as procedures and variables do not interfere when the program is run). This is the place to reject further ill-formed programs once further kinds of variables (like arrays) exist.
+    pub fn validate_var_kinds(&self) -> Result<(), VarKindError> {
+        let mut kinds: Map<VarName, VarKind> = self.procs().into_keys().chain(self.called_procs()).map(|p| (p, VarKind::Procedure)).collect();
+        // Rust Expl.: Sorting the variables makes the error message deterministic if there are several conflicts
+        let mut vars: Vec<VarName> = self.vars().into_iter().collect();
+        vars.sort();
+        vars.into_iter().map(|x| (x, VarKind::Scalar)).try_for_each(|(x, kind)| {
+            match kinds.insert(x.clone(), kind) {
+                Some(other) if other != kind => {Err(format!("The variable {} is used both as {} and as {}.", x, other, kind))}
+                _ => {Ok(())}
+            }
+        })
+    }
+
+    /// Helper function for `write_only_vars`: Return the set of variables that are read somewhere in the program, i.e. in right-hand sides of assignments and in guards (including procedure bodies)
+    fn read_vars(&self) -> Set<VarName> {
+        let Prog(ps) = self;
+        ps.iter().flat_map(|p| match p {
+            Skip | Call(_) | Havoc(_) | Break | Continue => {Set::new()}
+            Assign(_, a)           => {a.vars()}
+            Cond(b, p_tt, p_ff)    => {b.vars().into_iter().chain(p_tt.read_vars()).chain(p_ff.read_vars()).collect()}
+            While(b, p) | Repeat(p, b) => {b.vars().into_iter().chain(p.read_vars()).collect()}
+            Let(_, a, p)           => {a.vars().into_iter().chain(p.read_vars()).collect()}
+            Proc(_, p)             => {p.read_vars()}
+        }).collect()
+    }
+
+    /// Helper function for `stats`: Count the statements of the program, which is nested in `depth` loops, and collect its variables
+    fn collect_stats(&self, depth: usize, stats: &mut ProgStats, vars: &mut Set<VarName>) {
+        let Prog(ps) = self;
+        ps.iter().for_each(|p| match p {
+            Skip | Call(_) | Break | Continue => {}
+            Havoc(x)               => {
+                stats.assignments += 1;
+                vars.insert(x.clone());
+            }
+            Assign(x, a)           => {
+                stats.assignments += 1;
+                vars.insert(x.clone());
+                vars.extend(a.vars());
+            }
+            Cond(b, p_tt, p_ff)    => {
+                stats.conditionals += 1;
+                vars.extend(b.vars());
+                p_tt.collect_stats(depth, stats, vars);
+                p_ff.collect_stats(depth, stats, vars);
+            }
+            While(b, p) | Repeat(p, b) => {
+                stats.loops += 1;
+                stats.max_loop_depth = stats.max_loop_depth.max(depth + 1);
+                vars.extend(b.vars());
+                p.collect_stats(depth + 1, stats, vars);
+            }
+            Let(x, a, p)           => {
+                stats.assignments += 1;
+                vars.insert(x.clone());
+                vars.extend(a.vars());
+                p.collect_stats(depth, stats, vars);
+            }
+            Proc(_, p)             => {p.collect_stats(depth, stats, vars)}
+        });
+    }
+
+    /// Apply `f` to every arithmetic expression of the program, i.e. to the right-hand sides of assignments and to the operands of the comparisons in guards (including procedure bodies). The control structure is kept.
+    pub fn map_aexps(&self, f: impl Fn(&AExp) -> AExp) -> Prog {
+        self.map_exps(&f, &|b| b.map_aexps(&f))
+    }
+
+    /// Apply `f` to every guard of a conditional or loop (including procedure bodies). The control structure is kept.
+    pub fn map_bexps(&self, f: impl Fn(&BExp) -> BExp) -> Prog {
+        self.map_exps(&|a| a.clone(), &f)
+    }
+
+    /// Helper function for `map_aexps` and `map_bexps`: Rewrite the right-hand sides of assignments by `fa` and the guards by `fb`
+    fn map_exps(&self, fa: &dyn Fn(&AExp) -> AExp, fb: &dyn Fn(&BExp) -> BExp) -> Prog {
+        let Prog(ps) = self;
+        Prog(ps.iter().map(|p| match p {
+            Skip                   => {Skip}
+            Assign(x, a)           => {Assign(x.clone(), Box::new(fa(a)))}
+            Cond(b, p_tt, p_ff)    => {Cond(Box::new(fb(b)), Box::new(p_tt.map_exps(fa, fb)), Box::new(p_ff.map_exps(fa, fb)))}
+            While(b, p)            => {While(Box::new(fb(b)), Box::new(p.map_exps(fa, fb)))}
+            Repeat(p, b)           => {Repeat(Box::new(p.map_exps(fa, fb)), Box::new(fb(b)))}
+            Let(x, a, p)           => {Let(x.clone(), Box::new(fa(a)), Box::new(p.map_exps(fa, fb)))}
+            Proc(name, p)          => {Proc(name.clone(), Box::new(p.map_exps(fa, fb)))}
+            Call(name)             => {Call(name.clone())}
+            Havoc(x)               => {Havoc(x.clone())}
+            Break                  => {Break}
+            Continue               => {Continue}
+        }).collect())
+    }
+
+    /// Return all atoms of the program in pre-order, i.e. every atom before the atoms of its sub-programs (and the then-branch of a conditional before its else-branch), including procedure definitions and their bodies. This is the order in which the atoms occur in the source code.
+    pub fn atoms(&self) -> Vec<&ProgAtom> {
+        let Prog(ps) = self;
+        ps.iter().flat_map(|p| {
+            let sub_atoms = match p {
+                Skip | Assign(_, _) | Call(_) | Havoc(_) | Break | Continue => {vec![]}
+                Cond(_, p_tt, p_ff)    => {p_tt.atoms().into_iter().chain(p_ff.atoms()).collect()}
+                While(_, p) | Repeat(p, _) | Let(_, _, p) | Proc(_, p) => {p.atoms()}
+            };
+            iter::once(p).chain(sub_atoms)
+        }).collect()
+    }
+
+    /// Return the procedures defined at the top level of the program, indexed by their name
+    pub fn procs(&self) -> Map<VarName, &Prog> {
+        let Prog(ps) = self;
+        ps.iter().filter_map(|p| match p {
+            Proc(name, body) => {Some((name.clone(), body.as_ref()))}
+            _ => {None}
+        }).collect()
+    }
+
+    /// Return the names of all procedures that are called somewhere in the program
+    pub fn called_procs(&self) -> Set<VarName> {
+        let Prog(ps) = self;
+        ps.iter().flat_map(|p| match p {
+            Skip | Assign(_, _) | Havoc(_) | Break | Continue => {Set::new()}
+            Cond(_, p_tt, p_ff)    => {p_tt.called_procs().union(&p_ff.called_procs()).cloned().collect()}
+            While(_, p) | Repeat(p, _) | Let(_, _, p) | Proc(_, p) => {p.called_procs()}
+            Call(name)             => {vec![name.clone()].into_iter().collect()}
+        }).collect()
+    }
+
+    /// Return the set of integer literals occurring anywhere in the program (in assignments and guards, including procedure bodies)
+    pub fn literals(&self) -> Set<Int> {
+        let Prog(ps) = self;
+        ps.iter().flat_map(|p| match p {
+            Skip | Call(_) | Havoc(_) | Break | Continue => {Set::new()}
+            Assign(_, a)           => {a.literals()}
+            Cond(b, p_tt, p_ff)    => {b.literals().into_iter().chain(p_tt.literals()).chain(p_ff.literals()).collect()}
+            While(b, p) | Repeat(p, b) => {b.literals().into_iter().chain(p.literals()).collect()}
+            Let(_, a, p)           => {a.literals().into_iter().chain(p.literals()).collect()}
+            Proc(_, p)             => {p.literals()}
+        }).collect()
+    }
+
+    /// Return `true` if the program contains a `break` or `continue` that is not nested in a loop of the program itself, i.e. one that jumps out of the program to an enclosing loop (including procedure bodies, where this is not allowed)
+    pub fn has_jumps(&self) -> bool {
+        let Prog(ps) = self;
+        ps.iter().any(|p| match p {
+            Break | Continue       => {true}
+            Cond(_, p_tt, p_ff)    => {p_tt.has_jumps() || p_ff.has_jumps()}
+            Let(_, _, p) | Proc(_, p) => {p.has_jumps()}
+            Skip | Assign(_, _) | While(_, _) | Repeat(_, _) | Call(_) | Havoc(_) => {false}
+        })
+    }
+
+    /// Return the program without redundant `skip` statements, e.g. `skip; x := 1; skip` becomes `x := 1`. A block that consists of `skip` statements only (like a branch of a conditional, a loop body or the whole program) keeps a single `skip`, as blocks cannot be empty. As `skip` does nothing, the semantics is preserved.
+    pub fn canonicalize(&self) -> Prog {
+        let Prog(ps) = self;
+        let ps: Vec<ProgAtom> = ps.iter().filter(|p| **p != Skip).map(|p| match p {
+            Cond(b, p_tt, p_ff)    => {Cond(b.clone(), Box::new(p_tt.canonicalize()), Box::new(p_ff.canonicalize()))}
+            While(b, p)            => {While(b.clone(), Box::new(p.canonicalize()))}
+            Repeat(p, b)           => {Repeat(Box::new(p.canonicalize()), b.clone())}
+            Let(x, a, p)           => {Let(x.clone(), a.clone(), Box::new(p.canonicalize()))}
+            Proc(name, p)          => {Proc(name.clone(), Box::new(p.canonicalize()))}
+            Skip | Assign(_, _) | Call(_) | Havoc(_) | Break | Continue => {p.clone()}
+        }).collect();
+        if ps.is_empty() { Prog(vec![Skip]) } else { Prog(ps) }
+    }
+}
+
+/// The maximal number of times the CLI unrolls every `while` loop (see `unroll` and `--max-loop-unroll`): The unrolled program nests `k` conditionals per loop, which are translated recursively, and the copies of nested loops multiply, i.e. the size grows like `k^d` for loops nested `d` deep.
+pub const MAX_UNROLL: usize = 16;
+
+/// Unroll every `while` loop `k` times: `while b do p end` becomes `if b then p; if b then p; ... while b do p end end end` with `k` guarded copies of `p` before the residual loop. This is semantics-preserving and may make analyses more precise for loops with small trip counts, as the first iterations are analyzed separately.
+/// Nested loops (and loops in procedure bodies) are unrolled as well; the loop bodies are unrolled before they are copied. As the size of the result grows quickly with `k`, callers should bound it (see `MAX_UNROLL`). A loop whose body jumps out of it (see `Prog::has_jumps`) is not unrolled, as the copies of its body would not be inside the loop anymore.
+pub fn unroll(prog: &Prog, k: usize) -> Prog {
+    let Prog(ps) = prog;
+    Prog(ps.iter().map(|p| match p {
+        Skip | Assign(_, _) | Call(_) | Havoc(_) | Break | Continue => {p.clone()}
+        Cond(b, p_tt, p_ff)    => {Cond(b.clone(), Box::new(unroll(p_tt, k)), Box::new(unroll(p_ff, k)))}
+        While(b, p) if p.has_jumps() => {While(b.clone(), Box::new(unroll(p, k)))}
+        While(b, p)            => {
+            let body = unroll(p, k);
+            // Build the guarded copies from the inside out
+            (0..k).fold(While(b.clone(), Box::new(body.clone())), |inner, _| {
+                let Prog(mut copy) = body.clone();
+                copy.push(inner);
+                Cond(b.clone(), Box::new(Prog(copy)), Box::new(Prog(vec![Skip])))
+            })
+        }
+        Repeat(p, b)           => {Repeat(Box::new(unroll(p, k)), b.clone())}
+        Let(x, a, p)           => {Let(x.clone(), a.clone(), Box::new(unroll(p, k)))}
+        Proc(name, p)          => {Proc(name.clone(), Box::new(unroll(p, k)))}
+    }).collect())
+}
+
+impl Prog {
+    /// Like `to_source`, but with the given comments (see `Comments`): Leading comments are put on separate lines before their statement (indented by two spaces per enclosing block, like the statement that follows them), and a trailing comment at the end of the line of its statement. Parsing the result with `parser::parse_with_comments` yields the same program with the same comments.
+    pub fn to_source_with_comments(&self, comments: &Comments) -> String {
+        let mut s = self.source_with_comments(comments, &mut 0, 0);
+        comments.end.iter().for_each(|c| {
+            if !s.ends_with('\n') { s.push('\n') }
+            s.push_str(&format!("#{}\n", c));
+        });
+        s.trim_matches('\n').to_string()
+    }
+
+    /// Helper function for `to_source_with_comments`, where `idx` is the index of the first atom of the program (see `Prog::atoms`) and is advanced past its last atom, and `depth` is the number of enclosing blocks
+    fn source_with_comments(&self, comments: &Comments, idx: &mut usize, depth: usize) -> String {
+        let Prog(ps) = self;
+        let indent = "  ".repeat(depth);
+        let mut s = String::new();
+        ps.iter().enumerate().for_each(|(i, p)| {
+            let own = *idx;
+            *idx += 1;
+            if let Some(cs) = comments.leading.get(&own) {
+                if !s.ends_with('\n') { s.push('\n') }
+                cs.iter().for_each(|c| s.push_str(&format!("{}#{}\n", indent, c)));
+                s.push_str(&indent);
+            }
+            else if s.ends_with('\n') {
+                s.push_str(&indent);
+            }
+            else if i > 0 {
+                s.push(' ');
+            }
+            s.push_str(&p.source_with_comments(comments, idx, depth));
+            if i + 1 < ps.len() {
+                s.push(';');
+            }
+            // A comment extends to the end of its line, so the next token must start on a new line
+            if let Some(c) = comments.trailing.get(&own) {
+                s.push_str(&format!(" #{}\n", c));
+            }
+        });
+        s
+    }
+}
+
+impl ProgAtom {
+    /// Helper function for `Prog::to_source_with_comments`, where `idx` is the index of the first atom of the sub-programs and `depth` is the number of blocks enclosing the atom
+    fn source_with_comments(&self, comments: &Comments, idx: &mut usize, depth: usize) -> String {
+        // A block is separated from the surrounding keywords by a space, unless it starts with a comment line or ends with a comment (then the next keyword starts a new line)
+        let block = |p: &Prog, idx: &mut usize| {
+            let body = p.source_with_comments(comments, idx, depth + 1);
+            let before = if body.starts_with('\n') { String::new() } else { " ".to_string() };
+            let after = if body.ends_with('\n') { "  ".repeat(depth) } else { " ".to_string() };
+            format!("{}{}{}", before, body, after)
+        };
+        match &self {
+            Cond(bexp, p_tt, p_ff) => {
+                let tt = block(p_tt, idx);
+                // The implicit else-branch is only omitted if it has no comments
+                let has_comments = comments.leading.contains_key(idx) || comments.trailing.contains_key(idx);
+                if **p_ff == Prog(vec![Skip]) && !has_comments {
+                    *idx += 1;
+                    format!("if {} then{}end", bexp.to_source(), tt)
+                }
+                else {
+                    format!("if {} then{}else{}end", bexp.to_source(), tt, block(p_ff, idx))
+                }
+            }
+            While(bexp, p)         => {format!("while {} do{}end", bexp.to_source(), block(p, idx))}
+            Repeat(p, bexp)        => {format!("repeat{}until {} end", block(p, idx), bexp.to_source())}
+            Let(v, aexp, p)        => {format!("let {} := {} in{}end", v, aexp.to_source(), block(p, idx))}
+            Proc(name, p)          => {format!("proc {} do{}end", name, block(p, idx))}
+            Skip | Assign(_, _) | Call(_) | Havoc(_) | Break | Continue => {self.to_source()}
+        }
+    }
+
+    /// Pretty-print the program atom in the concrete syntax, see `Prog::to_source`
+    pub fn to_source(&self) -> String {
+        match &self {
+            Skip                   => {"skip".to_string()}
+            Assign(v, aexp)        => {format!("{} := {}", v, aexp.to_source())}
+            Cond(bexp, p_tt, p_ff) if **p_ff == Prog(vec![Skip]) => {format!("if {} then {} end", bexp.to_source(), p_tt.to_source())}
+            Cond(bexp, p_tt, p_ff) => {format!("if {} then {} else {} end", bexp.to_source(), p_tt.to_source(), p_ff.to_source())}
+            While(bexp, p)         => {format!("while {} do {} end", bexp.to_source(), p.to_source())}
+            Repeat(p, bexp)        => {format!("repeat {} until {} end", p.to_source(), bexp.to_source())}
+            Let(v, aexp, p)        => {format!("let {} := {} in {} end", v, aexp.to_source(), p.to_source())}
+            Proc(name, p)          => {format!("proc {} do {} end", name, p.to_source())}
+            Call(name)             => {format!("call {}", name)}
+            Havoc(x)               => {format!("havoc {}", x)}
+            Break                  => {"break".to_string()}
+            Continue               => {"continue".to_string()}
+        }
+    }
+}
+
+/// Write the program to the file `path` in the concrete syntax (see `Prog::to_source`), e.g. to generate the `prog.while` of a test fixture from its AST
+#[cfg(feature = "std")]
+pub fn write_while<P: AsRef<Path>>(prog: &Prog, path: P) -> std::io::Result<()> {
+    std::fs::write(path, prog.to_source() + "\n")
 }
 
 impl Display for Prog {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let Prog(ps) = self;
         let mut iter = ps.iter();
         match iter.next() {
@@ -38,12 +430,21 @@ impl Display for Prog {
 }
 
 impl Display for ProgAtom {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match &self {
             Skip                   => {write!(f, "skip")}
             Assign(v, aexp)        => {write!(f, "{} := {}", v, aexp)}
+            // An else-branch consisting of a single `skip` is omitted
+            Cond(bexp, p_tt, p_ff) if **p_ff == Prog(vec![Skip]) => {write!(f, "if {} then {} end", bexp, p_tt)}
             Cond(bexp, p_tt, p_ff) => {write!(f, "if {} then {} else {} end", bexp, p_tt, p_ff)}
             While(bexp, p)         => {write!(f, "while {} do {} end", bexp, p)}
+            Repeat(p, bexp)        => {write!(f, "repeat {} until {} end", p, bexp)}
+            Let(v, aexp, p)        => {write!(f, "let {} := {} in {} end", v, aexp, p)}
+            Proc(name, p)          => {write!(f, "proc {} do {} end", name, p)}
+            Call(name)             => {write!(f, "call {}", name)}
+            Havoc(x)               => {write!(f, "havoc {}", x)}
+            Break                  => {write!(f, "break")}
+            Continue               => {write!(f, "continue")}
         }
     }
 }
\ No newline at end of file
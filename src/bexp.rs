@@ -1,21 +1,31 @@
-use std::{fmt::Display, collections::HashSet};
+use core::{fmt::{self, Display}, hash::{Hash, Hasher}};
+#[cfg(feature = "std")]
+use core::{iter, ops::RangeInclusive};
+use alloc::{boxed::Box, format, string::String, sync::Arc, vec, vec::Vec};
 use serde::{Serialize, Deserialize};
 
 use crate::aexp::*;
+use crate::common::{VarName, Int, Set, Interner, StableHasher, interner};
+#[cfg(feature = "std")]
+use crate::interpreter::{MemConfig, eval_bexp};
 
 /// Boolean expression
-#[derive(PartialEq,Clone,Debug,Serialize,Deserialize,Eq,Hash)]
+///
+/// Expressions are totally ordered: First by their variant (`LessEq < Less < Neg < And < Or`), then lexicographically by their components (see `AExp` for the order of arithmetic expressions).
+#[derive(PartialEq,Clone,Debug,Serialize,Deserialize,Eq,Hash,PartialOrd,Ord)]
 pub enum BExp {
     LessEq(Box<AExp>, Box<AExp>),
+    Less(Box<AExp>, Box<AExp>),
     Neg(Box<BExp>),
     And(Box<BExp>,Box<BExp>),
     Or(Box<BExp>,Box<BExp>),
 }
 
 impl Display for BExp {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match &self {
             BExp::LessEq(left, right) => { write!(f, "{} <= {}", left, right) },
+            BExp::Less(left, right) => { write!(f, "{} < {}", left, right) },
             BExp::Neg(b) => { write!(f, "!{}", b) },
             BExp::And(left, right) => { write!(f, "{} && {}", left, right) },
             BExp::Or(left, right) => { write!(f, "{} || {}", left, right) }
@@ -24,9 +34,83 @@ impl Display for BExp {
 }
 
 impl BExp {
-    pub fn sub_aexps(&self) -> HashSet<AExp> {
+    /// Pretty-print the expression in the concrete syntax, such that parsing the result yields the very same expression (see `parser::parse_bexp`). Unlike `fmt`, this inserts all parentheses that are needed to preserve the structure. Conjunctions within disjunctions are parenthesized as well, which the parser would diagnose otherwise.
+    pub fn to_source(&self) -> String {
         match self {
-            BExp::LessEq(a1, a2) => {
+            BExp::LessEq(left, right) => {format!("{} <= {}", left.to_source(), right.to_source())}
+            BExp::Less(left, right) => {format!("{} < {}", left.to_source(), right.to_source())}
+            // A negation extends as far as possible, so it needs no parentheses at the top level
+            BExp::Neg(b) => {format!("!{}", b.to_source())}
+            BExp::And(left, right) => {format!("{} && {}", left.source_operand(matches!(**left, BExp::And(_, _))), right.source_operand(false))}
+            BExp::Or(left, right) => {format!("{} || {}", left.source_operand(matches!(**left, BExp::Or(_, _))), right.source_operand(false))}
+        }
+    }
+
+    /// Helper function for `to_source`: Pretty-print an operand of `&&` or `||`, which is parenthesized unless it is a comparison or `unparenthesized` holds (i.e. it is the left operand of the same, left-associative operator)
+    fn source_operand(&self, unparenthesized: bool) -> String {
+        match self {
+            BExp::LessEq(_, _) | BExp::Less(_, _) => {self.to_source()}
+            _ if unparenthesized                  => {self.to_source()}
+            _                                     => {format!("({})", self.to_source())}
+        }
+    }
+
+    /// Return `true` if there exists a variable somewhere in the boolean expression
+    pub fn contains_var(&self, x: &VarName) -> bool {
+        match self {
+            BExp::LessEq(a1, a2) | BExp::Less(a1, a2) => a1.contains_var(x) || a2.contains_var(x),
+            BExp::Neg(b) => b.contains_var(x),
+            BExp::And(b1, b2) | BExp::Or(b1, b2) => b1.contains_var(x) || b2.contains_var(x)
+        }
+    }
+
+    /// Return the set of variables occurring in the boolean expression
+    pub fn vars(&self) -> Set<VarName> {
+        match self {
+            BExp::LessEq(a1, a2) | BExp::Less(a1, a2) => a1.vars().union(&a2.vars()).cloned().collect(),
+            BExp::Neg(b) => b.vars(),
+            BExp::And(b1, b2) | BExp::Or(b1, b2) => b1.vars().union(&b2.vars()).cloned().collect()
+        }
+    }
+
+    /// Return the set of integer literals occurring in the boolean expression
+    pub fn literals(&self) -> Set<Int> {
+        match self {
+            BExp::LessEq(a1, a2) | BExp::Less(a1, a2) => a1.literals().union(&a2.literals()).cloned().collect(),
+            BExp::Neg(b) => b.literals(),
+            BExp::And(b1, b2) | BExp::Or(b1, b2) => b1.literals().union(&b2.literals()).cloned().collect()
+        }
+    }
+
+    /// Replace every occurrence of the variable `x` by the arithmetic expression `with`
+    pub fn substitute(&self, x: &VarName, with: &AExp) -> BExp {
+        let sub_a = |a: &AExp| Box::new(a.substitute(x, with));
+        let sub_b = |b: &BExp| Box::new(b.substitute(x, with));
+        match self {
+            BExp::LessEq(a1, a2) => BExp::LessEq(sub_a(a1), sub_a(a2)),
+            BExp::Less(a1, a2) => BExp::Less(sub_a(a1), sub_a(a2)),
+            BExp::Neg(b) => BExp::Neg(sub_b(b)),
+            BExp::And(b1, b2) => BExp::And(sub_b(b1), sub_b(b2)),
+            BExp::Or(b1, b2) => BExp::Or(sub_b(b1), sub_b(b2))
+        }
+    }
+
+    /// Apply `f` to the arithmetic operands of all comparisons, keeping the boolean structure
+    pub fn map_aexps(&self, f: &dyn Fn(&AExp) -> AExp) -> BExp {
+        let map_a = |a: &AExp| Box::new(f(a));
+        let map_b = |b: &BExp| Box::new(b.map_aexps(f));
+        match self {
+            BExp::LessEq(a1, a2) => BExp::LessEq(map_a(a1), map_a(a2)),
+            BExp::Less(a1, a2) => BExp::Less(map_a(a1), map_a(a2)),
+            BExp::Neg(b) => BExp::Neg(map_b(b)),
+            BExp::And(b1, b2) => BExp::And(map_b(b1), map_b(b2)),
+            BExp::Or(b1, b2) => BExp::Or(map_b(b1), map_b(b2))
+        }
+    }
+
+    pub fn sub_aexps(&self) -> Set<AExp> {
+        match self {
+            BExp::LessEq(a1, a2) | BExp::Less(a1, a2) => {
                 // Rust Expl.: See also `AExp::sub_aexps` for a more detailed explanation 
                 a1.sub_aexps().union(&a2.sub_aexps()).cloned().collect()
             }
@@ -38,4 +122,175 @@ impl BExp {
             }
         }
     }
-}
\ No newline at end of file
+
+    /// Enumerate all assignments of the variables `vars` to values within `range` and evaluate the expression for each of them (all other variables are `0`). The assignments are given as the values of `vars` (in this order) and are enumerated in lexicographic order. This is useful e.g. for checking that a transformation preserves the meaning of an expression.
+    #[cfg(feature = "std")]
+    pub fn truth_table(&self, vars: &[VarName], range: RangeInclusive<Int>) -> Vec<(Vec<Int>, bool)> {
+        let values: Vec<Int> = range.collect();
+        vars.iter().fold(vec![vec![]], |rows: Vec<Vec<Int>>, _| {
+            rows.iter().flat_map(|row| values.iter().map(move |n| row.iter().cloned().chain(iter::once(*n)).collect())).collect()
+        }).into_iter().map(|row| {
+            let mut mem = MemConfig::new();
+            vars.iter().zip(&row).for_each(|(x, n)| mem.assign(x, *n));
+            let b = eval_bexp(self, &mem);
+            (row, b)
+        }).collect()
+    }
+
+    /// Eliminate all `Neg`s by pushing them down to the comparisons: A negated comparison is rewritten into the complementary comparison with swapped operands (e.g. `!(x <= 5)` becomes `5 < x`), and `And`/`Or` are exchanged according to De Morgan's laws.
+    pub fn push_negations(&self) -> BExp {
+        self.push_negations_polarity(false)
+    }
+
+    /// This helper function does the work for `push_negations`. If `negate` is `true`, then the negation of `self` is produced.
+    fn push_negations_polarity(&self, negate: bool) -> BExp {
+        match (self, negate) {
+            (BExp::LessEq(a1, a2), false) => { BExp::LessEq(a1.clone(), a2.clone()) }
+            (BExp::LessEq(a1, a2), true)  => { BExp::Less(a2.clone(), a1.clone()) }
+            (BExp::Less(a1, a2), false)   => { BExp::Less(a1.clone(), a2.clone()) }
+            (BExp::Less(a1, a2), true)    => { BExp::LessEq(a2.clone(), a1.clone()) }
+            (BExp::Neg(b), _)             => { b.push_negations_polarity(!negate) }
+            (BExp::And(b1, b2), false) | (BExp::Or(b1, b2), true) => {
+                BExp::And(Box::new(b1.push_negations_polarity(negate)), Box::new(b2.push_negations_polarity(negate)))
+            }
+            (BExp::Or(b1, b2), false) | (BExp::And(b1, b2), true) => {
+                BExp::Or(Box::new(b1.push_negations_polarity(negate)), Box::new(b2.push_negations_polarity(negate)))
+            }
+        }
+    }
+}
+
+/// The key of an interned boolean expression in the table of the `Interner`: The variant (`0` to `4` for `<=`, `<`, `!`, `&&` and `||`) and the addresses of the interned operands (`0` for the missing second operand of `!`), see `ExpKey`
+#[derive(PartialEq,Eq,Hash,PartialOrd,Ord,Clone,Copy,Debug)]
+pub(crate) struct BExpKey(u8, usize, usize);
+
+/// A node of an interned boolean expression together with its precomputed hash, see `InternedBExp`
+#[derive(Debug)]
+pub(crate) struct BExpNode {
+    exp: BExpKind,
+    hash: u64
+}
+
+/// The expression of a `BExpNode`, whose operands are interned
+#[derive(Debug)]
+enum BExpKind {
+    LessEq(InternedAExp, InternedAExp),
+    Less(InternedAExp, InternedAExp),
+    Neg(InternedBExp),
+    And(InternedBExp, InternedBExp),
+    Or(InternedBExp, InternedBExp)
+}
+
+/// A hash-consed boolean expression, whose arithmetic subexpressions are interned as well. See `InternedAExp`, which works the same way.
+#[derive(Clone,Debug)]
+pub struct InternedBExp(Arc<BExpNode>);
+
+impl BExp {
+    /// Intern the expression, see `InternedBExp`. This takes time linear in the size of the expression.
+    pub fn interned(&self) -> InternedBExp {
+        intern_bexp(&mut interner(), self, true).unwrap()
+    }
+
+    /// Return the interned expression if the expression is currently interned (see `interned`), without interning it otherwise
+    pub fn find_interned(&self) -> Option<InternedBExp> {
+        intern_bexp(&mut interner(), self, false)
+    }
+}
+
+/// Helper function for `BExp::interned`, see `intern`
+fn intern_bexp(interner: &mut Interner, b: &BExp, insert: bool) -> Option<InternedBExp> {
+    let mut hasher = StableHasher::default();
+    let (key, exp) = match b {
+        BExp::LessEq(a1, a2) | BExp::Less(a1, a2) => {
+            let a1 = intern(interner, a1, insert)?;
+            let a2 = intern(interner, a2, insert)?;
+            let variant = if let BExp::LessEq(_, _) = b { 0 } else { 1 };
+            hasher.write_u8(variant);
+            hasher.write_u64(a1.hash_value());
+            hasher.write_u64(a2.hash_value());
+            let key = BExpKey(variant, a1.addr(), a2.addr());
+            (key, if variant == 0 { BExpKind::LessEq(a1, a2) } else { BExpKind::Less(a1, a2) })
+        }
+        BExp::Neg(b) => {
+            let b = intern_bexp(interner, b, insert)?;
+            hasher.write_u8(2);
+            hasher.write_u64(b.hash_value());
+            (BExpKey(2, b.addr(), 0), BExpKind::Neg(b))
+        }
+        BExp::And(b1, b2) | BExp::Or(b1, b2) => {
+            let b1 = intern_bexp(interner, b1, insert)?;
+            let b2 = intern_bexp(interner, b2, insert)?;
+            let variant = if let BExp::And(_, _) = b { 3 } else { 4 };
+            hasher.write_u8(variant);
+            hasher.write_u64(b1.hash_value());
+            hasher.write_u64(b2.hash_value());
+            let key = BExpKey(variant, b1.addr(), b2.addr());
+            (key, if variant == 3 { BExpKind::And(b1, b2) } else { BExpKind::Or(b1, b2) })
+        }
+    };
+    if let Some(node) = interner.bexps.get(&key) {
+        return Some(InternedBExp(node));
+    }
+    if !insert {
+        return None;
+    }
+    let node = Arc::new(BExpNode { exp, hash: hasher.finish() });
+    interner.bexps.insert(key, &node);
+    Some(InternedBExp(node))
+}
+
+impl InternedBExp {
+    /// The expression that was interned. This does not lock the `Interner`.
+    pub fn to_bexp(&self) -> BExp {
+        match &self.0.exp {
+            BExpKind::LessEq(a1, a2) => {BExp::LessEq(Box::new(a1.to_aexp()), Box::new(a2.to_aexp()))}
+            BExpKind::Less(a1, a2) => {BExp::Less(Box::new(a1.to_aexp()), Box::new(a2.to_aexp()))}
+            BExpKind::Neg(b) => {BExp::Neg(Box::new(b.to_bexp()))}
+            BExpKind::And(b1, b2) => {BExp::And(Box::new(b1.to_bexp()), Box::new(b2.to_bexp()))}
+            BExpKind::Or(b1, b2) => {BExp::Or(Box::new(b1.to_bexp()), Box::new(b2.to_bexp()))}
+        }
+    }
+
+    /// The precomputed hash value, see `InternedAExp`
+    pub fn hash_value(&self) -> u64 {
+        self.0.hash
+    }
+
+    /// The address of the node, see `InternedAExp::addr`
+    fn addr(&self) -> usize {
+        Arc::as_ptr(&self.0) as usize
+    }
+
+    /// Return the distinct arithmetic subexpressions of all comparisons, see `BExp::sub_aexps` and `InternedAExp::sub_aexps`
+    pub fn sub_aexps(&self) -> Vec<InternedAExp> {
+        let mut visited = Set::new();
+        let mut stack = vec![self];
+        let mut comparands = vec![];
+        while let Some(b) = stack.pop() {
+            if !visited.insert(b.addr()) {
+                continue;
+            }
+            match &b.0.exp {
+                BExpKind::LessEq(a1, a2) | BExpKind::Less(a1, a2) => {comparands.extend([a1, a2])}
+                BExpKind::Neg(b) => {stack.push(b)}
+                BExpKind::And(b1, b2) | BExpKind::Or(b1, b2) => {stack.extend([b2, b1])}
+            }
+        }
+        distinct_sub_aexps(comparands)
+    }
+}
+
+/// Interned expressions are equal iff they are the same node, i.e. iff the expressions are equal
+impl PartialEq for InternedBExp {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for InternedBExp {}
+
+impl Hash for InternedBExp {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.0.hash)
+    }
+}
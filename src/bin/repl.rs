@@ -0,0 +1,99 @@
+use flanelly::{cfg::Cfg, parser, cfg, ast::Prog, common::Int};
+use flanelly::flow_analysis::{mfp::mfp, mfp::MfpAnnot, const_prop::MultiConstLat, avail_exp::ExpSetLat};
+use flanelly::interpreter::eval_bounded;
+use std::io::{self, BufRead, Write};
+
+/// Number of loop iterations and procedure calls after which `:eval` considers a program to diverge
+const MAX_STEPS: u64 = 100000;
+
+const HELP: &str = "\
+Type a WHILE program (possibly spanning several lines), then one of the following commands:
+  :const    constant propagation
+  :avail    available expressions
+  :eval n   interpret with input x = n
+  :ast      abstract syntax tree
+  :cfg      control flow graph
+  :help     this help
+  :quit     quit
+The last program that was parsed successfully is used for all commands.";
+
+fn main() -> io::Result<()> {
+  let stdin = io::stdin();
+  let mut program: Option<Prog> = None;
+  // Program text typed since the last command
+  let mut buffer = String::new();
+
+  println!("Flow Analyzer REPL. Type :help for help.");
+  prompt()?;
+  for line in stdin.lock().lines() {
+    let line = line?;
+    let command = line.trim();
+    if !command.starts_with(':') {
+      buffer.push_str(&line);
+      buffer.push('\n');
+      // No prompt, as the program may continue on the next line
+      continue;
+    }
+    // A command finishes the program typed so far
+    if !buffer.trim().is_empty() {
+      let parsed = parser::parse(&buffer);
+      buffer.clear();
+      match parsed {
+        Ok(p) => { program = Some(p) }
+        Err(e) => {
+          println!("error: {}", e);
+          prompt()?;
+          continue;
+        }
+      }
+    }
+    if command == ":quit" {
+      break;
+    }
+    match run_command(command, &program) {
+      Ok(output) => { println!("{}", output.trim_end()) }
+      Err(e) => { println!("error: {}", e) }
+    }
+    prompt()?;
+  }
+
+  Ok(())
+}
+
+/// Print the prompt
+fn prompt() -> io::Result<()> {
+  print!("> ");
+  io::stdout().flush()
+}
+
+/// Run a single command on the current program, returning the output to be printed
+fn run_command(command: &str, program: &Option<Prog>) -> Result<String, String> {
+  let mut words = command.split_whitespace();
+  let name = words.next().unwrap_or("");
+  let arg = words.next();
+  if name == ":help" {
+    return Ok(HELP.to_string());
+  }
+  let p = program.as_ref().ok_or("No program given yet.")?;
+  match (name, arg) {
+    (":const", None) => {
+      let cfg_mfp: Cfg<MfpAnnot<MultiConstLat>> = mfp(&cfg::ast_to_cfg(p));
+      Ok(cfg_mfp.to_string())
+    }
+    (":avail", None) => {
+      let cfg_mfp: Cfg<MfpAnnot<ExpSetLat>> = mfp(&cfg::ast_to_cfg(p));
+      Ok(cfg_mfp.to_string())
+    }
+    (":eval", Some(n)) => {
+      let x = n.parse::<Int>().map_err(|_| format!("The input {} is not a number.", n))?;
+      match eval_bounded(p, x, MAX_STEPS) {
+        Some(z) => { Ok(z.to_string()) }
+        None => { Ok(format!("diverges (no result after {} steps)", MAX_STEPS)) }
+      }
+    }
+    (":eval", None) => { Err("The command :eval expects an input, e.g. :eval 5".to_string()) }
+    (":ast", None) => { serde_json::to_string_pretty(p).map_err(|e| e.to_string()) }
+    (":cfg", None) => { Ok(cfg::ast_to_cfg(p).to_string()) }
+    _ => { Err(format!("Unknown command {}. Type :help for help.", command)) }
+  }
+}
@@ -0,0 +1,11 @@
+use serde::{Serialize, de::DeserializeOwned};
+
+/// Serialize a value (e.g. a `Prog`, a `Cfg` or the result of an analysis) into a compact binary format. This is much smaller and faster than JSON, which makes it suitable for large CFGs.
+pub fn to_bytes<T: Serialize>(value: &T) -> Result<Vec<u8>, String> {
+    bincode::serialize(value).map_err(|e| format!("Serialization failed: {}", e))
+}
+
+/// Deserialize a value from the binary format produced by `to_bytes`
+pub fn from_bytes<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String> {
+    bincode::deserialize(bytes).map_err(|e| format!("Deserialization failed: {}", e))
+}
@@ -1,13 +1,15 @@
-use std::{fmt::{Display, Debug}, collections::HashSet, hash::Hash};
+use std::{fmt::{Display, Debug}, collections::{BTreeMap, BTreeSet, HashSet, HashMap, hash_map::DefaultHasher}, hash::{Hash, Hasher}, iter};
 
 use petgraph::{graph::Graph, Directed, graph::NodeIndex, EdgeDirection::Incoming, EdgeDirection::Outgoing};
 use vec1::Vec1;
-use serde::{Serialize, Deserialize};
+use serde::{Serialize, Deserialize, de::DeserializeOwned};
 
 use crate::{common::VarName, ast::ProgAtom, ast::Prog};
 use crate::aexp::*;
 use crate::bexp::*;
 use petgraph::graph::EdgeIndex;
+use petgraph::algo::{toposort, tarjan_scc, is_cyclic_directed, dominators::simple_fast};
+use petgraph::visit::{EdgeRef, Dfs, depth_first_search, DfsEvent};
 
 ////////////////////
 // CFG Definition //
@@ -17,26 +19,39 @@ use petgraph::graph::EdgeIndex;
 pub type NodeIdx = NodeIndex<u32>;
 
 /// A CFG is a graph containing annotated nodes and edges, as well as a pointer to the initial node
-#[derive(Debug,Serialize,Deserialize)]
+#[derive(Clone,Debug,Serialize,Deserialize)]
 pub struct Cfg<A> {
     pub graph: Graph<AnnotNode<A>, Edge, Directed>,
     pub init: NodeIdx
 }
 
+/// Errors are simply given by a message, see `Cfg::from_graph_checked`
+pub type CfgError = String;
+
 /// Node of a CFG
 /// - Init:     Used only once in every CFG to mark the program entry point
 /// - Terminal: Used only to tie together program flows (i.e. edges) that don't
 ///             point to further code
 /// - Skip:     Corresponds to a skip in the AST
 /// - Assign:   Corresponds to an assignment in the AST
+/// - Havoc:    Corresponds to a nondeterministic assignment in the AST
 /// - Branch:   Corresponds to either a conditional or a while loop in the AST
+/// - Call:     Corresponds to a procedure call in the AST
+/// - Entry:    Entry point of a procedure's body, targeted by the `Call` nodes of that procedure
+/// - Exit:     Exit point of a procedure's body, pointing back to the code following the `Call` nodes
+/// - Phi:      Only in SSA form (see `ssa::to_ssa`): Assigns to the variable the version that is paired with the predecessor the control came from. The phi nodes of a join point form a chain in front of it, and all of them refer to the predecessors of the chain.
 #[derive(Clone,Debug,Serialize,Deserialize,PartialEq,Eq,Hash)]
 pub enum Node {
     Init,
     Terminal,
     Skip,
     Assign(VarName, Box<AExp>),
-    Branch(Box<BExp>)
+    Havoc(VarName),
+    Branch(Box<BExp>),
+    Call(VarName),
+    Entry(VarName),
+    Exit(VarName),
+    Phi(VarName, Vec<(NodeIdx, VarName)>)
 }
 
 /// An annotated node with a generic annotation type `T`
@@ -50,34 +65,53 @@ pub struct AnnotNode<T> {
 #[derive(Debug,PartialEq,Clone,Serialize,Deserialize,Eq,Hash)]
 pub struct RawAnnot { }
 
-/// Three kinds of edges exist:
-/// - Plain: Standard sequencing
-/// - True:  Branch here if the guard evaluates to `true`
-/// - False: Branch here if the guard evaluates to `false`
+/// Five kinds of edges exist:
+/// - Plain:  Standard sequencing
+/// - True:   Branch here if the guard evaluates to `true`
+/// - False:  Branch here if the guard evaluates to `false`
+/// - Call:   From a `Call` node to the `Entry` node of the called procedure
+/// - Return: From the `Exit` node of a procedure back to the code following a call
 #[derive(PartialEq,Clone,Debug,Serialize,Deserialize,Eq,Hash)]
 pub enum Edge {
     Plain,
     True,
-    False
+    False,
+    Call,
+    Return
 }
 
 ///////////////////////////////
 // Convert an AST into a CFG //
 ///////////////////////////////
 
-/// Convert an AST into a CFG
+/// Options for the conversion of an AST into a CFG
+/// - `always_terminal`: Connect *all* loose ends of the program to a single `Terminal` node, including plain ones. This yields a canonical single-exit CFG, as needed e.g. by backward analyses. By default, a `Terminal` node is only added for the tt/ff/return-edges remaining at the end of the program.
+/// - `omit_skips`:      Do not emit `Skip` nodes at all: A `skip` statement passes its untargeted edges (and their labels) on to the next node. This yields the same CFG as `remove_skips`, except that several `skip`s at the end of the program share one `Terminal` node.
+#[derive(Debug,Clone,Default)]
+pub struct CfgOptions {
+    pub always_terminal: bool,
+    pub omit_skips: bool
+}
+
+/// Convert an AST into a CFG, using the default options
 pub fn ast_to_cfg(p: &Prog) -> Cfg<RawAnnot> {
+    ast_to_cfg_with(p, &CfgOptions::default())
+}
+
+/// Convert an AST into a CFG. This function is total: It even translates programs that call undefined procedures (which are rejected by the parser), treating such calls like `skip`.
+pub fn ast_to_cfg_with(p: &Prog, options: &CfgOptions) -> Cfg<RawAnnot> {
     // The CFG is essentially a graph
     let mut g = Graph::new();
     // Construct and add the initial node of the CFG
     let node_init = g.add_node(AnnotNode::new(Node::Init, RawAnnot {}));
     let mut cfg = Cfg::new(g, node_init);
+    let mut ctx = Context { procs: p.procs(), proc_nodes: HashMap::new(), omit_skips: options.omit_skips, lets: 0, loops: vec![] };
 
     // The function `ast_to_cfg_extend` takes the fresh CFG and does the translation
-    let terminals = ast_to_cfg_extend(&mut cfg, vec![UntargEdge(node_init, Edge::Plain)], p);
+    let terminals = ast_to_cfg_extend(&mut cfg, &mut ctx, vec![UntargEdge(node_init, Edge::Plain)], p);
 
-    // If there are any tt/ff-edges remaining, then connect them to a terminal node
-    let mut terminals_relevant = terminals.iter().filter(|UntargEdge(_, e)| {*e != Edge::Plain}).peekable();
+    // If there are any tt/ff/return-edges remaining (or any edges at all, if `always_terminal` is set), then connect them to a terminal node
+    let mut terminals_relevant = terminals.iter().filter(|UntargEdge(_, e)| {options.always_terminal || *e != Edge::Plain}).peekable();
     match terminals_relevant.peek() {
         Some(_) => {
             let node_terminal = cfg.graph.add_node(AnnotNode::new(Node::Terminal, RawAnnot {}));
@@ -98,33 +132,66 @@ pub fn ast_to_cfg(p: &Prog) -> Cfg<RawAnnot> {
 #[derive(Clone)]
 struct UntargEdge(NodeIdx, Edge);
 
+/// # Translation Context
+/// Intermediate data structure, only needed during `ast_to_cfg`
+/// - `procs`:      The procedure definitions of the program
+/// - `proc_nodes`: The `Entry` and `Exit` nodes of those procedures whose body is already translated. A procedure body is translated only once (at its first call), all calls share this translation.
+/// - `omit_skips`: Whether `skip` statements are translated without a node, see `CfgOptions`
+/// - `lets`:       The number of `let` blocks translated so far, used for naming the variables that save the outer values
+/// - `loops`:      The loops whose bodies are being translated (innermost last), see `LoopJumps`
+struct Context<'a> {
+    procs: HashMap<VarName, &'a Prog>,
+    proc_nodes: HashMap<VarName, (NodeIdx, NodeIdx)>,
+    omit_skips: bool,
+    lets: usize,
+    loops: Vec<LoopJumps>
+}
+
+/// # Loop Jumps
+/// Intermediate data structure, only needed during `ast_to_cfg`
+/// - `breaks`:    Loose ends of the `break`s of the loop, which leave the loop
+/// - `continues`: Loose ends of the `continue`s of the loop, which are connected to its branch node
+/// - `lets`:      The `let` blocks within the loop body that are being translated (innermost last), given by the scoped variable and the auxiliary variable that saves its outer value. A jump out of these blocks restores the outer values first.
+#[derive(Default)]
+struct LoopJumps {
+    breaks: Vec<UntargEdge>,
+    continues: Vec<UntargEdge>,
+    lets: Vec<(VarName, VarName)>
+}
+
 
 /// # Arguments
 /// `cfg`:          Mutable CFG
+/// `ctx`:          Translation context
 /// `untarg_edges`: Loose ends of the CFG, i.e. untargeted edges
 /// `p`:            Sub-AST that needs to be translated to a sub-CFG which then is
 ///                 integrated into the CFG (via the `untarg_edges`)
 /// # Result
 /// Loose ends (untargeted edges) of the translation of `p`
-fn ast_to_cfg_extend(cfg: &mut Cfg<RawAnnot>, untarg_edges: Vec<UntargEdge>, p: &Prog) -> Vec<UntargEdge> {
+fn ast_to_cfg_extend<'a>(cfg: &mut Cfg<RawAnnot>, ctx: &mut Context<'a>, untarg_edges: Vec<UntargEdge>, p: &'a Prog) -> Vec<UntargEdge> {
     let Prog::Prog(ps) = p;
     // Iterate through the sub-ASTs and successively translate and connect them to the CFG.
     let mut untarg_edges_cur = untarg_edges;
-    ps.into_iter().for_each(|p| {
-        untarg_edges_cur = ast_atom_to_cfg_extend(cfg, untarg_edges_cur.to_vec(), &p);
+    ps.iter().for_each(|p| {
+        untarg_edges_cur = ast_atom_to_cfg_extend(cfg, ctx, untarg_edges_cur.to_vec(), p);
     });
     untarg_edges_cur
 }
 
 /// # Arguments
 /// `cfg`:          Mutable CFG
+/// `ctx`:          Translation context
 /// `untarg_edges`: Loose ends of the CFG, i.e. untargeted edges
 /// `p`:            Sub-AST that needs to be translated to a sub-CFG which then is
 ///                 integrated into the CFG (via the `untarg_edges`)
 /// # Result
 /// Loose ends (untargeted edges) of the translation of `p`
-fn ast_atom_to_cfg_extend(cfg: &mut Cfg<RawAnnot>, untarg_edges: Vec<UntargEdge>, p: &ProgAtom) -> Vec<UntargEdge> {
+fn ast_atom_to_cfg_extend<'a>(cfg: &mut Cfg<RawAnnot>, ctx: &mut Context<'a>, untarg_edges: Vec<UntargEdge>, p: &'a ProgAtom) -> Vec<UntargEdge> {
     match p {
+        ProgAtom::Skip if ctx.omit_skips => {
+            // Without a skip node, the untargeted edges are passed through unchanged.
+            untarg_edges
+        }
         ProgAtom::Skip => {
             // Create new skip node.
             let skip = cfg.graph.add_node(AnnotNode::new(Node::Skip, RawAnnot {}));
@@ -141,14 +208,21 @@ fn ast_atom_to_cfg_extend(cfg: &mut Cfg<RawAnnot>, untarg_edges: Vec<UntargEdge>
             // The skip node has exactly one untargeted edge.
             vec![UntargEdge(assign, Edge::Plain)]
         }
+        ProgAtom::Havoc(v) => {
+            // Create new havoc node and connect the untargeted edges to it.
+            let havoc = cfg.graph.add_node(AnnotNode::new(Node::Havoc(v.clone()), RawAnnot {}));
+            untarg_edges.into_iter().for_each(|UntargEdge(t, e)| {cfg.graph.add_edge(t, havoc, e);});
+            // The havoc node has exactly one untargeted edge.
+            vec![UntargEdge(havoc, Edge::Plain)]
+        }
         ProgAtom::Cond(bexp, p_tt, p_ff) => {
             // Create new branch node
             let branch = cfg.graph.add_node(AnnotNode::new(Node::Branch(bexp.clone()), RawAnnot {}));
             // Connect the untargeted edges to the assign node.
             untarg_edges.into_iter().for_each(|UntargEdge(t, e)| {cfg.graph.add_edge(t, branch, e); ()});
             // Recursively translate the sub-ASTs and connect the resulting sub-CFGs via a `True` and a `False` edge.
-            let node_true_untarg_edges = ast_to_cfg_extend(cfg, vec!(UntargEdge(branch, Edge::True)), p_tt);
-            let node_false_untarg_edges = ast_to_cfg_extend(cfg, vec!(UntargEdge(branch, Edge::False)), p_ff);
+            let node_true_untarg_edges = ast_to_cfg_extend(cfg, ctx, vec!(UntargEdge(branch, Edge::True)), p_tt);
+            let node_false_untarg_edges = ast_to_cfg_extend(cfg, ctx, vec!(UntargEdge(branch, Edge::False)), p_ff);
             // Combine the untargeted edges of both sub-CFGs.
             let mut res: Vec<UntargEdge> = vec![];
             res.extend(node_true_untarg_edges);
@@ -161,12 +235,481 @@ fn ast_atom_to_cfg_extend(cfg: &mut Cfg<RawAnnot>, untarg_edges: Vec<UntargEdge>
             // Connect the untargeted edges to the branch node.
             untarg_edges.into_iter().for_each(|UntargEdge(t, e)| {cfg.graph.add_edge(t, branch, e); ()});
             // Recursively translate the sub-AST and connect the resulting sub-CFG via a `True` edge.
-            let p_untarg_edges = ast_to_cfg_extend(cfg, vec!(UntargEdge(branch, Edge::True)), p);
-            // Connect the loose ends of the sub-CFG back to the branch node (this closes the cycle).
-            p_untarg_edges.into_iter().for_each(|UntargEdge(t, e)| {cfg.graph.add_edge(t, branch, e); ()});
-            // The resulting CFG has exactly one untargeted edge, labelled by `False`.
-            vec![UntargEdge(branch, Edge::False)]
+            ctx.loops.push(LoopJumps::default());
+            let p_untarg_edges = ast_to_cfg_extend(cfg, ctx, vec!(UntargEdge(branch, Edge::True)), p);
+            let jumps = ctx.loops.pop().unwrap_or_default();
+            // Connect the loose ends of the sub-CFG and of the `continue`s back to the branch node (this closes the cycle).
+            p_untarg_edges.into_iter().chain(jumps.continues).for_each(|UntargEdge(t, e)| {cfg.graph.add_edge(t, branch, e); ()});
+            // The resulting CFG has one untargeted edge labelled by `False`, and those of the `break`s.
+            iter::once(UntargEdge(branch, Edge::False)).chain(jumps.breaks).collect()
+        }
+        ProgAtom::Repeat(p, bexp) => {
+            // Translate the sub-AST first. Its translation starts by connecting the untargeted edges to its first node, so the first new edge from one of their sources points to the entry of the loop body.
+            let first_edge = cfg.graph.edge_count();
+            let sources: Vec<NodeIdx> = untarg_edges.iter().map(|UntargEdge(t, _)| *t).collect();
+            ctx.loops.push(LoopJumps::default());
+            let p_untarg_edges = ast_to_cfg_extend(cfg, ctx, untarg_edges, p);
+            let jumps = ctx.loops.pop().unwrap_or_default();
+            // Create new branch node and connect the loose ends of the sub-CFG and of the `continue`s to it.
+            let branch = cfg.graph.add_node(AnnotNode::new(Node::Branch(bexp.clone()), RawAnnot {}));
+            p_untarg_edges.into_iter().chain(jumps.continues).for_each(|UntargEdge(t, e)| {cfg.graph.add_edge(t, branch, e);});
+            // Connect the branch node back to the loop body via a `False` edge (this closes the cycle). If the body has no node at all (a `skip` without node, see `CfgOptions`) or starts with a `continue`, the branch node loops to itself.
+            let entry = cfg.graph.raw_edges()[first_edge..].iter().find(|e| sources.contains(&e.source())).map_or(branch, |e| e.target());
+            cfg.graph.add_edge(branch, entry, Edge::False);
+            // The resulting CFG has one untargeted edge labelled by `True`, and those of the `break`s.
+            iter::once(UntargEdge(branch, Edge::True)).chain(jumps.breaks).collect()
+        }
+        ProgAtom::Break | ProgAtom::Continue => {
+            // Leaving `let` blocks restores the outer values of their variables (innermost first), see below.
+            let lets = ctx.loops.last().map_or(vec![], |jumps| jumps.lets.clone());
+            let untarg_edges = lets.into_iter().rev().fold(untarg_edges, |untarg_edges, (v, saved)| {
+                let untarg_edges = add_assign(cfg, untarg_edges, v, AExp::Var(saved.clone()));
+                add_assign(cfg, untarg_edges, saved, AExp::Num(0))
+            });
+            // The untargeted edges are connected by the innermost loop. Outside of loops (which is rejected by the parser), a jump is ignored.
+            match ctx.loops.last_mut() {
+                Some(jumps) if *p == ProgAtom::Break => {jumps.breaks.extend(untarg_edges)}
+                Some(jumps) => {jumps.continues.extend(untarg_edges)}
+                None => {return untarg_edges}
+            }
+            // The code following a jump is unreachable, i.e. there are no untargeted edges.
+            vec![]
+        }
+        ProgAtom::Let(v, aexp, p) => {
+            // Save the outer value of the scoped variable in an auxiliary variable, which cannot occur in the program (the parser does not accept primes in variable names).
+            let saved = VarName::new(&format!("{}'{}", v, ctx.lets));
+            ctx.lets += 1;
+            let untarg_edges = add_assign(cfg, untarg_edges, saved.clone(), AExp::Var(v.clone()));
+            let untarg_edges = add_assign(cfg, untarg_edges, v.clone(), (**aexp).clone());
+            // Recursively translate the sub-AST, then restore the outer value. Within a loop, a `break` or `continue` in the sub-AST restores the outer value, too.
+            if let Some(jumps) = ctx.loops.last_mut() { jumps.lets.push((v.clone(), saved.clone())) }
+            let p_untarg_edges = ast_to_cfg_extend(cfg, ctx, untarg_edges, p);
+            if let Some(jumps) = ctx.loops.last_mut() { jumps.lets.pop(); }
+            let untarg_edges = add_assign(cfg, p_untarg_edges, v.clone(), AExp::Var(saved.clone()));
+            // Reset the auxiliary variable, so that afterwards it holds `0` like any variable that is never assigned.
+            add_assign(cfg, untarg_edges, saved, AExp::Num(0))
+        }
+        ProgAtom::Proc(_, _) => {
+            // A procedure definition has no node by itself; its body is translated at its first call.
+            untarg_edges
+        }
+        ProgAtom::Call(name) => {
+            // Create new call node.
+            let call = cfg.graph.add_node(AnnotNode::new(Node::Call(name.clone()), RawAnnot {}));
+            // Connect the untargeted edges to the call node.
+            untarg_edges.into_iter().for_each(|UntargEdge(t, e)| {cfg.graph.add_edge(t, call, e);});
+            // Translate the procedure body, unless this has already been done (for a previous call).
+            let (entry, exit) = match ctx.proc_nodes.get(name) {
+                Some(nodes) => {*nodes}
+                None => {
+                    let entry = cfg.graph.add_node(AnnotNode::new(Node::Entry(name.clone()), RawAnnot {}));
+                    let exit = cfg.graph.add_node(AnnotNode::new(Node::Exit(name.clone()), RawAnnot {}));
+                    // Register the nodes before translating the body, so that recursive calls find them.
+                    ctx.proc_nodes.insert(name.clone(), (entry, exit));
+                    // The body is not part of the loops around the call, even if it is translated within one of them.
+                    let loops = std::mem::take(&mut ctx.loops);
+                    // An undefined procedure (which is rejected by the parser) has an empty body, i.e. behaves like `skip`.
+                    let body_untarg_edges = match ctx.procs.get(name) {
+                        Some(body) => {ast_to_cfg_extend(cfg, ctx, vec!(UntargEdge(entry, Edge::Plain)), body)}
+                        None => {vec!(UntargEdge(entry, Edge::Plain))}
+                    };
+                    ctx.loops = loops;
+                    body_untarg_edges.into_iter().for_each(|UntargEdge(t, e)| {cfg.graph.add_edge(t, exit, e);});
+                    (entry, exit)
+                }
+            };
+            cfg.graph.add_edge(call, entry, Edge::Call);
+            // The resulting CFG has exactly one untargeted edge, returning from the procedure.
+            vec![UntargEdge(exit, Edge::Return)]
+        }
+    }
+}
+
+/// Helper function for `ast_atom_to_cfg_extend`: Add an assign node `v := aexp`, connect the untargeted edges to it and return its (single) untargeted edge
+fn add_assign(cfg: &mut Cfg<RawAnnot>, untarg_edges: Vec<UntargEdge>, v: VarName, aexp: AExp) -> Vec<UntargEdge> {
+    let assign = cfg.graph.add_node(AnnotNode::new(Node::Assign(v, Box::new(aexp)), RawAnnot {}));
+    untarg_edges.into_iter().for_each(|UntargEdge(t, e)| {cfg.graph.add_edge(t, assign, e);});
+    vec![UntargEdge(assign, Edge::Plain)]
+}
+
+/////////////////////
+// Transformations //
+/////////////////////
+
+/// Normalize all branch nodes such that no guard is a top-level negation: A guard `!b` is replaced by `b` and the `True`/`False` labels of the outgoing edges are swapped.
+pub fn normalize_branches<A>(cfg: &mut Cfg<A>) {
+    let nodes: Vec<NodeIdx> = cfg.graph.node_indices().collect();
+    nodes.into_iter().for_each(|n| {
+        // Strip all top-level negations, counting them
+        let mut negations = 0;
+        while let Node::Branch(bexp) = &cfg.graph[n].node {
+            match bexp.as_ref() {
+                BExp::Neg(b) => {
+                    cfg.graph[n].node = Node::Branch(b.clone());
+                    negations += 1;
+                }
+                _ => { break }
+            }
+        }
+        // An odd number of negations swaps the edges
+        if negations % 2 == 1 {
+            let edges: Vec<EdgeIndex> = cfg.graph.edges_directed(n, Outgoing).map(|e| e.id()).collect();
+            edges.into_iter().for_each(|e| {
+                cfg.graph[e] = match cfg.graph[e] {
+                    Edge::True  => {Edge::False}
+                    Edge::False => {Edge::True}
+                    ref e       => {e.clone()}
+                }
+            });
+        }
+    });
+}
+
+/// Remove all `Skip` nodes. Every edge pointing to a `Skip` node is redirected (keeping its label) to the node following the `Skip` node.
+/// A `Skip` node without successor (i.e. at the end of the program) is removed only if all edges pointing to it are plain; otherwise, it is turned into a `Terminal` node, as `ast_to_cfg` ties together the remaining tt/ff/return-edges in a `Terminal` node.
+pub fn remove_skips<A>(cfg: &mut Cfg<A>) {
+    let is_removable = |cfg: &Cfg<A>, n: NodeIdx| {
+        cfg.graph[n].node == Node::Skip &&
+        (cfg.graph.neighbors_directed(n, Outgoing).next().is_some() || cfg.graph.edges_directed(n, Incoming).all(|e| *e.weight() == Edge::Plain))
+    };
+    cfg.graph.node_indices()
+        .filter(|n| cfg.graph[*n].node == Node::Skip && !is_removable(cfg, *n))
+        .collect::<Vec<_>>().into_iter()
+        .for_each(|n| cfg.graph[n].node = Node::Terminal);
+
+    while let Some(n) = cfg.graph.node_indices().find(|n| is_removable(cfg, *n)) {
+        // A `Skip` node has at most one successor, which is never the node itself
+        if let Some(succ) = cfg.graph.neighbors_directed(n, Outgoing).next() {
+            let incoming: Vec<(NodeIdx, Edge)> = cfg.graph.edges_directed(n, Incoming).map(|e| (e.source(), e.weight().clone())).collect();
+            incoming.into_iter().for_each(|(pred, e)| { cfg.graph.add_edge(pred, succ, e); });
+        }
+        // Rust Expl.: `remove_node` moves the last node into the index of the removed node, which might concern the init node
+        let last = NodeIndex::new(cfg.graph.node_count() - 1);
+        cfg.graph.remove_node(n);
+        if cfg.init == last {
+            cfg.init = n;
+        }
+    }
+}
+
+/////////////
+// Diffing //
+/////////////
+
+/// Difference between two CFGs, as computed by `diff`. Nodes are compared by weight (i.e. node and annotation), edges by the weights of their source and target nodes and by their label. Both are compared as multisets, i.e. duplicates are taken into account.
+#[derive(Debug,PartialEq,Eq)]
+pub struct CfgDiff<A> {
+    pub added_nodes: Vec<AnnotNode<A>>,
+    pub removed_nodes: Vec<AnnotNode<A>>,
+    pub added_edges: Vec<(AnnotNode<A>, AnnotNode<A>, Edge)>,
+    pub removed_edges: Vec<(AnnotNode<A>, AnnotNode<A>, Edge)>,
+}
+
+/// Compute the nodes and edges that are added and removed when going from `before` to `after`. The order of the reported nodes and edges follows the order of their indices in the respective CFG.
+pub fn diff<A: Clone + Eq + Hash>(before: &Cfg<A>, after: &Cfg<A>) -> CfgDiff<A> {
+    let nodes = |cfg: &Cfg<A>| -> Vec<AnnotNode<A>> { cfg.graph.raw_nodes().iter().map(|n| n.weight.clone()).collect() };
+    let edges = |cfg: &Cfg<A>| -> Vec<(AnnotNode<A>, AnnotNode<A>, Edge)> {
+        cfg.graph.raw_edges().iter().map(|e| (cfg.graph[e.source()].clone(), cfg.graph[e.target()].clone(), e.weight.clone())).collect()
+    };
+    let (nodes_before, nodes_after) = (nodes(before), nodes(after));
+    let (edges_before, edges_after) = (edges(before), edges(after));
+    CfgDiff {
+        added_nodes: multiset_diff(&nodes_after, &nodes_before),
+        removed_nodes: multiset_diff(&nodes_before, &nodes_after),
+        added_edges: multiset_diff(&edges_after, &edges_before),
+        removed_edges: multiset_diff(&edges_before, &edges_after),
+    }
+}
+
+/// Return the elements of `a` that are not in `b`, taking multiplicities into account
+fn multiset_diff<T: Clone + Eq + Hash>(a: &[T], b: &[T]) -> Vec<T> {
+    let mut counts: HashMap<&T, usize> = HashMap::new();
+    b.iter().for_each(|x| *counts.entry(x).or_insert(0) += 1);
+    a.iter().filter(|x| {
+        match counts.get_mut(x) {
+            Some(c) if *c > 0 => { *c -= 1; false }
+            _                 => { true }
+        }
+    }).cloned().collect()
+}
+
+impl<A> CfgDiff<A> {
+    /// Return whether the diff contains no changes at all
+    pub fn is_empty(&self) -> bool {
+        self.added_nodes.is_empty() && self.removed_nodes.is_empty() &&
+        self.added_edges.is_empty() && self.removed_edges.is_empty()
+    }
+}
+
+impl<A: Display> Display for CfgDiff<A> {
+    /// Display one change per line, prefixed by `+` (added) or `-` (removed)
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let one_line = |x: &dyn Display| x.to_string().replace('\n', " ");
+        let edge = |(src, tgt, e): &(AnnotNode<A>, AnnotNode<A>, Edge)| format!("{} -> {} [{}]", one_line(src), one_line(tgt), e);
+        self.removed_nodes.iter().try_for_each(|n| writeln!(f, "- {}", one_line(n)))?;
+        self.added_nodes.iter().try_for_each(|n| writeln!(f, "+ {}", one_line(n)))?;
+        self.removed_edges.iter().try_for_each(|e| writeln!(f, "- {}", edge(e)))?;
+        self.added_edges.iter().try_for_each(|e| writeln!(f, "+ {}", edge(e)))
+    }
+}
+
+///////////////////
+// Portable JSON //
+///////////////////
+
+/// Wire format of `to_portable_json`
+#[derive(Serialize,Deserialize)]
+struct PortableCfg<A> {
+    init: usize,
+    nodes: Vec<PortableNode<A>>,
+    edges: Vec<PortableEdge>
+}
+
+/// A node of the portable JSON format. Depending on the `kind`, some of the optional fields are given.
+#[derive(Serialize,Deserialize)]
+struct PortableNode<A> {
+    id: usize,
+    kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    var: Option<VarName>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expr: Option<AExp>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    guard: Option<BExp>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<VarName>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    args: Option<Vec<(usize, VarName)>>,
+    text: String,
+    annot: A
+}
+
+/// An edge of the portable JSON format
+#[derive(Serialize,Deserialize)]
+struct PortableEdge {
+    from: usize,
+    to: usize,
+    label: String
+}
+
+/// Serialize a CFG into a JSON format that does not depend on petgraph's internal representation (unlike the serde output of `Cfg`), e.g.:
+/// ```text
+/// { "init": 0,
+///   "nodes": [ { "id": 0, "kind": "init", "text": "init", "annot": {} },
+///              { "id": 1, "kind": "assign", "var": "y", "expr": { "Num": 1 }, "text": "y := 1", "annot": {} } ],
+///   "edges": [ { "from": 0, "to": 1, "label": "plain" } ] }
+/// ```
+/// - Nodes are listed by their index `id`. The `kind` is one of `init`, `terminal`, `skip`, `assign` (with `var` and `expr`), `havoc` (with `var`), `branch` (with `guard`), `call`, `entry` and `exit` (with the procedure `name`) and `phi` (with `var` and the `args` as pairs of a predecessor id and a variable). `text` is the human-readable `Display` output of the node.
+/// - Edges are listed in the order of their indices, their `label` is one of `plain`, `true`, `false`, `call` and `return`.
+pub fn to_portable_json<A: Serialize>(cfg: &Cfg<A>) -> String {
+    let nodes = cfg.graph.node_indices().map(|n| {
+        let node = &cfg.graph[n].node;
+        let (var, expr, guard, name) = match node {
+            Node::Init | Node::Terminal | Node::Skip => {(None, None, None, None)}
+            Node::Assign(x, a) => {(Some(x.clone()), Some((**a).clone()), None, None)}
+            Node::Havoc(x) | Node::Phi(x, _) => {(Some(x.clone()), None, None, None)}
+            Node::Branch(b)    => {(None, None, Some((**b).clone()), None)}
+            Node::Call(p) | Node::Entry(p) | Node::Exit(p) => {(None, None, None, Some(p.clone()))}
+        };
+        let args = match node {
+            Node::Phi(_, args) => {Some(args.iter().map(|(p, y)| (p.index(), y.clone())).collect())}
+            _ => {None}
+        };
+        PortableNode { id: n.index(), kind: node.kind().to_string(), var, expr, guard, name, args, text: node.to_string(), annot: &cfg.graph[n].annot }
+    }).collect();
+    let edges = cfg.graph.raw_edges().iter().map(|e| {
+        PortableEdge { from: e.source().index(), to: e.target().index(), label: e.weight.label().to_string() }
+    }).collect();
+    // Rust Expl.: Serializing into a `String` only fails if an annotation cannot be represented in JSON (e.g. a map with non-string keys), which is not the case for the annotations used in this crate.
+    serde_json::to_string_pretty(&PortableCfg { init: cfg.init.index(), nodes, edges }).unwrap()
+}
+
+/// Deserialize a CFG from the format produced by `to_portable_json`. The `text` fields are ignored. The node ids must be `0`, `1`, ... (in any order), every node must have the fields required by its kind, and the init node must satisfy the invariants checked by `Cfg::from_graph_checked`.
+pub fn from_portable_json<A: DeserializeOwned>(s: &str) -> Result<Cfg<A>, CfgError> {
+    let portable: PortableCfg<A> = serde_json::from_str(s).map_err(|e| format!("The portable JSON is malformed: {}", e))?;
+    let mut nodes: Vec<PortableNode<A>> = portable.nodes;
+    nodes.sort_by_key(|n| n.id);
+    let mut graph = Graph::new();
+    for (i, n) in nodes.into_iter().enumerate() {
+        if n.id != i {
+            return Err(format!("The node ids are not consecutive: {} is missing.", i));
+        }
+        let missing = |field: &str| format!("The {} node {} has no {}.", n.kind, n.id, field);
+        let node = match n.kind.as_str() {
+            "init"     => {Node::Init}
+            "terminal" => {Node::Terminal}
+            "skip"     => {Node::Skip}
+            "assign"   => {Node::Assign(n.var.clone().ok_or_else(|| missing("var"))?, Box::new(n.expr.clone().ok_or_else(|| missing("expr"))?))}
+            "havoc"    => {Node::Havoc(n.var.clone().ok_or_else(|| missing("var"))?)}
+            "branch"   => {Node::Branch(Box::new(n.guard.clone().ok_or_else(|| missing("guard"))?))}
+            "call"     => {Node::Call(n.name.clone().ok_or_else(|| missing("name"))?)}
+            "entry"    => {Node::Entry(n.name.clone().ok_or_else(|| missing("name"))?)}
+            "exit"     => {Node::Exit(n.name.clone().ok_or_else(|| missing("name"))?)}
+            "phi"      => {
+                let args = n.args.clone().ok_or_else(|| missing("args"))?;
+                Node::Phi(n.var.clone().ok_or_else(|| missing("var"))?, args.into_iter().map(|(p, y)| (NodeIndex::new(p), y)).collect())
+            }
+            kind       => {return Err(format!("The node {} has the unknown kind {}.", n.id, kind))}
+        };
+        graph.add_node(AnnotNode::new(node, n.annot));
+    }
+    for e in portable.edges {
+        let edge = match e.label.as_str() {
+            "plain" => {Edge::Plain}
+            "true" => {Edge::True}
+            "false" => {Edge::False}
+            "call" => {Edge::Call}
+            "return" => {Edge::Return}
+            label => {return Err(format!("The edge {} -> {} has the unknown label {}.", e.from, e.to, label))}
+        };
+        if e.from >= graph.node_count() || e.to >= graph.node_count() {
+            return Err(format!("The edge {} -> {} refers to a node that does not exist.", e.from, e.to));
+        }
+        graph.add_edge(NodeIndex::new(e.from), NodeIndex::new(e.to), edge);
+    }
+    Cfg::from_graph_checked(graph, NodeIndex::new(portable.init))
+}
+
+/////////////
+// Mermaid //
+/////////////
+
+/// Render a CFG as a Mermaid flowchart, e.g. for embedding it in Markdown:
+/// ```text
+/// flowchart TD
+///     n0(["init"])
+///     n1{"x #60;= 0"}
+///     n2["z := 1"]
+///     n0 --> n1
+///     n1 -->|tt| n2
+/// ```
+/// - Nodes are named `n<index>` and labelled by their `Display` output (including the annotation). The shape depends on the kind: `init` and `terminal` nodes are stadiums, `branch` nodes are rhombi, `call`, `entry` and `exit` nodes are subroutines, and all other nodes are rectangles.
+/// - Edges are given in the order of their indices. `True` and `False` edges are labelled by `tt` and `ff`, `Call` and `Return` edges are dotted and labelled by `call` and `return`.
+pub fn to_mermaid<A: Display>(cfg: &Cfg<A>) -> String {
+    let mut out = "flowchart TD\n".to_string();
+    cfg.graph.node_indices().for_each(|n| {
+        let label = format!("\"{}\"", escape_mermaid(&cfg.graph[n].to_string()));
+        let shape = match cfg.graph[n].node {
+            Node::Init | Node::Terminal                      => {format!("([{}])", label)}
+            Node::Branch(_)                                  => {format!("{{{}}}", label)}
+            Node::Call(_) | Node::Entry(_) | Node::Exit(_)   => {format!("[[{}]]", label)}
+            Node::Skip | Node::Assign(_, _) | Node::Havoc(_) | Node::Phi(_, _) => {format!("[{}]", label)}
+        };
+        out.push_str(&format!("    n{}{}\n", n.index(), shape));
+    });
+    cfg.graph.raw_edges().iter().for_each(|e| {
+        let arrow = match e.weight {
+            Edge::Plain                => {"-->".to_string()}
+            Edge::True | Edge::False   => {format!("-->|{}|", e.weight)}
+            Edge::Call | Edge::Return  => {format!("-.->|{}|", e.weight)}
+        };
+        out.push_str(&format!("    n{} {} n{}\n", e.source().index(), arrow, e.target().index()));
+    });
+    out
+}
+
+/// Helper function for `to_mermaid`: Escape a label, which is given in quotes. Characters that Mermaid (or the HTML it generates) would interpret are replaced by entity codes, new-lines become line breaks.
+fn escape_mermaid(s: &str) -> String {
+    let mut out = String::new();
+    s.chars().for_each(|c| match c {
+        '"' | '#' | '&' | '<' | '>' => {out.push_str(&format!("#{};", c as u32))}
+        '\n'                        => {out.push_str("<br>")}
+        _                           => {out.push(c)}
+    });
+    out
+}
+
+////////////////
+// Statistics //
+////////////////
+
+/// Statistics on the shape of a CFG, see `Cfg::stats`
+/// - `nodes`:          Number of nodes per kind (see `Node::kind`), kinds without nodes are omitted
+/// - `edges`:          Number of edges per label (see `Edge::label`), labels without edges are omitted
+/// - `loops`:          Number of loops, i.e. of strongly connected components with a cycle (see `Cfg::loops`)
+/// - `max_loop_depth`: Maximal nesting depth of loops, see `Cfg::max_loop_depth`
+/// - `reducible`:      Whether the CFG is reducible, see `Cfg::is_reducible`
+#[derive(PartialEq,Eq,Debug,Clone)]
+pub struct CfgStats {
+    pub nodes: BTreeMap<&'static str, usize>,
+    pub edges: BTreeMap<&'static str, usize>,
+    pub loops: usize,
+    pub max_loop_depth: usize,
+    pub reducible: bool
+}
+
+impl<A: Clone> Cfg<A> {
+    /// Compute statistics on the shape of the CFG, see `CfgStats`
+    pub fn stats(&self) -> CfgStats {
+        let mut nodes = BTreeMap::new();
+        self.graph.raw_nodes().iter().for_each(|n| *nodes.entry(n.weight.node.kind()).or_insert(0) += 1);
+        let mut edges = BTreeMap::new();
+        self.graph.raw_edges().iter().for_each(|e| *edges.entry(e.weight.label()).or_insert(0) += 1);
+        CfgStats { nodes, edges, loops: self.loops().len(), max_loop_depth: self.max_loop_depth(), reducible: self.is_reducible() }
+    }
+
+    /// Return the maximal nesting depth of loops, i.e. `0` for an acyclic CFG and `1` if no loop contains another one. The loops nested in a loop are found by cutting the edges back to the loop's entry (the first node targeted from outside of the loop, see `subgraph`), e.g. to the branch node of a `while` loop. For a CFG built from a program without procedures, this is the `max_loop_depth` of `Prog::stats`.
+    pub fn max_loop_depth(&self) -> usize {
+        self.loops().into_iter().map(|l| {
+            let (mut graph, entry) = self.subgraph(&l.into_iter().collect()).into_graph();
+            graph.retain_edges(|g, e| g.edge_endpoints(e).is_none_or(|(_, target)| target != entry));
+            1 + Cfg::new(graph, entry).max_loop_depth()
+        }).max().unwrap_or(0)
+    }
+}
+
+impl<A> Cfg<A> {
+    /// Return whether the CFG is reducible, i.e. whether it becomes acyclic by removing its back edges, which are the edges whose target dominates their source (every path from the init node to the source passes the target). In a reducible CFG, every loop has a single entry; CFGs built from programs are reducible unless procedures are called in a way that creates loops with several entries. Nodes that are not reachable from the init node are not taken into account.
+    pub fn is_reducible(&self) -> bool {
+        if self.graph.node_weight(self.init).is_none() {
+            return true;
         }
+        let doms = simple_fast(&self.graph, self.init);
+        let dominates = |d: NodeIdx, n: NodeIdx| doms.dominators(n).is_some_and(|mut ds| ds.any(|m| m == d));
+        let forward = self.graph.filter_map(
+            |n, _| doms.dominators(n).map(|_| ()),
+            |e, _| match self.graph.edge_endpoints(e) {
+                Some((source, target)) if !dominates(target, source) => {Some(())}
+                _ => {None}
+            });
+        !is_cyclic_directed(&forward)
+    }
+
+    /// Return the dominance frontier of every node that is reachable from the init node: The dominance frontier of `n` consists of the nodes `m` such that `n` dominates a predecessor of `m`, but does not strictly dominate `m` itself. These are the join points where the values computed in `n` meet other values, e.g. the node following a conditional for the nodes of its branches.
+    pub fn dominance_frontiers(&self) -> HashMap<NodeIdx, BTreeSet<NodeIdx>> {
+        if self.graph.node_weight(self.init).is_none() {
+            return HashMap::new();
+        }
+        let doms = simple_fast(&self.graph, self.init);
+        let reachable = |n: NodeIdx| doms.dominators(n).is_some();
+        let mut frontiers: HashMap<NodeIdx, BTreeSet<NodeIdx>> = self.graph.node_indices().filter(|n| reachable(*n)).map(|n| (n, BTreeSet::new())).collect();
+        for m in self.graph.node_indices().filter(|m| reachable(*m)) {
+            let preds: BTreeSet<NodeIdx> = self.graph.neighbors_directed(m, Incoming).filter(|p| reachable(*p)).collect();
+            if preds.len() < 2 {
+                continue;
+            }
+            // Walk up the dominator tree from every predecessor to the immediate dominator of `m`
+            for p in preds {
+                let mut runner = Some(p);
+                while let Some(r) = runner.filter(|r| Some(*r) != doms.immediate_dominator(m)) {
+                    frontiers.get_mut(&r).unwrap().insert(m);
+                    runner = doms.immediate_dominator(r);
+                }
+            }
+        }
+        frontiers
+    }
+}
+
+/// Pretty-printer, with one line per statistic, e.g. `nodes: 3 (assign: 1, init: 1, terminal: 1)`
+impl Display for CfgStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let counts = |m: &BTreeMap<&'static str, usize>| itertools::join(m.iter().map(|(k, n)| format!("{}: {}", k, n)), ", ");
+        writeln!(f, "nodes: {} ({})", self.nodes.values().sum::<usize>(), counts(&self.nodes))?;
+        writeln!(f, "edges: {} ({})", self.edges.values().sum::<usize>(), counts(&self.edges))?;
+        writeln!(f, "loops: {}", self.loops)?;
+        writeln!(f, "max loop depth: {}", self.max_loop_depth)?;
+        write!(f, "reducible: {}", if self.reducible { "yes" } else { "no" })
     }
 }
 
@@ -181,17 +724,105 @@ impl<A> Cfg<A> {
         Self { graph, init }
     }
 
+    /// Constructor that checks the invariants of the init node: It must be part of the graph, be the only `Init` node and have no incoming edges. Use this to reconstruct a CFG from a graph that was modified by other petgraph algorithms (see `into_graph`).
+    pub fn from_graph_checked(graph: Graph<AnnotNode<A>, Edge, Directed>, init: NodeIdx) -> Result<Self, CfgError> {
+        match graph.node_weight(init) {
+            None => {return Err(format!("The init node {} is not part of the graph.", init.index()))}
+            Some(n) if n.node != Node::Init => {return Err(format!("The init node {} is labelled {} instead of init.", init.index(), n.node))}
+            Some(_) => {}
+        }
+        if let Some(n) = graph.node_indices().find(|n| *n != init && graph[*n].node == Node::Init) {
+            return Err(format!("The node {} is a second init node.", n.index()));
+        }
+        if let Some(pred) = graph.neighbors_directed(init, Incoming).next() {
+            return Err(format!("The init node {} has an incoming edge from node {}.", init.index(), pred.index()));
+        }
+        Ok(Self { graph, init })
+    }
+
+    /// The underlying graph, e.g. for running petgraph algorithms on it. Nodes and edges are indexed as in the CFG, and `init` is a node of this graph.
+    pub fn as_graph(&self) -> &Graph<AnnotNode<A>, Edge, Directed> {
+        &self.graph
+    }
+
+    /// Take the CFG apart into the underlying graph and the init node, e.g. for modifying the graph with petgraph. See `from_graph_checked` for putting it back together.
+    pub fn into_graph(self) -> (Graph<AnnotNode<A>, Edge, Directed>, NodeIdx) {
+        (self.graph, self.init)
+    }
+
+    /// Compare the structure of two CFGs like `PartialEq`, but ignoring the annotations: Only the nodes (without annotations), the edges between them and the init node are compared, so the CFGs may even have different annotation types. E.g. a CFG is structurally equal to its MFP solution. Nodes and edges are compared as multisets, so e.g. a CFG with two `skip` nodes is not structurally equal to one with a single `skip` node.
+    pub fn structurally_eq<B>(&self, other: &Cfg<B>) -> bool {
+        let a_nodes = multiset(self.graph.raw_nodes().iter().map(|n| &n.weight.node));
+        let b_nodes = multiset(other.graph.raw_nodes().iter().map(|n| &n.weight.node));
+
+        let a_edges = multiset(self.graph.raw_edges().iter().map(|e| (&self.graph[e.source()].node, &self.graph[e.target()].node, &e.weight)));
+        let b_edges = multiset(other.graph.raw_edges().iter().map(|e| (&other.graph[e.source()].node, &other.graph[e.target()].node, &e.weight)));
+
+        a_nodes == b_nodes &&
+        a_edges == b_edges &&
+        self.graph.node_weight(self.init).map(|n| &n.node) == other.graph.node_weight(other.init).map(|n| &n.node)
+    }
+
     /// Map a Cfg<A> to a Cfg<B> by mapping the node annotations according to `f`
     pub fn map<B, F>(self: &Cfg<A>, f: F) -> Cfg<B>
     where F: Fn(&A) -> B {
-        let node_map = |_: NodeIndex, node: &AnnotNode<A>| {
-            return AnnotNode::new(node.node.clone(), f(&node.annot));
+        self.map_with(|_, _, annot| f(annot))
+    }
+
+    /// Like `map`, but `f` additionally gets the index and the (unannotated) node, so that the new annotation may depend on them
+    pub fn map_with<B, F>(self: &Cfg<A>, f: F) -> Cfg<B>
+    where F: Fn(NodeIdx, &Node, &A) -> B {
+        let node_map = |n: NodeIndex, node: &AnnotNode<A>| {
+            return AnnotNode::new(node.node.clone(), f(n, &node.node, &node.annot));
         };
         let edge_map = |_: EdgeIndex, x: &Edge| x.clone();
         let mapped_graph = self.graph.map(node_map, edge_map);
         return Cfg::new(mapped_graph, self.init)
     }
 
+    /// Map a Cfg<A> to a Cfg<B> by mapping the node annotations according to the fallible `f`. The first error returned by `f` is forwarded; otherwise, the resulting CFG has the same nodes (and node indices), edges and init node.
+    pub fn try_map<B, E, F>(self: &Cfg<A>, f: F) -> Result<Cfg<B>, E>
+    where F: Fn(&A) -> Result<B, E> {
+        let mut mapped_graph = Graph::with_capacity(self.graph.node_count(), self.graph.edge_count());
+        // Rust Expl.: Nodes are added in the order of their indices, so that every node keeps its index in the new graph.
+        for node in self.graph.raw_nodes() {
+            mapped_graph.add_node(AnnotNode::new(node.weight.node.clone(), f(&node.weight.annot)?));
+        }
+        for edge in self.graph.raw_edges() {
+            mapped_graph.add_edge(edge.source(), edge.target(), edge.weight.clone());
+        }
+        Ok(Cfg::new(mapped_graph, self.init))
+    }
+
+    /// Place two CFGs side by side in one graph. The nodes of `a` keep their indices, whereas the indices of the nodes of `b` are shifted by the number of nodes of `a`. The resulting CFG has `a`'s init node as its init node; the (shifted) init node of `b` is returned alongside.
+    pub fn disjoint_union(a: Cfg<A>, b: Cfg<A>) -> (Cfg<A>, NodeIdx) {
+        let Cfg { mut graph, init } = a;
+        let offset = graph.node_count();
+        let shift = |n: NodeIdx| NodeIndex::new(n.index() + offset);
+        let (nodes, edges) = b.graph.into_nodes_edges();
+        // Rust Expl.: Nodes are added in the order of their indices, so that each node's index is shifted by exactly `offset`.
+        nodes.into_iter().for_each(|n| { graph.add_node(n.weight); });
+        edges.into_iter().for_each(|e| { graph.add_edge(shift(e.source()), shift(e.target()), e.weight); });
+        (Cfg::new(graph, init), shift(b.init))
+    }
+
+    /// Extract the sub-CFG consisting of the given nodes and the edges among them. The nodes keep their relative order, but are renumbered consecutively.
+    /// The init node of the sub-CFG is the original init node if it is contained in `nodes`, otherwise the first node (by index) that is targeted by an edge from outside of `nodes`, otherwise the first node (by index) of `nodes`. If `nodes` is empty, the init node is `0` (which is not part of the empty graph).
+    pub fn subgraph(self: &Cfg<A>, nodes: &HashSet<NodeIdx>) -> Cfg<A>
+    where A: Clone {
+        let graph = self.graph.filter_map(
+            |n, node| if nodes.contains(&n) { Some(node.clone()) } else { None },
+            |_, e| Some(e.clone()));
+        // Rust Expl.: `filter_map` keeps the relative order of the nodes, so the new index of a node is the number of contained nodes with a smaller index.
+        let new_index = |n: NodeIdx| NodeIndex::new(nodes.iter().filter(|m| **m < n).count());
+        let mut sorted: Vec<NodeIdx> = nodes.iter().cloned().collect();
+        sorted.sort();
+        let is_entry = |n: &&NodeIdx| self.graph.neighbors_directed(**n, Incoming).any(|p| !nodes.contains(&p));
+        let init = if nodes.contains(&self.init) { Some(self.init) }
+                   else { sorted.iter().find(is_entry).or_else(|| sorted.first()).cloned() };
+        Cfg::new(graph, init.map(new_index).unwrap_or_else(|| NodeIndex::new(0)))
+    }
+
     /// Return the predecessor nodes of a given node. If there are no predecessors (only possible for the initial node), then return `None`.
     pub fn predecessors(self: &Cfg<A>, n: NodeIdx) -> Option<Vec1<NodeIdx>> {
         let predecs_vec = self.graph.neighbors_directed(n, Incoming).collect();
@@ -205,6 +836,141 @@ impl<A> Cfg<A> {
     pub fn successors(self: &Cfg<A>, n: NodeIdx) -> Vec<NodeIdx> {
         self.graph.neighbors_directed(n, Outgoing).collect()
     }
+
+    /// Return the exit nodes, i.e. all nodes without successors. For a CFG built with `always_terminal` set, this is exactly one `Terminal` node.
+    pub fn exits(self: &Cfg<A>) -> Vec<NodeIdx> {
+        self.graph.externals(Outgoing).collect()
+    }
+
+    /// Return the paths from the init node to an exit node (see `exits`) with at most `max_len` nodes each, in depth-first order (visiting the successors of a node in ascending order). As the length is bounded, every loop is followed a bounded number of times, e.g. for bounded model checking or for computing the meet over all paths. Parallel edges (like the `True` and `False` edge of a branch whose successor is the same node) do not lead to duplicate paths.
+    pub fn paths_up_to(&self, max_len: usize) -> impl Iterator<Item = Vec<NodeIdx>> + '_ {
+        // The prefixes of paths that are yet to be extended, the next one last
+        let mut stack = if max_len == 0 { vec![] } else { vec![vec![self.init]] };
+        iter::from_fn(move || {
+            while let Some(path) = stack.pop() {
+                let mut succs = self.successors(path[path.len() - 1]);
+                if succs.is_empty() {
+                    return Some(path);
+                }
+                if path.len() < max_len {
+                    succs.sort();
+                    succs.dedup();
+                    stack.extend(succs.into_iter().rev().map(|n| path.iter().cloned().chain(iter::once(n)).collect()));
+                }
+            }
+            None
+        })
+    }
+
+    /// Return whether a `Terminal` node is reachable from the init node (which is not the case without init node, e.g. for an empty subgraph)
+    pub fn terminal_reachable(self: &Cfg<A>) -> bool {
+        if self.graph.node_weight(self.init).is_none() {
+            return false;
+        }
+        let mut dfs = Dfs::new(&self.graph, self.init);
+        std::iter::from_fn(|| dfs.next(&self.graph)).any(|n| self.graph[n].node == Node::Terminal)
+    }
+
+    /// Return the loops of the CFG, i.e. its (maximal) strongly connected components that contain a cycle. Nested loops are part of the surrounding loop; recursive procedures also form loops. Each loop is given by its nodes in ascending order, and the loops are ordered by their first node.
+    pub fn loops(self: &Cfg<A>) -> Vec<Vec<NodeIdx>> {
+        let mut loops: Vec<Vec<NodeIdx>> = tarjan_scc(&self.graph).into_iter()
+            .filter(|c| c.len() > 1 || self.graph.contains_edge(c[0], c[0]))
+            .map(|mut c| { c.sort(); c })
+            .collect();
+        loops.sort();
+        loops
+    }
+
+    /// Return the loop heads of the CFG, i.e. the targets of the back edges found by a depth-first search starting at the init node (continued at the remaining nodes in ascending order). Every cycle contains at least one loop head; for a `while` loop, this is its branch node.
+    pub fn loop_heads(self: &Cfg<A>) -> BTreeSet<NodeIdx> {
+        let mut heads = BTreeSet::new();
+        let starts = std::iter::once(self.init).filter(|n| self.graph.node_weight(*n).is_some()).chain(self.graph.node_indices());
+        depth_first_search(&self.graph, starts, |event| {
+            if let DfsEvent::BackEdge(_, head) = event {
+                heads.insert(head);
+            }
+        });
+        heads
+    }
+
+    /// Return all nodes in a topological order, i.e. every node comes after all of its predecessors. If the CFG contains a cycle (e.g. due to a loop), `None` is returned.
+    pub fn topo_order(self: &Cfg<A>) -> Option<Vec<NodeIdx>> {
+        toposort(&self.graph, None).ok()
+    }
+
+    /// Return the maximal cost of a path starting at the init node, where the cost of a path is the sum of the `weight`s of its nodes. As this is only well-defined for acyclic CFGs, `None` is returned if the CFG contains a cycle (or has no init node, e.g. for an empty subgraph).
+    pub fn longest_path_cost<W>(self: &Cfg<A>, weight: W) -> Option<u64>
+    where W: Fn(&Node) -> u64 {
+        let order = self.topo_order()?;
+        // Cost of the most expensive path from the init node to the respective node (if reachable)
+        let mut cost: HashMap<NodeIdx, u64> = HashMap::new();
+        cost.insert(self.init, weight(&self.graph.node_weight(self.init)?.node));
+        // In topological order, all predecessors of a node are dealt with before the node itself
+        order.into_iter().filter(|n| *n != self.init).for_each(|n| {
+            let max_predec = self.graph.neighbors_directed(n, Incoming).filter_map(|p| cost.get(&p)).max().cloned();
+            if let Some(c) = max_predec {
+                cost.insert(n, c.saturating_add(weight(&self.graph[n].node)));
+            }
+        });
+        cost.values().max().cloned()
+    }
+}
+
+/// The probability (between `0` and `1`) of taking the `True` edge of a `Branch` node. Annotating a CFG with it (i.e. using a `Cfg<BranchProbability>`, where the annotations of all other nodes are ignored) allows for profile-guided experiments, see `Cfg::most_likely_path`.
+pub type BranchProbability = f64;
+
+impl Cfg<BranchProbability> {
+    /// Return the most likely path starting at the init node: At every `Branch` node, the more likely edge is followed (the `True` edge on a tie), at all other nodes the (first) outgoing edge. The path ends at a node without outgoing edges, or just before it would visit a node for the second time (e.g. when a loop is likely to be entered again). Without init node (e.g. for an empty subgraph), the path is empty.
+    pub fn most_likely_path(self: &Cfg<BranchProbability>) -> Vec<NodeIdx> {
+        let mut path = vec![];
+        if self.graph.node_weight(self.init).is_none() {
+            return path;
+        }
+        let mut n = self.init;
+        loop {
+            path.push(n);
+            let mut edges: Vec<_> = self.graph.edges_directed(n, Outgoing).collect();
+            // Rust Expl.: petgraph iterates over the outgoing edges in reverse order of their insertion
+            edges.reverse();
+            let likely = match &self.graph[n].node {
+                Node::Branch(_) => {
+                    let label = if self.graph[n].annot >= 0.5 { Edge::True } else { Edge::False };
+                    edges.iter().find(|e| *e.weight() == label)
+                }
+                _ => {edges.first()}
+            };
+            match likely.map(|e| e.target()) {
+                Some(next) if !path.contains(&next) => {n = next}
+                _ => {return path}
+            }
+        }
+    }
+}
+
+impl<A: Hash> Cfg<A> {
+    /// Compute a fingerprint of the CFG, e.g. as a key for caching analysis results. It only depends on the node weights (node and annotation), the edges given by their labels and the weights of their endpoints, and the weight of the init node; node and edge indices do not matter. Thus, CFGs that are equal (see `PartialEq`) have equal fingerprints, in particular CFGs that are built from the same program.
+    pub fn fingerprint(self: &Cfg<A>) -> u64 {
+        let hash = |x: &dyn Fn(&mut DefaultHasher)| { let mut h = DefaultHasher::new(); x(&mut h); h.finish() };
+        // As for equality, nodes and edges are treated as sets, which are brought into a canonical order by sorting
+        let canonical = |mut hashes: Vec<u64>| { hashes.sort_unstable(); hashes.dedup(); hashes };
+        let nodes = canonical(self.graph.raw_nodes().iter().map(|n| hash(&|h| n.weight.hash(h))).collect());
+        let edges = canonical(self.graph.raw_edges().iter().map(|e| hash(&|h| {
+            self.graph[e.source()].hash(h);
+            self.graph[e.target()].hash(h);
+            e.weight.hash(h);
+        })).collect());
+        hash(&|h| {
+            nodes.hash(h);
+            edges.hash(h);
+            // A CFG without init node (e.g. an empty subgraph) hashes `None`
+            self.graph.node_weight(self.init).hash(h);
+        })
+    }
+}
+
+/// Count how often every item occurs, i.e. turn the items into a multiset
+fn multiset<T: Hash + Eq, I: Iterator<Item = T>>(items: I) -> HashMap<T, usize> {
+    items.fold(HashMap::new(), |mut counts, item| { *counts.entry(item).or_insert(0) += 1; counts })
 }
 
 /// Two CFGs are equal if they have the same nodes and the same edges
@@ -218,7 +984,75 @@ impl<A: PartialEq + Eq + Hash> PartialEq for Cfg<A> {
 
         a_nodes == b_nodes &&
         a_edges == b_edges &&
-        self.graph[self.init] == other.graph[other.init]
+        self.graph.node_weight(self.init) == other.graph.node_weight(other.init)
+    }
+}
+
+impl<A: Display> Cfg<A> {
+    /// The label of the node `idx` for rendering, i.e. its `Display` output (including the annotation) prefixed by its index, e.g. `3: y := 1`. The index refers to the node in the underlying graph, which helps with debugging.
+    pub fn node_label(&self, idx: NodeIdx) -> String {
+        format!("{}: {}", idx.index(), self.graph[idx])
+    }
+}
+
+impl<A: Display> Display for Cfg<A> {
+    /// Display the CFG as an adjacency listing: Every node (in the order of its index) is given on a line of its own, followed by its indented annotation (if any) and its outgoing edges (in the order of their index). E.g.:
+    /// ```text
+    /// 0: init
+    ///     -> 1
+    /// 1: y := 1
+    /// ```
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.graph.node_indices().try_for_each(|n| {
+            writeln!(f, "{}: {}", n.index(), self.graph[n].node)?;
+            let annot = self.graph[n].annot.to_string();
+            annot.lines().try_for_each(|line| writeln!(f, "    {}", line))?;
+            let mut edges: Vec<_> = self.graph.edges_directed(n, Outgoing).collect();
+            edges.sort_by_key(|e| e.id());
+            edges.into_iter().try_for_each(|e| {
+                match e.weight() {
+                    Edge::Plain => {writeln!(f, "    -> {}", e.target().index())}
+                    label       => {writeln!(f, "    -{}-> {}", label, e.target().index())}
+                }
+            })
+        })
+    }
+}
+
+impl Node {
+    /// The kind of the node (i.e. its variant) in lower case, e.g. `assign`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Node::Init         => {"init"}
+            Node::Terminal     => {"terminal"}
+            Node::Skip         => {"skip"}
+            Node::Assign(_, _) => {"assign"}
+            Node::Havoc(_)     => {"havoc"}
+            Node::Branch(_)    => {"branch"}
+            Node::Call(_)      => {"call"}
+            Node::Entry(_)     => {"entry"}
+            Node::Exit(_)      => {"exit"}
+            Node::Phi(_, _)    => {"phi"}
+        }
+    }
+
+    /// Return the set of variables whose values are read by the node
+    pub fn read_vars(&self) -> HashSet<VarName> {
+        match self {
+            Node::Assign(_, a) => {a.vars()}
+            Node::Branch(b)    => {b.vars()}
+            Node::Phi(_, args) => {args.iter().map(|(_, y)| y.clone()).collect()}
+            _                  => {HashSet::new()}
+        }
+    }
+
+    /// Return `true` if the node saves or restores the outer value of a `let` variable, i.e. it copies between the variable and its auxiliary variable (see `ast_to_cfg_extend`). Such nodes do not occur in the program, so their reads are not reported by `undefined_reads` and `uninit_reads`.
+    pub fn is_let_copy(&self) -> bool {
+        let is_aux = |x: &VarName| x.as_str().contains('\'');
+        match self {
+            Node::Assign(v, a) => {matches!(&**a, AExp::Var(w) if is_aux(v) || is_aux(w))}
+            _ => {false}
+        }
     }
 }
 
@@ -230,7 +1064,12 @@ impl Display for Node {
             Node::Terminal        => {write!(f, "terminal")}
             Node::Skip            => {write!(f, "skip")}
             Node::Assign(v, aexp) => {write!(f, "{} := {}", v, aexp)}
+            Node::Havoc(v)        => {write!(f, "havoc {}", v)}
             Node::Branch(bexp)    => {write!(f, "{}", bexp)}
+            Node::Call(name)      => {write!(f, "call {}", name)}
+            Node::Entry(name)     => {write!(f, "entry {}", name)}
+            Node::Exit(name)      => {write!(f, "exit {}", name)}
+            Node::Phi(v, args)    => {write!(f, "{} := phi({})", v, itertools::join(args.iter().map(|(_, y)| y), ", "))}
         }
     }
 }
@@ -260,6 +1099,19 @@ impl Display for RawAnnot {
     fn fmt(&self, _: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { Ok(()) }
 }
 
+impl Edge {
+    /// The label of the edge (i.e. its variant) in lower case, e.g. `plain`. Unlike the `Display` output, this is never empty.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Edge::Plain  => {"plain"}
+            Edge::True   => {"true"}
+            Edge::False  => {"false"}
+            Edge::Call   => {"call"}
+            Edge::Return => {"return"}
+        }
+    }
+}
+
 impl Display for Edge {
     /// Display an edge
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -267,6 +1119,8 @@ impl Display for Edge {
             Edge::Plain => {write!(f, "")}
             Edge::True  => {write!(f, "tt")}
             Edge::False => {write!(f, "ff")}
+            Edge::Call  => {write!(f, "call")}
+            Edge::Return => {write!(f, "return")}
         }
     }
 }
\ No newline at end of file
@@ -1,17 +1,286 @@
-use std::{fmt::{Display, Debug}};
-use serde::{Serialize, Deserialize};
+use core::{fmt::{self, Display, Debug}, hash::{BuildHasherDefault, Hash, Hasher}, cmp::Ordering, ops::RangeInclusive};
+use alloc::{boxed::Box, format, string::{String, ToString}, sync::{Arc, Weak}};
+#[cfg(feature = "std")]
+use std::sync::{Mutex, MutexGuard, OnceLock};
+#[cfg(not(feature = "std"))]
+use core::{cell::UnsafeCell, ops::{Deref, DerefMut}, sync::atomic::{AtomicBool, Ordering::{Acquire, Relaxed, Release}}};
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
 
-/// A program variable `x` is just a reference to a string.
-#[derive(PartialEq,Clone,Eq,Hash,Debug,Serialize,Deserialize)]
-pub struct VarName(String);
+use crate::aexp::{ExpKey, ExpNode};
+use crate::bexp::{BExpKey, BExpNode};
+
+/// The integers the programs compute with. By default, these are 32 bits wide; the `i64` feature selects 64 bits.
+#[cfg(not(feature = "i64"))]
+pub type Int = i32;
+/// The integers the programs compute with. By default, these are 32 bits wide; the `i64` feature selects 64 bits.
+#[cfg(feature = "i64")]
+pub type Int = i64;
+
+/// The semantics of the modulo operator `n % m` given a non-zero divisor `m`: The Euclidean remainder, i.e. the unique `r` with `0 <= r < |m|` and `n = q*m + r` for some integer `q`. Unlike Rust's `%`, the result is never negative, e.g. `-7 % 3 = 2` (whereas Rust yields `-1`). For `m = 0`, the result is undefined and `None` is returned.
+pub fn checked_mod(n: Int, m: Int) -> Option<Int> {
+    if m == 0 { None } else { Some(n.wrapping_rem_euclid(m)) }
+}
+
+/// The semantics of the exponentiation `n ^ m` given a non-negative exponent `m`: Repeated multiplication, which wraps around on overflow (like `*`). In particular, `n ^ 0 = 1` for every `n`. For `m < 0`, the result is undefined and `None` is returned.
+pub fn checked_pow(n: Int, m: Int) -> Option<Int> {
+    if m < 0 {
+        return None;
+    }
+    // Square-and-multiply, as the exponent may be large
+    let (mut base, mut exp, mut acc): (Int, Int, Int) = (n, m, 1);
+    while exp > 0 {
+        if exp % 2 == 1 {
+            acc = acc.wrapping_mul(base);
+        }
+        base = base.wrapping_mul(base);
+        exp /= 2;
+    }
+    Some(acc)
+}
+
+/// The sets used by the expression and AST types, e.g. for the variables of an expression. These are hash sets, except without the `std` feature, where ordered sets take their place.
+#[cfg(feature = "std")]
+pub type Set<T> = std::collections::HashSet<T>;
+/// The sets used by the expression and AST types, e.g. for the variables of an expression. These are hash sets, except without the `std` feature, where ordered sets take their place.
+#[cfg(not(feature = "std"))]
+pub type Set<T> = alloc::collections::BTreeSet<T>;
+
+/// The maps used by the expression and AST types, see `Set`
+#[cfg(feature = "std")]
+pub type Map<K, V> = std::collections::HashMap<K, V>;
+/// The maps used by the expression and AST types, see `Set`
+#[cfg(not(feature = "std"))]
+pub type Map<K, V> = alloc::collections::BTreeMap<K, V>;
+
+/// A hasher that does not depend on a random seed (unlike the default hasher of `HashMap`), namely the 64-bit FNV-1a hash. Hash maps built with `StableState` therefore iterate in the same order in every process run, given the same sequence of insertions, which makes pretty-printing and serialization reproducible.
+#[derive(Clone,Copy,Debug)]
+pub struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self { StableHasher(0xcbf2_9ce4_8422_2325) }
+}
+
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        bytes.iter().for_each(|b| {
+            self.0 = (self.0 ^ u64::from(*b)).wrapping_mul(0x0100_0000_01b3);
+        });
+    }
+
+    fn finish(&self) -> u64 { self.0 }
+}
+
+/// The `BuildHasher` for hash maps and sets with a deterministic iteration order, see `StableHasher`
+pub type StableState = BuildHasherDefault<StableHasher>;
+
+/// A pseudo-random number generator for random testing, namely SplitMix64. Given the same seed, it yields the same numbers on every platform and in every run, so that random tests are reproducible. It is not suitable for cryptography.
+#[derive(Clone,Debug)]
+pub struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self { SplitMix64(seed) }
+
+    /// Return the next pseudo-random number
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Return the next pseudo-random number in the given (non-empty) range. The numbers are reduced modulo the size of the range, so they are not exactly uniformly distributed, which is negligible for ranges that are much smaller than `2^64`.
+    pub fn next_in(&mut self, range: &RangeInclusive<Int>) -> Int {
+        let size = (*range.end() as i128 - *range.start() as i128 + 1) as u128;
+        (*range.start() as i128 + (self.next_u64() as u128 % size) as i128) as Int
+    }
+}
+
+/// A program variable `x`. Variable names are interned: A `VarName` is the index of its name in a global table together with the (leaked) name itself, so cloning it does not allocate and equality is a comparison of indices. The table is never shrunk, i.e. every name that was ever created stays in memory.
+///
+/// Hashing, ordering, (de)serialization and pretty-printing go by the name, so they do not depend on the order in which names were interned (e.g. maps of variables are still ordered alphabetically, and hash values are the same in every run). As the name is stored in the `VarName`, none of them needs to lock the table.
+#[derive(Clone)]
+pub struct VarName(u32, &'static str);
+
+/// The global table of interned variable names (see `VarName`) and of interned arithmetic and boolean expressions (see `InternedAExp` and `InternedBExp`)
+#[derive(Default)]
+pub(crate) struct Interner {
+    indices: Map<&'static str, u32>,
+    pub(crate) exps: WeakTable<ExpKey, ExpNode>,
+    pub(crate) bexps: WeakTable<BExpKey, BExpNode>
+}
+
+/// A table of interned expressions, which only holds weak references to them, so that an expression is freed as soon as it is not used anymore. The entries of freed expressions are removed whenever the table has doubled in size since they were last removed, so the table stays proportional to the number of expressions in use.
+pub(crate) struct WeakTable<K, V> {
+    entries: Map<K, Weak<V>>,
+    purged_len: usize
+}
+
+impl<K: Ord + Hash + Eq, V> WeakTable<K, V> {
+    /// An empty table, for initializing the global `Interner` without `std`
+    #[cfg(not(feature = "std"))]
+    const fn new() -> Self {
+        WeakTable { entries: Map::new(), purged_len: 0 }
+    }
+
+    /// The value with the key `key`, if it is still in use
+    pub(crate) fn get(&self, key: &K) -> Option<Arc<V>> {
+        self.entries.get(key).and_then(Weak::upgrade)
+    }
+
+    pub(crate) fn insert(&mut self, key: K, value: &Arc<V>) {
+        if self.entries.len() >= 2 * self.purged_len.max(32) {
+            self.entries.retain(|_, value| value.strong_count() > 0);
+            self.purged_len = self.entries.len();
+        }
+        self.entries.insert(key, Arc::downgrade(value));
+    }
+}
+
+impl<K: Ord + Hash + Eq, V> Default for WeakTable<K, V> {
+    fn default() -> Self { WeakTable { entries: Map::default(), purged_len: 0 } }
+}
+
+/// Access the global `Interner`, which is shared by all threads
+#[cfg(feature = "std")]
+pub(crate) fn interner() -> MutexGuard<'static, Interner> {
+    static INTERNER: OnceLock<Mutex<Interner>> = OnceLock::new();
+    INTERNER.get_or_init(|| Mutex::new(Interner::default())).lock().unwrap()
+}
+
+/// Without `std`, there is no `Mutex`, so the global `Interner` is guarded by a spin lock instead
+#[cfg(not(feature = "std"))]
+struct InternerLock {
+    locked: AtomicBool,
+    interner: UnsafeCell<Interner>
+}
+
+// Rust Expl.: Sharing the lock between threads is sound, as the interner is only accessed through an `InternerGuard`, i.e. while holding the lock.
+#[cfg(not(feature = "std"))]
+unsafe impl Sync for InternerLock {}
+
+/// Exclusive access to the global `Interner`, which releases the lock when dropped
+#[cfg(not(feature = "std"))]
+pub(crate) struct InternerGuard(&'static InternerLock);
+
+#[cfg(not(feature = "std"))]
+impl Deref for InternerGuard {
+    type Target = Interner;
+    fn deref(&self) -> &Interner { unsafe { &*self.0.interner.get() } }
+}
+
+#[cfg(not(feature = "std"))]
+impl DerefMut for InternerGuard {
+    fn deref_mut(&mut self) -> &mut Interner { unsafe { &mut *self.0.interner.get() } }
+}
+
+#[cfg(not(feature = "std"))]
+impl Drop for InternerGuard {
+    fn drop(&mut self) { self.0.locked.store(false, Release) }
+}
+
+/// Access the global `Interner`, which is shared by all threads
+#[cfg(not(feature = "std"))]
+pub(crate) fn interner() -> InternerGuard {
+    static INTERNER: InternerLock = InternerLock {
+        locked: AtomicBool::new(false),
+        interner: UnsafeCell::new(Interner { indices: Map::new(), exps: WeakTable::new(), bexps: WeakTable::new() })
+    };
+    while INTERNER.locked.compare_exchange_weak(false, true, Acquire, Relaxed).is_err() {
+        core::hint::spin_loop();
+    }
+    InternerGuard(&INTERNER)
+}
 
 impl VarName {
-    pub fn new(s: &str) -> VarName { VarName(s.to_string()) }
+    pub fn new(s: &str) -> VarName {
+        let mut interner = interner();
+        if let Some((name, i)) = interner.indices.get_key_value(s) {
+            return VarName(*i, name);
+        }
+        // Rust Expl.: Leaking the string yields a reference that lives until the end of the program, which is fine as interned names are never removed.
+        let name: &'static str = Box::leak(s.to_string().into_boxed_str());
+        let i = interner.indices.len() as u32;
+        interner.indices.insert(name, i);
+        VarName(i, name)
+    }
+
+    /// The name of the variable
+    pub fn as_str(&self) -> &'static str {
+        self.1
+    }
+
+    /// The index of the name in the `Interner`
+    pub(crate) fn index(&self) -> u32 {
+        self.0
+    }
+}
+
+impl PartialEq for VarName {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for VarName {}
+
+impl Hash for VarName {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl PartialOrd for VarName {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for VarName {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if self == other { Ordering::Equal } else { self.as_str().cmp(other.as_str()) }
+    }
+}
+
+impl Serialize for VarName {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for VarName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(|s| VarName::new(&s))
+    }
+}
+
+/// Debug output as if `VarName` still wrapped its name, e.g. `VarName("x")`
+impl Debug for VarName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("VarName").field(&self.as_str()).finish()
+    }
+}
+
+/// A supply of fresh variable names `t0`, `t1`, ... (for a given prefix `t`). As the parser accepts only alphabetical variable names, these never clash with the variables of a program.
+#[derive(Debug,Clone)]
+pub struct FreshVars {
+    prefix: String,
+    next: usize
+}
+
+impl FreshVars {
+    pub fn new(prefix: &str) -> Self { Self { prefix: prefix.to_string(), next: 0 } }
+
+    /// Return a variable name that has not been returned before. This mutates the object.
+    pub fn fresh(&mut self) -> VarName {
+        let x = VarName::new(&format!("{}{}", self.prefix, self.next));
+        self.next += 1;
+        x
+    }
 }
 
 impl Display for VarName {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let VarName(s) = self;
-        write!(f, "{}", s)
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
     }
 }
\ No newline at end of file
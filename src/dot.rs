@@ -0,0 +1,134 @@
+use std::fmt::{Display, Write};
+
+use petgraph::visit::{Bfs, EdgeRef};
+use petgraph::EdgeDirection::Outgoing;
+
+use crate::cfg::{Cfg, Edge, NodeIdx};
+
+/// How edges are rendered in the DOT output
+/// - Labelled: Edges are labelled by their kind (`tt`, `ff`; `Plain` edges stay unlabelled)
+/// - Numbered: Additionally, each edge is prefixed by its position in a breadth-first traversal starting at the init node, which gives a hint on the sequencing order
+/// - Colored:  Edges are labelled by their kind and styled according to it (`Plain` edges are solid black, `True` edges green, `False` edges red, `Call`/`Return` edges dashed blue)
+#[derive(Debug,PartialEq,Clone,Copy,Eq)]
+pub enum EdgeStyle {
+    Labelled,
+    Numbered,
+    Colored
+}
+
+/// The Graphviz layout engine that is requested via the `layout` graph attribute
+#[derive(Debug,PartialEq,Clone,Copy,Eq)]
+pub enum DotEngine {
+    Dot,
+    Neato,
+    Fdp,
+    Circo
+}
+
+/// Options for rendering a CFG in the Graphviz DOT format
+/// - `edge_style`:    See `EdgeStyle`
+/// - `engine`:        The layout engine to request, if any (otherwise, the engine is chosen by the caller of Graphviz)
+/// - `cluster_loops`: Wrap the nodes of every loop (see `Cfg::loops`) in a `cluster_N` subgraph, so that loops are visually grouped
+/// - `node_indices`:  Prefix every node label by the index of the node (see `Cfg::node_label`)
+#[derive(Debug,PartialEq,Clone,Eq)]
+pub struct DotOptions {
+    pub edge_style: EdgeStyle,
+    pub engine: Option<DotEngine>,
+    pub cluster_loops: bool,
+    pub node_indices: bool
+}
+
+impl Default for DotOptions {
+    fn default() -> Self {
+        DotOptions { edge_style: EdgeStyle::Labelled, engine: None, cluster_loops: false, node_indices: false }
+    }
+}
+
+/// Render a CFG in the Graphviz DOT format. Nodes are labelled by their `Display` output (or by `Cfg::node_label` if `node_indices` is set).
+pub fn to_dot<A: Display>(cfg: &Cfg<A>, options: &DotOptions) -> String {
+    let g = &cfg.graph;
+    let mut out = String::new();
+    // Rust Expl.: Writing to a `String` cannot fail, so the results of `writeln!` can safely be unwrapped.
+    writeln!(out, "digraph {{").unwrap();
+    if let Some(engine) = options.engine {
+        writeln!(out, "    layout = \"{}\"", engine).unwrap();
+    }
+    let loops = if options.cluster_loops { cfg.loops() } else { vec![] };
+    let label = |n: NodeIdx| if options.node_indices { cfg.node_label(n) } else { g[n].to_string() };
+    let node_line = |n: NodeIdx| format!("{} [ label = \"{}\" ]", n.index(), escape(&label(n)));
+    g.node_indices().filter(|n| !loops.iter().any(|l| l.contains(n))).for_each(|n| {
+        writeln!(out, "    {}", node_line(n)).unwrap();
+    });
+    loops.iter().enumerate().for_each(|(i, l)| {
+        writeln!(out, "    subgraph cluster_{} {{", i).unwrap();
+        writeln!(out, "        label = \"loop {}\"", i).unwrap();
+        l.iter().for_each(|n| writeln!(out, "        {}", node_line(*n)).unwrap());
+        writeln!(out, "    }}").unwrap();
+    });
+
+    // Number the edges in breadth-first order, starting at the init node (edges that are not reachable come last)
+    let mut edge_numbers: Vec<Option<usize>> = vec![None; g.edge_count()];
+    let mut counter = 0;
+    // Without init node (e.g. for an empty subgraph), no edge is reachable
+    if g.node_weight(cfg.init).is_some() {
+        let mut bfs = Bfs::new(g, cfg.init);
+        while let Some(n) = bfs.next(g) {
+            // Rust Expl.: petgraph iterates over the outgoing edges in reverse order of their insertion
+            let mut edges: Vec<_> = g.edges_directed(n, Outgoing).map(|e| e.id()).collect();
+            edges.reverse();
+            edges.into_iter().for_each(|e| {
+                counter += 1;
+                edge_numbers[e.index()] = Some(counter);
+            });
+        }
+    }
+
+    g.edge_references().for_each(|e| {
+        let label = e.weight().to_string();
+        let attrs = match options.edge_style {
+            EdgeStyle::Labelled => { format!("label = \"{}\" ", label) }
+            EdgeStyle::Numbered => {
+                let number = edge_numbers[e.id().index()].unwrap_or_else(|| { counter += 1; counter });
+                if label.is_empty() { format!("label = \"{}\" ", number) }
+                else { format!("label = \"{}: {}\" ", number, label) }
+            }
+            EdgeStyle::Colored => { format!("label = \"{}\" {}", label, edge_color(e.weight())) }
+        };
+        writeln!(out, "    {} -> {} [ {}]", e.source().index(), e.target().index(), attrs).unwrap();
+    });
+    writeln!(out, "}}").unwrap();
+    out
+}
+
+/// The style attributes of an edge for `EdgeStyle::Colored`
+fn edge_color(e: &Edge) -> &'static str {
+    match e {
+        Edge::Plain                => { "color = \"black\" style = \"solid\" " }
+        Edge::True                 => { "color = \"green\" style = \"solid\" " }
+        Edge::False                => { "color = \"red\" style = \"solid\" " }
+        Edge::Call | Edge::Return  => { "color = \"blue\" style = \"dashed\" " }
+    }
+}
+
+/// The name of the engine, as expected by Graphviz
+impl Display for DotEngine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DotEngine::Dot   => { write!(f, "dot") }
+            DotEngine::Neato => { write!(f, "neato") }
+            DotEngine::Fdp   => { write!(f, "fdp") }
+            DotEngine::Circo => { write!(f, "circo") }
+        }
+    }
+}
+
+/// Escape a label for DOT: Quotes and backslashes are escaped, new-lines become left-justified line breaks
+fn escape(s: &str) -> String {
+    let mut out = String::new();
+    s.chars().for_each(|c| match c {
+        '"' | '\\' => { out.push('\\'); out.push(c) }
+        '\n'       => { out.push_str("\\l") }
+        _          => { out.push(c) }
+    });
+    out
+}
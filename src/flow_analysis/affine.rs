@@ -0,0 +1,265 @@
+use crate::{common::{VarName, Int, checked_mod, checked_pow}, cfg::{Node, Edge}};
+use crate::aexp::*;
+use serde::{Serialize, Deserialize};
+
+use super::common::{SemiLat, FlowSemantics};
+
+use std::{collections::BTreeMap, fmt::Display};
+use AffineLat::*;
+
+/// An affine form `c + a_1*x_1 + ... + a_n*x_n` with integer coefficients, where the `x_i` stand for the *initial* values of the variables (e.g. the input `x`), not for their current values. The arithmetic wraps around like the one of the programs, which keeps it exact: Wrapping addition and multiplication satisfy the usual laws (e.g. `x + x = 2*x`).
+/// - Internal representation:
+///     - `constant`: The constant `c`
+///     - `coeffs`:   Map from the variables `x_i` to their coefficients `a_i`, which are never `0` (so that equal forms have equal representations)
+#[derive(PartialEq,Clone,Eq,Hash,Debug,Serialize,Deserialize)]
+pub struct AffineForm {
+    constant: Int,
+    coeffs: BTreeMap<VarName, Int>
+}
+
+impl AffineForm {
+    /// The constant form `n`
+    pub fn constant(n: Int) -> Self {
+        AffineForm { constant: n, coeffs: BTreeMap::new() }
+    }
+
+    /// The form `x` (i.e. `0 + 1*x`) for the initial value of the variable `x`
+    pub fn var(x: &VarName) -> Self {
+        AffineForm { constant: 0, coeffs: vec![(x.clone(), 1)].into_iter().collect() }
+    }
+
+    /// Return the constant `n` if the form is constant, i.e. has no variables
+    pub fn as_constant(&self) -> Option<Int> {
+        if self.coeffs.is_empty() { Some(self.constant) } else { None }
+    }
+
+    /// The coefficient of the variable `x`, which is `0` if `x` does not occur
+    pub fn coeff(&self, x: &VarName) -> Int {
+        self.coeffs.get(x).cloned().unwrap_or(0)
+    }
+
+    /// The sum of two forms
+    pub fn add(&self, other: &Self) -> Self {
+        let mut coeffs = self.coeffs.clone();
+        other.coeffs.iter().for_each(|(x, a)| {
+            let sum = coeffs.get(x).cloned().unwrap_or(0).wrapping_add(*a);
+            if sum == 0 { coeffs.remove(x); } else { coeffs.insert(x.clone(), sum); }
+        });
+        AffineForm { constant: self.constant.wrapping_add(other.constant), coeffs }
+    }
+
+    /// The form multiplied by the constant `n`
+    pub fn scale(&self, n: Int) -> Self {
+        let coeffs = self.coeffs.iter()
+            .map(|(x, a)| (x.clone(), a.wrapping_mul(n)))
+            .filter(|(_, a)| *a != 0)
+            .collect();
+        AffineForm { constant: self.constant.wrapping_mul(n), coeffs }
+    }
+}
+
+/// # "Affine" Lattice
+/// - Used for tracking the value of a single variable as an `AffineForm` in the initial values of the variables, for the affine analysis
+/// - Values: `Top` (no affine form is known), `Affine(f)` and `Bot`
+/// - Partial order: Flat, i.e. different forms are incomparable (e.g. the forms `x` and `2*x` join to `Top`, even though they coincide for `x = 0`)
+#[derive(Debug,PartialEq,Clone,Eq,Hash,Serialize,Deserialize)]
+pub enum AffineLat {
+    Top,
+    Affine(AffineForm),
+    Bot
+}
+
+impl AffineLat {
+    /// The constant form `n`
+    pub fn constant(n: Int) -> Self {
+        Affine(AffineForm::constant(n))
+    }
+}
+
+impl SemiLat for AffineLat {
+    fn join_bin(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Bot, v) | (v, Bot)         => {v.clone()}
+            (v1, v2) if v1 == v2        => {v1.clone()}
+            _                           => {Top}
+        }
+    }
+}
+
+/// # "Multi-Affine" Lattice
+/// - Is the property space for the affine analysis, which infers linear relations between the variables and the input: E.g. after `y := x + x`, `y` is `2*x`, i.e. twice the input. Unlike constant propagation, this keeps track of values that depend on the input.
+/// - Can be seen as a vector of `AffineLat` values, one for each variable
+/// - Internal representation:
+///     - `map`:     Map from variable names to `AffineLat` values
+///     - `default`: The value assigned to any unspecified variable
+/// - Operate on `MultiAffineLat` only via its methods
+#[derive(PartialEq,Clone,Eq,Hash,Debug,Serialize,Deserialize)]
+pub struct MultiAffineLat {
+    map: BTreeMap<VarName, AffineLat>,
+    default: AffineLat
+}
+
+impl MultiAffineLat {
+    pub fn new(map: BTreeMap<VarName, AffineLat>, default: AffineLat) -> Self { Self { map, default } }
+
+    /// Update/insert a variable value. This mutates the object.
+    pub fn insert(&mut self, x: VarName, v: AffineLat) {
+        self.map.insert(x, v);
+    }
+
+    /// Lookup a variable value.
+    pub fn lookup(&self, x: &VarName) -> &AffineLat {
+        match self.map.get(x) {
+            Some(v) => {v}
+            None => {&self.default}
+        }
+    }
+
+    /// Return `true` if this is the bottom element, i.e. all variables are `Bot` (the corresponding program point is unreachable)
+    pub fn is_bot(&self) -> bool {
+        self.default == Bot && self.map.values().all(|v| *v == Bot)
+    }
+
+    /// Evaluate an arithmetic expression on a `MultiAffineLat` object. Sums and products with a constant are evaluated exactly, whereas the product of two non-constant forms (e.g. `x*x`) as well as modulo and exponentiation are only evaluated for constants and yield `Top` otherwise.
+    pub fn eval_aexp(&self, a: &AExp) -> AffineLat {
+        match a {
+            AExp::Num(n) => {AffineLat::constant(*n)}
+            AExp::Var(v) => {self.lookup(v).clone()}
+            AExp::Add(a1, a2) => {
+                match (self.eval_aexp(a1), self.eval_aexp(a2)) {
+                    (Affine(f1), Affine(f2)) => {Affine(f1.add(&f2))}
+                    (Bot, _) | (_, Bot)      => {Bot}
+                    _                        => {Top}
+                }
+            }
+            AExp::Mul(a1, a2) => {
+                match (self.eval_aexp(a1), self.eval_aexp(a2)) {
+                    (Affine(f1), Affine(f2)) => {
+                        match (f1.as_constant(), f2.as_constant()) {
+                            (Some(n), _) => {Affine(f2.scale(n))}
+                            (_, Some(n)) => {Affine(f1.scale(n))}
+                            _            => {Top}
+                        }
+                    }
+                    (Bot, _) | (_, Bot)      => {Bot}
+                    _                        => {Top}
+                }
+            }
+            // As for constant propagation, undefined results (e.g. of `n % 0`) may be any value
+            AExp::Mod(a1, a2) => {self.eval_constant_op(a1, a2, checked_mod)}
+            AExp::Pow(a1, a2) => {self.eval_constant_op(a1, a2, checked_pow)}
+        }
+    }
+
+    /// Helper function for `eval_aexp`: Evaluate a (partial) binary operation `f` on constant operands
+    fn eval_constant_op<F>(&self, a1: &AExp, a2: &AExp, f: F) -> AffineLat
+    where F: Fn(Int, Int) -> Option<Int> {
+        match (self.eval_aexp(a1), self.eval_aexp(a2)) {
+            (Bot, _) | (_, Bot) => {Bot}
+            (Affine(f1), Affine(f2)) => {
+                match (f1.as_constant(), f2.as_constant()) {
+                    (Some(n), Some(m)) => {f(n, m).map_or(Top, AffineLat::constant)}
+                    _                  => {Top}
+                }
+            }
+            _ => {Top}
+        }
+    }
+}
+
+impl SemiLat for MultiAffineLat {
+    fn join_bin(&self, other: &Self) -> Self {
+        // Variables with a specific value in either of both maps are joined pointwise, all others via the defaults
+        let map = self.map.keys().chain(other.map.keys())
+            .map(|x| (x.clone(), self.lookup(x).join_bin(other.lookup(x))))
+            .collect();
+        MultiAffineLat { map, default: self.default.join_bin(&other.default) }
+    }
+}
+
+impl FlowSemantics for MultiAffineLat {
+    fn eval_transfer_function(n: &Node, mem: &Self) -> Self {
+        match n {
+            // Only assignments (and `havoc`s and phi nodes) change the memory; an unreachable node stays unreachable
+            Node::Assign(v, a) if !mem.is_bot() => {
+                let evaluated_expr = mem.eval_aexp(a);
+                let mut mem = mem.clone();
+                mem.insert(v.clone(), evaluated_expr);
+                mem
+            }
+            Node::Havoc(v) if !mem.is_bot() => {
+                let mut mem = mem.clone();
+                mem.insert(v.clone(), Top);
+                mem
+            }
+            // The value of `v` is the one of any of the arguments
+            Node::Phi(v, args) if !mem.is_bot() => {
+                let value = args.iter().fold(Bot, |acc, (_, y)| acc.join_bin(mem.lookup(y)));
+                let mut mem = mem.clone();
+                mem.insert(v.clone(), value);
+                mem
+            }
+            _ => {mem.clone()}
+        }
+    }
+
+    /// The guards are not taken into account, i.e. the memory is passed on unchanged
+    fn eval_edge(_n: &Node, mem: &Self, _e: &Edge) -> Self {
+        mem.clone()
+    }
+
+    /// The input `x` is initially the form `x` (i.e. its own initial value), and all other variables are initially set to `0`.
+    fn init_start() -> Self {
+        let x = VarName::new("x");
+        let mut m = MultiAffineLat::new(BTreeMap::new(), AffineLat::constant(0));
+        m.insert(x.clone(), Affine(AffineForm::var(&x)));
+        m
+    }
+
+    /// The init element is the "bot" element of the semi-lattice, i.e. all variables are assigned to `Bot`.
+    fn init() -> Self {
+        MultiAffineLat::new(BTreeMap::new(), Bot)
+    }
+}
+
+/// Pretty-printer, e.g. `2*x - 1`: The variables in alphabetical order, followed by the constant (if it is not `0`)
+impl Display for AffineForm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let terms = self.coeffs.iter().map(|(x, a)| (*a, Some(x)));
+        let constant = Some((self.constant, None)).filter(|_| self.constant != 0 || self.coeffs.is_empty());
+        terms.chain(constant).enumerate().try_for_each(|(i, (a, x))| {
+            // Rust Expl.: `unsigned_abs` also works for the minimal `Int`, whose negation would overflow.
+            match (i, a < 0) {
+                (0, true)  => {write!(f, "-")?}
+                (0, false) => {}
+                (_, true)  => {write!(f, " - ")?}
+                (_, false) => {write!(f, " + ")?}
+            }
+            match x {
+                Some(x) if a.unsigned_abs() == 1 => {write!(f, "{}", x)}
+                Some(x) => {write!(f, "{}*{}", a.unsigned_abs(), x)}
+                None    => {write!(f, "{}", a.unsigned_abs())}
+            }
+        })
+    }
+}
+
+/// Pretty-printer
+impl Display for AffineLat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            Top       => {write!(f, "tt")}
+            Affine(a) => {write!(f, "{}", a)}
+            Bot       => {write!(f, "bb")}
+        }
+    }
+}
+
+/// Pretty-printer
+impl Display for MultiAffineLat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<")?;
+        self.map.iter().try_for_each(|(x, v)| {write!(f, "{} = {}, ", x, v)})?;
+        write!(f, "_ = {}>", self.default)
+    }
+}
@@ -1,27 +1,41 @@
-use crate::{common::VarName, cfg::Node};
+use crate::{common::{VarName, StableState}, cfg::Node};
 use crate::aexp::*;
 use serde::{Serialize, Deserialize};
 
 use super::common::{FlowSemantics, SemiLat};
 
-use std::{collections::HashSet, fmt::Display, hash::Hash};
+use std::{collections::{BTreeSet, HashSet}, fmt::Display, hash::{Hash, Hasher}};
 
 /// # "Expression Set" Lattice 
 /// - Used for tracking the available expressions for the available expressions analysis
 /// - Partial order: `s1 <= s2   <=>   s1.set.is_superset(s2)` (See how subset vs. superset is exchanged here - but this is just convention, so that it fits our definition of `join_bin` instead of `meet_bin`)
 /// - For now: Only arithmetic expressions, could be extended in the future to boolean expressions and more
-/// - Internal implementation as a hash set
+/// - Internal implementation as a hash set of interned expressions (see `InternedAExp`), so that hashing and comparing the expressions takes constant time even for large expressions. Iteration (and thus pretty-printing and serialization) resolves and sorts the expressions, so that it is deterministic.
 #[derive(Debug,PartialEq,Clone,Eq,Serialize,Deserialize)]
+#[serde(into = "SortedExpSet", from = "SortedExpSet")]
 pub struct ExpSetLat {
-    set: HashSet<AExp>
+    set: HashSet<InternedAExp, StableState>
 }
 
-impl Hash for ExpSetLat {
-    fn hash<H: std::hash::Hasher>(&self, _: &mut H) { }
+/// The serialized form of an `ExpSetLat`, with the expressions in ascending order
+#[derive(Serialize,Deserialize)]
+struct SortedExpSet {
+    set: BTreeSet<AExp>
 }
 
 impl ExpSetLat {
-    pub fn new(set: HashSet<AExp>) -> Self { Self { set } }
+    pub fn new(set: BTreeSet<AExp>) -> Self { Self { set: set.iter().map(AExp::interned).collect() } }
+
+    /// Return `true` if the expression `a` is available
+    pub fn contains(&self, a: &AExp) -> bool {
+        // An expression that has never been interned cannot be in the set
+        a.find_interned().is_some_and(|a| self.set.contains(&a))
+    }
+
+    /// Iterate over the expressions in ascending order. The expressions are resolved once per call, without locking the `Interner`.
+    pub fn iter(&self) -> impl Iterator<Item = AExp> {
+        self.set.iter().map(InternedAExp::to_aexp).collect::<BTreeSet<_>>().into_iter()
+    }
 
     /// Remove all expressions that contain a variable `x`
     pub fn clear_var(&mut self, x: &VarName) {
@@ -29,8 +43,24 @@ impl ExpSetLat {
     }
 
     /// Add a set of expressions
-    pub fn extend(&mut self, set: HashSet<AExp>) {
-        set.into_iter().for_each(|a| {self.set.insert(a);});
+    pub fn extend<I: IntoIterator<Item = AExp>>(&mut self, set: I) {
+        self.set.extend(set.into_iter().map(|a| a.interned()));
+    }
+}
+
+impl From<SortedExpSet> for ExpSetLat {
+    fn from(sorted: SortedExpSet) -> Self { ExpSetLat::new(sorted.set) }
+}
+
+impl From<ExpSetLat> for SortedExpSet {
+    fn from(set: ExpSetLat) -> Self { SortedExpSet { set: set.iter().collect() } }
+}
+
+/// The hash value does not depend on the order of the expressions in the set, as the hashes of the expressions are summed up
+impl Hash for ExpSetLat {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.set.len());
+        state.write_u64(self.set.iter().fold(0, |sum: u64, a| sum.wrapping_add(a.hash_value())));
     }
 }
 
@@ -38,8 +68,7 @@ impl ExpSetLat {
 /// Here thus, `join_bin` means "intersection"
 impl SemiLat for ExpSetLat {
     fn join_bin(self: &Self, other: &Self) -> Self {
-        let intersection: HashSet<AExp, _> = self.set.intersection(&other.set).cloned().collect();
-        ExpSetLat::new(intersection)
+        ExpSetLat { set: self.set.intersection(&other.set).cloned().collect() }
     }
 }
 
@@ -50,13 +79,18 @@ impl FlowSemantics for ExpSetLat {
             Node::Init => {out}
             Node::Terminal => {out}
             Node::Skip => {out}
+            Node::Call(_) | Node::Entry(_) | Node::Exit(_) => {out}
             Node::Assign(v, a) => {
                 out.clear_var(&v);
-                out.extend(a.sub_aexps());
+                out.set.extend(a.interned().sub_aexps());
+                out
+            }
+            Node::Havoc(v) | Node::Phi(v, _) => {
+                out.clear_var(v);
                 out
             }
             Node::Branch(bexp) => {
-                out.extend(bexp.sub_aexps());
+                out.set.extend(bexp.interned().sub_aexps());
                 out
             }
         }
@@ -69,15 +103,16 @@ impl FlowSemantics for ExpSetLat {
 
     /// The init element is the "top" element of the semi-lattice, i.e. the empty set
     fn init() -> Self {
-        ExpSetLat::new(HashSet::new())
+        ExpSetLat { set: HashSet::default() }
     }
 }
 
-/// Pretty-printer
+/// Pretty-printer: The expressions are listed in ascending order. Expressions that are equal modulo commutativity (see `AExp::normalized`), e.g. `x + y` and `y + x`, are listed only once, namely the smallest of them.
 impl Display for ExpSetLat {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{{")?;
-        let mut iter = self.set.iter();
+        let mut seen = HashSet::new();
+        let mut iter = self.iter().filter(|a| seen.insert(a.normalized()));
         match iter.next() {
             Some(a) => {
                 write!(f, "{:}", a)?;
@@ -1,6 +1,8 @@
 use vec1::Vec1;
 
-use crate::cfg::Node;
+use crate::cfg::{Node, Edge, NodeIdx};
+
+use std::fmt::Display;
 
 /// # Semi-Lattice
 /// - We use semi-lattices here (require only the `join` operation)
@@ -15,15 +17,91 @@ pub trait SemiLat: Sized + Eq + Clone {
         let tl = vs.to_vec();
         tl.iter().fold(hd.clone(), |acc, x| Self::join_bin(&acc, x))
     }
+
+    /// Widening: An upper bound of `self` (the previous value) and `other` (the new value) such that repeated widening stabilizes after finitely many steps, even if the values keep growing. This is needed for property spaces with (very) long ascending chains, see `mfp_widening`. By default, this is `join_bin`, which suffices for property spaces of small height.
+    fn widen(&self, other: &Self) -> Self {
+        self.join_bin(other)
+    }
+
+    /// Narrowing: A value between `other` (the new, smaller value) and `self` (the previous value) such that repeated narrowing stabilizes after finitely many steps. This recovers precision after widening, see `mfp_narrowing`. By default, `self` is kept, i.e. no precision is recovered.
+    fn narrow(&self, _other: &Self) -> Self {
+        self.clone()
+    }
+}
+
+/// The direction in which the values of an analysis flow through a CFG, see `FlowSemantics::DIRECTION`
+#[derive(Debug,PartialEq,Eq,Clone,Copy)]
+pub enum AnalysisDirection {
+    /// Along the edges, from the init node to the exits (see `mfp`)
+    Forward,
+    /// Against the edges, from the exits to the init node (see `mfp_backward`)
+    Backward
 }
 
 /// Flow semantics represent a way of computing "through a CFG". This trait is typically implemented by some lattice (called the "property space") which represents the values that "flow" through the CFG. For every node then, one can take an incoming value and produce an outgoing value. This is also known as "evaluating the node's transfer function".
 pub trait FlowSemantics {
+    /// The direction of the analysis. The MFP solvers reject property spaces of the other direction, as their transfer functions would be applied the wrong way round. By default, an analysis is a forward analysis.
+    const DIRECTION: AnalysisDirection = AnalysisDirection::Forward;
     /// Evaluate a node's transfer function
     fn eval_transfer_function(n: &Node, x: &Self) -> Self;
+    /// Evaluate the transfer function of the node `n`, whose index in the CFG is `idx`. The solvers call this function, so that property spaces which record where their values come from (see `Tracked`) can override it. By default, the index is ignored.
+    fn eval_transfer_function_at(_idx: NodeIdx, n: &Node, x: &Self) -> Self where Self: Sized {
+        Self::eval_transfer_function(n, x)
+    }
+    /// Compute the value that flows from the node `n` along an outgoing edge labelled `e`, given the node's outgoing value `x` (i.e. the result of its transfer function). This allows for refining the value per edge, e.g. by taking the guard of a branch node into account on its `True` and `False` edges. By default, the value is passed on unchanged.
+    fn eval_edge(_n: &Node, x: &Self, _e: &Edge) -> Self where Self: Clone {
+        x.clone()
+    }
     /// The element that is used as initialization of all annotations (except for the very first one, the init node - see `init_start()` for this)
     /// This element is either the "top" or the "bottom" element of the used semi-lattice.
     fn init() -> Self;
     /// The `init_start` value is an initial static value (an element of the property space) that is attached to the first node. See the concrete implementations of `FlowSemantics` for details.
     fn init_start() -> Self;
-}
\ No newline at end of file
+}
+
+/// # "Unrefined" Lattice
+/// - Is the property space `L` without its edge refinements (see `FlowSemantics::eval_edge`), i.e. the guards of branch nodes are not taken into account. This allows for measuring how much precision the refinements add, e.g. by comparing `Unrefined<MultiConstLat>` to `MultiConstLat` (see `diff_annots`).
+/// - Partial order, join, widening, narrowing and transfer functions: Those of `L`
+#[derive(Debug,PartialEq,Clone,Eq,Hash)]
+pub struct Unrefined<L>(pub L);
+
+impl<L: SemiLat> SemiLat for Unrefined<L> {
+    fn join_bin(&self, other: &Self) -> Self {
+        Unrefined(self.0.join_bin(&other.0))
+    }
+
+    fn widen(&self, other: &Self) -> Self {
+        Unrefined(self.0.widen(&other.0))
+    }
+
+    fn narrow(&self, other: &Self) -> Self {
+        Unrefined(self.0.narrow(&other.0))
+    }
+}
+
+impl<L: FlowSemantics> FlowSemantics for Unrefined<L> {
+    const DIRECTION: AnalysisDirection = L::DIRECTION;
+
+    fn eval_transfer_function(n: &Node, x: &Self) -> Self {
+        Unrefined(L::eval_transfer_function(n, &x.0))
+    }
+
+    fn eval_transfer_function_at(idx: NodeIdx, n: &Node, x: &Self) -> Self {
+        Unrefined(L::eval_transfer_function_at(idx, n, &x.0))
+    }
+
+    fn init() -> Self {
+        Unrefined(L::init())
+    }
+
+    fn init_start() -> Self {
+        Unrefined(L::init_start())
+    }
+}
+
+/// Pretty-printer, like the one of `L`
+impl<L: Display> Display for Unrefined<L> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
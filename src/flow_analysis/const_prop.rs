@@ -1,10 +1,15 @@
-use crate::{common::VarName, cfg::Node};
+use crate::{common::{VarName, Int, checked_mod, checked_pow, StableState}, cfg::{self, Cfg, Node, Edge, CfgOptions, NodeIdx, RawAnnot}};
 use crate::aexp::*;
+use crate::bexp::BExp;
+use crate::ast::{Prog, ProgAtom, Span};
+use crate::parser::{parse_with_spans, ParseError};
+use crate::interpreter::{MemConfig, eval_prog_observed};
 use serde::{Serialize, Deserialize};
 
 use super::common::{SemiLat, FlowSemantics};
+use super::mfp::{mfp, mfp_explained_from, MfpAnnot, MfpOptions};
 
-use std::{collections::HashMap, fmt::Display, hash::Hash};
+use std::{collections::{HashMap, BTreeSet}, fmt::Display, hash::Hash, ops::RangeInclusive};
 use ConstLat::*;
 
 /// # "Constant" Lattice 
@@ -13,7 +18,7 @@ use ConstLat::*;
 #[derive(Debug,PartialEq,Clone,Eq,Hash,Serialize,Deserialize)]
 pub enum ConstLat {
     Top,
-    Const(i32),
+    Const(Int),
     Bot
 }
 
@@ -27,16 +32,26 @@ impl SemiLat for ConstLat {
     }
 }
 
+/// # Absorbing-Element Policy
+/// - Decides whether absorbing elements are taken into account when evaluating arithmetic operations on `ConstLat` values
+/// - `Never`: Only `Const` operands are folded. Any `Top` operand makes the result `Top`, otherwise any `Bot` operand makes the result `Bot` (classical semantics)
+/// - `Zero`:  `0` absorbs multiplication, i.e. `0 * v = v * 0 = 0` for every `v` other than `Bot`, including `Top`. This is sound, as multiplying any concrete value by `0` yields `0`. In order to keep the operation monotone, any `Bot` operand makes the result `Bot` (`Bot * v = Bot` for every `v`, including `0` and `Top`), since otherwise `Bot * 0 = 0` would be greater than `Bot * Top = Bot` although `0 <= Top`
+#[derive(Debug,PartialEq,Clone,Copy,Eq,Hash,Serialize,Deserialize)]
+pub enum AbsorbPolicy {
+    Never,
+    Zero
+}
+
 /// # "Multi-Constant" Lattice
 /// - Is the property space for constant propagation analysis
 /// - Can be seen as a vector of `ConstLat` values, one for each variable
 /// - Internal representation:
-///     - `map`:     HashMap mapping variable names to `ConstLat` values
+///     - `map`:     HashMap mapping variable names to `ConstLat` values. It uses a `StableState` hasher, so that the iteration order (and thus pretty-printing and serialization) is the same in every run.
 ///     - `default`: The value assigned to any unspecified variable
 /// - Operate on `MultiConstLat` only via its methods
 #[derive(PartialEq,Clone,Eq,Debug,Serialize,Deserialize)]
 pub struct MultiConstLat {
-    map: HashMap<VarName, ConstLat>,
+    map: HashMap<VarName, ConstLat, StableState>,
     default: ConstLat
 }
 
@@ -48,7 +63,7 @@ impl Hash for MultiConstLat {
 }
 
 impl MultiConstLat {
-    pub fn new(map: HashMap<VarName, ConstLat>, default: ConstLat) -> Self { Self { map, default } }
+    pub fn new(map: HashMap<VarName, ConstLat>, default: ConstLat) -> Self { Self { map: map.into_iter().collect(), default } }
 
     /// Update/insert a variable value. This mutates the object.
     pub fn insert(&mut self, x: VarName, v: ConstLat) {
@@ -63,31 +78,123 @@ impl MultiConstLat {
         }
     }
 
-    /// Helper function: Evaluate an arithmetic expression on a `MultiConstLat` object.
+    /// Return the variables with a specific value, i.e. all other variables have the default value
+    pub fn specified_vars(&self) -> Vec<&VarName> {
+        self.map.keys().collect()
+    }
+
+    /// Return the value of all unspecified variables, see `specified_vars`
+    pub fn default_value(&self) -> &ConstLat {
+        &self.default
+    }
+
+    /// The `init_start()` value, where the given variables are additionally known to hold the given constants. This can be passed to `mfp_from` if some inputs are fixed.
+    pub fn seeded(bindings: &[(VarName, Int)]) -> Self {
+        let mut m = Self::init_start();
+        bindings.iter().for_each(|(x, n)| m.insert(x.clone(), ConstLat::Const(*n)));
+        m
+    }
+
+    /// Helper function: Evaluate an arithmetic expression on a `MultiConstLat` object. Multiplications by `0` yield `0`, even if the other operand is `Top` (see `AbsorbPolicy::Zero`): Whatever value the other operand has at runtime, the product is `0`, so this is sound. For additions, there is no such absorbing element, e.g. `Top + 0` stays `Top`.
     fn eval_aexp(self: &MultiConstLat, a: &AExp) -> ConstLat {
+        self.eval_aexp_with(a, AbsorbPolicy::Zero)
+    }
+
+    /// Return `true` if this is the bottom element, i.e. all variables are `Bot` (the corresponding program point is unreachable)
+    pub fn is_bot(&self) -> bool {
+        self.default == Bot && self.map.values().all(|v| *v == Bot)
+    }
+
+    /// Evaluate a boolean expression on a `MultiConstLat` object. Returns `None` if the truth value is not determined by the constant variables.
+    pub fn eval_bexp(&self, b: &BExp) -> Option<bool> {
+        match b {
+            BExp::LessEq(a1, a2) | BExp::Less(a1, a2) => {
+                match (self.eval_aexp(a1), self.eval_aexp(a2)) {
+                    (Const(v1), Const(v2)) => {Some(if let BExp::LessEq(_, _) = b { v1 <= v2 } else { v1 < v2 })}
+                    _ => {None}
+                }
+            }
+            BExp::Neg(b) => {self.eval_bexp(b).map(|v| !v)}
+            BExp::And(b1, b2) => {
+                match (self.eval_bexp(b1), self.eval_bexp(b2)) {
+                    (Some(false), _) | (_, Some(false)) => {Some(false)}
+                    (Some(true), Some(true)) => {Some(true)}
+                    _ => {None}
+                }
+            }
+            BExp::Or(b1, b2) => {
+                match (self.eval_bexp(b1), self.eval_bexp(b2)) {
+                    (Some(true), _) | (_, Some(true)) => {Some(true)}
+                    (Some(false), Some(false)) => {Some(false)}
+                    _ => {None}
+                }
+            }
+        }
+    }
+
+    /// Replace every variable of `a` that is `Const(n)` by `n`, see `propagate_constants`
+    pub fn substitute_consts(&self, a: &AExp) -> AExp {
+        a.vars().into_iter().fold(a.clone(), |a, x| match self.lookup(&x) {
+            Const(n) => {a.substitute(&x, &AExp::Num(*n))}
+            _ => {a}
+        })
+    }
+
+    /// Refine the memory by assuming that `b` evaluates to `truth`. If this contradicts the constants known for the variables (e.g. assuming `x <= 5` to be false for `x = Const(3)`), the program point is unreachable and the bottom element `init()` is returned. Otherwise, the memory is returned unchanged, as constants cannot express bounds like `x <= 5`.
+    pub fn assume(&self, b: &BExp, truth: bool) -> MultiConstLat {
+        match self.eval_bexp(b) {
+            Some(v) if v != truth => {MultiConstLat::init()}
+            _ => {self.clone()}
+        }
+    }
+
+    /// Evaluate an arithmetic expression on a `MultiConstLat` object, using the given policy for absorbing elements.
+    pub fn eval_aexp_with(self: &MultiConstLat, a: &AExp, policy: AbsorbPolicy) -> ConstLat {
         match a {
             AExp::Num(n) => {Const(*n)}
             AExp::Var(v) => {self.lookup(v).clone()}
             AExp::Add(a1, a2) => {
-                let plus = |x, y| x+y;
-                self.eval_aexp(a1).eval_bin_op(plus, self.eval_aexp(a2))
+                let plus = |x: Int, y| x.wrapping_add(y);
+                self.eval_aexp_with(a1, policy).eval_bin_op(plus, self.eval_aexp_with(a2, policy), None)
             }
             AExp::Mul(a1, a2) => {
-                let mul = |x, y| x*y;
-                self.eval_aexp(a1).eval_bin_op(mul, self.eval_aexp(a2))
+                let mul = |x: Int, y| x.wrapping_mul(y);
+                let absorbing = match policy {
+                    AbsorbPolicy::Never => {None}
+                    AbsorbPolicy::Zero  => {Some(0)}
+                };
+                self.eval_aexp_with(a1, policy).eval_bin_op(mul, self.eval_aexp_with(a2, policy), absorbing)
+            }
+            AExp::Mod(a1, a2) => {
+                match (self.eval_aexp_with(a1, policy), self.eval_aexp_with(a2, policy)) {
+                    // The result of `n % 0` is undefined, so any value is possible
+                    (Const(n), Const(m))    => {checked_mod(n, m).map_or(Top, Const)}
+                    (Top, _) | (_, Top)     => {Top}
+                    _                       => {Bot}
+                }
+            }
+            AExp::Pow(a1, a2) => {
+                match (self.eval_aexp_with(a1, policy), self.eval_aexp_with(a2, policy)) {
+                    // The result of `n ^ m` is undefined for negative `m`, so any value is possible
+                    (Const(n), Const(m))    => {checked_pow(n, m).map_or(Top, Const)}
+                    (Top, _) | (_, Top)     => {Top}
+                    _                       => {Bot}
+                }
             }
         }
     }
 }
 
 impl ConstLat {
-    /// Helper function: Evaluate a binary operation on a `ConstLat` object.
-    fn eval_bin_op<F>(self: ConstLat, f: F, other: ConstLat) -> ConstLat
-    where F: Fn(i32, i32) -> i32 {
-        match (self, other) {
-            (Const(v1), Const(v2)) => {Const(f(v1, v2))}
-            (Top, _) | (_, Top)    => {Top}
-            _                      => {Bot}
+    /// Helper function: Evaluate a binary operation on a `ConstLat` object. If an `absorbing` element is given, it is returned whenever one of the operands equals it and none is `Bot` (see `AbsorbPolicy`).
+    fn eval_bin_op<F>(self: ConstLat, f: F, other: ConstLat, absorbing: Option<Int>) -> ConstLat
+    where F: Fn(Int, Int) -> Int {
+        match (absorbing, self, other) {
+            (_, Const(v1), Const(v2))     => {Const(f(v1, v2))}
+            (Some(_), Bot, _) | (Some(_), _, Bot) => {Bot}
+            (Some(z), Const(v), _) | (Some(z), _, Const(v)) if v == z => {Const(z)}
+            (_, Top, _) | (_, _, Top)     => {Top}
+            _                             => {Bot}
         }
     }
 }
@@ -100,7 +207,7 @@ impl SemiLat for MultiConstLat {
 
         // 1)
         // Create a new map
-        let mut m = HashMap::new();
+        let mut m = HashMap::default();
         // Iterate through all variable assignments of `self`
         self.map.iter().for_each(|(x, v1)| {
             // Get corresponding variable assignment of `other` and join
@@ -112,7 +219,7 @@ impl SemiLat for MultiConstLat {
                 // If `x` has already an assignment, there is nothing to do.
                 Some(_) => { }
                 // Otherwise, join.
-                None => { m.insert(x.clone(), v2.join_bin(&other.lookup(x))); }
+                None => { m.insert(x.clone(), self.lookup(x).join_bin(v2)); }
             }
         });
 
@@ -126,11 +233,14 @@ impl SemiLat for MultiConstLat {
 impl FlowSemantics for MultiConstLat {
     fn eval_transfer_function(n: &Node, mem: &Self) -> Self {
         match n {
-            // `Init`, `Terminal`, `Skip` and `Branch` have no interesting semantics: They leave the memory untouched.
+            // `Init`, `Terminal`, `Skip`, `Branch`, `Call`, `Entry` and `Exit` have no interesting semantics: They leave the memory untouched.
             Node::Init => {mem.clone()}
             Node::Terminal => {mem.clone()}
             Node::Skip => {mem.clone()}
             Node::Branch(_) => {mem.clone()}
+            Node::Call(_) | Node::Entry(_) | Node::Exit(_) => {mem.clone()}
+            // An unreachable node stays unreachable
+            Node::Assign(_, _) if mem.is_bot() => {mem.clone()}
             // Update variable on `Assign`
             Node::Assign(v, a) => {
                 let evaluated_expr = mem.eval_aexp(a);
@@ -138,6 +248,30 @@ impl FlowSemantics for MultiConstLat {
                 mem.insert(v.clone(), evaluated_expr);
                 mem
             }
+            // The value of `v` is unknown afterwards
+            Node::Havoc(_) if mem.is_bot() => {mem.clone()}
+            Node::Havoc(v) => {
+                let mut mem = mem.clone();
+                mem.insert(v.clone(), ConstLat::Top);
+                mem
+            }
+            // The value of `v` is the one of any of the arguments
+            Node::Phi(_, _) if mem.is_bot() => {mem.clone()}
+            Node::Phi(v, args) => {
+                let value = args.iter().fold(ConstLat::Bot, |acc, (_, y)| acc.join_bin(mem.lookup(y)));
+                let mut mem = mem.clone();
+                mem.insert(v.clone(), value);
+                mem
+            }
+        }
+    }
+
+    /// Leaving a branch node, the `True` edge is taken only if the guard holds and the `False` edge only if it does not hold (see `assume`). If this is contradictory, the edge can never be taken and the bottom element flows along it.
+    fn eval_edge(n: &Node, mem: &Self, e: &Edge) -> Self {
+        match (n, e) {
+            (Node::Branch(b), Edge::True)  => {mem.assume(b, true)}
+            (Node::Branch(b), Edge::False) => {mem.assume(b, false)}
+            _ => {mem.clone()}
         }
     }
 
@@ -150,11 +284,175 @@ impl FlowSemantics for MultiConstLat {
 
     /// The init element is the "bot" element of the semi-lattice, i.e. all variables are assigned to `Bot`.
     fn init() -> Self {
-        MultiConstLat { map: HashMap::new(),
+        MultiConstLat { map: HashMap::default(),
                         default: ConstLat::Bot }
     }
 }
 
+/// Constant propagation as a program transformation: Every variable that is `Const(n)` where it is read is replaced by `n`, e.g. `y := 2; z := x + y` becomes `y := 2; z := x + 2`. The expressions are not simplified any further (see `AExp::folded`), and the assignments are kept.
+/// The constants are computed on the AST, in the same way as the constant propagation analysis does on the CFG (including infeasible branches, see `assume`), with the loop invariants as least fixed points. Procedures are not analyzed interprocedurally: A procedure body starts with all variables `Top`, and after a `call`, all variables are `Top`.
+pub fn propagate_constants(prog: &Prog) -> Prog {
+    propagate_prog(prog, &MultiConstLat::init_start(), &mut vec![]).0
+}
+
+/// Return the memory after every atom of the program (in the order of `Prog::atoms`) as computed by `propagate_constants`. For an atom in a loop body, this is the memory after the atom in any iteration.
+pub fn consts_after_atoms(prog: &Prog) -> Vec<MultiConstLat> {
+    let mut after = vec![];
+    propagate_prog(prog, &MultiConstLat::init_start(), &mut after);
+    after
+}
+
+/// Highlight the lines of the program `source` after which the variable `x` is a constant (see `consts_after_atoms`), by appending a comment `# const = n`. A line is highlighted according to the statement that ends last on it (i.e. the outermost one if several end at the same position).
+pub fn highlight_consts(source: &str, x: &VarName) -> Result<String, ParseError> {
+    let (prog, spans) = parse_with_spans(source)?;
+    let after = consts_after_atoms(&prog);
+    let lines = source.lines().enumerate().map(|(idx, line)| {
+        // Rust Expl.: `fold` keeps the first of equally large elements, unlike `max_by_key`, which keeps the last one
+        let last = spans.iter().zip(after.iter())
+            .filter(|(span, _)| span.end.line == idx + 1)
+            .fold(None, |last: Option<(&Span, &MultiConstLat)>, (span, mem)| match last {
+                Some((last_span, _)) if last_span.end >= span.end => {last}
+                _ => {Some((span, mem))}
+            });
+        match last.map(|(_, mem)| mem.lookup(x)) {
+            Some(Const(n)) => {format!("{} # const = {}", line, n)}
+            _ => {line.to_string()}
+        }
+    });
+    Ok(itertools::join(lines, "\n"))
+}
+
+/// Helper function for `propagate_constants`: Transform `p`, where `mem` holds before `p`, and return the result with the memory after `p`. The memory after every atom is appended to `after`, see `consts_after_atoms`.
+fn propagate_prog(p: &Prog, mem: &MultiConstLat, after: &mut Vec<MultiConstLat>) -> (Prog, MultiConstLat) {
+    let Prog::Prog(ps) = p;
+    let mut mem = mem.clone();
+    let ps = ps.iter().map(|p| {
+        // The atom precedes the atoms of its sub-programs
+        let idx = after.len();
+        after.push(MultiConstLat::init());
+        let (p, mem_after) = propagate_prog_atom(p, &mem, after);
+        after[idx] = mem_after.clone();
+        mem = mem_after;
+        p
+    }).collect();
+    (Prog::Prog(ps), mem)
+}
+
+/// Helper function for `propagate_constants`: Transform a single statement, see `propagate_prog`
+fn propagate_prog_atom(p: &ProgAtom, mem: &MultiConstLat, after: &mut Vec<MultiConstLat>) -> (ProgAtom, MultiConstLat) {
+    let top = || MultiConstLat::new(HashMap::new(), Top);
+    match p {
+        ProgAtom::Skip => {(ProgAtom::Skip, mem.clone())}
+        ProgAtom::Assign(x, a) => {
+            let after = MultiConstLat::eval_transfer_function(&Node::Assign(x.clone(), a.clone()), mem);
+            (ProgAtom::Assign(x.clone(), Box::new(mem.substitute_consts(a))), after)
+        }
+        ProgAtom::Cond(b, p_tt, p_ff) => {
+            let (p_tt, mem_tt) = propagate_prog(p_tt, &mem.assume(b, true), after);
+            let (p_ff, mem_ff) = propagate_prog(p_ff, &mem.assume(b, false), after);
+            let b = b.map_aexps(&|a| mem.substitute_consts(a));
+            (ProgAtom::Cond(Box::new(b), Box::new(p_tt), Box::new(p_ff)), mem_tt.join_bin(&mem_ff))
+        }
+        // A loop whose body contains a `break` or `continue` is approximated like a call: Nothing is known at the head and after the loop (unless it is unreachable)
+        ProgAtom::While(b, body) if body.has_jumps() => {
+            let head = if mem.is_bot() { mem.clone() } else { top() };
+            let (body, _) = propagate_prog(body, &head, after);
+            (ProgAtom::While(b.clone(), Box::new(body)), head)
+        }
+        ProgAtom::Repeat(body, b) if body.has_jumps() => {
+            let head = if mem.is_bot() { mem.clone() } else { top() };
+            let (body, _) = propagate_prog(body, &head, after);
+            (ProgAtom::Repeat(Box::new(body), b.clone()), head)
+        }
+        ProgAtom::While(b, body) => {
+            let mut head = mem.clone();
+            loop {
+                let next = mem.join_bin(&propagate_prog(body, &head.assume(b, true), &mut vec![]).1);
+                if next == head { break }
+                head = next;
+            }
+            let (body, _) = propagate_prog(body, &head.assume(b, true), after);
+            let guard = b.map_aexps(&|a| head.substitute_consts(a));
+            (ProgAtom::While(Box::new(guard), Box::new(body)), head.assume(b, false))
+        }
+        // The body is entered initially and whenever the guard does not hold at its end
+        ProgAtom::Repeat(body, b) => {
+            let mut head = mem.clone();
+            loop {
+                let next = mem.join_bin(&propagate_prog(body, &head, &mut vec![]).1.assume(b, false));
+                if next == head { break }
+                head = next;
+            }
+            let (body, end) = propagate_prog(body, &head, after);
+            let guard = b.map_aexps(&|a| end.substitute_consts(a));
+            (ProgAtom::Repeat(Box::new(body), Box::new(guard)), end.assume(b, true))
+        }
+        // The outer value of `x` is restored after the block
+        ProgAtom::Let(x, a, body) => {
+            let inner = MultiConstLat::eval_transfer_function(&Node::Assign(x.clone(), a.clone()), mem);
+            let (body, mut mem_after) = propagate_prog(body, &inner, after);
+            mem_after.insert(x.clone(), mem.lookup(x).clone());
+            (ProgAtom::Let(x.clone(), Box::new(mem.substitute_consts(a)), Box::new(body)), mem_after)
+        }
+        ProgAtom::Havoc(x) => {(p.clone(), MultiConstLat::eval_transfer_function(&Node::Havoc(x.clone()), mem))}
+        ProgAtom::Proc(name, body) => {(ProgAtom::Proc(name.clone(), Box::new(propagate_prog(body, &top(), after).0)), mem.clone())}
+        // An unreachable call stays unreachable
+        ProgAtom::Call(_) => {(p.clone(), if mem.is_bot() { mem.clone() } else { top() })}
+        // The code following a jump is unreachable
+        ProgAtom::Break | ProgAtom::Continue => {(p.clone(), MultiConstLat::init())}
+    }
+}
+
+/// A counterexample to the soundness of constant propagation: For the input `x = input`, the analysis claims that the variable `var` holds `claimed` at the end of the program, but the interpreter yields `actual`.
+#[derive(Debug,PartialEq,Clone,Eq)]
+pub struct Counterexample {
+    pub input: Int,
+    pub var: VarName,
+    pub claimed: Int,
+    pub actual: Int
+}
+
+/// Check constant propagation against the interpreter: For every input `x` in `inputs`, the program is interpreted, and every variable that the analysis claims to be `Const(n)` at the `Terminal` node must hold `n` at the end.
+/// Returns all counterexamples (in the order of the inputs, and by variable name for each input). An empty result means that the analysis is sound on the given inputs. Inputs for which the evaluation is aborted after `max_steps` steps (see `eval_bounded`) are skipped, as there is no final value to compare with.
+pub fn soundness_counterexamples(p: &Prog, inputs: RangeInclusive<Int>, max_steps: u64) -> Vec<Counterexample> {
+    // With `always_terminal`, every terminating execution ends in the single `Terminal` node
+    let cfg = cfg::ast_to_cfg_with(p, &CfgOptions { always_terminal: true, ..Default::default() });
+    let cfg_mfp: Cfg<MfpAnnot<MultiConstLat>> = mfp(&cfg);
+    let claim = cfg_mfp.graph.raw_nodes().iter()
+        .map(|n| &n.weight)
+        .find(|n| n.node == Node::Terminal)
+        .map_or_else(MultiConstLat::init, |n| n.annot.post().clone());
+    inputs.flat_map(|x| {
+        let mut mem = MemConfig::new();
+        mem.assign(&VarName::new("x"), x);
+        let mut steps = 0;
+        let result = eval_prog_observed(p, &p.procs(), mem, &mut |_| { steps += 1; steps <= max_steps });
+        let mem = match result { Ok(mem) => {mem} Err(_) => {return vec![]} };
+        // Unassigned variables hold `0` and are compared against the default value
+        let vars: BTreeSet<&VarName> = claim.map.keys().chain(mem.entries().map(|(y, _)| y)).collect();
+        vars.into_iter().filter_map(|y| match *claim.lookup(y) {
+            Const(n) if n != mem.lookup(y) => {Some(Counterexample { input: x, var: y.clone(), claimed: n, actual: mem.lookup(y) })}
+            _ => {None}
+        }).collect()
+    }).collect()
+}
+
+/// Compute constant propagation starting with `start` at the init node (see `mfp_from`) and track where the variable `x` first became `Top` during the fixpoint iteration (see `mfp_explained_from`). This is the node of the first iteration whose `post` value maps `x` to `Top`: All values flowing into the node were still more precise at that time, so its transfer function or the join of its incoming values lost the precision. If `x` is `Top` in `start` already, this is the init node.
+/// Returns the MFP solution together with the node, or `None` if `x` never becomes `Top`.
+pub fn top_origin(cfg: &Cfg<RawAnnot>, options: &MfpOptions, start: MultiConstLat, x: &VarName) -> (Cfg<MfpAnnot<MultiConstLat>>, Option<NodeIdx>) {
+    let top_at_init = *start.lookup(x) == Top;
+    let (cfg_mfp, steps) = mfp_explained_from(cfg, options, start);
+    let origin = if top_at_init { Some(cfg.init) } else { steps.iter().find(|step| *step.post.lookup(x) == Top).map(|step| step.node) };
+    (cfg_mfp, origin)
+}
+
+/// Pretty-printer
+impl Display for Counterexample {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "x = {}: {} is claimed to be {}, but is {}", self.input, self.var, self.claimed, self.actual)
+    }
+}
+
 /// Pretty-printer
 impl Display for ConstLat {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -0,0 +1,46 @@
+use crate::cfg::{Cfg, RawAnnot, Node, NodeIdx, Edge};
+use crate::bexp::BExp;
+
+use super::interval::{MultiIntervalLat, IntervalLat};
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use petgraph::visit::EdgeRef;
+
+/// Return the pairs `(b1, b2)` of (different) branch nodes such that the outcome of `b2` is determined by the outcome of `b1` along some path from `b1` to `b2`, e.g. for `if x <= 0 then y := 1 end; if x <= 0 then z := y end`, the second guard takes the same outcome as the first one. The result is sorted.
+/// - Assuming the outcome of `b1` (by `MultiIntervalLat::assume`, starting from a memory where all variables are unknown) bounds the variables of its guard. The outcome of `b2` is determined if assuming one of its outcomes as well is contradictory.
+/// - The path from `b1` to `b2` must not assign to any variable of `b1`'s guard, and must not contain a `call` (or pass `b1` again), as this would invalidate the bounds.
+/// - Guards whose outcome is determined anyway (e.g. `1 <= 2`) are not taken into account.
+pub fn correlated_branches(cfg: &Cfg<RawAnnot>) -> Vec<(NodeIdx, NodeIdx)> {
+    let unknown = MultiIntervalLat::new(BTreeMap::new(), IntervalLat::top());
+    let determined = |mem: &MultiIntervalLat, b: &BExp| mem.assume(b, true).is_bot() || mem.assume(b, false).is_bot();
+    let mut pairs = BTreeSet::new();
+    for b1 in cfg.graph.node_indices() {
+        let guard = match &cfg.graph[b1].node {
+            Node::Branch(b) => {b}
+            _ => {continue}
+        };
+        let vars = guard.vars();
+        for e in cfg.graph.edges(b1) {
+            let mem = unknown.assume(guard, *e.weight() == Edge::True);
+            if mem.is_bot() {
+                continue;
+            }
+            // Search forward from the edge, stopping at nodes that invalidate the bounds
+            let mut visited = HashSet::new();
+            let mut stack = vec![e.target()];
+            while let Some(n) = stack.pop() {
+                if n == b1 || !visited.insert(n) {
+                    continue;
+                }
+                match &cfg.graph[n].node {
+                    Node::Assign(x, _) | Node::Havoc(x) | Node::Phi(x, _) if vars.contains(x) => {continue}
+                    Node::Call(_) => {continue}
+                    Node::Branch(b) if determined(&mem, b) && !determined(&unknown, b) => {pairs.insert((b1, n));}
+                    _ => {}
+                }
+                stack.extend(cfg.graph.neighbors(n));
+            }
+        }
+    }
+    pairs.into_iter().collect()
+}
@@ -0,0 +1,90 @@
+use crate::{common::VarName, cfg::{Node, NodeIdx, Cfg}, ast::Prog, cfg};
+use serde::{Serialize, Deserialize};
+
+use super::common::{FlowSemantics, SemiLat};
+use super::mfp::{mfp, MfpAnnot};
+
+use std::{collections::HashSet, fmt::Display};
+
+/// # "Defined Variables" Lattice
+/// - Used for tracking the variables that are definitely assigned (on every path) for the defined variables analysis
+/// - Partial order: `s1 <= s2   <=>   s1.is_superset(s2)` (analogously to `ExpSetLat`)
+/// - Internal representation:
+///     - `All`:      The set of all variables (i.e. the neutral element of `join_bin`)
+///     - `Vars(..)`: The given finite set of variables
+#[derive(Debug,PartialEq,Clone,Eq,Serialize,Deserialize)]
+pub enum DefVarsLat {
+    All,
+    Vars(HashSet<VarName>)
+}
+
+impl DefVarsLat {
+    /// Return `true` if the variable `x` is definitely assigned
+    pub fn contains(&self, x: &VarName) -> bool {
+        match self {
+            DefVarsLat::All => {true}
+            DefVarsLat::Vars(set) => {set.contains(x)}
+        }
+    }
+}
+
+/// Here, `join_bin` means "intersection"
+impl SemiLat for DefVarsLat {
+    fn join_bin(&self, other: &Self) -> Self {
+        match (self, other) {
+            (DefVarsLat::All, v) | (v, DefVarsLat::All) => {v.clone()}
+            (DefVarsLat::Vars(s1), DefVarsLat::Vars(s2)) => {DefVarsLat::Vars(s1.intersection(s2).cloned().collect())}
+        }
+    }
+}
+
+impl FlowSemantics for DefVarsLat {
+    fn eval_transfer_function(n: &Node, vars: &Self) -> Self {
+        match (n, vars) {
+            (Node::Assign(x, _), DefVarsLat::Vars(set)) | (Node::Havoc(x), DefVarsLat::Vars(set)) | (Node::Phi(x, _), DefVarsLat::Vars(set)) => {
+                let mut set = set.clone();
+                set.insert(x.clone());
+                DefVarsLat::Vars(set)
+            }
+            _ => {vars.clone()}
+        }
+    }
+
+    /// In the beginning, only the input variable `x` is assigned
+    fn init_start() -> Self {
+        DefVarsLat::Vars(vec![VarName::new("x")].into_iter().collect())
+    }
+
+    /// The init element is the "top" element of the semi-lattice, i.e. the set of all variables
+    fn init() -> Self {
+        DefVarsLat::All
+    }
+}
+
+/// Return the reads of variables that are not assigned before on some path (except for the input variable `x`), together with the reading nodes. The result is sorted by the node index and the variable name. The nodes that save and restore the outer values of `let` variables are skipped, as they do not occur in the program (see `Node::is_let_copy`).
+pub fn undefined_reads(prog: &Prog) -> Vec<(VarName, NodeIdx)> {
+    let cfg: Cfg<MfpAnnot<DefVarsLat>> = mfp(&cfg::ast_to_cfg(prog));
+    let mut reads: Vec<(VarName, NodeIdx)> = cfg.graph.node_indices().filter(|n| !cfg.graph[*n].node.is_let_copy()).flat_map(|n| {
+        let annot = &cfg.graph[n].annot;
+        cfg.graph[n].node.read_vars().into_iter()
+            .filter(|x| !annot.pre().contains(x))
+            .map(|x| (x, n))
+            .collect::<Vec<_>>()
+    }).collect();
+    reads.sort_by(|(x1, n1), (x2, n2)| n1.cmp(n2).then(x1.to_string().cmp(&x2.to_string())));
+    reads
+}
+
+/// Pretty-printer
+impl Display for DefVarsLat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DefVarsLat::All => {write!(f, "all")}
+            DefVarsLat::Vars(set) => {
+                let mut vars: Vec<String> = set.iter().map(|x| x.to_string()).collect();
+                vars.sort();
+                write!(f, "{{{}}}", vars.join(", "))
+            }
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use crate::{common::{VarName, Int, checked_mod, checked_pow}, cfg::Node};
+use crate::aexp::*;
+use serde::{Serialize, Deserialize};
+
+use super::common::{SemiLat, FlowSemantics};
+
+use std::{collections::{BTreeMap, BTreeSet}, fmt::Display};
+use FiniteSetLat::*;
+
+/// The maximal number of concrete values tracked by a `FiniteSetLat` before collapsing to `Top`
+pub const FINITE_SET_MAX: usize = 4;
+
+/// # "Finite Set" Lattice
+/// - Used for tracking the content of a single variable, as a more precise alternative to `ConstLat`
+/// - `Values(s)` represents that the variable holds one of the values in `s`; the empty set is the bottom element
+/// - As soon as more than `FINITE_SET_MAX` values are possible, the set collapses to `Top`
+/// - Partial order: `Values(s) <= Values(t)` iff `s` is a subset of `t`, and `Values(s) <= Top` for all `s`
+#[derive(Debug,PartialEq,Clone,Eq,Hash,Serialize,Deserialize)]
+pub enum FiniteSetLat {
+    Top,
+    Values(BTreeSet<Int>)
+}
+
+impl FiniteSetLat {
+    /// Standard constructor, collapsing to `Top` if there are too many values
+    pub fn new(values: BTreeSet<Int>) -> Self {
+        if values.len() > FINITE_SET_MAX { Top } else { Values(values) }
+    }
+
+    /// The bottom element, i.e. no possible value at all
+    pub fn bot() -> Self {
+        Values(BTreeSet::new())
+    }
+
+    /// A single possible value
+    pub fn singleton(n: Int) -> Self {
+        Values(std::iter::once(n).collect())
+    }
+
+    /// Helper function: Evaluate a binary operation on all combinations of possible values. As for `ConstLat`, any `Top` operand makes the result `Top`.
+    fn eval_bin_op<F>(&self, f: F, other: &FiniteSetLat) -> FiniteSetLat
+    where F: Fn(Int, Int) -> Int {
+        match (self, other) {
+            (Top, _) | (_, Top) => {Top}
+            (Values(s), Values(t)) => {
+                FiniteSetLat::new(s.iter().flat_map(|v1| t.iter().map(|v2| f(*v1, *v2)).collect::<Vec<_>>()).collect())
+            }
+        }
+    }
+}
+
+impl SemiLat for FiniteSetLat {
+    fn join_bin(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Top, _) | (_, Top) => {Top}
+            (Values(s), Values(t)) => {FiniteSetLat::new(s.union(t).cloned().collect())}
+        }
+    }
+}
+
+/// # "Multi-Finite-Set" Lattice
+/// - Is the property space for a constant propagation analysis that tracks sets of possible values
+/// - Can be seen as a vector of `FiniteSetLat` values, one for each variable
+/// - Internal representation:
+///     - `map`:     Map from variable names to `FiniteSetLat` values
+///     - `default`: The value assigned to any unspecified variable
+/// - Operate on `MultiFiniteSetLat` only via its methods
+#[derive(PartialEq,Clone,Eq,Hash,Debug,Serialize,Deserialize)]
+pub struct MultiFiniteSetLat {
+    map: BTreeMap<VarName, FiniteSetLat>,
+    default: FiniteSetLat
+}
+
+impl MultiFiniteSetLat {
+    pub fn new(map: BTreeMap<VarName, FiniteSetLat>, default: FiniteSetLat) -> Self { Self { map, default } }
+
+    /// Update/insert a variable value. This mutates the object.
+    pub fn insert(&mut self, x: VarName, v: FiniteSetLat) {
+        self.map.insert(x, v);
+    }
+
+    /// Lookup a variable value.
+    pub fn lookup(&self, x: &VarName) -> &FiniteSetLat {
+        match self.map.get(x) {
+            Some(v) => {v}
+            None => {&self.default}
+        }
+    }
+
+    /// The `init_start()` value, where the given variables are additionally known to hold the given constants, see `MultiConstLat::seeded`
+    pub fn seeded(bindings: &[(VarName, Int)]) -> Self {
+        let mut m = Self::init_start();
+        bindings.iter().for_each(|(x, n)| m.insert(x.clone(), FiniteSetLat::singleton(*n)));
+        m
+    }
+
+    /// Evaluate an arithmetic expression on a `MultiFiniteSetLat` object.
+    pub fn eval_aexp(&self, a: &AExp) -> FiniteSetLat {
+        match a {
+            AExp::Num(n) => {FiniteSetLat::singleton(*n)}
+            AExp::Var(v) => {self.lookup(v).clone()}
+            AExp::Add(a1, a2) => {self.eval_aexp(a1).eval_bin_op(Int::wrapping_add, &self.eval_aexp(a2))}
+            AExp::Mul(a1, a2) => {self.eval_aexp(a1).eval_bin_op(Int::wrapping_mul, &self.eval_aexp(a2))}
+            AExp::Mod(a1, a2) => {
+                match self.eval_aexp(a2) {
+                    // The result of `n % 0` is undefined, so any value is possible
+                    Values(s) if s.contains(&0) => {Top}
+                    divisor => {self.eval_aexp(a1).eval_bin_op(|n, m| checked_mod(n, m).unwrap(), &divisor)}
+                }
+            }
+            AExp::Pow(a1, a2) => {
+                match self.eval_aexp(a2) {
+                    // The result of `n ^ m` is undefined for negative `m`, so any value is possible
+                    Values(s) if s.iter().any(|m| *m < 0) => {Top}
+                    exponent => {self.eval_aexp(a1).eval_bin_op(|n, m| checked_pow(n, m).unwrap(), &exponent)}
+                }
+            }
+        }
+    }
+}
+
+impl SemiLat for MultiFiniteSetLat {
+    fn join_bin(&self, other: &Self) -> Self {
+        // Variables with a specific value in either of both maps are joined pointwise, all others via the defaults
+        let map = self.map.keys().chain(other.map.keys())
+            .map(|x| (x.clone(), self.lookup(x).join_bin(other.lookup(x))))
+            .collect();
+        MultiFiniteSetLat { map, default: self.default.join_bin(&other.default) }
+    }
+}
+
+impl FlowSemantics for MultiFiniteSetLat {
+    fn eval_transfer_function(n: &Node, mem: &Self) -> Self {
+        match n {
+            // Only assignments (and `havoc`s and phi nodes) change the memory
+            Node::Assign(v, a) => {
+                let evaluated_expr = mem.eval_aexp(a);
+                let mut mem = mem.clone();
+                mem.insert(v.clone(), evaluated_expr);
+                mem
+            }
+            Node::Havoc(v) => {
+                let mut mem = mem.clone();
+                mem.insert(v.clone(), Top);
+                mem
+            }
+            // The value of `v` is the one of any of the arguments
+            Node::Phi(v, args) => {
+                let value = args.iter().fold(FiniteSetLat::Values(BTreeSet::new()), |acc, (_, y)| acc.join_bin(mem.lookup(y)));
+                let mut mem = mem.clone();
+                mem.insert(v.clone(), value);
+                mem
+            }
+            _ => {mem.clone()}
+        }
+    }
+
+    /// As for constant propagation, the input is given in `x` and all other variables are initially set to `0`.
+    fn init_start() -> Self {
+        let mut m = MultiFiniteSetLat::new(BTreeMap::new(), FiniteSetLat::singleton(0));
+        m.insert(VarName::new("x"), Top);
+        m
+    }
+
+    /// The init element is the "bot" element of the semi-lattice, i.e. all variables are assigned to the empty set.
+    fn init() -> Self {
+        MultiFiniteSetLat::new(BTreeMap::new(), FiniteSetLat::bot())
+    }
+}
+
+/// Pretty-printer
+impl Display for FiniteSetLat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            Top => {write!(f, "tt")}
+            Values(s) if s.is_empty() => {write!(f, "bb")}
+            Values(s) => {write!(f, "{{{}}}", itertools::join(s, ", "))}
+        }
+    }
+}
+
+/// Pretty-printer
+impl Display for MultiFiniteSetLat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<")?;
+        self.map.iter().try_for_each(|(x, v)| {write!(f, "{} = {}, ", x, v)})?;
+        write!(f, "_ = {}>", self.default)
+    }
+}
\ No newline at end of file
@@ -0,0 +1,303 @@
+use crate::{common::{VarName, Int, checked_mod, checked_pow}, cfg::{Node, Edge}};
+use crate::aexp::*;
+use crate::bexp::BExp;
+use serde::{Serialize, Deserialize};
+
+use super::common::{SemiLat, FlowSemantics};
+
+use std::{collections::BTreeMap, convert::TryFrom, fmt::Display};
+use IntervalLat::*;
+
+/// # "Interval" Lattice
+/// - Used for tracking the range of values of a single variable, for the interval analysis
+/// - `Range(lo, hi)` represents that the variable holds a value `v` with `lo <= v <= hi`, where `lo <= hi`; `Bot` represents no value at all
+/// - As the programs compute with bounded integers, the range `Range(Int::MIN, Int::MAX)` is the top element (printed as `[-inf, inf]`)
+/// - Partial order: `Range(l1, h1) <= Range(l2, h2)` iff `l2 <= l1` and `h1 <= h2`, and `Bot <= v` for all `v`
+/// - The ascending chains are very long (e.g. `[0, 0] <= [0, 1] <= [0, 2] <= ...`), so this lattice should be used with widening and narrowing, see `mfp_widening`
+#[derive(Debug,PartialEq,Clone,Copy,Eq,Hash,Serialize,Deserialize)]
+pub enum IntervalLat {
+    Bot,
+    Range(Int, Int)
+}
+
+impl IntervalLat {
+    /// Standard constructor, yielding `Bot` if `lo > hi`
+    pub fn new(lo: Int, hi: Int) -> Self {
+        if lo <= hi { Range(lo, hi) } else { Bot }
+    }
+
+    /// The top element, i.e. any value is possible
+    pub fn top() -> Self {
+        Range(Int::MIN, Int::MAX)
+    }
+
+    /// A single possible value
+    pub fn singleton(n: Int) -> Self {
+        Range(n, n)
+    }
+
+    /// Intersection of two intervals
+    pub fn meet(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Bot, _) | (_, Bot) => {Bot}
+            (Range(l1, h1), Range(l2, h2)) => {IntervalLat::new(*l1.max(l2), *h1.min(h2))}
+        }
+    }
+
+    /// Helper function: Evaluate a binary operation whose result is bounded by the results on the corners of both intervals (as for addition and multiplication). If any of these overflows, the result wraps around, so any value is possible.
+    fn eval_corners<F>(&self, f: F, other: &IntervalLat) -> IntervalLat
+    where F: Fn(Int, Int) -> Option<Int> {
+        match (self, other) {
+            (Bot, _) | (_, Bot) => {Bot}
+            (Range(l1, h1), Range(l2, h2)) => {
+                let corners: Option<Vec<Int>> = vec![f(*l1, *l2), f(*l1, *h2), f(*h1, *l2), f(*h1, *h2)].into_iter().collect();
+                match corners {
+                    Some(cs) => {Range(*cs.iter().min().unwrap(), *cs.iter().max().unwrap())}
+                    None => {IntervalLat::top()}
+                }
+            }
+        }
+    }
+
+    /// Helper function: Evaluate the (Euclidean) modulo operation, whose result is non-negative and smaller than the absolute value of the divisor
+    fn eval_mod(&self, other: &IntervalLat) -> IntervalLat {
+        match (self, other) {
+            (Bot, _) | (_, Bot) => {Bot}
+            // The result of `n % 0` is undefined, so any value is possible
+            (_, Range(l2, h2)) if *l2 <= 0 && 0 <= *h2 => {IntervalLat::top()}
+            (Range(l1, h1), Range(l2, h2)) if l1 == h1 && l2 == h2 => {IntervalLat::singleton(checked_mod(*l1, *l2).unwrap())}
+            (_, Range(l2, h2)) => {
+                let max_abs = l2.checked_abs().unwrap_or(Int::MAX).max(h2.checked_abs().unwrap_or(Int::MAX));
+                Range(0, max_abs - 1)
+            }
+        }
+    }
+
+    /// Helper function: Evaluate the exponentiation. Apart from single values, the result is bounded only for a fixed, non-negative exponent and a non-negative base (on which exponentiation is monotone), and if no overflow occurs.
+    fn eval_pow(&self, other: &IntervalLat) -> IntervalLat {
+        match (self, other) {
+            (Bot, _) | (_, Bot) => {Bot}
+            (Range(l1, h1), Range(l2, h2)) if l1 == h1 && l2 == h2 => {checked_pow(*l1, *l2).map_or(IntervalLat::top(), IntervalLat::singleton)}
+            (Range(l1, h1), Range(l2, h2)) if l2 == h2 && 0 <= *l1 && 0 <= *l2 => {
+                let pow = |n: Int| u32::try_from(*l2).ok().and_then(|m| n.checked_pow(m));
+                match (pow(*l1), pow(*h1)) {
+                    (Some(lo), Some(hi)) => {Range(lo, hi)}
+                    _ => {IntervalLat::top()}
+                }
+            }
+            _ => {IntervalLat::top()}
+        }
+    }
+}
+
+impl SemiLat for IntervalLat {
+    fn join_bin(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Bot, v) | (v, Bot) => {*v}
+            (Range(l1, h1), Range(l2, h2)) => {Range(*l1.min(l2), *h1.max(h2))}
+        }
+    }
+
+    /// A bound that moves outwards jumps to the extreme value right away
+    fn widen(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Bot, v) | (v, Bot) => {*v}
+            (Range(l1, h1), Range(l2, h2)) => {
+                Range(if l2 < l1 { Int::MIN } else { *l1 }, if h2 > h1 { Int::MAX } else { *h1 })
+            }
+        }
+    }
+
+    /// Only the extreme bounds (i.e. those introduced by widening) are replaced by the new ones
+    fn narrow(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Bot, _) | (_, Bot) => {Bot}
+            (Range(l1, h1), Range(l2, h2)) => {
+                IntervalLat::new(if *l1 == Int::MIN { *l2 } else { *l1 }, if *h1 == Int::MAX { *h2 } else { *h1 })
+            }
+        }
+    }
+}
+
+/// # "Multi-Interval" Lattice
+/// - Is the property space for the interval analysis
+/// - Can be seen as a vector of `IntervalLat` values, one for each variable
+/// - Internal representation:
+///     - `map`:     Map from variable names to `IntervalLat` values
+///     - `default`: The value assigned to any unspecified variable
+/// - Operate on `MultiIntervalLat` only via its methods
+#[derive(PartialEq,Clone,Eq,Hash,Debug,Serialize,Deserialize)]
+pub struct MultiIntervalLat {
+    map: BTreeMap<VarName, IntervalLat>,
+    default: IntervalLat
+}
+
+impl MultiIntervalLat {
+    pub fn new(map: BTreeMap<VarName, IntervalLat>, default: IntervalLat) -> Self { Self { map, default } }
+
+    /// Update/insert a variable value. This mutates the object.
+    pub fn insert(&mut self, x: VarName, v: IntervalLat) {
+        self.map.insert(x, v);
+    }
+
+    /// Lookup a variable value.
+    pub fn lookup(&self, x: &VarName) -> &IntervalLat {
+        match self.map.get(x) {
+            Some(v) => {v}
+            None => {&self.default}
+        }
+    }
+
+    /// Return `true` if this is the bottom element, i.e. all variables are `Bot` (the corresponding program point is unreachable)
+    pub fn is_bot(&self) -> bool {
+        self.default == Bot && self.map.values().all(|v| *v == Bot)
+    }
+
+    /// Evaluate an arithmetic expression on a `MultiIntervalLat` object.
+    pub fn eval_aexp(&self, a: &AExp) -> IntervalLat {
+        match a {
+            AExp::Num(n) => {IntervalLat::singleton(*n)}
+            AExp::Var(v) => {*self.lookup(v)}
+            AExp::Add(a1, a2) => {self.eval_aexp(a1).eval_corners(Int::checked_add, &self.eval_aexp(a2))}
+            AExp::Mul(a1, a2) => {self.eval_aexp(a1).eval_corners(Int::checked_mul, &self.eval_aexp(a2))}
+            AExp::Mod(a1, a2) => {self.eval_aexp(a1).eval_mod(&self.eval_aexp(a2))}
+            AExp::Pow(a1, a2) => {self.eval_aexp(a1).eval_pow(&self.eval_aexp(a2))}
+        }
+    }
+
+    /// Refine the memory under the assumption that the boolean expression `b` evaluates to `truth`: The ranges of variables that are compared are narrowed, e.g. assuming `x <= 10` to be `true` bounds `x` from above by `10`. If the assumption cannot hold, the bottom element is returned.
+    pub fn assume(&self, b: &BExp, truth: bool) -> Self {
+        if self.is_bot() {
+            return self.clone();
+        }
+        match b {
+            BExp::LessEq(a1, a2) if truth => {self.assume_le(a1, a2, 0)}
+            BExp::LessEq(a1, a2)          => {self.assume_le(a2, a1, 1)}
+            BExp::Less(a1, a2) if truth   => {self.assume_le(a1, a2, 1)}
+            BExp::Less(a1, a2)            => {self.assume_le(a2, a1, 0)}
+            BExp::Neg(b) => {self.assume(b, !truth)}
+            BExp::And(b1, b2) if truth => {self.assume(b1, true).assume(b2, true)}
+            BExp::Or(b1, b2) if !truth => {self.assume(b1, false).assume(b2, false)}
+            // Either of both operands determines the truth value
+            BExp::And(b1, b2) | BExp::Or(b1, b2) => {self.assume(b1, truth).join_bin(&self.assume(b2, truth))}
+        }
+    }
+
+    /// Helper function for `assume`: Refine the memory under the assumption `a1 + gap <= a2` (with `gap` being `0` or `1`)
+    fn assume_le(&self, a1: &AExp, a2: &AExp, gap: Int) -> Self {
+        let (v1, v2) = (self.eval_aexp(a1), self.eval_aexp(a2));
+        match (v1, v2) {
+            // The assumption holds for some values iff the smallest value of `a1` is small enough
+            (Range(l1, _), Range(_, h2)) if (l1 as i128) + (gap as i128) <= h2 as i128 => {
+                let mut m = self.clone();
+                if let AExp::Var(x) = a1 {
+                    m.insert(x.clone(), v1.meet(&Range(Int::MIN, h2 - gap)));
+                }
+                if let AExp::Var(y) = a2 {
+                    let v2 = *m.lookup(y);
+                    m.insert(y.clone(), v2.meet(&Range(l1 + gap, Int::MAX)));
+                }
+                m
+            }
+            _ => {MultiIntervalLat::init()}
+        }
+    }
+}
+
+impl SemiLat for MultiIntervalLat {
+    fn join_bin(&self, other: &Self) -> Self {
+        // Variables with a specific value in either of both maps are joined pointwise, all others via the defaults
+        let map = self.map.keys().chain(other.map.keys())
+            .map(|x| (x.clone(), self.lookup(x).join_bin(other.lookup(x))))
+            .collect();
+        MultiIntervalLat { map, default: self.default.join_bin(&other.default) }
+    }
+
+    /// Variables are widened pointwise
+    fn widen(&self, other: &Self) -> Self {
+        let map = self.map.keys().chain(other.map.keys())
+            .map(|x| (x.clone(), self.lookup(x).widen(other.lookup(x))))
+            .collect();
+        MultiIntervalLat { map, default: self.default.widen(&other.default) }
+    }
+
+    /// Variables are narrowed pointwise
+    fn narrow(&self, other: &Self) -> Self {
+        let map = self.map.keys().chain(other.map.keys())
+            .map(|x| (x.clone(), self.lookup(x).narrow(other.lookup(x))))
+            .collect();
+        MultiIntervalLat { map, default: self.default.narrow(&other.default) }
+    }
+}
+
+impl FlowSemantics for MultiIntervalLat {
+    fn eval_transfer_function(n: &Node, mem: &Self) -> Self {
+        match n {
+            // Only assignments (and `havoc`s and phi nodes) change the memory; an unreachable node stays unreachable
+            Node::Assign(v, a) if !mem.is_bot() => {
+                let evaluated_expr = mem.eval_aexp(a);
+                let mut mem = mem.clone();
+                mem.insert(v.clone(), evaluated_expr);
+                mem
+            }
+            Node::Havoc(v) if !mem.is_bot() => {
+                let mut mem = mem.clone();
+                mem.insert(v.clone(), IntervalLat::top());
+                mem
+            }
+            // The value of `v` is the one of any of the arguments
+            Node::Phi(v, args) if !mem.is_bot() => {
+                let value = args.iter().fold(Bot, |acc, (_, y)| acc.join_bin(mem.lookup(y)));
+                let mut mem = mem.clone();
+                mem.insert(v.clone(), value);
+                mem
+            }
+            _ => {mem.clone()}
+        }
+    }
+
+    /// The `True` and `False` edges of a branch node refine the memory by the guard, see `assume`
+    fn eval_edge(n: &Node, mem: &Self, e: &Edge) -> Self {
+        match (n, e) {
+            (Node::Branch(b), Edge::True)  => {mem.assume(b, true)}
+            (Node::Branch(b), Edge::False) => {mem.assume(b, false)}
+            _ => {mem.clone()}
+        }
+    }
+
+    /// As for constant propagation, the input is given in `x` and all other variables are initially set to `0`.
+    fn init_start() -> Self {
+        let mut m = MultiIntervalLat::new(BTreeMap::new(), IntervalLat::singleton(0));
+        m.insert(VarName::new("x"), IntervalLat::top());
+        m
+    }
+
+    /// The init element is the "bot" element of the semi-lattice, i.e. all variables are assigned to `Bot`.
+    fn init() -> Self {
+        MultiIntervalLat::new(BTreeMap::new(), Bot)
+    }
+}
+
+/// Pretty-printer
+impl Display for IntervalLat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let bound = |n: Int| match n {
+            Int::MIN => {"-inf".to_string()}
+            Int::MAX => {"inf".to_string()}
+            n        => {n.to_string()}
+        };
+        match &self {
+            Bot => {write!(f, "bb")}
+            Range(lo, hi) => {write!(f, "[{}, {}]", bound(*lo), bound(*hi))}
+        }
+    }
+}
+
+/// Pretty-printer
+impl Display for MultiIntervalLat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<")?;
+        self.map.iter().try_for_each(|(x, v)| {write!(f, "{} = {}, ", x, v)})?;
+        write!(f, "_ = {}>", self.default)
+    }
+}
@@ -0,0 +1,271 @@
+use crate::{common::VarName, cfg::{Node, NodeIdx, Cfg, RawAnnot}, ast::{Prog, ProgAtom}, aexp::AExp};
+use serde::{Serialize, Deserialize};
+
+use super::common::{FlowSemantics, SemiLat, AnalysisDirection};
+use super::mfp::{mfp_backward, MfpAnnot};
+
+use std::{collections::BTreeSet, fmt::Display};
+
+/// # "Live Variables" Lattice
+/// - Used for tracking the variables whose current value may be read later on, for the (backward) live variables analysis
+/// - Partial order: `s1 <= s2   <=>   s1.vars.is_subset(s2)`
+/// - Internal implementation as a sorted set, so that iteration (and thus pretty-printing and serialization) is deterministic
+#[derive(Debug,PartialEq,Clone,Eq,Hash,Serialize,Deserialize)]
+pub struct LiveVarsLat {
+    vars: BTreeSet<VarName>
+}
+
+impl LiveVarsLat {
+    pub fn new(vars: BTreeSet<VarName>) -> Self { Self { vars } }
+
+    /// Return `true` if the variable `x` is live
+    pub fn contains(&self, x: &VarName) -> bool {
+        self.vars.contains(x)
+    }
+}
+
+/// Here, `join_bin` means "union"
+impl SemiLat for LiveVarsLat {
+    fn join_bin(&self, other: &Self) -> Self {
+        LiveVarsLat::new(self.vars.union(&other.vars).cloned().collect())
+    }
+}
+
+/// The transfer functions compute the live variables *before* a node from the live variables *after* it, see `mfp_backward`.
+impl FlowSemantics for LiveVarsLat {
+    const DIRECTION: AnalysisDirection = AnalysisDirection::Backward;
+
+    fn eval_transfer_function(n: &Node, live: &Self) -> Self {
+        let mut vars = live.vars.clone();
+        // An assignment (or `havoc` or phi node) kills the assigned variable, afterwards the read variables are generated
+        if let Node::Assign(x, _) | Node::Havoc(x) | Node::Phi(x, _) = n {
+            vars.remove(x);
+        }
+        vars.extend(n.read_vars());
+        LiveVarsLat::new(vars)
+    }
+
+    /// At the end of the program, only the output variable `z` is live
+    fn init_start() -> Self {
+        LiveVarsLat::new(vec![VarName::new("z")].into_iter().collect())
+    }
+
+    /// The init element is the "bot" element of the semi-lattice, i.e. the empty set
+    fn init() -> Self {
+        LiveVarsLat::new(BTreeSet::new())
+    }
+}
+
+/// Return the dead stores, i.e. the `Assign(x, _)` nodes where `x` is not live afterwards, in ascending order
+pub fn dead_stores(cfg: &Cfg<RawAnnot>) -> Vec<NodeIdx> {
+    let cfg_live: Cfg<MfpAnnot<LiveVarsLat>> = mfp_backward(cfg);
+    cfg_live.graph.node_indices().filter(|n| {
+        match &cfg_live.graph[*n].node {
+            Node::Assign(x, _) => {!cfg_live.graph[*n].annot.post().contains(x)}
+            _ => {false}
+        }
+    }).collect()
+}
+
+/// Replace all dead stores by `Skip` nodes. As this may render further assignments dead (those whose variable was only read by a dead store), this is repeated until no dead stores remain.
+pub fn remove_dead_stores(cfg: &mut Cfg<RawAnnot>) {
+    loop {
+        let stores = dead_stores(cfg);
+        if stores.is_empty() {
+            return;
+        }
+        stores.into_iter().for_each(|n| cfg.graph[n].node = Node::Skip);
+    }
+}
+
+/// Inline single-use variables: An assignment `t := a` is removed and `a` is substituted into the next statement if this is an assignment `y := b` that reads `t` exactly once, e.g. `t := x + 1; z := t * 2` becomes `z := (x + 1)*2`. This is repeated until no more assignments can be inlined.
+/// This is semantics-preserving (for the output `z`), as it fires only if
+/// - there are only assignments and `skip`s in between (i.e. straight-line code), none of which assigns `t` or a variable read by `a`,
+/// - and `t` is not live after `y := b` (unless `y` is `t`), i.e. the value of `t` is not needed anymore.
+///
+/// The live variables are computed on the AST, in the same way as the live variables analysis does on the CFG. Sub-programs (branches, loop bodies, procedure bodies) are transformed as well.
+pub fn inline_single_use(prog: &Prog) -> Prog {
+    let all = prog.vars().into_iter().collect();
+    inline_prog(prog, &LiveVarsLat::init_start().vars, &all)
+}
+
+/// Helper function for `inline_single_use`: Transform `p`, where `live_out` are the variables that are live after `p` and `all` are all variables of the program
+fn inline_prog(p: &Prog, live_out: &BTreeSet<VarName>, all: &BTreeSet<VarName>) -> Prog {
+    let Prog::Prog(ps) = p;
+    let mut ps = ps.clone();
+    while let Some((i, j)) = find_inlining(&ps, live_out, all) {
+        if let (ProgAtom::Assign(t, a), ProgAtom::Assign(y, b)) = (&ps[i], &ps[j]) {
+            ps[j] = ProgAtom::Assign(y.clone(), Box::new(b.substitute(t, a)));
+        }
+        ps.remove(i);
+    }
+    // Transform the sub-programs, from the last statement to the first one in order to keep track of the live variables
+    let mut live = live_out.clone();
+    let mut res: Vec<ProgAtom> = ps.iter().rev().map(|p| {
+        let p = match p {
+            ProgAtom::Cond(b, p_tt, p_ff) => {ProgAtom::Cond(b.clone(), Box::new(inline_prog(p_tt, &live, all)), Box::new(inline_prog(p_ff, &live, all)))}
+            ProgAtom::While(b, body) => {
+                let live_head = live_before(p, &live, all);
+                ProgAtom::While(b.clone(), Box::new(inline_prog(body, &live_head, all)))
+            }
+            ProgAtom::Repeat(body, b) => {
+                let exit: BTreeSet<VarName> = live.iter().cloned().chain(b.vars()).collect();
+                let live_body_out = &exit | &live_before(p, &live, all);
+                ProgAtom::Repeat(Box::new(inline_prog(body, &live_body_out, all)), b.clone())
+            }
+            ProgAtom::Let(x, a, body) => {
+                let mut live_body_out = live.clone();
+                live_body_out.remove(x);
+                ProgAtom::Let(x.clone(), a.clone(), Box::new(inline_prog(body, &live_body_out, all)))
+            }
+            // A procedure may be called anywhere, so every variable may be live afterwards
+            ProgAtom::Proc(name, body) => {ProgAtom::Proc(name.clone(), Box::new(inline_prog(body, all, all)))}
+            _ => {p.clone()}
+        };
+        live = live_before(&p, &live, all);
+        p
+    }).collect();
+    res.reverse();
+    Prog::Prog(res)
+}
+
+/// Remove the dead assignments, i.e. the assignments `x := a` (and `havoc x` statements) where `x` is not live afterwards, like `remove_dead_stores` does on the CFG. The live variables are computed on the AST, see `inline_single_use`.
+/// As the statements are processed from the last one to the first one, assignments that were only read by (removed) dead assignments of the same block are removed as well. A block that becomes empty is replaced by `skip`. Sub-programs are transformed as well, and the initializations of `let` blocks are kept.
+pub fn remove_dead_assignments(prog: &Prog) -> Prog {
+    let all = prog.vars().into_iter().collect();
+    remove_dead_prog(prog, &LiveVarsLat::init_start().vars, &all)
+}
+
+/// Helper function for `remove_dead_assignments`: Transform `p`, where `live_out` are the variables that are live after `p` and `all` are all variables of the program
+fn remove_dead_prog(p: &Prog, live_out: &BTreeSet<VarName>, all: &BTreeSet<VarName>) -> Prog {
+    let Prog::Prog(ps) = p;
+    let mut live = live_out.clone();
+    let mut res: Vec<ProgAtom> = ps.iter().rev().filter_map(|p| {
+        let p = match p {
+            ProgAtom::Assign(x, _) | ProgAtom::Havoc(x) if !live.contains(x) => {return None}
+            ProgAtom::Cond(b, p_tt, p_ff) => {ProgAtom::Cond(b.clone(), Box::new(remove_dead_prog(p_tt, &live, all)), Box::new(remove_dead_prog(p_ff, &live, all)))}
+            ProgAtom::While(b, body) => {
+                let live_head = live_before(p, &live, all);
+                ProgAtom::While(b.clone(), Box::new(remove_dead_prog(body, &live_head, all)))
+            }
+            ProgAtom::Repeat(body, b) => {
+                let exit: BTreeSet<VarName> = live.iter().cloned().chain(b.vars()).collect();
+                let live_body_out = &exit | &live_before(p, &live, all);
+                ProgAtom::Repeat(Box::new(remove_dead_prog(body, &live_body_out, all)), b.clone())
+            }
+            ProgAtom::Let(x, a, body) => {
+                let mut live_body_out = live.clone();
+                live_body_out.remove(x);
+                ProgAtom::Let(x.clone(), a.clone(), Box::new(remove_dead_prog(body, &live_body_out, all)))
+            }
+            ProgAtom::Proc(name, body) => {ProgAtom::Proc(name.clone(), Box::new(remove_dead_prog(body, all, all)))}
+            _ => {p.clone()}
+        };
+        live = live_before(&p, &live, all);
+        Some(p)
+    }).collect();
+    if res.is_empty() {
+        res.push(ProgAtom::Skip);
+    }
+    res.reverse();
+    Prog::Prog(res)
+}
+
+/// Helper function for `inline_single_use`: Find an assignment `ps[i]` that can be inlined into `ps[j]`
+fn find_inlining(ps: &[ProgAtom], live_out: &BTreeSet<VarName>, all: &BTreeSet<VarName>) -> Option<(usize, usize)> {
+    // The variables that are live after each statement
+    let mut live_after = vec![live_out.clone(); ps.len()];
+    (1..ps.len()).rev().for_each(|k| live_after[k - 1] = live_before(&ps[k], &live_after[k], all));
+    (0..ps.len()).find_map(|i| {
+        let (t, a) = match &ps[i] { ProgAtom::Assign(t, a) => {(t, a)} _ => {return None} };
+        let a_vars = a.vars();
+        for j in i + 1..ps.len() {
+            match &ps[j] {
+                ProgAtom::Assign(y, b) if y == t || b.contains_var(t) => {
+                    let single_use = occurrences(b, t) == 1;
+                    return if single_use && (y == t || !live_after[j].contains(t)) { Some((i, j)) } else { None };
+                }
+                ProgAtom::Assign(y, _) if !a_vars.contains(y) => {}
+                ProgAtom::Skip => {}
+                _ => {return None}
+            }
+        }
+        None
+    })
+}
+
+/// Helper function for `inline_single_use`: The variables that are live before `p`, given the variables `live_after` that are live after it
+fn live_before(p: &ProgAtom, live_after: &BTreeSet<VarName>, all: &BTreeSet<VarName>) -> BTreeSet<VarName> {
+    let live_prog = |p: &Prog, live_after: &BTreeSet<VarName>| {
+        let Prog::Prog(ps) = p;
+        ps.iter().rev().fold(live_after.clone(), |live, p| live_before(p, &live, all))
+    };
+    match p {
+        ProgAtom::Skip | ProgAtom::Proc(_, _) => {live_after.clone()}
+        ProgAtom::Assign(x, a) => {
+            let mut live = live_after.clone();
+            live.remove(x);
+            live.extend(a.vars());
+            live
+        }
+        ProgAtom::Havoc(x) => {
+            let mut live = live_after.clone();
+            live.remove(x);
+            live
+        }
+        ProgAtom::Cond(b, p_tt, p_ff) => {
+            let mut live = &live_prog(p_tt, live_after) | &live_prog(p_ff, live_after);
+            live.extend(b.vars());
+            live
+        }
+        // The live variables at the loop head are the least fixed point
+        ProgAtom::While(b, body) => {
+            let exit: BTreeSet<VarName> = live_after.iter().cloned().chain(b.vars()).collect();
+            let mut live = exit.clone();
+            loop {
+                let next = &exit | &live_prog(body, &live);
+                if next == live { return live }
+                live = next;
+            }
+        }
+        ProgAtom::Repeat(body, b) => {
+            let exit: BTreeSet<VarName> = live_after.iter().cloned().chain(b.vars()).collect();
+            let mut live = live_prog(body, &exit);
+            loop {
+                let next = live_prog(body, &(&exit | &live));
+                if next == live { return live }
+                live = next;
+            }
+        }
+        // The outer value of `x` is restored after the block, and by every jump out of it
+        ProgAtom::Let(x, a, body) => {
+            let mut body_out = live_after.clone();
+            body_out.remove(x);
+            let mut live = live_prog(body, &body_out);
+            live.remove(x);
+            live.extend(a.vars());
+            if live_after.contains(x) || body.has_jumps() { live.insert(x.clone()); }
+            live
+        }
+        // The procedure may read every variable
+        ProgAtom::Call(_) => {live_after | all}
+        // The code at the target of a jump may read every variable
+        ProgAtom::Break | ProgAtom::Continue => {all.clone()}
+    }
+}
+
+/// Helper function for `inline_single_use`: The number of occurrences of the variable `x` in `a`
+fn occurrences(a: &AExp, x: &VarName) -> usize {
+    match a {
+        AExp::Num(_) => {0}
+        AExp::Var(y) => {if x == y { 1 } else { 0 }}
+        AExp::Add(a1, a2) | AExp::Mul(a1, a2) | AExp::Mod(a1, a2) | AExp::Pow(a1, a2) => {occurrences(a1, x) + occurrences(a2, x)}
+    }
+}
+
+/// Pretty-printer
+impl Display for LiveVarsLat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{{{}}}", itertools::join(&self.vars, ", "))
+    }
+}
\ No newline at end of file
@@ -1,4 +1,5 @@
-use std::{collections::HashSet, fmt::Debug, fmt::Display};
+use std::{collections::{BTreeSet, HashMap, HashSet}, fmt::Debug, fmt::Display};
+use petgraph::{visit::{EdgeRef, Dfs}, Direction::{Incoming, Outgoing}};
 use serde::{Serialize, Deserialize};
 
 use vec1::Vec1;
@@ -7,8 +8,7 @@ use crate::cfg::{NodeIdx, RawAnnot};
 use crate::cfg::Cfg;
 use crate::flow_analysis::common::SemiLat;
 
-use super::common::FlowSemantics;
-use std::iter::FromIterator;
+use super::common::{FlowSemantics, AnalysisDirection};
 
 /// An annotation consisting of a pre-value and a post-value. Both values will be elements of the property space `T`.
 #[derive(PartialEq,Clone,Debug,Serialize,Deserialize,Eq,Hash)]
@@ -17,49 +17,376 @@ pub struct MfpAnnot<L> {
     post: L
 }
 
+/// Compute the MFP solution for the property space `L`. This function is total, even for CFGs that were not produced by `ast_to_cfg`: Nodes without predecessors (other than the init node) keep the `init()` value, and if the init node is not part of the CFG, no node gets the `init_start()` value.
+/// The `pre` value of a node is the join of the values that flow in along its incoming edges, i.e. the predecessors' `post` values as refined by `FlowSemantics::eval_edge`.
 pub fn mfp<L: SemiLat + FlowSemantics>(cfg_raw: &Cfg<RawAnnot>) -> Cfg<MfpAnnot<L>> {
-    // Init CFG
-    let mut cfg = cfg_raw.map(|_| MfpAnnot::new(L::init(), L::init()));
-    // Init node gets a special initialization
-    cfg.graph[cfg.init].annot = MfpAnnot::new(L::init_start(), L::init_start());
+    mfp_with(cfg_raw, &MfpOptions::default())
+}
 
-    // Init worklist
-    let mut worklist: HashSet<NodeIdx> = cfg.graph.node_indices().collect();
+/// Options for computing the MFP solution
+/// - `only_reachable`: Only analyze the nodes that are reachable from the init node. All other nodes are never visited and keep the `init()` value as `pre` and `post` value, so that they do not influence the reachable nodes either (by default, their transfer functions are evaluated on `init()`).
+#[derive(Debug,PartialEq,Clone,Eq,Default)]
+pub struct MfpOptions {
+    pub only_reachable: bool
+}
+
+/// Like `mfp`, but with custom options
+pub fn mfp_with<L: SemiLat + FlowSemantics>(cfg_raw: &Cfg<RawAnnot>, options: &MfpOptions) -> Cfg<MfpAnnot<L>> {
+    mfp_from(cfg_raw, options, L::init_start())
+}
+
+/// Like `mfp_with`, but the init node gets the value `start` instead of `init_start()`. This allows for seeding an analysis with knowledge about the inputs, see e.g. `MultiConstLat::seeded`.
+pub fn mfp_from<L: SemiLat + FlowSemantics>(cfg_raw: &Cfg<RawAnnot>, options: &MfpOptions, start: L) -> Cfg<MfpAnnot<L>> {
+    mfp_observed(cfg_raw, options, start, |_, _, _, _| {})
+}
+
+/// A single iteration of the worklist algorithm (see `mfp_explained`):
+/// - `node`:    The node taken out of the worklist
+/// - `pre`:     The join of the values flowing in along the incoming edges (see `FlowSemantics::eval_edge`)
+/// - `post`:    The result of the node's transfer function
+/// - `changed`: Whether `post` differs from the node's previous `post` value (in which case the successors are put into the worklist)
+#[derive(PartialEq,Clone,Debug,Serialize,Deserialize,Eq)]
+pub struct IterationStep<L> {
+    pub node: NodeIdx,
+    pub pre: L,
+    pub post: L,
+    pub changed: bool
+}
+
+/// Like `mfp`, but additionally return the iterations of the worklist algorithm in the order of their execution. The worklist always yields the node with the smallest index, so that the iterations are deterministic.
+pub fn mfp_explained<L: SemiLat + FlowSemantics>(cfg_raw: &Cfg<RawAnnot>) -> (Cfg<MfpAnnot<L>>, Vec<IterationStep<L>>) {
+    mfp_explained_with(cfg_raw, &MfpOptions::default())
+}
+
+/// Like `mfp_explained`, but with custom options
+pub fn mfp_explained_with<L: SemiLat + FlowSemantics>(cfg_raw: &Cfg<RawAnnot>, options: &MfpOptions) -> (Cfg<MfpAnnot<L>>, Vec<IterationStep<L>>) {
+    mfp_explained_from(cfg_raw, options, L::init_start())
+}
+
+/// Like `mfp_explained_with`, but with a custom value for the init node (see `mfp_from`)
+pub fn mfp_explained_from<L: SemiLat + FlowSemantics>(cfg_raw: &Cfg<RawAnnot>, options: &MfpOptions, start: L) -> (Cfg<MfpAnnot<L>>, Vec<IterationStep<L>>) {
+    let mut steps = vec![];
+    let cfg = mfp_observed(cfg_raw, options, start, |node, pre: &L, post: &L, changed| {
+        steps.push(IterationStep { node, pre: pre.clone(), post: post.clone(), changed })
+    });
+    (cfg, steps)
+}
+
+/// Like `mfp`, but additionally report for every node the length of the ascending chain its `pre` value climbed, i.e. how often the join of the incoming values yielded a strictly greater value than before (starting from `init()`). This helps to spot precision cliffs, e.g. a variable jumping to `Top` right after its first constant. Nodes that are never visited (such as the init node) are not reported.
+pub fn mfp_ascents<L: SemiLat + FlowSemantics>(cfg_raw: &Cfg<RawAnnot>) -> (Cfg<MfpAnnot<L>>, HashMap<NodeIdx, usize>) {
+    let mut ascents = HashMap::new();
+    let mut last_pre: HashMap<NodeIdx, L> = HashMap::new();
+    let cfg = mfp_observed(cfg_raw, &MfpOptions::default(), L::init_start(), |node, pre: &L, _, _| {
+        let count = ascents.entry(node).or_insert(0);
+        // As the transfer functions are monotone, a different `pre` value is a strictly greater one
+        if last_pre.get(&node).map_or(*pre != L::init(), |last| last != pre) {
+            *count += 1;
+        }
+        last_pre.insert(node, pre.clone());
+    });
+    (cfg, ascents)
+}
+
+/// Run the worklist algorithm on all nodes (or only the reachable ones, see `MfpOptions`), starting with the value `start` at the init node and calling `on_step` after every iteration (see `iterate`).
+fn mfp_observed<L, F>(cfg_raw: &Cfg<RawAnnot>, options: &MfpOptions, start: L, on_step: F) -> Cfg<MfpAnnot<L>>
+where L: SemiLat + FlowSemantics, F: FnMut(NodeIdx, &L, &L, bool) {
+    // Init CFG, where the init node gets a special initialization
+    let mut cfg = cfg_raw.map_with(|n, _, _| {
+        if n == cfg_raw.init { MfpAnnot::new(start.clone(), start.clone()) }
+        else                 { MfpAnnot::new(L::init(), L::init()) }
+    });
+
+    let worklist = initial_worklist(&cfg, options);
+    iterate(&mut cfg, worklist, on_step);
+    cfg
+}
+
+/// Helper function: The nodes that are initially put into the worklist, i.e. all nodes (or only the reachable ones, see `MfpOptions`) except for the init node
+fn initial_worklist<A>(cfg: &Cfg<A>, options: &MfpOptions) -> BTreeSet<NodeIdx> {
+    let mut worklist: BTreeSet<NodeIdx> = if !options.only_reachable {
+        cfg.graph.node_indices().collect()
+    }
+    else if cfg.graph.node_weight(cfg.init).is_some() {
+        let mut dfs = Dfs::new(&cfg.graph, cfg.init);
+        std::iter::from_fn(|| dfs.next(&cfg.graph)).collect()
+    }
+    else {
+        // Without init node, no node is reachable
+        BTreeSet::new()
+    };
     // The init node is not really part of the CFG (it does not have any predecessors but only serves as a predecessor itself)
     worklist.remove(&cfg.init);
+    worklist
+}
 
+/// Update the MFP solution `prev` after the nodes `changed_nodes` have been modified (e.g. the right-hand side of an assignment was edited in `prev.graph`), reusing the annotations of all nodes that are not affected by the change. The result equals a fresh `mfp` run on the modified CFG.
+///
+/// The affected nodes are the changed nodes and all nodes reachable from them. Only their annotations are reset to `init()` and only they are put into the worklist: Keeping their previous annotations instead would be imprecise if values decrease, since the old values would flow back along loops and could never be removed by joining.
+pub fn mfp_incremental<L: SemiLat + FlowSemantics>(prev: &Cfg<MfpAnnot<L>>, changed_nodes: &HashSet<NodeIdx>) -> Cfg<MfpAnnot<L>> {
+    let mut cfg = prev.clone();
+    let mut affected: BTreeSet<NodeIdx> = BTreeSet::new();
+    changed_nodes.iter().for_each(|n| {
+        let mut dfs = Dfs::new(&cfg.graph, *n);
+        while let Some(m) = dfs.next(&cfg.graph) {
+            affected.insert(m);
+        }
+    });
+    // The init node keeps the `init_start()` value
+    affected.remove(&cfg.init);
+    affected.iter().for_each(|n| cfg.graph[*n].annot = MfpAnnot::new(L::init(), L::init()));
+
+    iterate(&mut cfg, affected, |_, _, _, _| {});
+    cfg
+}
+
+/// The worklist algorithm: Process the nodes of the worklist until all annotations are stable, calling `on_step` with the node, its `pre` value, its (new) `post` value and whether the `post` value changed, after every iteration.
+fn iterate<L, F>(cfg: &mut Cfg<MfpAnnot<L>>, worklist: BTreeSet<NodeIdx>, on_step: F)
+where L: SemiLat + FlowSemantics, F: FnMut(NodeIdx, &L, &L, bool) {
+    iterate_with(cfg, worklist, |_, _, pre| pre, on_step)
+}
+
+/// Like `iterate`, but the new `pre` value of a node `n` is `update_pre(n, old_pre, joined)`, where `joined` is the join of the incoming values (see `mfp_widening`)
+fn iterate_with<L, G, F>(cfg: &mut Cfg<MfpAnnot<L>>, mut worklist: BTreeSet<NodeIdx>, update_pre: G, mut on_step: F)
+where L: SemiLat + FlowSemantics, G: Fn(NodeIdx, &L, L) -> L, F: FnMut(NodeIdx, &L, &L, bool) {
+    check_direction::<L>(AnalysisDirection::Forward);
     while !worklist.is_empty() {
         // Take a node out of worklist
         let n = *worklist.iter().next().unwrap();
         worklist.remove(&n);
 
         // Combine annotations of predecessors
-        let predecs: Vec1<&L> = cfg.predecessors(n).unwrap().mapped(|n_pre| &cfg.graph[n_pre].annot.post);
-        cfg.graph[n].annot.pre = SemiLat::join(predecs);
+        if let Some(pre) = join_incoming(cfg, n) {
+            cfg.graph[n].annot.pre = update_pre(n, &cfg.graph[n].annot.pre, pre);
+        }
 
         // Compute f(in_n)
-        let f_in_n = FlowSemantics::eval_transfer_function(&cfg.graph[n].node, &cfg.graph[n].annot.pre);
+        let f_in_n = FlowSemantics::eval_transfer_function_at(n, &cfg.graph[n].node, &cfg.graph[n].annot.pre);
 
         // If n is not stable...
-        if f_in_n.ne(&cfg.graph[n].annot.post) {
+        let changed = f_in_n.ne(&cfg.graph[n].annot.post);
+        if changed {
             // update post
             cfg.graph[n].annot.post = f_in_n;
-            // mark successors
-            // create set of successor nodes
-            let successors = HashSet::from_iter(cfg.successors(n));
-            // worklist U successors
-            worklist = worklist.union(&successors).cloned().collect();
+            // mark successors, i.e. worklist U successors
+            worklist.extend(cfg.successors(n));
         }
+        on_step(n, &cfg.graph[n].annot.pre, &cfg.graph[n].annot.post, changed);
     }
+}
+
+/// Compute a solution for the property space `L` like `mfp`, but use widening (see `SemiLat::widen`) to guarantee fast termination, even for property spaces with long ascending chains such as `MultiIntervalLat`. As widening over-approximates, precision is recovered afterwards by narrowing, i.e. this is `mfp_narrowing` applied to `mfp_widening_ascent`. The result is less precise than the MFP solution in general, but still sound.
+pub fn mfp_widening<L: SemiLat + FlowSemantics>(cfg_raw: &Cfg<RawAnnot>) -> Cfg<MfpAnnot<L>> {
+    mfp_widening_with(cfg_raw, &MfpOptions::default())
+}
+
+/// Like `mfp_widening`, but with custom options, which apply to both phases
+pub fn mfp_widening_with<L: SemiLat + FlowSemantics>(cfg_raw: &Cfg<RawAnnot>, options: &MfpOptions) -> Cfg<MfpAnnot<L>> {
+    narrowing_with(&widening_ascent_with(cfg_raw, options), options)
+}
+
+/// The ascending phase of `mfp_widening`: Run the worklist algorithm, where the `pre` value of a loop head (see `Cfg::loop_heads`) is the widening of its previous `pre` value by the join of the incoming values. As every cycle contains a loop head, this terminates, yielding a solution that over-approximates the MFP solution.
+pub fn mfp_widening_ascent<L: SemiLat + FlowSemantics>(cfg_raw: &Cfg<RawAnnot>) -> Cfg<MfpAnnot<L>> {
+    widening_ascent_with(cfg_raw, &MfpOptions::default())
+}
+
+/// Helper function for `mfp_widening_ascent` with custom options
+fn widening_ascent_with<L: SemiLat + FlowSemantics>(cfg_raw: &Cfg<RawAnnot>, options: &MfpOptions) -> Cfg<MfpAnnot<L>> {
+    let mut cfg = cfg_raw.map_with(|n, _, _| {
+        if n == cfg_raw.init { MfpAnnot::new(L::init_start(), L::init_start()) }
+        else                 { MfpAnnot::new(L::init(), L::init()) }
+    });
+    let heads = cfg.loop_heads();
+    let worklist = initial_worklist(&cfg, options);
+    iterate_with(&mut cfg, worklist, |n, old, pre| if heads.contains(&n) { old.widen(&pre) } else { pre }, |_, _, _, _| {});
+    cfg
+}
+
+/// The descending phase of `mfp_widening`: Starting from a solution `prev` (e.g. one computed by `mfp_widening_ascent`), run the worklist algorithm again, where the `pre` value of a loop head is the narrowing (see `SemiLat::narrow`) of its previous `pre` value by the join of the incoming values. Thus, the values can only decrease, while they still over-approximate the MFP solution.
+pub fn mfp_narrowing<L: SemiLat + FlowSemantics>(prev: &Cfg<MfpAnnot<L>>) -> Cfg<MfpAnnot<L>> {
+    narrowing_with(prev, &MfpOptions::default())
+}
 
+/// Helper function for `mfp_narrowing` with custom options
+fn narrowing_with<L: SemiLat + FlowSemantics>(prev: &Cfg<MfpAnnot<L>>, options: &MfpOptions) -> Cfg<MfpAnnot<L>> {
+    let mut cfg = prev.clone();
+    let heads = cfg.loop_heads();
+    let worklist = initial_worklist(&cfg, options);
+    iterate_with(&mut cfg, worklist, |n, old, pre| if heads.contains(&n) { old.narrow(&pre) } else { pre }, |_, _, _, _| {});
+    cfg
+}
+
+/// Compute the MFP solution for the property space `L` like `mfp`, but in a single sweep over the nodes in topological order if the CFG is acyclic: Then all predecessors of a node are final before the node itself is visited, so no worklist is needed. For CFGs with cycles, this falls back to `mfp`.
+pub fn mfp_acyclic<L: SemiLat + FlowSemantics>(cfg_raw: &Cfg<RawAnnot>) -> Cfg<MfpAnnot<L>> {
+    let order = match cfg_raw.topo_order() {
+        Some(order) => {order}
+        None => {return mfp(cfg_raw)}
+    };
+    check_direction::<L>(AnalysisDirection::Forward);
+    let mut cfg = cfg_raw.map_with(|n, _, _| {
+        if n == cfg_raw.init { MfpAnnot::new(L::init_start(), L::init_start()) }
+        else                 { MfpAnnot::new(L::init(), L::init()) }
+    });
+    order.into_iter().filter(|n| *n != cfg_raw.init).for_each(|n| {
+        if let Some(pre) = join_incoming(&cfg, n) {
+            cfg.graph[n].annot.pre = pre;
+        }
+        cfg.graph[n].annot.post = FlowSemantics::eval_transfer_function_at(n, &cfg.graph[n].node, &cfg.graph[n].annot.pre);
+    });
     cfg
 }
 
-/// Standard constructor
+/// Compute the meet over all paths (MOP) solution for the property space `L`, e.g. to measure how much precision the MFP solution loses: The `pre` value of a node is the join over all paths from the init node to the node of the transfer functions composed along the path (refined along its edges, see `FlowSemantics::eval_edge`), and the `post` value is the join over these paths of the node's transfer function applied to the respective value. For distributive analyses (like available expressions), this equals the MFP solution; otherwise (like for constant propagation), it may be more precise.
+/// As a loop leads to infinitely many paths, only the paths that visit every node at most `max_loop_iters + 1` times are considered, i.e. every loop is followed at most `max_loop_iters` times in a row. For CFGs with cycles, the result may thus be less than the actual MOP solution (and the MFP solution). Nodes that are not reached by such a path keep the `init()` value. Note that the number of paths grows exponentially with the number of consecutive branches.
+pub fn mop<L: SemiLat + FlowSemantics>(cfg_raw: &Cfg<RawAnnot>, max_loop_iters: usize) -> Cfg<MfpAnnot<L>> {
+    check_direction::<L>(AnalysisDirection::Forward);
+    let mut joined: HashMap<NodeIdx, MfpAnnot<L>> = HashMap::new();
+    if cfg_raw.graph.node_weight(cfg_raw.init).is_some() {
+        mop_paths(cfg_raw, cfg_raw.init, L::init_start(), &mut HashMap::new(), max_loop_iters + 1, &mut joined);
+    }
+    cfg_raw.map_with(|n, _, _| joined.get(&n).cloned().unwrap_or_else(|| MfpAnnot::new(L::init(), L::init())))
+}
+
+/// Helper function for `mop`: Extend the path (whose nodes are counted in `visits`) by the node `n`, where `pre` flows in, join the values at `n` into `joined`, and continue with every successor, unless `n` has been visited `max_visits` times already
+fn mop_paths<L: SemiLat + FlowSemantics>(cfg: &Cfg<RawAnnot>, n: NodeIdx, pre: L, visits: &mut HashMap<NodeIdx, usize>, max_visits: usize, joined: &mut HashMap<NodeIdx, MfpAnnot<L>>) {
+    if visits.get(&n).cloned().unwrap_or(0) == max_visits {
+        return;
+    }
+    *visits.entry(n).or_insert(0) += 1;
+    // As in the MFP solution, the init node keeps the `init_start()` value
+    let post = if n == cfg.init { pre.clone() } else { L::eval_transfer_function_at(n, &cfg.graph[n].node, &pre) };
+    cfg.graph.edges_directed(n, Outgoing).for_each(|e| {
+        let value = L::eval_edge(&cfg.graph[n].node, &post, e.weight());
+        mop_paths(cfg, e.target(), value, visits, max_visits, joined);
+    });
+    let annot = match joined.remove(&n) {
+        Some(annot) => {MfpAnnot::new(annot.pre.join_bin(&pre), annot.post.join_bin(&post))}
+        None => {MfpAnnot::new(pre, post)}
+    };
+    joined.insert(n, annot);
+    *visits.entry(n).or_insert(0) -= 1;
+}
+
+/// Join the values flowing into the node `n` along its incoming edges, i.e. the predecessors' `post` values, each one refined along its edge to `n`. Returns `None` if `n` has no predecessors.
+fn join_incoming<L: SemiLat + FlowSemantics>(cfg: &Cfg<MfpAnnot<L>>, n: NodeIdx) -> Option<L> {
+    let incoming: Vec<L> = cfg.graph.edges_directed(n, Incoming).map(|e| {
+        let pred = &cfg.graph[e.source()];
+        L::eval_edge(&pred.node, &pred.annot.post, e.weight())
+    }).collect();
+    Vec1::try_from_vec(incoming.iter().collect()).ok().map(SemiLat::join)
+}
+
+/// Panic if the property space `L` is not meant for a solver of the direction `expected` (see `FlowSemantics::DIRECTION`)
+fn check_direction<L: FlowSemantics>(expected: AnalysisDirection) {
+    assert_eq!(L::DIRECTION, expected, "The analysis is a {:?} analysis, but is solved as a {:?} analysis.", L::DIRECTION, expected);
+}
+
+/// Compute the MFP solution of a backward analysis for the property space `L`: Values flow against the edges, i.e. the `post` value of a node is the join of its successors' `pre` values and the transfer function computes the `pre` value from the `post` value. Nodes without successors (i.e. the exits of the program) get the `init_start()` value as their `post` value; all other values are initialized to `init()`. Edge refinements (see `FlowSemantics::eval_edge`) are not applied, as they describe the forward flow along an edge.
+pub fn mfp_backward<L: SemiLat + FlowSemantics>(cfg_raw: &Cfg<RawAnnot>) -> Cfg<MfpAnnot<L>> {
+    check_direction::<L>(AnalysisDirection::Backward);
+    // Init CFG, where the exit nodes get a special initialization
+    let mut cfg = cfg_raw.map_with(|n, _, _| {
+        if cfg_raw.successors(n).is_empty() { MfpAnnot::new(L::init(), L::init_start()) }
+        else                                { MfpAnnot::new(L::init(), L::init()) }
+    });
+
+    // Init worklist
+    let mut worklist: BTreeSet<NodeIdx> = cfg.graph.node_indices().collect();
+
+    while !worklist.is_empty() {
+        // Take a node out of worklist
+        let n = *worklist.iter().next().unwrap();
+        worklist.remove(&n);
+
+        // Combine annotations of successors (the `post` value of an exit node stays `init_start()`)
+        if let Ok(succs) = Vec1::try_from_vec(cfg.successors(n)) {
+            let succs: Vec1<&L> = succs.mapped(|n_succ| &cfg.graph[n_succ].annot.pre);
+            cfg.graph[n].annot.post = SemiLat::join(succs);
+        }
+
+        // Compute f(out_n)
+        let f_out_n = FlowSemantics::eval_transfer_function_at(n, &cfg.graph[n].node, &cfg.graph[n].annot.post);
+
+        // If n is not stable...
+        if f_out_n.ne(&cfg.graph[n].annot.pre) {
+            // update pre
+            cfg.graph[n].annot.pre = f_out_n;
+            // mark predecessors
+            if let Some(predecs) = cfg.predecessors(n) {
+                worklist.extend(predecs);
+            }
+        }
+    }
+
+    cfg
+}
+
+/// An annotation for a compact display of an MFP solution, where a redundant `pre` value is omitted (see `compact_annots`)
+#[derive(PartialEq,Clone,Debug,Eq,Hash)]
+pub struct CompactMfpAnnot<L> {
+    pre: Option<L>,
+    post: L
+}
+
+/// Prepare the MFP solution `cfg` for a compact display (e.g. in DOT), where only the `post` value is shown if the `pre` value is redundant: This is the case if the `pre` value equals the `post` value of every predecessor (which is where it can be read off then), or, for a node without predecessors like the init node, if it equals the node's own `post` value. Thus, only the `pre` values of joins and refined edges (see `FlowSemantics::eval_edge`) remain.
+pub fn compact_annots<L: SemiLat>(cfg: &Cfg<MfpAnnot<L>>) -> Cfg<CompactMfpAnnot<L>> {
+    cfg.map_with(|n, _, annot| {
+        let redundant = match cfg.predecessors(n) {
+            Some(preds) => {preds.iter().all(|pred| cfg.graph[*pred].annot.post == annot.pre)}
+            None => {annot.pre == annot.post}
+        };
+        CompactMfpAnnot { pre: if redundant { None } else { Some(annot.pre.clone()) }, post: annot.post.clone() }
+    })
+}
+
+impl<L> CompactMfpAnnot<L> {
+    /// The value before the node, unless it is redundant
+    pub fn pre(&self) -> Option<&L> { self.pre.as_ref() }
+
+    /// The value after the node
+    pub fn post(&self) -> &L { &self.post }
+}
+
+/// A node whose annotations differ between two MFP solutions, see `diff_annots`:
+/// - `node`:  The index of the node
+/// - `label`: The (unannotated) node, e.g. `y := 1`
+/// - `fst`:   The annotation of the first solution, pretty-printed
+/// - `snd`:   The annotation of the second solution, pretty-printed
+#[derive(Debug,PartialEq,Clone,Eq)]
+pub struct AnnotDiff {
+    pub node: NodeIdx,
+    pub label: String,
+    pub fst: MfpAnnot<String>,
+    pub snd: MfpAnnot<String>
+}
+
+/// Compare two MFP solutions of the same CFG node by node and return the nodes whose annotations differ (in the order of their indices). The annotations are compared via their pretty-printed form (see `Display`), so that the solutions of different property spaces can be compared, e.g. `MultiConstLat` and `Unrefined<MultiConstLat>`.
+pub fn diff_annots<L1: Display, L2: Display>(fst: &Cfg<MfpAnnot<L1>>, snd: &Cfg<MfpAnnot<L2>>) -> Vec<AnnotDiff> {
+    fst.graph.node_indices().zip(snd.graph.node_indices()).filter_map(|(n, m)| {
+        let (fst_annot, snd_annot) = (fst.graph[n].annot.to_strings(), snd.graph[m].annot.to_strings());
+        if fst_annot == snd_annot { None } else {
+            Some(AnnotDiff { node: n, label: fst.graph[n].node.to_string(), fst: fst_annot, snd: snd_annot })
+        }
+    }).collect()
+}
+
+/// Standard constructor and getters
 impl<L> MfpAnnot<L> {
     pub fn new(pre: L, post: L) -> Self {
         Self { pre, post }
     }
+
+    /// The value before the node, i.e. the join of the values flowing in along the incoming edges (for a backward analysis: the result of the node's transfer function)
+    pub fn pre(&self) -> &L { &self.pre }
+
+    /// The value after the node, i.e. the result of the node's transfer function (for a backward analysis: the join of the successors' `pre` values)
+    pub fn post(&self) -> &L { &self.post }
+}
+
+impl<L: Display> MfpAnnot<L> {
+    /// The annotation with both values pretty-printed
+    pub fn to_strings(&self) -> MfpAnnot<String> {
+        MfpAnnot::new(self.pre.to_string(), self.post.to_string())
+    }
 }
 
 /// Pretty-printer
@@ -70,4 +397,43 @@ impl<L: Display> Display for MfpAnnot<L> {
         write!(f, "\npost: ")?;
         Display::fmt(&self.post, f)
     }
+}
+
+/// Pretty-printer, like the one for `MfpAnnot` but without the omitted `pre` value
+impl<L: Display> Display for CompactMfpAnnot<L> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(pre) = &self.pre {
+            write!(f, "pre: ")?;
+            Display::fmt(pre, f)?;
+            writeln!(f)?;
+        }
+        write!(f, "post: ")?;
+        Display::fmt(&self.post, f)
+    }
+}
+
+/// Pretty-printer: The node (prefixed by its index), followed by the differing values, prefixed by `-` (first solution) or `+` (second solution), e.g.:
+/// ```text
+/// 3: z := 5
+/// - pre: <_ = bb>
+/// + pre: <x = tt, y = 1, _ = 0>
+/// ```
+impl Display for AnnotDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.node.index(), self.label)?;
+        if self.fst.pre != self.snd.pre {
+            write!(f, "\n- pre: {}\n+ pre: {}", self.fst.pre, self.snd.pre)?;
+        }
+        if self.fst.post != self.snd.post {
+            write!(f, "\n- post: {}\n+ post: {}", self.fst.post, self.snd.post)?;
+        }
+        Ok(())
+    }
+}
+
+/// Pretty-printer
+impl<L: Display> Display for IterationStep<L> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "node {}: pre: {}, post: {} ({})", self.node.index(), self.pre, self.post, if self.changed { "changed" } else { "stable" })
+    }
 }
\ No newline at end of file
@@ -5,4 +5,16 @@
 pub mod common;
 pub mod mfp;
 pub mod const_prop;
-pub mod avail_exp;
\ No newline at end of file
+pub mod avail_exp;
+pub mod def_vars;
+pub mod finite_set;
+pub mod live_vars;
+pub mod interval;
+
+pub mod optimize;
+pub mod uninit;
+pub mod sign;
+pub mod product;
+pub mod correlation;
+pub mod affine;
+pub mod tracked;
@@ -0,0 +1,74 @@
+use crate::{ast::{Prog, ProgAtom}, bexp::BExp, interpreter::{MemConfig, eval_bexp}};
+
+use super::const_prop::propagate_constants;
+use super::live_vars::remove_dead_assignments;
+
+/// The maximal number of rounds of `optimize`. A round can only shrink the program or replace expressions by numbers, so the fixpoint is usually reached after a few rounds; the cap is just a safeguard.
+pub const OPTIMIZE_MAX_ITERATIONS: usize = 16;
+
+/// Optimize the program by rounds of constant propagation (see `propagate_constants`), constant folding (see `fold_constants`) and dead code elimination (see `eliminate_dead_code`), until a round does not change the program anymore (or after `OPTIMIZE_MAX_ITERATIONS` rounds).
+/// The transformations enable each other, so a single round is not enough in general: E.g. in `y := x % 1; z := y + 1`, folding yields `y := 0`, so that only the next round propagates `0` into `z := 0 + 1`, which is folded to `z := 1`, and `y := 0` becomes dead.
+pub fn optimize(prog: &Prog) -> Prog {
+    let mut prog = prog.clone();
+    for _ in 0..OPTIMIZE_MAX_ITERATIONS {
+        let next = eliminate_dead_code(&fold_constants(&propagate_constants(&prog)));
+        if next == prog {
+            break;
+        }
+        prog = next;
+    }
+    prog
+}
+
+/// Fold every arithmetic expression of the program, see `AExp::folded`
+pub fn fold_constants(prog: &Prog) -> Prog {
+    prog.map_aexps(|a| a.folded())
+}
+
+/// Remove dead code:
+/// - Conditionals whose guard contains no variables are replaced by the branch that is taken, and loops that are never entered (`while` with a false guard) or never repeated (`repeat` with a true guard) are resolved as well.
+/// - `skip` statements are removed, unless a block would become empty.
+/// - Dead assignments are removed, see `remove_dead_assignments`.
+pub fn eliminate_dead_code(prog: &Prog) -> Prog {
+    remove_dead_assignments(&prune(prog))
+}
+
+/// Helper function for `eliminate_dead_code`: Resolve the constant guards and remove the `skip` statements
+fn prune(p: &Prog) -> Prog {
+    let Prog::Prog(ps) = p;
+    let mut res: Vec<ProgAtom> = ps.iter().flat_map(|p| match p {
+        ProgAtom::Skip => {vec![]}
+        ProgAtom::Cond(b, p_tt, p_ff) => {
+            match constant_guard(b) {
+                Some(true)  => {atoms(prune(p_tt))}
+                Some(false) => {atoms(prune(p_ff))}
+                None        => {vec![ProgAtom::Cond(b.clone(), Box::new(prune(p_tt)), Box::new(prune(p_ff)))]}
+            }
+        }
+        ProgAtom::While(b, _) if constant_guard(b) == Some(false) => {vec![]}
+        ProgAtom::While(b, body) => {vec![ProgAtom::While(b.clone(), Box::new(prune(body)))]}
+        // A body with jumps must stay inside the loop, as its jumps would refer to an enclosing loop otherwise
+        ProgAtom::Repeat(body, b) if constant_guard(b) == Some(true) && !body.has_jumps() => {atoms(prune(body))}
+        ProgAtom::Repeat(body, b) => {vec![ProgAtom::Repeat(Box::new(prune(body)), b.clone())]}
+        ProgAtom::Let(x, a, body) => {vec![ProgAtom::Let(x.clone(), a.clone(), Box::new(prune(body)))]}
+        ProgAtom::Proc(name, body) => {vec![ProgAtom::Proc(name.clone(), Box::new(prune(body)))]}
+        _ => {vec![p.clone()]}
+    }).collect();
+    if res.is_empty() {
+        res.push(ProgAtom::Skip);
+    }
+    Prog::Prog(res)
+}
+
+/// Helper function for `prune`: The truth value of a guard without variables
+fn constant_guard(b: &BExp) -> Option<bool> {
+    if b.vars().is_empty() { Some(eval_bexp(b, &MemConfig::new())) } else { None }
+}
+
+/// Helper function for `prune`: The statements of a (pruned) program, where a single `skip` stands for no statements
+fn atoms(p: Prog) -> Vec<ProgAtom> {
+    match p {
+        Prog::Prog(ps) if ps == vec![ProgAtom::Skip] => {vec![]}
+        Prog::Prog(ps) => {ps}
+    }
+}
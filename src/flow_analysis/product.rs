@@ -0,0 +1,132 @@
+use crate::cfg::{Node, Edge, NodeIdx};
+use crate::common::VarName;
+
+use super::common::{SemiLat, FlowSemantics, AnalysisDirection};
+use super::const_prop::{MultiConstLat, ConstLat};
+use super::sign::{MultiSignLat, SignLat};
+
+use std::{collections::{BTreeMap, BTreeSet, HashMap}, fmt::Display, marker::PhantomData};
+
+/// A reduction refines the components of a `ProductLat` by each other, e.g. a variable that is known to be `Const(0)` must have the sign `Zero`. The result must represent the same concrete states as the arguments (or fewer, if some are contradictory).
+pub trait Reduction<A, B> {
+    fn reduce(a: A, b: B) -> (A, B);
+}
+
+/// The trivial reduction, which keeps both components, i.e. they are computed independently of each other
+#[derive(Debug,PartialEq,Clone,Copy,Eq,Hash)]
+pub struct NoReduction;
+
+impl<A, B> Reduction<A, B> for NoReduction {
+    fn reduce(a: A, b: B) -> (A, B) {
+        (a, b)
+    }
+}
+
+/// # Product Lattice
+/// - Is the property space for running the analyses of `A` and `B` in lockstep, i.e. every value consists of a value `fst` of `A` and a value `snd` of `B`
+/// - After every transfer function and edge, the reduction `R` lets the components refine each other (see `Reduction`), which makes the product (a "reduced product") more precise than both analyses on their own. By default, there is no reduction.
+/// - Both analyses must have the same direction (otherwise, the solvers panic, see `check_directions`)
+/// - Partial order, join, widening and narrowing: Componentwise
+#[derive(Debug,PartialEq,Clone,Eq,Hash)]
+pub struct ProductLat<A, B, R = NoReduction> {
+    pub fst: A,
+    pub snd: B,
+    reduction: PhantomData<R>
+}
+
+impl<A, B, R: Reduction<A, B>> ProductLat<A, B, R> {
+    /// Standard constructor, which reduces the components
+    pub fn new(fst: A, snd: B) -> Self {
+        let (fst, snd) = R::reduce(fst, snd);
+        ProductLat { fst, snd, reduction: PhantomData }
+    }
+}
+
+impl<A: SemiLat, B: SemiLat, R: Eq + Clone> SemiLat for ProductLat<A, B, R> {
+    fn join_bin(&self, other: &Self) -> Self {
+        ProductLat { fst: self.fst.join_bin(&other.fst), snd: self.snd.join_bin(&other.snd), reduction: PhantomData }
+    }
+
+    fn widen(&self, other: &Self) -> Self {
+        ProductLat { fst: self.fst.widen(&other.fst), snd: self.snd.widen(&other.snd), reduction: PhantomData }
+    }
+
+    fn narrow(&self, other: &Self) -> Self {
+        ProductLat { fst: self.fst.narrow(&other.fst), snd: self.snd.narrow(&other.snd), reduction: PhantomData }
+    }
+}
+
+impl<A, B, R> FlowSemantics for ProductLat<A, B, R>
+where A: FlowSemantics + Clone, B: FlowSemantics + Clone, R: Reduction<A, B> {
+    const DIRECTION: AnalysisDirection = A::DIRECTION;
+
+    fn eval_transfer_function(n: &Node, x: &Self) -> Self {
+        ProductLat::new(A::eval_transfer_function(n, &x.fst), B::eval_transfer_function(n, &x.snd))
+    }
+
+    fn eval_transfer_function_at(idx: NodeIdx, n: &Node, x: &Self) -> Self {
+        ProductLat::new(A::eval_transfer_function_at(idx, n, &x.fst), B::eval_transfer_function_at(idx, n, &x.snd))
+    }
+
+    fn eval_edge(n: &Node, x: &Self, e: &Edge) -> Self {
+        ProductLat::new(A::eval_edge(n, &x.fst, e), B::eval_edge(n, &x.snd, e))
+    }
+
+    fn init() -> Self {
+        check_directions::<A, B>();
+        ProductLat { fst: A::init(), snd: B::init(), reduction: PhantomData }
+    }
+
+    fn init_start() -> Self {
+        check_directions::<A, B>();
+        ProductLat::new(A::init_start(), B::init_start())
+    }
+}
+
+/// Panic if the components of a product are analyses of different directions (see `FlowSemantics::DIRECTION`), as no solver can apply both of their transfer functions the right way round. All solvers start from `init` or `init_start`, so they fail before computing anything.
+fn check_directions<A: FlowSemantics, B: FlowSemantics>() {
+    assert_eq!(A::DIRECTION, B::DIRECTION, "The components of the product are a {:?} and a {:?} analysis.", A::DIRECTION, B::DIRECTION);
+}
+
+/// The reduction of constant propagation and sign analysis: A constant determines the sign, and the sign `Zero` determines the constant `0`. A constant of the wrong sign is contradictory, i.e. it makes the program point unreachable (e.g. `Const(1)` and `NonPos` after assuming `x <= 0` is true).
+#[derive(Debug,PartialEq,Clone,Copy,Eq,Hash)]
+pub struct ConstSignReduction;
+
+impl Reduction<MultiConstLat, MultiSignLat> for ConstSignReduction {
+    fn reduce(c: MultiConstLat, s: MultiSignLat) -> (MultiConstLat, MultiSignLat) {
+        let reduced = || -> Option<(MultiConstLat, MultiSignLat)> {
+            let (c_default, s_default) = reduce_const_sign(c.default_value(), s.default_value())?;
+            let mut c_reduced = MultiConstLat::new(HashMap::new(), c_default);
+            let mut s_reduced = MultiSignLat::new(BTreeMap::new(), s_default);
+            let vars: BTreeSet<&VarName> = c.specified_vars().into_iter().chain(s.specified_vars()).collect();
+            for x in vars {
+                let (cv, sv) = reduce_const_sign(c.lookup(x), s.lookup(x))?;
+                c_reduced.insert(x.clone(), cv);
+                s_reduced.insert(x.clone(), sv);
+            }
+            Some((c_reduced, s_reduced))
+        };
+        reduced().unwrap_or_else(|| (MultiConstLat::init(), MultiSignLat::init()))
+    }
+}
+
+/// Helper function for `ConstSignReduction`: Reduce the values of a single variable, returning `None` if they are contradictory
+fn reduce_const_sign(c: &ConstLat, s: &SignLat) -> Option<(ConstLat, SignLat)> {
+    match (c, s) {
+        (ConstLat::Bot, _) | (_, SignLat::Bot) => {None}
+        (ConstLat::Const(n), s) if !s.contains(*n) => {None}
+        (ConstLat::Const(n), _)                => {Some((c.clone(), SignLat::of(*n)))}
+        (ConstLat::Top, SignLat::Zero)        => {Some((ConstLat::Const(0), SignLat::Zero))}
+        _                                      => {Some((c.clone(), *s))}
+    }
+}
+
+/// The reduced product of constant propagation and sign analysis, see `ConstSignReduction`
+pub type ConstSignLat = ProductLat<MultiConstLat, MultiSignLat, ConstSignReduction>;
+
+/// Pretty-printer
+impl<A: Display, B: Display, R> Display for ProductLat<A, B, R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {})", self.fst, self.snd)
+    }
+}
@@ -0,0 +1,313 @@
+use crate::{common::{VarName, Int}, cfg::{Node, Edge}};
+use crate::aexp::*;
+use crate::bexp::BExp;
+use serde::{Serialize, Deserialize};
+
+use super::common::{SemiLat, FlowSemantics};
+
+use std::{collections::BTreeMap, fmt::Display};
+use SignLat::*;
+
+/// # "Sign" Lattice
+/// - Used for tracking the sign of a single variable, for the sign analysis
+/// - Every element represents a set of signs: `Neg` (`< 0`), `Zero` (`= 0`) and `Pos` (`> 0`), their unions `NonPos`, `NonZero` and `NonNeg`, all of them (`Top`) and none (`Bot`)
+/// - Partial order: Set inclusion, e.g. `Zero <= NonNeg <= Top`
+/// - As the programs compute with wrapping integers, adding two positive values may yield a negative value, so that many operations yield `NonZero` or `Top` only
+#[derive(Debug,PartialEq,Clone,Copy,Eq,Hash,Serialize,Deserialize)]
+pub enum SignLat {
+    Top,
+    NonPos,
+    NonZero,
+    NonNeg,
+    Neg,
+    Zero,
+    Pos,
+    Bot
+}
+
+/// The bits of the signs, see `SignLat::bits`
+const NEG: u8 = 1;
+const ZERO: u8 = 2;
+const POS: u8 = 4;
+
+impl SignLat {
+    /// The sign of a single value
+    pub fn of(n: Int) -> Self {
+        match n {
+            n if n < 0 => {Neg}
+            0          => {Zero}
+            _          => {Pos}
+        }
+    }
+
+    /// Return `true` if the value `n` has one of the signs
+    pub fn contains(&self, n: Int) -> bool {
+        self.bits() & SignLat::of(n).bits() != 0
+    }
+
+    /// Intersection of two sets of signs
+    pub fn meet(&self, other: &Self) -> Self {
+        SignLat::from_bits(self.bits() & other.bits())
+    }
+
+    /// Helper function: The set of signs as bits, see `NEG`, `ZERO` and `POS`
+    fn bits(&self) -> u8 {
+        match self {
+            Top     => {NEG | ZERO | POS}
+            NonPos  => {NEG | ZERO}
+            NonZero => {NEG | POS}
+            NonNeg  => {ZERO | POS}
+            Neg     => {NEG}
+            Zero    => {ZERO}
+            Pos     => {POS}
+            Bot     => {0}
+        }
+    }
+
+    /// Helper function: The inverse of `bits`
+    fn from_bits(bits: u8) -> Self {
+        [Top, NonPos, NonZero, NonNeg, Neg, Zero, Pos, Bot].iter()
+            .find(|s| s.bits() == bits)
+            .copied()
+            .unwrap()
+    }
+
+    /// Helper function: Evaluate a binary operation on `SignLat` values, where `f` yields the possible signs of the result for single signs of the operands. The results for all combinations are joined.
+    fn eval_bin_op<F>(&self, f: F, other: &SignLat) -> SignLat
+    where F: Fn(u8, u8) -> u8 {
+        let signs = |s: &SignLat| vec![NEG, ZERO, POS].into_iter().filter(|b| s.bits() & b != 0).collect::<Vec<_>>();
+        let bits = signs(self).into_iter()
+            .flat_map(|b1| signs(other).into_iter().map(|b2| f(b1, b2)).collect::<Vec<_>>())
+            .fold(0, |acc, b| acc | b);
+        SignLat::from_bits(bits)
+    }
+}
+
+impl SemiLat for SignLat {
+    fn join_bin(&self, other: &Self) -> Self {
+        SignLat::from_bits(self.bits() | other.bits())
+    }
+}
+
+/// Helper function: The possible signs of `n + m` for the signs `b1` of `n` and `b2` of `m`. The sum of two positive values never wraps around to `0` (but may to a negative value), whereas the sum of two negative values may wrap around to any value.
+fn add_signs(b1: u8, b2: u8) -> u8 {
+    match (b1, b2) {
+        (ZERO, b) | (b, ZERO) => {b}
+        (POS, POS)            => {POS | NEG}
+        _                     => {NEG | ZERO | POS}
+    }
+}
+
+/// Helper function: The possible signs of `n * m`, see `add_signs`. A product of non-zero values may wrap around to any value.
+fn mul_signs(b1: u8, b2: u8) -> u8 {
+    match (b1, b2) {
+        (ZERO, _) | (_, ZERO) => {ZERO}
+        _                     => {NEG | ZERO | POS}
+    }
+}
+
+/// Helper function: The possible signs of `n % m`, see `add_signs`. The (Euclidean) modulo is never negative, and undefined (i.e. any value) for `m = 0`.
+fn mod_signs(b1: u8, b2: u8) -> u8 {
+    match (b1, b2) {
+        (_, ZERO)             => {NEG | ZERO | POS}
+        (ZERO, _)             => {ZERO}
+        _                     => {ZERO | POS}
+    }
+}
+
+/// Helper function: The possible signs of `n ^ m`, see `add_signs`. A power with exponent `0` is `1`, and undefined (i.e. any value) for `m < 0`.
+fn pow_signs(b1: u8, b2: u8) -> u8 {
+    match (b1, b2) {
+        (_, ZERO)             => {POS}
+        (ZERO, POS)           => {ZERO}
+        _                     => {NEG | ZERO | POS}
+    }
+}
+
+/// # "Multi-Sign" Lattice
+/// - Is the property space for the sign analysis
+/// - Can be seen as a vector of `SignLat` values, one for each variable
+/// - Internal representation:
+///     - `map`:     Map from variable names to `SignLat` values
+///     - `default`: The value assigned to any unspecified variable
+/// - Operate on `MultiSignLat` only via its methods
+#[derive(PartialEq,Clone,Eq,Hash,Debug,Serialize,Deserialize)]
+pub struct MultiSignLat {
+    map: BTreeMap<VarName, SignLat>,
+    default: SignLat
+}
+
+impl MultiSignLat {
+    pub fn new(map: BTreeMap<VarName, SignLat>, default: SignLat) -> Self { Self { map, default } }
+
+    /// Update/insert a variable value. This mutates the object.
+    pub fn insert(&mut self, x: VarName, v: SignLat) {
+        self.map.insert(x, v);
+    }
+
+    /// Lookup a variable value.
+    pub fn lookup(&self, x: &VarName) -> &SignLat {
+        match self.map.get(x) {
+            Some(v) => {v}
+            None => {&self.default}
+        }
+    }
+
+    /// Return the variables with a specific value, i.e. all other variables have the default value
+    pub fn specified_vars(&self) -> Vec<&VarName> {
+        self.map.keys().collect()
+    }
+
+    /// Return the value of all unspecified variables, see `specified_vars`
+    pub fn default_value(&self) -> &SignLat {
+        &self.default
+    }
+
+    /// Return `true` if this is the bottom element, i.e. all variables are `Bot` (the corresponding program point is unreachable)
+    pub fn is_bot(&self) -> bool {
+        self.default == Bot && self.map.values().all(|v| *v == Bot)
+    }
+
+    /// Evaluate an arithmetic expression on a `MultiSignLat` object.
+    pub fn eval_aexp(&self, a: &AExp) -> SignLat {
+        match a {
+            AExp::Num(n) => {SignLat::of(*n)}
+            AExp::Var(v) => {*self.lookup(v)}
+            AExp::Add(a1, a2) => {self.eval_aexp(a1).eval_bin_op(add_signs, &self.eval_aexp(a2))}
+            AExp::Mul(a1, a2) => {self.eval_aexp(a1).eval_bin_op(mul_signs, &self.eval_aexp(a2))}
+            AExp::Mod(a1, a2) => {self.eval_aexp(a1).eval_bin_op(mod_signs, &self.eval_aexp(a2))}
+            AExp::Pow(a1, a2) => {self.eval_aexp(a1).eval_bin_op(pow_signs, &self.eval_aexp(a2))}
+        }
+    }
+
+    /// Refine the memory under the assumption that the boolean expression `b` evaluates to `truth`, like `MultiIntervalLat::assume`: E.g. assuming `x <= 0` to be `true` restricts `x` to `NonPos`. If the assumption cannot hold, the bottom element is returned.
+    pub fn assume(&self, b: &BExp, truth: bool) -> Self {
+        if self.is_bot() {
+            return self.clone();
+        }
+        match b {
+            BExp::LessEq(a1, a2) if truth => {self.assume_le(a1, a2, 0)}
+            BExp::LessEq(a1, a2)          => {self.assume_le(a2, a1, 1)}
+            BExp::Less(a1, a2) if truth   => {self.assume_le(a1, a2, 1)}
+            BExp::Less(a1, a2)            => {self.assume_le(a2, a1, 0)}
+            BExp::Neg(b) => {self.assume(b, !truth)}
+            BExp::And(b1, b2) if truth => {self.assume(b1, true).assume(b2, true)}
+            BExp::Or(b1, b2) if !truth => {self.assume(b1, false).assume(b2, false)}
+            // Either of both operands determines the truth value
+            BExp::And(b1, b2) | BExp::Or(b1, b2) => {self.assume(b1, truth).join_bin(&self.assume(b2, truth))}
+        }
+    }
+
+    /// Helper function for `assume`: Refine the memory under the assumption `a1 + gap <= a2` (with `gap` being `0` or `1`). A variable `a1` is bounded from above by the sign of `a2`, and a variable `a2` from below by the sign of `a1`.
+    fn assume_le(&self, a1: &AExp, a2: &AExp, gap: Int) -> Self {
+        let (v1, v2) = (self.eval_aexp(a1), self.eval_aexp(a2));
+        let upper = match (v2, gap) {
+            (Neg, _) | (NonPos, 1) | (Zero, 1) => {Neg}
+            (NonPos, _) | (Zero, _)            => {NonPos}
+            _                                  => {Top}
+        };
+        let lower = match (v1, gap) {
+            (Pos, _) | (NonNeg, 1) | (Zero, 1) => {Pos}
+            (NonNeg, _) | (Zero, _)            => {NonNeg}
+            _                                  => {Top}
+        };
+        let mut m = self.clone();
+        if let AExp::Var(x) = a1 {
+            m.insert(x.clone(), v1.meet(&upper));
+        }
+        if let AExp::Var(y) = a2 {
+            let v2 = *m.lookup(y);
+            m.insert(y.clone(), v2.meet(&lower));
+        }
+        // The assumption is contradictory if a sign contradicts a bound, also for expressions other than variables (e.g. `1 <= -1`)
+        if v1.meet(&upper) == Bot || v2.meet(&lower) == Bot || m.map.values().any(|v| *v == Bot) {
+            MultiSignLat::init()
+        }
+        else {
+            m
+        }
+    }
+}
+
+impl SemiLat for MultiSignLat {
+    fn join_bin(&self, other: &Self) -> Self {
+        // Variables with a specific value in either of both maps are joined pointwise, all others via the defaults
+        let map = self.map.keys().chain(other.map.keys())
+            .map(|x| (x.clone(), self.lookup(x).join_bin(other.lookup(x))))
+            .collect();
+        MultiSignLat { map, default: self.default.join_bin(&other.default) }
+    }
+}
+
+impl FlowSemantics for MultiSignLat {
+    fn eval_transfer_function(n: &Node, mem: &Self) -> Self {
+        match n {
+            // Only assignments (and `havoc`s and phi nodes) change the memory; an unreachable node stays unreachable
+            Node::Assign(v, a) if !mem.is_bot() => {
+                let evaluated_expr = mem.eval_aexp(a);
+                let mut mem = mem.clone();
+                mem.insert(v.clone(), evaluated_expr);
+                mem
+            }
+            Node::Havoc(v) if !mem.is_bot() => {
+                let mut mem = mem.clone();
+                mem.insert(v.clone(), Top);
+                mem
+            }
+            // The value of `v` is the one of any of the arguments
+            Node::Phi(v, args) if !mem.is_bot() => {
+                let value = args.iter().fold(Bot, |acc, (_, y)| acc.join_bin(mem.lookup(y)));
+                let mut mem = mem.clone();
+                mem.insert(v.clone(), value);
+                mem
+            }
+            _ => {mem.clone()}
+        }
+    }
+
+    /// The `True` and `False` edges of a branch node refine the memory by the guard, see `assume`
+    fn eval_edge(n: &Node, mem: &Self, e: &Edge) -> Self {
+        match (n, e) {
+            (Node::Branch(b), Edge::True)  => {mem.assume(b, true)}
+            (Node::Branch(b), Edge::False) => {mem.assume(b, false)}
+            _ => {mem.clone()}
+        }
+    }
+
+    /// As for constant propagation, the input is given in `x` and all other variables are initially set to `0`.
+    fn init_start() -> Self {
+        let mut m = MultiSignLat::new(BTreeMap::new(), Zero);
+        m.insert(VarName::new("x"), Top);
+        m
+    }
+
+    /// The init element is the "bot" element of the semi-lattice, i.e. all variables are assigned to `Bot`.
+    fn init() -> Self {
+        MultiSignLat::new(BTreeMap::new(), Bot)
+    }
+}
+
+/// Pretty-printer
+impl Display for SignLat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            Top     => {write!(f, "tt")}
+            NonPos  => {write!(f, "<=0")}
+            NonZero => {write!(f, "!=0")}
+            NonNeg  => {write!(f, ">=0")}
+            Neg     => {write!(f, "<0")}
+            Zero    => {write!(f, "0")}
+            Pos     => {write!(f, ">0")}
+            Bot     => {write!(f, "bb")}
+        }
+    }
+}
+
+/// Pretty-printer
+impl Display for MultiSignLat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<")?;
+        self.map.iter().try_for_each(|(x, v)| {write!(f, "{} = {}, ", x, v)})?;
+        write!(f, "_ = {}>", self.default)
+    }
+}
@@ -0,0 +1,74 @@
+use crate::cfg::{Node, Edge, NodeIdx};
+
+use super::common::{SemiLat, FlowSemantics, AnalysisDirection};
+
+use std::{collections::BTreeSet, fmt::Display};
+
+/// # "Tracked" Lattice
+/// - Is the property space `L` where every value additionally records its provenance, i.e. the nodes that contributed to it. This helps to explain a value, e.g. why a variable is `Top` in constant propagation, without modifying the analysis itself.
+/// - A node contributes if its transfer function changes the value (see `FlowSemantics::eval_transfer_function_at`), and the provenance of a joined value is the union of the joined provenances. Thus, at a join, the provenance includes the nodes of all incoming branches.
+/// - Partial order, join, widening, narrowing, transfer functions and edge refinements: Those of `L` for `value`, while `provenance` grows by union
+#[derive(Debug,PartialEq,Clone,Eq,Hash)]
+pub struct Tracked<L> {
+    pub value: L,
+    pub provenance: BTreeSet<NodeIdx>
+}
+
+impl<L> Tracked<L> {
+    /// A value without provenance
+    pub fn new(value: L) -> Self {
+        Tracked { value, provenance: BTreeSet::new() }
+    }
+}
+
+impl<L: SemiLat> SemiLat for Tracked<L> {
+    fn join_bin(&self, other: &Self) -> Self {
+        Tracked { value: self.value.join_bin(&other.value), provenance: self.provenance.union(&other.provenance).cloned().collect() }
+    }
+
+    fn widen(&self, other: &Self) -> Self {
+        Tracked { value: self.value.widen(&other.value), provenance: self.provenance.union(&other.provenance).cloned().collect() }
+    }
+
+    fn narrow(&self, other: &Self) -> Self {
+        Tracked { value: self.value.narrow(&other.value), provenance: self.provenance.union(&other.provenance).cloned().collect() }
+    }
+}
+
+impl<L: FlowSemantics + Eq + Clone> FlowSemantics for Tracked<L> {
+    const DIRECTION: AnalysisDirection = L::DIRECTION;
+
+    /// Without the index of the node, the provenance cannot be extended
+    fn eval_transfer_function(n: &Node, x: &Self) -> Self {
+        Tracked { value: L::eval_transfer_function(n, &x.value), provenance: x.provenance.clone() }
+    }
+
+    fn eval_transfer_function_at(idx: NodeIdx, n: &Node, x: &Self) -> Self {
+        let value = L::eval_transfer_function_at(idx, n, &x.value);
+        let mut provenance = x.provenance.clone();
+        if value != x.value {
+            provenance.insert(idx);
+        }
+        Tracked { value, provenance }
+    }
+
+    fn eval_edge(n: &Node, x: &Self, e: &Edge) -> Self {
+        Tracked { value: L::eval_edge(n, &x.value, e), provenance: x.provenance.clone() }
+    }
+
+    fn init() -> Self {
+        Tracked::new(L::init())
+    }
+
+    fn init_start() -> Self {
+        Tracked::new(L::init_start())
+    }
+}
+
+/// Pretty-printer: The value of `L`, followed by the indices of the contributing nodes, e.g. `<y = tt, _ = 0> from {1, 3}`
+impl<L: Display> Display for Tracked<L> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.value, f)?;
+        write!(f, " from {{{}}}", itertools::join(self.provenance.iter().map(|n| n.index()), ", "))
+    }
+}
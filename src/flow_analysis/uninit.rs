@@ -0,0 +1,185 @@
+use crate::{common::{VarName, Int, checked_mod, checked_pow}, cfg::{self, Node, NodeIdx, Cfg}, ast::Prog};
+use crate::aexp::*;
+use serde::{Serialize, Deserialize};
+
+use super::common::{SemiLat, FlowSemantics};
+use super::mfp::{mfp, MfpAnnot};
+
+use std::{collections::BTreeMap, fmt::Display};
+use UninitLat::*;
+
+/// # "Uninitialized Constant" Lattice
+/// - Used for tracking the content of a single variable like `ConstLat`, but distinguishing a variable that was never assigned from one that holds `0`
+/// - `Uninit` represents a variable that is definitely uninitialized, i.e. that was never assigned on any path (or only assigned a value computed from uninitialized variables)
+/// - `Init` represents a variable that is definitely initialized, but whose value is unknown
+/// - `Top` represents a variable that may be uninitialized, e.g. after joining a path that leaves it uninitialized with one that assigns it
+/// - Partial order: `Bot <= Const(n) <= Init <= Top` for all `n`, and `Bot <= Uninit <= Top`
+#[derive(Debug,PartialEq,Clone,Eq,Hash,Serialize,Deserialize)]
+pub enum UninitLat {
+    Top,
+    Init,
+    Const(Int),
+    Uninit,
+    Bot
+}
+
+impl UninitLat {
+    /// Return `true` if the variable may be uninitialized
+    pub fn may_be_uninit(&self) -> bool {
+        matches!(self, Top | Uninit)
+    }
+
+    /// Helper function: Evaluate a binary operation on `UninitLat` values. An unreachable (`Bot`) operand makes the result `Bot`, otherwise an uninitialized operand makes the result `Uninit` (as computing with an uninitialized value yields garbage), and otherwise the result is as for `ConstLat` (where `Init` operands yield `Init`). The operation `f` returns `None` if the result is undefined, which yields `Init`.
+    fn eval_bin_op<F>(&self, f: F, other: &UninitLat) -> UninitLat
+    where F: Fn(Int, Int) -> Option<Int> {
+        match (self, other) {
+            (Bot, _) | (_, Bot)                 => {Bot}
+            (Uninit, _) | (_, Uninit)           => {Uninit}
+            (Top, _) | (_, Top)                 => {Top}
+            (Const(n), Const(m))                => {f(*n, *m).map_or(Init, Const)}
+            _                                   => {Init}
+        }
+    }
+}
+
+impl SemiLat for UninitLat {
+    fn join_bin(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Bot, v) | (v, Bot)                 => {v.clone()}
+            (v1, v2) if v1 == v2                => {v1.clone()}
+            (Top, _) | (_, Top)                 => {Top}
+            (Uninit, _) | (_, Uninit)           => {Top}
+            // Two different constants, or a constant and `Init`
+            _                                   => {Init}
+        }
+    }
+}
+
+/// # "Multi-Uninitialized-Constant" Lattice
+/// - Is the property space for a constant propagation analysis that keeps track of uninitialized variables, see `UninitLat`
+/// - Can be seen as a vector of `UninitLat` values, one for each variable
+/// - Internal representation:
+///     - `map`:     Map from variable names to `UninitLat` values
+///     - `default`: The value assigned to any unspecified variable
+/// - Operate on `MultiUninitLat` only via its methods
+#[derive(PartialEq,Clone,Eq,Hash,Debug,Serialize,Deserialize)]
+pub struct MultiUninitLat {
+    map: BTreeMap<VarName, UninitLat>,
+    default: UninitLat
+}
+
+impl MultiUninitLat {
+    pub fn new(map: BTreeMap<VarName, UninitLat>, default: UninitLat) -> Self { Self { map, default } }
+
+    /// Update/insert a variable value. This mutates the object.
+    pub fn insert(&mut self, x: VarName, v: UninitLat) {
+        self.map.insert(x, v);
+    }
+
+    /// Lookup a variable value.
+    pub fn lookup(&self, x: &VarName) -> &UninitLat {
+        match self.map.get(x) {
+            Some(v) => {v}
+            None => {&self.default}
+        }
+    }
+
+    /// Evaluate an arithmetic expression on a `MultiUninitLat` object.
+    pub fn eval_aexp(&self, a: &AExp) -> UninitLat {
+        match a {
+            AExp::Num(n) => {Const(*n)}
+            AExp::Var(v) => {self.lookup(v).clone()}
+            AExp::Add(a1, a2) => {self.eval_aexp(a1).eval_bin_op(|n, m| Some(n.wrapping_add(m)), &self.eval_aexp(a2))}
+            AExp::Mul(a1, a2) => {self.eval_aexp(a1).eval_bin_op(|n, m| Some(n.wrapping_mul(m)), &self.eval_aexp(a2))}
+            AExp::Mod(a1, a2) => {self.eval_aexp(a1).eval_bin_op(checked_mod, &self.eval_aexp(a2))}
+            AExp::Pow(a1, a2) => {self.eval_aexp(a1).eval_bin_op(checked_pow, &self.eval_aexp(a2))}
+        }
+    }
+}
+
+impl SemiLat for MultiUninitLat {
+    fn join_bin(&self, other: &Self) -> Self {
+        // Variables with a specific value in either of both maps are joined pointwise, all others via the defaults
+        let map = self.map.keys().chain(other.map.keys())
+            .map(|x| (x.clone(), self.lookup(x).join_bin(other.lookup(x))))
+            .collect();
+        MultiUninitLat { map, default: self.default.join_bin(&other.default) }
+    }
+}
+
+impl FlowSemantics for MultiUninitLat {
+    fn eval_transfer_function(n: &Node, mem: &Self) -> Self {
+        match n {
+            // Only assignments (and `havoc`s and phi nodes) change the memory
+            Node::Assign(v, a) => {
+                let evaluated_expr = mem.eval_aexp(a);
+                let mut mem = mem.clone();
+                mem.insert(v.clone(), evaluated_expr);
+                mem
+            }
+            Node::Havoc(v) => {
+                let mut mem = mem.clone();
+                mem.insert(v.clone(), Init);
+                mem
+            }
+            // The value of `v` is the one of any of the arguments
+            Node::Phi(v, args) => {
+                let value = args.iter().fold(Bot, |acc, (_, y)| acc.join_bin(mem.lookup(y)));
+                let mut mem = mem.clone();
+                mem.insert(v.clone(), value);
+                mem
+            }
+            _ => {mem.clone()}
+        }
+    }
+
+    /// Unlike for constant propagation, only the input `x` is initialized, and all other variables are uninitialized (instead of `0`).
+    fn init_start() -> Self {
+        let mut m = MultiUninitLat::new(BTreeMap::new(), Uninit);
+        m.insert(VarName::new("x"), Init);
+        m
+    }
+
+    /// The init element is the "bot" element of the semi-lattice, i.e. all variables are assigned to `Bot`.
+    fn init() -> Self {
+        MultiUninitLat::new(BTreeMap::new(), Bot)
+    }
+}
+
+/// Return the reads of variables that may be uninitialized, together with the reading nodes and the value of the variable before the node, which is `Uninit` if the variable is definitely uninitialized and `Top` if it is uninitialized on some path only. The result is sorted by the node index and the variable name.
+/// Unlike `undefined_reads`, this also reports variables that were assigned a value computed from uninitialized variables. As for `undefined_reads`, the nodes that save and restore the outer values of `let` variables are skipped (see `Node::is_let_copy`).
+pub fn uninit_reads(prog: &Prog) -> Vec<(VarName, NodeIdx, UninitLat)> {
+    let cfg: Cfg<MfpAnnot<MultiUninitLat>> = mfp(&cfg::ast_to_cfg(prog));
+    let mut reads: Vec<(VarName, NodeIdx, UninitLat)> = cfg.graph.node_indices().filter(|n| !cfg.graph[*n].node.is_let_copy()).flat_map(|n| {
+        let annot = &cfg.graph[n].annot;
+        cfg.graph[n].node.read_vars().into_iter()
+            .map(|x| (annot.pre().lookup(&x).clone(), x))
+            .filter(|(v, _)| v.may_be_uninit())
+            .map(|(v, x)| (x, n, v))
+            .collect::<Vec<_>>()
+    }).collect();
+    reads.sort_by(|(x1, n1, _), (x2, n2, _)| n1.cmp(n2).then(x1.cmp(x2)));
+    reads
+}
+
+/// Pretty-printer
+impl Display for UninitLat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            Top => {write!(f, "tt")}
+            Init => {write!(f, "init")}
+            Const(c) => {write!(f, "{}", c)}
+            Uninit => {write!(f, "uninit")}
+            Bot => {write!(f, "bb")}
+        }
+    }
+}
+
+/// Pretty-printer
+impl Display for MultiUninitLat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<")?;
+        self.map.iter().try_for_each(|(x, v)| {write!(f, "{} = {}, ", x, v)})?;
+        write!(f, "_ = {}>", self.default)
+    }
+}
@@ -1,87 +1,463 @@
-use std::collections::HashMap;
+use std::{collections::{HashMap, HashSet}, fmt::Display, hash::{Hash, Hasher}, ops::RangeInclusive};
 
-use crate::{ast::{Prog, ProgAtom, ProgAtom::*}, aexp::AExp, aexp::AExp::*, bexp::BExp::*, common::VarName, bexp::BExp};
+use crate::{ast::{Prog, ProgAtom, ProgAtom::*}, aexp::AExp, aexp::AExp::*, bexp::BExp::*, common::{VarName, Int, SplitMix64, checked_mod, checked_pow}, bexp::BExp};
+use crate::cfg::{Cfg, Node, Edge, NodeIdx};
+use petgraph::{EdgeDirection::Outgoing, visit::EdgeRef};
 
-/// This struct represents a memory configuration. Each variable is assigned an `i32` via a `HashMap`; if there is no entry in the `HashMap`, then the assignment is `0`.
-#[derive(Debug)]
-pub struct MemConfig(HashMap<VarName, i32>);
+/// This struct represents a memory configuration. Each variable is assigned an `Int` via a `HashMap`; if there is no entry in the `HashMap`, then the assignment is `0`.
+/// Equality and hashing follow this semantics, i.e. an entry `x = 0` is the same as no entry for `x`.
+#[derive(Debug,Clone)]
+pub struct MemConfig(HashMap<VarName, Int>);
+
+impl PartialEq for MemConfig {
+    fn eq(&self, other: &Self) -> bool {
+        let MemConfig(map) = self;
+        let MemConfig(other_map) = other;
+        map.keys().chain(other_map.keys()).all(|x| self.lookup(x) == other.lookup(x))
+    }
+}
+
+impl Eq for MemConfig {}
+
+impl Hash for MemConfig {
+    /// Hash the non-zero entries in the order of their variables, so that equal memory configurations have equal hashes
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let MemConfig(map) = self;
+        let mut entries: Vec<(&VarName, &Int)> = map.iter().filter(|(_, n)| **n != 0).collect();
+        entries.sort();
+        entries.hash(state)
+    }
+}
 
 impl MemConfig {
     pub fn new() -> Self { Self(HashMap::new()) }
     
     /// Read operation (with `0` as default value)
-    pub fn lookup(&self, x: &VarName) -> i32 {
+    pub fn lookup(&self, x: &VarName) -> Int {
         let MemConfig(map) = self;
         return *map.get(x).unwrap_or(&(0));
     }
 
     /// Write operation
-    pub fn assign(&mut self, x: &VarName, n: i32) {
+    pub fn assign(&mut self, x: &VarName, n: Int) {
         let MemConfig(map) = self;
         map.insert(x.clone(), n);
     }
+
+    /// Iterate over the variables that were assigned, together with their values (in arbitrary order)
+    pub fn entries(&self) -> impl Iterator<Item = (&VarName, Int)> {
+        let MemConfig(map) = self;
+        map.iter().map(|(x, n)| (x, *n))
+    }
+
+    /// Return `true` if both memory configurations agree on the variables `vars` (unassigned variables being `0`)
+    pub fn eq_on(&self, other: &MemConfig, vars: &[VarName]) -> bool {
+        vars.iter().all(|x| self.lookup(x) == other.lookup(x))
+    }
 }
 
 /// Input: Program + Assignment to "x" variable
 /// Output:
 /// - If `p` terminates: Assignment to "y" variable
 /// - If `p` diverges: This function diverges, too 
-pub fn eval(p: &Prog, input: i32) -> i32 {
+///
+/// Apart from diverging, this function is total: Arithmetic wraps around on overflow and a call of an undefined procedure (which is rejected by the parser) behaves like `skip`.
+pub fn eval(p: &Prog, input: Int) -> Int {
     let mut mem = MemConfig::new();
     mem.assign(&VarName::new("x"), input);
     mem = eval_prog(p, mem);
     mem.lookup(&VarName::new("z"))
 }
 
+/// The procedures that can be called, indexed by their name
+pub type Procs<'a> = HashMap<VarName, &'a Prog>;
+
 /// Evaluate program on given memory configuration. This functin may diverge.
+/// The procedures defined at the top level of `p` can be called.
 pub fn eval_prog(p: &Prog, mem: MemConfig) -> MemConfig {
+    eval_prog_with(p, &p.procs(), mem)
+}
+
+/// Evaluate program on given memory configuration, where `procs` are the procedures that can be called. This function may diverge.
+pub fn eval_prog_with(p: &Prog, procs: &Procs, mem: MemConfig) -> MemConfig {
+    // Without an observer that aborts, evaluation cannot be aborted
+    eval_prog_observed(p, procs, mem, &mut |_| true).unwrap_or_else(|Aborted| unreachable!())
+}
+
+/// How the evaluation of a statement ends, see `eval_prog_atom`
+/// - `Normal`:   The evaluation continues with the next statement
+/// - `Break`:    A `break` was executed, i.e. the evaluation continues after the innermost enclosing loop
+/// - `Continue`: A `continue` was executed, i.e. the evaluation continues with the guard of the innermost enclosing loop
+#[derive(Debug,PartialEq,Clone,Copy,Eq)]
+pub enum Completion {
+    Normal,
+    Break,
+    Continue
+}
+
+/// Evaluate atomic program on given memory configuration, where `procs` are the procedures that can be called. This function may diverge.
+/// Besides the memory configuration, the result tells whether the statement ended by a `break` or `continue` (which only happens for a statement in the body of a loop) or normally.
+pub fn eval_prog_atom(p: &ProgAtom, procs: &Procs, mem: MemConfig) -> (MemConfig, Completion) {
+    eval_prog_atom_observed(p, procs, mem, &mut |_| true).unwrap_or_else(|Aborted| unreachable!())
+}
+
+/// The evaluation was aborted by the step observer.
+#[derive(Debug,PartialEq,Clone,Copy,Eq)]
+pub struct Aborted;
+
+/// Evaluate program on given memory configuration, where `procs` are the procedures that can be called.
+/// The step observer `on_step` is invoked with the current memory configuration before every iteration of a loop and before every procedure call, i.e. at least once per potentially diverging step. If it returns `false`, the evaluation is aborted.
+pub fn eval_prog_observed<F>(p: &Prog, procs: &Procs, mem: MemConfig, on_step: &mut F) -> Result<MemConfig, Aborted>
+where F: FnMut(&MemConfig) -> bool {
+    // A `break` or `continue` outside of loops (which is rejected by the parser) ends the program
+    eval_prog_located(p, procs, mem, &mut vec![], &mut |_, mem| on_step(mem)).map(|(mem, _)| mem)
+}
+
+/// Evaluate atomic program on given memory configuration, where `procs` are the procedures that can be called. See `eval_prog_observed` for the step observer `on_step` and `eval_prog_atom` for the result.
+/// Calling an undefined procedure (which cannot happen for parsed programs) behaves like `skip`.
+pub fn eval_prog_atom_observed<F>(p: &ProgAtom, procs: &Procs, mem: MemConfig, on_step: &mut F) -> Result<(MemConfig, Completion), Aborted>
+where F: FnMut(&MemConfig) -> bool {
+    eval_prog_atom_located(p, procs, mem, &mut vec![], &mut |_, mem| on_step(mem))
+}
+
+/// The location of a step, see `eval_prog_located`: The active procedure calls and `let` blocks (outermost first), followed by the loop or call that is about to be executed. A `let` block comes with the outer value of its variable (`None` if unassigned), which is restored at its end, so that the location and the memory configuration together determine the rest of the evaluation.
+type Location<'a> = Vec<(&'a ProgAtom, Option<Int>)>;
+
+/// Like `eval_prog_observed`, but the step observer additionally gets the location of the step (see `Location`), where `calls` holds the active procedure calls and `let` blocks.
+/// The statements after a `break` or `continue` are skipped, and the completion is passed on to the enclosing loop.
+fn eval_prog_located<'a, F>(p: &'a Prog, procs: &Procs<'a>, mut mem: MemConfig, calls: &mut Location<'a>, on_step: &mut F) -> Result<(MemConfig, Completion), Aborted>
+where F: FnMut(&[(&'a ProgAtom, Option<Int>)], &MemConfig) -> bool {
     let Prog::Prog(ps) = p;
-    ps.iter().fold(mem, |mem,p| eval_prog_atom(p, mem))
+    for p in ps {
+        let (next, completion) = eval_prog_atom_located(p, procs, mem, calls, on_step)?;
+        mem = next;
+        if completion != Completion::Normal {
+            return Ok((mem, completion));
+        }
+    }
+    Ok((mem, Completion::Normal))
+}
+
+/// Helper function for `eval_prog_located`: Invoke the step observer before the step `p`
+fn observe<'a, F>(p: &'a ProgAtom, mem: &MemConfig, calls: &mut Location<'a>, on_step: &mut F) -> Result<(), Aborted>
+where F: FnMut(&[(&'a ProgAtom, Option<Int>)], &MemConfig) -> bool {
+    calls.push((p, None));
+    let proceed = on_step(calls, mem);
+    calls.pop();
+    if proceed { Ok(()) } else { Err(Aborted) }
 }
 
-/// Evaluate atomic program on given memory configuration. This function may diverge.
-pub fn eval_prog_atom(p: &ProgAtom, mut mem: MemConfig) -> MemConfig {
+/// Atomic version of `eval_prog_located`
+fn eval_prog_atom_located<'a, F>(p: &'a ProgAtom, procs: &Procs<'a>, mut mem: MemConfig, calls: &mut Location<'a>, on_step: &mut F) -> Result<(MemConfig, Completion), Aborted>
+where F: FnMut(&[(&'a ProgAtom, Option<Int>)], &MemConfig) -> bool {
     match p {
-        Skip => { mem }
+        Skip => { Ok((mem, Completion::Normal)) }
         Assign(x, a) => {
             let n = eval_aexp(a, &mem);
             mem.assign(x, n);
-            mem
+            Ok((mem, Completion::Normal))
         }
+        // Without an input oracle, the arbitrary value is always `0`
+        Havoc(x) => {
+            mem.assign(x, 0);
+            Ok((mem, Completion::Normal))
+        }
+        Break => { Ok((mem, Completion::Break)) }
+        Continue => { Ok((mem, Completion::Continue)) }
         Cond(b, p1, p2) => {
             let result = eval_bexp(b, &mem);
             return if result {
-                eval_prog(p1, mem)
+                eval_prog_located(p1, procs, mem, calls, on_step)
             } else {
-                eval_prog(p2, mem)
+                eval_prog_located(p2, procs, mem, calls, on_step)
             }
         }
-        While(b, p) => {
-            //TODO maybe fix later clone mem
+        // A `break` leaves the loop, whereas a `continue` merely ends the current iteration
+        While(b, body) => {
             while eval_bexp(b,&mem) {
-                mem = eval_prog(p,mem);
+                observe(p, &mem, calls, on_step)?;
+                let (next, completion) = eval_prog_located(body, procs, mem, calls, on_step)?;
+                mem = next;
+                if completion == Completion::Break { break }
+            }
+            return Ok((mem, Completion::Normal));
+        }
+        Repeat(body, b) => {
+            loop {
+                observe(p, &mem, calls, on_step)?;
+                let (next, completion) = eval_prog_located(body, procs, mem, calls, on_step)?;
+                mem = next;
+                if completion == Completion::Break || eval_bexp(b, &mem) { return Ok((mem, Completion::Normal)) }
+            }
+        }
+        Let(x, a, body) => {
+            // Remember the outer binding (`None` if `x` is unassigned), so that the scoped value does not leak out of the block
+            let outer = mem.0.get(x).cloned();
+            let n = eval_aexp(a, &mem);
+            mem.assign(x, n);
+            // The outer binding is part of the location of the steps in the block. It is also restored if the block is left by a `break` or `continue`.
+            calls.push((p, outer));
+            let result = eval_prog_located(body, procs, mem, calls, on_step);
+            calls.pop();
+            let (mut mem, completion) = result?;
+            match outer {
+                Some(n) => { mem.assign(x, n) }
+                None => { mem.0.remove(x); }
+            }
+            Ok((mem, completion))
+        }
+        // Procedure definitions have been collected beforehand, they do nothing by themselves.
+        Proc(_, _) => { Ok((mem, Completion::Normal)) }
+        Call(name) => {
+            observe(p, &mem, calls, on_step)?;
+            match procs.get(name) {
+                Some(body) => {
+                    calls.push((p, None));
+                    // A `break` or `continue` cannot leave a procedure body (this is rejected by the parser)
+                    let result = eval_prog_located(body, procs, mem, calls, on_step);
+                    calls.pop();
+                    result.map(|(mem, _)| (mem, Completion::Normal))
+                }
+                None => { Ok((mem, Completion::Normal)) }
             }
-            return mem;
         }
     }
 }
 
-/// Evaluate arithmetic expression on given memory configuration. This function always returns.
-pub fn eval_aexp(a: &AExp, mem: &MemConfig) -> i32 {
-    match a {
-        Num(n) => { *n }
-        Var(x) => { mem.lookup(x) }
-        Add(a1, a2) => { eval_aexp(a1, mem) + eval_aexp(a2, mem) }
-        Mul(a1, a2) => { eval_aexp(a1, mem) * eval_aexp(a2, mem) }
+/// Like `eval`, but the callback `on_step` is invoked with the current memory configuration before every loop iteration and procedure call (see `eval_prog_observed`). If it returns `false`, the evaluation is aborted. This allows for custom termination heuristics, e.g. a step budget (see `eval_bounded`) or detecting a memory configuration that repeats.
+pub fn eval_with_callback<F>(p: &Prog, input: Int, mut on_step: F) -> Result<Int, Aborted>
+where F: FnMut(&MemConfig) -> bool {
+    let mut mem = MemConfig::new();
+    mem.assign(&VarName::new("x"), input);
+    let mem = eval_prog_observed(p, &p.procs(), mem, &mut on_step)?;
+    Ok(mem.lookup(&VarName::new("z")))
+}
+
+/// Like `eval`, but returns `None` instead of diverging: The evaluation is aborted after `max_steps` steps (loop iterations and procedure calls).
+pub fn eval_bounded(p: &Prog, input: Int, max_steps: u64) -> Option<Int> {
+    let mut steps = 0;
+    eval_with_callback(p, input, |_| { steps += 1; steps <= max_steps }).ok()
+}
+
+/// The evaluation was found to diverge, see `eval_detect_loops`.
+#[derive(Debug,PartialEq,Clone,Copy,Eq)]
+pub struct NonTerminating;
+
+/// Like `eval`, but returns `NonTerminating` instead of diverging if the evaluation reaches the same state twice: Before every loop iteration and procedure call, the memory configuration is recorded together with the location (the loop or call, and the active procedure calls and `let` blocks with the outer values they restore, see `Location`). As the semantics is deterministic, reaching the same location with the same memory configuration again means that the evaluation repeats itself forever.
+/// Evaluations that diverge without repeating a state are not detected, so this function may still diverge. In particular, this is the case for an infinite recursion, where the active procedure calls keep growing. All states are kept, i.e. the memory consumption grows with the number of steps.
+pub fn eval_detect_loops(p: &Prog, input: Int) -> Result<Int, NonTerminating> {
+    let mut mem = MemConfig::new();
+    mem.assign(&VarName::new("x"), input);
+    // Rust Expl.: Locations are identified by the addresses of their AST nodes, which do not move during the evaluation.
+    type State = (Vec<(*const ProgAtom, Option<Int>)>, MemConfig);
+    let mut seen: HashSet<State> = HashSet::new();
+    let (mem, _) = eval_prog_located(p, &p.procs(), mem, &mut vec![], &mut |location, mem| {
+        let location = location.iter().map(|(p, outer)| (*p as *const ProgAtom, *outer)).collect();
+        seen.insert((location, mem.clone()))
+    }).map_err(|Aborted| NonTerminating)?;
+    Ok(mem.lookup(&VarName::new("z")))
+}
+
+/// Run `eval_bounded` on every input of the given range, returning the input/output pairs (where the output is `None` if the evaluation was aborted).
+pub fn eval_range(p: &Prog, inputs: RangeInclusive<Int>, max_steps: u64) -> Vec<(Int, Option<Int>)> {
+    inputs.map(|x| (x, eval_bounded(p, x, max_steps))).collect()
+}
+
+/// Run `eval_bounded` on `samples` random inputs from the given (non-empty) range, which are drawn by a `SplitMix64` generator with the given seed. As for `eval_range`, the input/output pairs are returned, in the order in which the inputs were drawn.
+pub fn eval_random(p: &Prog, inputs: RangeInclusive<Int>, samples: usize, seed: u64, max_steps: u64) -> Vec<(Int, Option<Int>)> {
+    let mut rng = SplitMix64::new(seed);
+    (0..samples).map(|_| {
+        let x = rng.next_in(&inputs);
+        (x, eval_bounded(p, x, max_steps))
+    }).collect()
+}
+
+/// Statistics on the outputs of a program for a number of inputs, see `output_stats`
+/// - `samples`:   Number of inputs
+/// - `diverging`: Number of inputs for which the evaluation was aborted
+/// - `min`, `max` and `mean`: Minimal, maximal and mean output of the other inputs (`None` if there are none)
+#[derive(Debug,PartialEq,Clone)]
+pub struct OutputStats {
+    pub samples: usize,
+    pub diverging: usize,
+    pub min: Option<Int>,
+    pub max: Option<Int>,
+    pub mean: Option<f64>
+}
+
+/// Compute statistics on the input/output pairs returned by `eval_range` or `eval_random`
+pub fn output_stats(results: &[(Int, Option<Int>)]) -> OutputStats {
+    let outputs: Vec<Int> = results.iter().filter_map(|(_, z)| *z).collect();
+    // The sum is computed with 128 bits, so that it cannot overflow
+    let mean = if outputs.is_empty() { None } else { Some(outputs.iter().map(|z| *z as i128).sum::<i128>() as f64 / outputs.len() as f64) };
+    OutputStats {
+        samples: results.len(),
+        diverging: results.len() - outputs.len(),
+        min: outputs.iter().min().cloned(),
+        max: outputs.iter().max().cloned(),
+        mean
+    }
+}
+
+/// Pretty-printer, e.g.:
+/// ```text
+/// samples: 100 (diverging: 2)
+/// min: -4
+/// max: 17
+/// mean: 6.35
+/// ```
+/// The mean is rounded to two decimal places. If every evaluation was aborted, the last three lines are omitted.
+impl Display for OutputStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "samples: {} (diverging: {})", self.samples, self.diverging)?;
+        if let (Some(min), Some(max), Some(mean)) = (self.min, self.max, self.mean) {
+            write!(f, "\nmin: {}\nmax: {}\nmean: {:.2}", min, max, mean)?;
+        }
+        Ok(())
     }
 }
 
+/// Check whether the programs `p1` and `p2` agree on the final values of the variables `vars` for every input `x` in `range`. See `counterexample_loopfree` for the precondition.
+pub fn equivalent_loopfree(p1: &Prog, p2: &Prog, vars: &[VarName], range: RangeInclusive<Int>) -> bool {
+    counterexample_loopfree(p1, p2, vars, range).is_none()
+}
+
+/// Return the first input `x` in `range` for which the programs `p1` and `p2` disagree on the final value of one of the variables `vars`, or `None` if there is no such input.
+/// Both programs are run on every input, so they must terminate: This is guaranteed for loop-free programs without recursive procedures; otherwise, this function may diverge.
+pub fn counterexample_loopfree(p1: &Prog, p2: &Prog, vars: &[VarName], range: RangeInclusive<Int>) -> Option<Int> {
+    let run = |p: &Prog, x| {
+        let mut mem = MemConfig::new();
+        mem.assign(&VarName::new("x"), x);
+        eval_prog(p, mem)
+    };
+    range.into_iter().find(|x| !run(p1, *x).eq_on(&run(p2, *x), vars))
+}
+
+/// Input: CFG + Assignment to "x" variable
+/// Output: Like `eval`, but the CFG is executed instead of an AST: Starting at the init node, the nodes are applied to the memory configuration and the outgoing edges are followed (at a `Branch` node, the edge corresponding to the value of the guard is taken). Execution stops at a node without outgoing edges.
+/// A phi node (see `ssa::to_ssa`) assigns the argument of the last node before its chain of phi nodes.
+/// As the CFG does not tell which call a procedure returns to, the evaluation fails with `AmbiguousSuccessor` when it reaches the `Exit` node of a procedure that is called more than once (which has a `Return` edge for every call). This function may diverge.
+/// It panics if the init node is not part of the CFG, which cannot happen for CFGs produced by `ast_to_cfg`.
+pub fn eval_cfg<A>(cfg: &Cfg<A>, input: Int) -> Result<Int, AmbiguousSuccessor> {
+    let mut mem = MemConfig::new();
+    mem.assign(&VarName::new("x"), input);
+    Ok(eval_cfg_mem(cfg, mem)?.lookup(&VarName::new("z")))
+}
+
+/// The evaluation of a CFG reached a node (other than a `Branch` node) with more than one successor, so that the next node is not determined, see `eval_cfg`.
+#[derive(Debug,PartialEq,Clone,Copy,Eq)]
+pub struct AmbiguousSuccessor(pub NodeIdx);
+
+/// Like `eval_cfg`, but starting from the memory configuration `mem` and returning the final one (cf. `eval_prog`), e.g. for reading another variable than `z`, such as the version of `z` in SSA form (see `ssa::to_ssa_with_exit_versions`).
+pub fn eval_cfg_mem<A>(cfg: &Cfg<A>, mut mem: MemConfig) -> Result<MemConfig, AmbiguousSuccessor> {
+    let mut n = cfg.init;
+    // The last node that is not a phi node, which selects the arguments of the phi nodes
+    let mut pred = cfg.init;
+    loop {
+        // Execute the node. At a `Branch`, this determines the edge to take.
+        let branch_edge = match &cfg.graph[n].node {
+            Node::Assign(x, a) => {
+                let v = eval_aexp(a, &mem);
+                mem.assign(x, v);
+                None
+            }
+            Node::Havoc(x) => {
+                mem.assign(x, 0);
+                None
+            }
+            Node::Phi(x, args) => {
+                let (_, y) = args.iter().find(|(p, _)| *p == pred)
+                    .unwrap_or_else(|| panic!("The phi node {} has no argument for the predecessor {}.", cfg.graph[n].node, cfg.graph[pred].node));
+                let v = mem.lookup(y);
+                mem.assign(x, v);
+                None
+            }
+            Node::Branch(b) => { Some(if eval_bexp(b, &mem) { Edge::True } else { Edge::False }) }
+            _ => { None }
+        };
+        let mut edges = cfg.graph.edges_directed(n, Outgoing);
+        let next = match branch_edge {
+            Some(edge) => { edges.find(|e| *e.weight() == edge) }
+            None => {
+                // All other nodes have at most one successor, except for the `Exit` node of a procedure that is called more than once
+                let next = edges.next();
+                if edges.next().is_some() {
+                    return Err(AmbiguousSuccessor(n))
+                }
+                next
+            }
+        };
+        if !matches!(cfg.graph[n].node, Node::Phi(_, _)) {
+            pred = n;
+        }
+        match next {
+            Some(e) => { n = e.target() }
+            None => { return Ok(mem) }
+        }
+    }
+}
+
+/// Evaluate arithmetic expression on given memory configuration. This function always returns; on overflow, arithmetic wraps around.
+/// It does not recurse but uses an explicit stack, so that it can deal with deeply nested expressions.
+pub fn eval_aexp(a: &AExp, mem: &MemConfig) -> Int {
+    eval_aexp_observed(a, mem, |_, _| {})
+}
+
+/// Evaluate arithmetic expression on given memory configuration like `eval_aexp`, additionally returning every sub-expression together with its value in post-order (i.e. the order in which the values are computed, left operands first). The last step is the whole expression. This shows how an expression is evaluated step by step, e.g. for teaching.
+pub fn eval_aexp_traced(a: &AExp, mem: &MemConfig) -> (Int, Vec<(AExp, Int)>) {
+    let mut steps = vec![];
+    let n = eval_aexp_observed(a, mem, |a, n| steps.push((a.clone(), n)));
+    (n, steps)
+}
+
+/// Evaluate arithmetic expression, calling `on_value` for every sub-expression as soon as its value is computed
+fn eval_aexp_observed<'a, F>(a: &'a AExp, mem: &MemConfig, mut on_value: F) -> Int
+where F: FnMut(&'a AExp, Int) {
+    // A task is either to evaluate a sub-expression (pushing its value on `values`), or to combine the two topmost `values` into the value of the given `Add`/`Mul`/`Mod`/`Pow` expression.
+    enum Task<'a> { Eval(&'a AExp), Combine(&'a AExp) }
+    let mut tasks = vec![Task::Eval(a)];
+    let mut values: Vec<Int> = vec![];
+    while let Some(task) = tasks.pop() {
+        let (a, n) = match task {
+            Task::Eval(a @ Num(n)) => { (a, *n) }
+            Task::Eval(a @ Var(x)) => { (a, mem.lookup(x)) }
+            // The right operand is pushed first, so that the left operand is evaluated first.
+            Task::Eval(a @ Add(a1, a2)) | Task::Eval(a @ Mul(a1, a2)) | Task::Eval(a @ Mod(a1, a2)) | Task::Eval(a @ Pow(a1, a2)) => {
+                tasks.extend(vec![Task::Combine(a), Task::Eval(a2), Task::Eval(a1)]);
+                continue;
+            }
+            Task::Combine(a) => {
+                // Rust Expl.: Two operands must be present, since every combination task is pushed below the evaluation tasks of its operands.
+                let n2 = values.pop().unwrap();
+                let n1 = values.pop().unwrap();
+                (a, match a { Mul(_, _) => { n1.wrapping_mul(n2) } Mod(_, _) => { eval_mod(n1, n2) } Pow(_, _) => { eval_pow(n1, n2) } _ => { n1.wrapping_add(n2) } })
+            }
+        };
+        on_value(a, n);
+        values.push(n);
+    }
+    values.pop().unwrap()
+}
+
+/// The modulo operation of the interpreter, see `checked_mod`. In order to keep the interpreter total, `n % 0` evaluates to `n` (which is the remainder of `n = q*0 + n`); the analyses do not rely on this and consider the result as unknown.
+fn eval_mod(n: Int, m: Int) -> Int {
+    checked_mod(n, m).unwrap_or(n)
+}
+
+/// The exponentiation of the interpreter, see `checked_pow`. In order to keep the interpreter total, a negative exponent yields `0` (which is `1 / n^|m|` rounded towards zero, unless `n` is `1` or `-1`); the analyses do not rely on this and consider the result as unknown.
+fn eval_pow(n: Int, m: Int) -> Int {
+    checked_pow(n, m).unwrap_or(0)
+}
+
 /// Evaluate boolean expression on given memory configuration. This function always returns.
 pub fn eval_bexp(a: &BExp, mem: &MemConfig) -> bool {
     return match a {
         LessEq(a1, a2) => {
             eval_aexp(a1, mem) <= eval_aexp(a2, mem)
         }
+        Less(a1, a2) => {
+            eval_aexp(a1, mem) < eval_aexp(a2, mem)
+        }
         Or(b1,b2) => {
             eval_bexp(b1, mem) || eval_bexp(b2, mem)
         }
@@ -1,8 +1,28 @@
+//! Without the (default) `std` feature, the crate is `no_std` and consists of the expression and AST types only (`common`, `aexp`, `bexp` and `ast`), which need nothing but an allocator.
+#![cfg_attr(not(feature = "std"), no_std)]
+extern crate alloc;
+
 pub mod common;
 pub mod aexp;
 pub mod bexp;
 pub mod ast;
+#[cfg(feature = "std")]
 pub mod parser;
+#[cfg(feature = "std")]
 pub mod cfg;
+#[cfg(feature = "std")]
+pub mod dot;
+#[cfg(feature = "std")]
 pub mod interpreter;
-pub mod flow_analysis;
\ No newline at end of file
+#[cfg(feature = "std")]
+pub mod wp;
+#[cfg(feature = "std")]
+pub mod tac;
+#[cfg(feature = "std")]
+pub mod ssa;
+#[cfg(feature = "std")]
+pub mod playground;
+#[cfg(feature = "std")]
+pub mod flow_analysis;
+#[cfg(feature = "binary")]
+pub mod binary;
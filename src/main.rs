@@ -1,10 +1,12 @@
 extern crate nom;
 
-use flanelly::{cfg::Cfg, parser, cfg};
-use petgraph::dot::Dot;
-use flanelly::flow_analysis::{mfp:: mfp, mfp::MfpAnnot, const_prop::MultiConstLat, avail_exp::ExpSetLat};
-use flanelly::interpreter::eval;
-use std::io::{self, Read};
+use flanelly::{cfg::{Cfg, RawAnnot}, parser, cfg, ast, common::{Int, VarName}};
+use flanelly::dot::{to_dot, DotOptions, EdgeStyle, DotEngine};
+use flanelly::flow_analysis::{mfp::{mfp_from, mfp_explained_from, compact_annots, MfpOptions}, mfp::MfpAnnot, common::{SemiLat, FlowSemantics}, const_prop::{ConstLat, MultiConstLat, soundness_counterexamples, highlight_consts, top_origin}, avail_exp::ExpSetLat, finite_set::MultiFiniteSetLat};
+use flanelly::interpreter::{eval, eval_range, eval_random, output_stats};
+use flanelly::flow_analysis::{def_vars::undefined_reads, uninit::{uninit_reads, UninitLat}};
+use flanelly::flow_analysis::{mfp::{mfp_with, mfp_widening_with, diff_annots}, common::Unrefined, interval::MultiIntervalLat, sign::MultiSignLat, affine::MultiAffineLat};
+use std::{io::{self, Read}, fmt::Display};
 use clap::{Arg, App};
 
 fn main() -> io::Result<()> {
@@ -17,42 +19,323 @@ fn main() -> io::Result<()> {
       .arg(Arg::with_name("avail_exp")
            .short("a")
            .help("available expressions"))
+      .arg(Arg::with_name("finite_set")
+           .short("f")
+           .help("constant propagation tracking sets of possible values"))
       .arg(Arg::with_name("interpret")
            .short("i")
            .help("interpret")
            .takes_value(true)
            .allow_hyphen_values(true))
+      .arg(Arg::with_name("sweep")
+           .long("sweep")
+           .help("interpret for every input in the inclusive range min,max")
+           .takes_value(true)
+           .value_name("min,max")
+           .allow_hyphen_values(true))
+      .arg(Arg::with_name("compare")
+           .long("compare")
+           .help("check constant propagation against the interpreter for every input in the inclusive range min,max and report unsound constants")
+           .takes_value(true)
+           .value_name("min,max")
+           .allow_hyphen_values(true))
+      .arg(Arg::with_name("diff_analyses")
+           .long("diff-analyses")
+           .help("run two analyses and print the nodes where their annotations differ; the analyses are const, avail, finite, interval, sign or affine, each optionally with the suffix -unrefined to ignore the guards of branches")
+           .takes_value(true)
+           .value_name("first,second"))
+      .arg(Arg::with_name("input_distribution")
+           .long("input-distribution")
+           .help("interpret for random inputs in the inclusive range min,max and print statistics on the outputs")
+           .takes_value(true)
+           .value_name("min,max")
+           .allow_hyphen_values(true))
+      .arg(Arg::with_name("samples")
+           .long("samples")
+           .help("number of random inputs (for --input-distribution)")
+           .takes_value(true)
+           .default_value("1000"))
+      .arg(Arg::with_name("rng_seed")
+           .long("rng-seed")
+           .help("seed of the random number generator (for --input-distribution)")
+           .takes_value(true)
+           .default_value("0"))
+      .arg(Arg::with_name("max_steps")
+           .long("max-steps")
+           .help("number of loop iterations and procedure calls after which a program is considered to diverge (for --sweep, --compare and --input-distribution)")
+           .takes_value(true)
+           .default_value("100000"))
+      .arg(Arg::with_name("explain")
+           .long("explain")
+           .help("print the iterations of the MFP worklist algorithm to StdErr"))
+      .arg(Arg::with_name("seed")
+           .long("seed")
+           .help("start constant propagation (-c or -f) with the given inputs as constants, e.g. x=5,n=10")
+           .takes_value(true)
+           .conflicts_with_all(&["avail_exp", "compare", "sweep", "input_distribution", "highlight", "interpret", "diff_analyses", "graph_stats"]))
+      .arg(Arg::with_name("fail_on_top")
+           .long("fail-on-top")
+           .help("after constant propagation, exit with an error naming the node where the given output variable (default z) first became tt if it is tt at the end of the program")
+           .takes_value(true)
+           .min_values(0)
+           .value_name("var"))
+      .arg(Arg::with_name("graph_stats")
+           .long("graph-stats")
+           .help("print statistics on the CFG instead of analyzing it"))
+      .arg(Arg::with_name("compact_annots")
+           .long("compact-annots")
+           .help("omit the pre value of a node in the DOT output if it equals the post value of every predecessor"))
+      .arg(Arg::with_name("max_loop_unroll")
+           .long("max-loop-unroll")
+           .help("unroll every while loop the given number of times (at most 16) before the analysis")
+           .takes_value(true)
+           .value_name("k"))
+      .arg(Arg::with_name("only_reachable")
+           .long("only-reachable")
+           .help("only analyze the nodes that are reachable from the init node"))
+      .arg(Arg::with_name("warn_undefined")
+           .long("warn-undefined")
+           .help("warn about variables that may be read before being assigned"))
+      .arg(Arg::with_name("warn_uninit")
+           .long("warn-uninit")
+           .help("warn about reads of variables that may be uninitialized, including variables that hold values computed from uninitialized variables"))
+      .arg(Arg::with_name("highlight")
+           .long("highlight")
+           .help("print the program with a comment on every line after which the given variable is a constant")
+           .takes_value(true)
+           .value_name("var"))
+      .arg(Arg::with_name("edge_style")
+           .long("edge-style")
+           .help("style of the edges in the DOT output")
+           .takes_value(true)
+           .possible_values(&["labelled", "numbered", "colored"])
+           .default_value("labelled"))
+      .arg(Arg::with_name("dot_engine")
+           .long("dot-engine")
+           .help("Graphviz layout engine to request in the DOT output")
+           .takes_value(true)
+           .possible_values(&["dot", "neato", "fdp", "circo"]))
+      .arg(Arg::with_name("cluster_loops")
+           .long("cluster-loops")
+           .help("group the nodes of each loop in a cluster in the DOT output"))
+      .arg(Arg::with_name("node_indices")
+           .long("node-indices")
+           .help("prefix every node label by the index of the node in the DOT output"))
       .get_matches();
 
   // Read program from StdIn and parse AST
   let mut program_buffer = String::new();
   io::stdin().read_to_string(&mut program_buffer)?;
-  let p = parser::parse(&program_buffer).unwrap();
+  let (p, diagnostics) = parser::parse_with_diagnostics(&program_buffer);
+  diagnostics.iter().for_each(|d| eprintln!("Warning: {}", d.message));
+  let p = p.unwrap_or_else(|e| exit_with_error(&e));
+
+  if arguments.is_present("warn_undefined") {
+    undefined_reads(&p).iter().for_each(|(x, _)| {
+      eprintln!("Warning: The variable {} may be read before being assigned.", x)
+    });
+  }
+
+  if arguments.is_present("warn_uninit") {
+    let cfg = cfg::ast_to_cfg(&p);
+    uninit_reads(&p).iter().for_each(|(x, n, v)| {
+      let certainty = if *v == UninitLat::Uninit { "is" } else { "may be" };
+      eprintln!("Warning: The variable {} {} uninitialized when read in {}.", x, certainty, cfg.graph[*n].node)
+    });
+  }
+
+  let max_steps = || arguments.value_of("max_steps").unwrap_or("100000").parse::<u64>()
+    .unwrap_or_else(|_| exit_with_error("The argument of --max-steps must be a non-negative number."));
 
   // Which action to do?
-  if arguments.is_present("interpret") {
-    let x = arguments.value_of("interpret").unwrap_or("0").parse::<i32>().unwrap_or(0);
+  if let Some(range) = arguments.value_of("compare") {
+    let (min, max) = parse_range(range).unwrap_or_else(|e| exit_with_error(&e));
+    let counterexamples = soundness_counterexamples(&p, min..=max, max_steps());
+    if counterexamples.is_empty() {
+      println!("No counterexamples for the inputs {} to {}.", min, max);
+    }
+    else {
+      counterexamples.iter().for_each(|c| println!("{}", c));
+      std::process::exit(1)
+    }
+  }
+  else if let Some(range) = arguments.value_of("sweep") {
+    let (min, max) = parse_range(range).unwrap_or_else(|e| exit_with_error(&e));
+    let max_steps = max_steps();
+    eval_range(&p, min..=max, max_steps).iter().for_each(|(x, z)| {
+      match z {
+        Some(z) => println!("{} -> {}", x, z),
+        None    => println!("{} -> diverges", x)
+      }
+    })
+  }
+  else if let Some(range) = arguments.value_of("input_distribution") {
+    let (min, max) = parse_range(range).unwrap_or_else(|e| exit_with_error(&e));
+    if min > max {
+      exit_with_error(&format!("The range {} is empty.", range));
+    }
+    let samples = arguments.value_of("samples").unwrap_or("1000").parse::<usize>()
+      .unwrap_or_else(|_| exit_with_error("The argument of --samples must be a non-negative number."));
+    let seed = arguments.value_of("rng_seed").unwrap_or("0").parse::<u64>()
+      .unwrap_or_else(|_| exit_with_error("The argument of --rng-seed must be a non-negative number."));
+    println!("{}", output_stats(&eval_random(&p, min..=max, samples, seed, max_steps())))
+  }
+  else if let Some(x) = arguments.value_of("highlight") {
+    let highlighted = highlight_consts(&program_buffer, &VarName::new(x)).unwrap_or_else(|e| exit_with_error(&e));
+    println!("{}", highlighted)
+  }
+  else if arguments.is_present("interpret") {
+    let x = arguments.value_of("interpret").unwrap_or("0").parse::<Int>().unwrap_or(0);
     // May terminate or diverge
     let z = eval(&p, x);
     println!("{}", z)
   }
   else {
-    let do_const_prop = arguments.is_present("const_prop") || !arguments.is_present("avail_exp");
+    let do_finite_set = arguments.is_present("finite_set") && !arguments.is_present("const_prop");
+    let do_const_prop = arguments.is_present("const_prop") || !(arguments.is_present("avail_exp") || do_finite_set);
 
-    let cfg = cfg::ast_to_cfg(&p);
+    let unrolled = arguments.value_of("max_loop_unroll").map(|k| {
+      let k = k.parse::<usize>().ok().filter(|k| *k <= ast::MAX_UNROLL)
+        .unwrap_or_else(|| exit_with_error(&format!("The argument of --max-loop-unroll must be a number from 0 to {}.", ast::MAX_UNROLL)));
+      ast::unroll(&p, k)
+    });
+    let cfg = cfg::ast_to_cfg(unrolled.as_ref().unwrap_or(&p));
+    if arguments.is_present("graph_stats") {
+      println!("{}", cfg.stats());
+      return Ok(());
+    }
+    let edge_style = match arguments.value_of("edge_style") {
+      Some("numbered") => EdgeStyle::Numbered,
+      Some("colored")  => EdgeStyle::Colored,
+      _                => EdgeStyle::Labelled
+    };
+    let engine = match arguments.value_of("dot_engine") {
+      Some("dot")   => Some(DotEngine::Dot),
+      Some("neato") => Some(DotEngine::Neato),
+      Some("fdp")   => Some(DotEngine::Fdp),
+      Some("circo") => Some(DotEngine::Circo),
+      _             => None
+    };
+    let dot_options = DotOptions { edge_style, engine, cluster_loops: arguments.is_present("cluster_loops"), node_indices: arguments.is_present("node_indices") };
+    let explain = arguments.is_present("explain");
+    let compact = arguments.is_present("compact_annots");
+    let mfp_options = MfpOptions { only_reachable: arguments.is_present("only_reachable") };
+    let seed = parse_seed(arguments.value_of("seed").unwrap_or("")).unwrap_or_else(|e| exit_with_error(&e));
+
+    if let Some(analyses) = arguments.value_of("diff_analyses") {
+      let (fst, snd) = match analyses.split(',').map(|a| a.trim()).collect::<Vec<_>>().as_slice() {
+        [fst, snd] => (*fst, *snd),
+        _ => exit_with_error(&format!("The analyses {} are not of the form first,second.", analyses))
+      };
+      let annotate = |name| analyze_by_name(&cfg, &mfp_options, name).unwrap_or_else(|e| exit_with_error(&e));
+      let diffs = diff_annots(&annotate(fst), &annotate(snd));
+      if diffs.is_empty() {
+        println!("The analyses {} and {} agree on every node.", fst, snd);
+      }
+      diffs.iter().for_each(|d| println!("{}", d));
+      return Ok(());
+    }
   
     // Analyze and output to StdOut
     if do_const_prop {
-      // Rust Expl.: By giving the following type annotation, the compiler knows which type (and therefore which implementation) to fill in for the generic type variables `L` in the `mfp` function (namely, the `MultiConstLat` one).
-      let cfg_mfp: Cfg<MfpAnnot<MultiConstLat>> = mfp(&cfg);
-      println!("{}", Dot::new(&cfg_mfp.graph));
+      // Rust Expl.: By giving the following type annotation, the compiler knows which type (and therefore which implementation) to fill in for the generic type variables `L` in the `analyze` function (namely, the `MultiConstLat` one).
+      let cfg_mfp: Cfg<MfpAnnot<MultiConstLat>> = analyze(&cfg, &mfp_options, MultiConstLat::seeded(&seed), explain);
+      print!("{}", render(&cfg_mfp, &dot_options, compact));
+      if arguments.is_present("fail_on_top") {
+        let x = VarName::new(arguments.value_of("fail_on_top").unwrap_or("z"));
+        fail_on_top(&cfg, &cfg_mfp, &mfp_options, MultiConstLat::seeded(&seed), &x);
+      }
+    }
+    else if do_finite_set {
+      let cfg_mfp: Cfg<MfpAnnot<MultiFiniteSetLat>> = analyze(&cfg, &mfp_options, MultiFiniteSetLat::seeded(&seed), explain);
+      print!("{}", render(&cfg_mfp, &dot_options, compact));
     }
     else {
-      // Rust Expl.: By giving the following type annotation, the compiler knows which type (and therefore which implementation) to fill in for the generic type variables `L` in the `mfp` function (namely, the `ExpSetLat` one).
-      let cfg_mfp: Cfg<MfpAnnot<ExpSetLat>> = mfp(&cfg);
-      println!("{}", Dot::new(&cfg_mfp.graph));
+      // Rust Expl.: By giving the following type annotation, the compiler knows which type (and therefore which implementation) to fill in for the generic type variables `L` in the `analyze` function (namely, the `ExpSetLat` one).
+      let cfg_mfp: Cfg<MfpAnnot<ExpSetLat>> = analyze(&cfg, &mfp_options, ExpSetLat::init_start(), explain);
+      print!("{}", render(&cfg_mfp, &dot_options, compact));
     }
   }
 
   Ok(())
+}
+
+/// Compute the MFP solution, starting with the value `start` at the init node. If `explain` is set, the iterations of the worklist algorithm are printed to StdErr.
+fn analyze<L: SemiLat + FlowSemantics + Display>(cfg: &Cfg<RawAnnot>, options: &MfpOptions, start: L, explain: bool) -> Cfg<MfpAnnot<L>> {
+  if !explain {
+    return mfp_from(cfg, options, start);
+  }
+  let (cfg_mfp, steps) = mfp_explained_from(cfg, options, start);
+  steps.iter().for_each(|step| eprintln!("[{}] {}", cfg.graph[step.node].node, step));
+  cfg_mfp
+}
+
+/// Exit with an error if the variable `x` is `Top` at the end of the program (i.e. after a node without successors) in the constant propagation solution `cfg_mfp`, naming the node where `x` first became `Top` (see `top_origin`)
+fn fail_on_top(cfg: &Cfg<RawAnnot>, cfg_mfp: &Cfg<MfpAnnot<MultiConstLat>>, options: &MfpOptions, start: MultiConstLat, x: &VarName) {
+  let top_at_end = cfg_mfp.graph.node_indices()
+    .filter(|n| cfg_mfp.graph.neighbors(*n).next().is_none())
+    .any(|n| *cfg_mfp.graph[n].annot.post().lookup(x) == ConstLat::Top);
+  if top_at_end {
+    match top_origin(cfg, options, start, x).1 {
+      Some(n) => {exit_with_error(&format!("The variable {} is tt at the end of the program. It first became tt in node {}: {}", x, n.index(), cfg.graph[n].node))}
+      None => {exit_with_error(&format!("The variable {} is tt at the end of the program.", x))}
+    }
+  }
+}
+
+/// Compute the MFP solution of the analysis `name` (see `--diff-analyses`) with pretty-printed annotations, so that the solutions of different analyses can be compared. The interval analysis is computed with widening.
+fn analyze_by_name(cfg: &Cfg<RawAnnot>, options: &MfpOptions, name: &str) -> Result<Cfg<MfpAnnot<String>>, String> {
+  let (analysis, unrefined) = match name.strip_suffix("-unrefined") {
+    Some(analysis) => (analysis, true),
+    None => (name, false)
+  };
+  match analysis {
+    "const"    => Ok(analyze_stringified::<MultiConstLat>(cfg, options, unrefined, false)),
+    "avail"    => Ok(analyze_stringified::<ExpSetLat>(cfg, options, unrefined, false)),
+    "finite"   => Ok(analyze_stringified::<MultiFiniteSetLat>(cfg, options, unrefined, false)),
+    "interval" => Ok(analyze_stringified::<MultiIntervalLat>(cfg, options, unrefined, true)),
+    "sign"     => Ok(analyze_stringified::<MultiSignLat>(cfg, options, unrefined, false)),
+    "affine"   => Ok(analyze_stringified::<MultiAffineLat>(cfg, options, unrefined, false)),
+    _ => Err(format!("Unknown analysis {}, expected one of const, avail, finite, interval, sign or affine (optionally with the suffix -unrefined).", name))
+  }
+}
+
+/// Helper function for `analyze_by_name`: Compute the MFP solution for `L` (or `Unrefined<L>`, optionally with widening) and pretty-print the annotations
+fn analyze_stringified<L: SemiLat + FlowSemantics + Display>(cfg: &Cfg<RawAnnot>, options: &MfpOptions, unrefined: bool, widening: bool) -> Cfg<MfpAnnot<String>> {
+  match (unrefined, widening) {
+    (false, false) => mfp_with::<L>(cfg, options).map(MfpAnnot::to_strings),
+    (true, false)  => mfp_with::<Unrefined<L>>(cfg, options).map(MfpAnnot::to_strings),
+    (false, true)  => mfp_widening_with::<L>(cfg, options).map(MfpAnnot::to_strings),
+    (true, true)   => mfp_widening_with::<Unrefined<L>>(cfg, options).map(MfpAnnot::to_strings)
+  }
+}
+
+/// Render the MFP solution in the DOT format. If `compact` is set, redundant `pre` values are omitted (see `compact_annots`).
+fn render<L: SemiLat + Display>(cfg_mfp: &Cfg<MfpAnnot<L>>, options: &DotOptions, compact: bool) -> String {
+  if compact { to_dot(&compact_annots(cfg_mfp), options) } else { to_dot(cfg_mfp, options) }
+}
+
+/// Parse a range given as `min,max`
+fn parse_range(s: &str) -> Result<(Int, Int), String> {
+  let bounds: Vec<Option<Int>> = s.split(',').map(|n| n.trim().parse::<Int>().ok()).collect();
+  match bounds.as_slice() {
+    [Some(min), Some(max)] => Ok((*min, *max)),
+    _ => Err(format!("The range {} is not of the form min,max.", s))
+  }
+}
+
+/// Parse variable bindings given as `x=5,n=10` (the empty string yields no bindings)
+fn parse_seed(s: &str) -> Result<Vec<(VarName, Int)>, String> {
+  s.split(',').filter(|b| !b.trim().is_empty()).map(|b| {
+    match b.split('=').map(|w| w.trim()).collect::<Vec<_>>().as_slice() {
+      [x, n] if !x.is_empty() => n.parse::<Int>().map(|n| (VarName::new(x), n)).map_err(|_| format!("The value {} of the seed {} is not a number.", n, b)),
+      _ => Err(format!("The seed {} is not of the form x=n.", b))
+    }
+  }).collect()
+}
+
+/// Print the error message to StdErr and exit with a non-zero exit code
+fn exit_with_error(msg: &str) -> ! {
+  eprintln!("{}", msg);
+  std::process::exit(1)
 }
\ No newline at end of file
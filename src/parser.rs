@@ -1,67 +1,194 @@
-use nom::{combinator::{peek, verify, not}, character::complete::multispace0};
+use nom::{combinator::{peek, verify, not, opt, map_res, recognize, value}, character::complete::multispace0, error::ErrorKind};
+use std::{cell::{Cell, RefCell}, collections::BTreeMap};
 use crate::ast::{*, ProgAtom::*};
 
 use crate::aexp::{*, AExp::*};
 use crate::bexp::{*, BExp::*};
-use crate::common::{VarName};
+use crate::common::{VarName, Int, Set};
 
 use itertools::join;
 
 use nom::character::{complete::{alpha1, digit1, anychar, multispace1}, is_alphanumeric};
 use nom::branch::alt;
-use nom::{multi::{separated_nonempty_list, }, IResult, bytes::complete::{tag}};
+use nom::{multi::{separated_nonempty_list, many0}, IResult, bytes::complete::{tag}};
 use nom::{sequence::delimited};
-use nom::{sequence::{pair}};
+use nom::{sequence::{pair, preceded}};
+
+/// The maximal nesting depth (of parentheses, negations and sub-programs) accepted by `parse`
+pub const MAX_NESTING_DEPTH: usize = 128;
+
+/// Errors of the parser are human-readable messages
+pub type ParseError = String;
+
+/// A warning about a program that is parsed successfully, but whose meaning may differ from what it looks like
+#[derive(Debug,PartialEq,Clone,Eq)]
+pub struct Diagnostic {
+    pub message: String
+}
 
 /// Main function that does the parsing: It takes a string and produces the AST for it.
-pub fn parse(s: &str) -> Result<Prog, String> {
+pub fn parse(s: &str) -> Result<Prog, ParseError> {
+    parse_with_max_depth(s, MAX_NESTING_DEPTH)
+}
+
+/// Like `parse`, but with a custom maximal nesting depth. Deeper nested programs are rejected with an error instead of risking a stack overflow during parsing.
+pub fn parse_with_max_depth(s: &str, max_depth: usize) -> Result<Prog, ParseError> {
+    let p = parse_complete(s, max_depth, top_prog)?;
+    // Finally, check that every procedure is defined once, that all called procedures are defined and that `break` and `continue` only occur inside loops.
+    let procs = p.procs();
+    let Prog::Prog(atoms) = &p;
+    let defined = atoms.iter().filter(|a| matches!(a, Proc(_, _))).count();
+    if defined > procs.len() {
+        let mut seen = Set::new();
+        let name = atoms.iter().find_map(|a| match a { Proc(name, _) if !seen.insert(name.clone()) => {Some(name)} _ => {None} });
+        return Err(format!("Parsing failed. The procedure {} is defined more than once.", name.unwrap()));
+    }
+    match p.called_procs().into_iter().find(|name| !procs.contains_key(name)) {
+        Some(name) => {Err(format!("Parsing failed. The procedure {} is called but not defined.", name))}
+        None if p.has_jumps() => {Err("Parsing failed. A break or continue is only allowed inside a loop.".to_string())}
+        None => {Ok(p)}
+    }
+}
+
+/// Like `parse`, but additionally return diagnostics, e.g. for `&&` and `||` that are mixed without parentheses. Diagnostics are also returned if parsing fails.
+pub fn parse_with_diagnostics(s: &str) -> (Result<Prog, ParseError>, Vec<Diagnostic>) {
+    DIAGNOSTICS.with(|d| *d.borrow_mut() = Some(vec![]));
+    let p = parse(s);
+    // Due to backtracking, the same code may have been parsed (and diagnosed) several times
+    let diagnostics = DIAGNOSTICS.with(|d| d.borrow_mut().take()).unwrap_or_default();
+    let diagnostics = diagnostics.into_iter().fold(vec![], |mut ds: Vec<Diagnostic>, d| { if !ds.contains(&d) { ds.push(d) }; ds });
+    (p, diagnostics)
+}
+
+/// Like `parse`, but additionally return the source span of every statement (see `Span`), in the order of `Prog::atoms`.
+pub fn parse_with_spans(s: &str) -> Result<(Prog, Vec<Span>), ParseError> {
+    let p = parse(s)?;
+    // The recorded offsets count from the end of the input `parse_complete` parses (which is trimmed, but has the same lines as `s`), which makes them independent of how far the input was consumed
+    let stripped = strip_comments(s);
+    let end = stripped.trim_end().len();
+    let position = |rest_len: usize| {
+        let before = &stripped[..end - rest_len];
+        let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+        Position { line: before.matches('\n').count() + 1, column: before.len() - line_start + 1 }
+    };
+    // Due to backtracking, the same statement may have been recorded several times, but always with the same span. A larger rest means an earlier statement.
+    let spans = SPANS.with(|spans| spans.borrow().iter().rev()
+        .map(|(start, end)| Span { start: position(*start), end: position(*end) })
+        .collect::<Vec<_>>());
+    Ok((p, spans))
+}
+
+/// Like `parse`, but additionally return the comments, attached to the statements (see `Comments`), e.g. for reformatting a program without losing its comments (see `Prog::to_source_with_comments`):
+/// - A comment after code on the same line is a trailing comment of the statement that ends last on this line before the comment (i.e. the outermost one if several end at the same position).
+/// - Any other comment (e.g. on a line of its own, or after a `do` that starts a loop body) is a leading comment of the next statement, i.e. the (outermost) statement that starts first after the comment. If there is no such statement, the comment is an `end` comment.
+pub fn parse_with_comments(s: &str) -> Result<(Prog, Comments), ParseError> {
+    let (p, spans) = parse_with_spans(s)?;
+    let mut comments = Comments::default();
+    s.lines().enumerate().for_each(|(idx, line)| {
+        let col = match line.find('#') { Some(col) => {col} None => {return} };
+        let pos = Position { line: idx + 1, column: col + 1 };
+        let text = line[col + 1..].to_string();
+        // Rust Expl.: `fold` keeps the first of equally large elements, unlike `max_by_key`, which keeps the last one
+        let ending = spans.iter().enumerate()
+            .filter(|(_, span)| span.end.line == pos.line && span.end <= pos)
+            .fold(None, |last: Option<(usize, &Span)>, (i, span)| match last {
+                Some((_, last_span)) if last_span.end >= span.end => {last}
+                _ => {Some((i, span))}
+            });
+        let starting = spans.iter().enumerate()
+            .filter(|(_, span)| span.start >= pos)
+            .fold(None, |first: Option<(usize, &Span)>, (i, span)| match first {
+                Some((_, first_span)) if first_span.start <= span.start => {first}
+                _ => {Some((i, span))}
+            });
+        match (ending, starting) {
+            (Some((i, _)), _) if !line[..col].trim().is_empty() => {comments.trailing.insert(i, text);}
+            (_, Some((i, _))) => {comments.leading.entry(i).or_default().push(text)}
+            (_, None) => {comments.end.push(text)}
+        }
+    });
+    Ok((p, comments))
+}
+
+/// Parse a single arithmetic expression (e.g. `x + 2*y`), surrounded by optional whitespace.
+pub fn parse_aexp(s: &str) -> Result<AExp, ParseError> {
+    parse_complete(s, MAX_NESTING_DEPTH, aexp)
+}
+
+/// Parse a single boolean expression (e.g. `x <= 1 && y <= 2`), surrounded by optional whitespace.
+pub fn parse_bexp(s: &str) -> Result<BExp, ParseError> {
+    parse_complete(s, MAX_NESTING_DEPTH, bexp)
+}
+
+/// Apply the parser `f` to the whole input `s`, after removing comments and surrounding whitespace. Any input not consumed by `f` results in an error.
+fn parse_complete<T, F>(s: &str, max_depth: usize, f: F) -> Result<T, ParseError>
+where F: Fn(&str) -> IResult<&str, T> {
     // First remove any comments
     // Rust Expl.: The following line declares a new binding `s`. It does not change the value of the immutable argument `s`, but merely shadows the binding and introduces a new binding `s`.
-    let s = join(s.lines().map(
-        |line| {
-            line.find("#")
-                .map(|idx| &line[..idx])
-                .unwrap_or(line)
-        }), "\n");
+    let s = strip_comments(s);
     
     // Then, remove surrounding whitespace.
     let s = s.trim();
 
     // Then, parse.
-    match prog(&s) {
-        Ok((rest, p)) => {
+    DEPTH.with(|d| d.set((0, max_depth)));
+    SPANS.with(|spans| spans.borrow_mut().clear());
+    match f(&s) {
+        Ok((rest, x)) => {
             if rest.is_empty() {
-                Ok(p)
+                Ok(x)
             }
             else {
                 Err(format!("Parsing failed. The following code was not parsed. {:}", rest))
             }
         }
+        Err(nom::Err::Failure((_, ErrorKind::TooLarge))) => {
+            Err(format!("Parsing failed. The maximal nesting depth of {} is exceeded.", max_depth))
+        }
         Err(e) => {Err(format!("Parsing failed. {:}", e))}
     }
 } 
 
+/// Helper function for `parse_complete`: Remove the comments, i.e. everything from a `#` to the end of its line. The lines are kept, such that line numbers stay the same.
+fn strip_comments(s: &str) -> String {
+    join(s.lines().map(
+        |line| {
+            line.find("#")
+                .map(|idx| &line[..idx])
+                .unwrap_or(line)
+        }), "\n")
+}
+
 /// Grammar for the concrete syntax:
 ///
 /// ```latex
+/// top_prog  ::= top_atom ; ... ; top_atom
+/// top_atom  ::= proc | prog_atom
+/// proc      ::= `proc` x `do` prog `end`
+///
 /// prog      ::= prog_atom ; ... ; prog_atom
-/// prog_atom ::= `skip` | assign | cond | while
+/// prog_atom ::= `skip` | `break` | `continue` | assign | cond | while | repeat | let | call | havoc
 /// assign    ::= x `:=` aexp
-/// cond      ::= `if` bexp `then` prog `else` prog `end`
+/// cond      ::= `if` bexp `then` prog `else` prog `end` | `if` bexp `then` prog `end`
 /// while     ::= `while` bexp `do` prog `end`
+/// repeat    ::= `repeat` prog `until` bexp `end`
+/// let       ::= `let` x `:=` aexp `in` prog `end`
+/// call      ::= `call` x
+/// havoc     ::= `havoc` x
 ///
-/// aexp      ::= num_neg | add
-/// num_neg   ::= `-`n
+/// aexp      ::= add
 /// add       ::= mul + ... + mul
-/// mul       ::= aexp_atom * ... * aexp_atom
-/// aexp_atom ::= n | x | `(` aexp `)`
+/// mul       ::= pow op ... op pow   with op ::= `*` | `%`
+/// pow       ::= aexp_atom | aexp_atom `^` pow
+/// aexp_atom ::= n | `-`n | x | `(` aexp `)`
 ///
 /// bexp      ::= bool_neg | bool_or
 /// lesseq    ::= aexp `<=` aexp
+/// less      ::= aexp `<` aexp
 /// bool_neg  ::= `!`bexp
 /// bool_or   ::= bool_and `||` ... `||` bool_and
 /// bool_and  ::= bexp_atom `&&` ... `&&` bexp_atom
-/// bexp_atom ::= lesseq | `(` bexp `)`
+/// bexp_atom ::= lesseq | less | `(` bexp `)`
 ///
 /// with $n \in \mathbb{N}$ and $x \in \mathit{Var}$
 /// ```
@@ -70,6 +197,12 @@ pub fn parse(s: &str) -> Result<Prog, String> {
 // Top-level Syntactic Categories //
 ////////////////////////////////////
 
+/// Parse a top-level program, which may additionally contain procedure definitions.
+fn top_prog(s: &str) -> IResult<&str, Prog> {
+    let (s, subprogs) = separated_nonempty_list(|s2| bin_op(";", s2), alt((|s| spanned(proc, s), prog_atom)))(s)?;
+    Ok((s, Prog::Prog(subprogs)))
+}
+
 /// Parse a program by first parsing semicolon-separated sub-programs, then sequencing those.
 fn prog(s: &str) -> IResult<&str, Prog> {
     // TODO: Get rid of the closure in the next line
@@ -77,9 +210,9 @@ fn prog(s: &str) -> IResult<&str, Prog> {
     Ok((s, Prog::Prog(subprogs)))
 }
 
-/// An arithmentic expression is either a negative number or an addition term.
+/// An arithmentic expression is an addition term.
 fn aexp(s: &str) -> IResult<&str, AExp> {
-    alt((num_neg, add))(s)
+    add(s)
 }
 
 /// A boolean expression is a less-eq comparison.
@@ -105,6 +238,42 @@ fn keyword<'a>(k: &str, s: &'a str) -> IResult<&'a str, ()> {
     Ok((s, ()))
 }
 
+// Rust Expl.: A thread-local variable is a global variable of which each thread has its own copy. It stores the current nesting depth together with the maximal one.
+thread_local! {
+    static DEPTH: Cell<(usize, usize)> = const { Cell::new((0, MAX_NESTING_DEPTH)) };
+}
+
+// Rust Expl.: Analogously, the diagnostics found while parsing are collected in a thread-local variable. It is `None` unless `parse_with_diagnostics` is running, so that other parses do not collect (and accumulate) diagnostics.
+thread_local! {
+    static DIAGNOSTICS: RefCell<Option<Vec<Diagnostic>>> = const { RefCell::new(None) };
+}
+
+// Rust Expl.: Also the spans of the parsed statements are collected in a thread-local variable (see `parse_with_spans`). A span is stored as the lengths of the rest of the input at its start and at its end, indexed by the former.
+thread_local! {
+    static SPANS: RefCell<BTreeMap<usize, usize>> = const { RefCell::new(BTreeMap::new()) };
+}
+
+/// Run the parser `f` for a statement and record its span if it succeeds
+fn spanned<'a, O, F>(f: F, s: &'a str) -> IResult<&'a str, O>
+where F: Fn(&'a str) -> IResult<&'a str, O> {
+    let (rest, x) = f(s)?;
+    SPANS.with(|spans| spans.borrow_mut().insert(s.len(), rest.len()));
+    Ok((rest, x))
+}
+
+/// Run the parser `f` one nesting level deeper. If the maximal nesting depth is exceeded, parsing is aborted by a `Failure` (which, unlike an `Error`, is not recovered from by `alt`).
+fn nested<'a, O, F>(f: F, s: &'a str) -> IResult<&'a str, O>
+where F: Fn(&'a str) -> IResult<&'a str, O> {
+    let (depth, max_depth) = DEPTH.with(|d| d.get());
+    if depth >= max_depth {
+        return Err(nom::Err::Failure((s, ErrorKind::TooLarge)));
+    }
+    DEPTH.with(|d| d.set((depth + 1, max_depth)));
+    let res = f(s);
+    DEPTH.with(|d| d.set((depth, max_depth)));
+    res
+}
+
 /// A binary operator `op` (e.g. `+`) is parsed. Whitespace before or afterwards is consumed.
 fn bin_op<'a>(op: &str, s: &'a str) -> IResult<&'a str, ()> {
     let (s, _) = delimited(multispace0, tag(op), multispace0)(s)?;
@@ -115,12 +284,11 @@ fn bin_op<'a>(op: &str, s: &'a str) -> IResult<&'a str, ()> {
 // Arithmetic Expressions //
 ////////////////////////////
 
-/// A negative number
+/// A negative number. Numbers that are not representable as `Int` are rejected.
 fn num_neg(s: &str) -> IResult<&str, AExp> {
-    let (s, _) = tag("-")(s)?;
-    let (s, n_str) = digit1(s)?;
-    let n: i32 = n_str.parse().unwrap();
-    Ok((s, {Num(-n)}))
+    // Rust Expl.: The sign is parsed together with the digits, as e.g. `-2147483648` is representable as a 32-bit integer whereas `2147483648` is not.
+    let (s, n) = map_res(recognize(pair(tag("-"), digit1)), |n_str: &str| n_str.parse::<Int>())(s)?;
+    Ok((s, {Num(n)}))
 }
 
 /// An addition term consists of multiple multiplication terms. mul + ... + mul
@@ -134,26 +302,36 @@ fn add(s: &str) -> IResult<&str, AExp> {
     Ok((s, res))
 }
 
-/// A multiplication term consists of multiple arithmetic atomic terms.  aexp_atom * ... * aexp_atom
+/// A multiplication term consists of multiple arithmetic atomic terms, which are multiplied or taken modulo one another (from left to right).  aexp_atom op ... op aexp_atom  with op being `*` or `%`
 fn mul(s: &str) -> IResult<&str, AExp> {
-    // TODO: Get rid of the closure in the next line
-    let (s, factors) = separated_nonempty_list(|s2| bin_op("*", s2), aexp_atom)(s)?;
-    // TODO: Use `fold_first` in the future: https://github.com/rust-lang/rust/issues/68125
-    let mut iter = factors.into_iter();
-    let hd = iter.next().unwrap();
-    let res = iter.fold(hd, |acc: AExp, x: AExp| -> AExp {Mul(Box::new(acc), Box::new(x))});
+    let (s, hd) = pow(s)?;
+    let op = alt((value('*', |s2| bin_op("*", s2)), value('%', |s2| bin_op("%", s2))));
+    let (s, factors) = many0(pair(op, pow))(s)?;
+    let res = factors.into_iter().fold(hd, |acc: AExp, (op, x): (char, AExp)| -> AExp {
+        if op == '*' { Mul(Box::new(acc), Box::new(x)) } else { Mod(Box::new(acc), Box::new(x)) }
+    });
     Ok((s, res))
 }
 
-/// An arithmetic atomic term is either a non-negative number, a variable or an parenthesized arithmetic expression.
+/// An exponentiation, which binds stronger than multiplication and associates to the right (i.e. `2^3^2` is `2^(3^2)`). Every exponent counts as a nesting level, see `nested`.
+fn pow(s: &str) -> IResult<&str, AExp> {
+    let (s, base) = aexp_atom(s)?;
+    let (s, exponent) = opt(preceded(|s2| bin_op("^", s2), |s2| nested(pow, s2)))(s)?;
+    Ok((s, match exponent {
+        Some(e) => {Pow(Box::new(base), Box::new(e))}
+        None => {base}
+    }))
+}
+
+/// An arithmetic atomic term is either a number, a variable or an parenthesized arithmetic expression.
+/// Negative numbers are atoms, so that they can be used as factors and summands (e.g. `2 * -3`). As there is no subtraction, `2 -3` is rejected rather than read as `2 + -3`.
 fn aexp_atom(s: &str) -> IResult<&str, AExp> {
-    alt((num_nonneg, var, aexp_parens))(s)
+    alt((num_nonneg, num_neg, var, aexp_parens))(s)
 }
 
-/// A non-negative number
+/// A non-negative number. Numbers that are not representable as `Int` are rejected.
 fn num_nonneg(s: &str) -> IResult<&str, AExp> {
-    let (s, n_str) = digit1(s)?;
-    let n: i32 = n_str.parse().unwrap();
+    let (s, n) = map_res(digit1, |n_str: &str| n_str.parse::<Int>())(s)?;
     Ok((s, {Num(n)}))
 }
 
@@ -166,7 +344,7 @@ fn var(s: &str) -> IResult<&str, AExp> {
 /// A parenthesized arithmetic expression
 fn aexp_parens(s: &str) -> IResult<&str, AExp> {
     delimited(pair(tag("("), multispace0),
-              aexp,
+              |s| nested(aexp, s),
               pair(multispace0, tag(")")))(s)
 }
 
@@ -182,9 +360,17 @@ fn lesseq(s: &str) -> IResult<&str, BExp> {
     Ok((s, LessEq(Box::new(left), Box::new(right))))
 }
 
+/// A strict less-than comparison
+fn less(s: &str) -> IResult<&str, BExp> {
+    let (s, left) = aexp(s)?;
+    let (s, _) =  bin_op("<", s)?;
+    let (s, right) = aexp(s)?;
+    Ok((s, Less(Box::new(left), Box::new(right))))
+}
+
 fn neg(s: &str) -> IResult<&str, BExp> {
     let (s, _) = tag("!")(s)?;
-    let (s, b) = bexp(s)?;
+    let (s, b) = nested(bexp, s)?;
     Ok((s, Neg(Box::new(b))))
 }
 
@@ -192,33 +378,51 @@ fn neg(s: &str) -> IResult<&str, BExp> {
 fn or(s: &str) -> IResult<&str, BExp> {
     // TODO: Get rid of the closure in the next line
     let (s, summands) = separated_nonempty_list(|s2| bin_op("||", s2), and)(s)?;
+    // Mixing `&&` and `||` without parentheses is diagnosed
+    let mixed = summands.len() > 1 && summands.iter().any(|(_, n)| *n > 1);
     // TODO: Use `fold_first` in the future: https://github.com/rust-lang/rust/issues/68125
-    let mut iter = summands.into_iter();
+    let mut iter = summands.into_iter().map(|(b, _)| b);
     let hd = iter.next().unwrap();
     let res = iter.fold(hd, |acc: BExp, x: BExp| -> BExp {Or(Box::new(acc), Box::new(x))});
+    if mixed {
+        let message = format!("`&&` and `||` are mixed without parentheses in `{}`. As `&&` binds stronger than `||`, this is read as `{}`; add parentheses to make the grouping explicit.", res, with_parens(&res));
+        DIAGNOSTICS.with(|d| if let Some(ds) = d.borrow_mut().as_mut() { ds.push(Diagnostic { message }) });
+    }
     Ok((s, res))
 }
 
-/// A multiplication term consists of multiple arithmetic atomic terms.  aexp_atom * ... * aexp_atom
-fn and(s: &str) -> IResult<&str, BExp> {
+/// Helper function for diagnostics: Pretty-print a disjunction with parenthesized conjunctions
+fn with_parens(b: &BExp) -> String {
+    match b {
+        Or(b1, b2) => {format!("{} || {}", with_parens(b1), with_parens(b2))}
+        And(_, _) => {format!("({})", b)}
+        _ => {b.to_string()}
+    }
+}
+
+/// An and term consists of multiple boolean atomic terms. bexp_atom && ... && bexp_atom
+/// The number of atomic terms is returned as well.
+fn and(s: &str) -> IResult<&str, (BExp, usize)> {
     // TODO: Get rid of the closure in the next line
     let (s, factors) = separated_nonempty_list(|s2| bin_op("&&", s2), bexp_atom)(s)?;
+    let n = factors.len();
     // TODO: Use `fold_first` in the future: https://github.com/rust-lang/rust/issues/68125
     let mut iter = factors.into_iter();
     let hd = iter.next().unwrap();
     let res = iter.fold(hd, |acc: BExp, x: BExp| -> BExp {And(Box::new(acc), Box::new(x))});
-    Ok((s, res))
+    Ok((s, (res, n)))
 }
 
-/// A boolean atomic term is either a lesseq expression or a parenthesized boolean expression.
+/// A boolean atomic term is either a lesseq expression, a less expression or a parenthesized boolean expression.
 fn bexp_atom(s: &str) -> IResult<&str, BExp> {
-    alt((lesseq, bexp_parens))(s)
+    // `lesseq` needs to be tried before `less`, as `<` is a prefix of `<=`
+    alt((lesseq, less, bexp_parens))(s)
 }
 
-/// A parenthesized arithmetic expression
+/// A parenthesized boolean expression
 fn bexp_parens(s: &str) -> IResult<&str, BExp> {
     delimited(pair(tag("("), multispace0),
-              bexp,
+              |s| nested(bexp, s),
               pair(multispace0, tag(")")))(s)
 }
 
@@ -226,9 +430,9 @@ fn bexp_parens(s: &str) -> IResult<&str, BExp> {
 // Programs //
 //////////////
 
-/// An atomic program is either a skip, an assignment, a conditional or a while loop.
+/// An atomic program is either a skip, a break, a continue, an assignment, a conditional, a while loop, a repeat loop, a let block, a procedure call or a havoc.
 fn prog_atom(s: &str) -> IResult<&str, ProgAtom> {
-    alt((skip, assign, cond, wwhile))(s)
+    spanned(alt((skip, bbreak, ccontinue, assign, cond, wwhile, repeat, llet, call, havoc)), s)
 }
 
 /// A skip.
@@ -237,6 +441,18 @@ fn skip(s: &str) -> IResult<&str, ProgAtom> {
     Ok((s, Skip))
 }
 
+/// A break, which leaves the innermost enclosing loop.
+fn bbreak(s: &str) -> IResult<&str, ProgAtom> {
+    let (s, _) = keyword("break", s)?;
+    Ok((s, Break))
+}
+
+/// A continue, which skips the rest of the body of the innermost enclosing loop.
+fn ccontinue(s: &str) -> IResult<&str, ProgAtom> {
+    let (s, _) = keyword("continue", s)?;
+    Ok((s, Continue))
+}
+
 /// An assignment.
 fn assign(s: &str) -> IResult<&str, ProgAtom> {
     let (s, v) = varname(s)?;
@@ -253,17 +469,29 @@ fn cond(s: &str) -> IResult<&str, ProgAtom> {
     let (s, _) = multispace1(s)?;
     let (s, _) = keyword("then", s)?;
     let (s, _) = multispace1(s)?;
-    let (s, prog_true) = prog(s)?;
-    let (s, _) = multispace1(s)?;
-    let (s, _) = keyword("else", s)?;
-    let (s, _) = multispace1(s)?;
-    let (s, prog_false) = prog(s)?;
+    let (s, prog_true) = nested(prog, s)?;
     let (s, _) = multispace1(s)?;
+    // The else-branch is optional and defaults to `skip`
+    let (s, prog_false) = opt(else_branch)(s)?;
+    let prog_false = prog_false.unwrap_or_else(|| {
+        // The implicit `skip` gets an empty span
+        SPANS.with(|spans| spans.borrow_mut().insert(s.len(), s.len()));
+        Prog::Prog(vec![Skip])
+    });
     let (s, _) = keyword("end", s)?;
 
     Ok((s, Cond(Box::new(bexp), Box::new(prog_true), Box::new(prog_false))))
 }
 
+/// The else-branch of a conditional.
+fn else_branch(s: &str) -> IResult<&str, Prog> {
+    let (s, _) = keyword("else", s)?;
+    let (s, _) = multispace1(s)?;
+    let (s, prog_false) = nested(prog, s)?;
+    let (s, _) = multispace1(s)?;
+    Ok((s, prog_false))
+}
+
 /// A while loop.
 fn wwhile(s: &str) -> IResult<&str, ProgAtom> {
     let (s, _) = keyword("while", s)?;
@@ -272,9 +500,72 @@ fn wwhile(s: &str) -> IResult<&str, ProgAtom> {
     let (s, _) = multispace1(s)?;
     let (s, _) = keyword("do", s)?;
     let (s, _) = multispace1(s)?;
-    let (s, prog) = prog(s)?;
+    let (s, prog) = nested(prog, s)?;
     let (s, _) = multispace1(s)?;
     let (s, _) = keyword("end", s)?;
 
     Ok((s, While(Box::new(bexp), Box::new(prog))))
 }
+
+/// A repeat loop, i.e. a loop whose guard is tested after the body.
+fn repeat(s: &str) -> IResult<&str, ProgAtom> {
+    let (s, _) = keyword("repeat", s)?;
+    let (s, _) = multispace1(s)?;
+    let (s, prog) = nested(prog, s)?;
+    let (s, _) = multispace1(s)?;
+    let (s, _) = keyword("until", s)?;
+    let (s, _) = multispace1(s)?;
+    let (s, bexp) = bexp(s)?;
+    let (s, _) = multispace1(s)?;
+    let (s, _) = keyword("end", s)?;
+
+    Ok((s, Repeat(Box::new(prog), Box::new(bexp))))
+}
+
+/// A let block, i.e. a sub-program with a scoped variable.
+fn llet(s: &str) -> IResult<&str, ProgAtom> {
+    let (s, _) = keyword("let", s)?;
+    let (s, _) = multispace1(s)?;
+    let (s, v) = varname(s)?;
+    let (s, _) = bin_op(":=", s)?;
+    let (s, aexp) = aexp(s)?;
+    let (s, _) = multispace1(s)?;
+    let (s, _) = keyword("in", s)?;
+    let (s, _) = multispace1(s)?;
+    let (s, prog) = nested(prog, s)?;
+    let (s, _) = multispace1(s)?;
+    let (s, _) = keyword("end", s)?;
+
+    Ok((s, Let(v, Box::new(aexp), Box::new(prog))))
+}
+
+/// A procedure definition.
+fn proc(s: &str) -> IResult<&str, ProgAtom> {
+    let (s, _) = keyword("proc", s)?;
+    let (s, _) = multispace1(s)?;
+    let (s, name) = varname(s)?;
+    let (s, _) = multispace1(s)?;
+    let (s, _) = keyword("do", s)?;
+    let (s, _) = multispace1(s)?;
+    let (s, body) = nested(prog, s)?;
+    let (s, _) = multispace1(s)?;
+    let (s, _) = keyword("end", s)?;
+
+    Ok((s, Proc(name, Box::new(body))))
+}
+
+/// A procedure call.
+fn call(s: &str) -> IResult<&str, ProgAtom> {
+    let (s, _) = keyword("call", s)?;
+    let (s, _) = multispace1(s)?;
+    let (s, name) = varname(s)?;
+    Ok((s, Call(name)))
+}
+
+/// A nondeterministic assignment.
+fn havoc(s: &str) -> IResult<&str, ProgAtom> {
+    let (s, _) = keyword("havoc", s)?;
+    let (s, _) = multispace1(s)?;
+    let (s, x) = varname(s)?;
+    Ok((s, Havoc(x)))
+}
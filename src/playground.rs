@@ -0,0 +1,39 @@
+use serde::Serialize;
+use std::fmt::Display;
+
+use crate::parser::parse;
+use crate::cfg::{ast_to_cfg, Cfg};
+use crate::dot::{to_dot, DotOptions};
+use crate::flow_analysis::mfp::{mfp, mfp_widening, MfpAnnot};
+use crate::flow_analysis::{const_prop::MultiConstLat, avail_exp::ExpSetLat, finite_set::MultiFiniteSetLat, interval::MultiIntervalLat, sign::MultiSignLat};
+
+/// The error type of `analyze_source`, e.g. a parse error or an unknown kind of analysis
+pub type AnalyzeError = String;
+
+/// Parse the *WHILE* program `src`, analyze its CFG and return the result as a string, without any IO. This is the entry point for embedding the analyzer, e.g. into a browser-based playground via WebAssembly.
+///
+/// The `kind` of analysis is one of `const` (constant propagation, like `mfp`), `avail` (available expressions), `finite` (finite sets of values), `interval` (intervals, using `mfp_widening`) or `sign` (signs). The result is the annotated CFG in JSON (like the fixtures `cfg_const_prop.json` etc.), or in the Graphviz format (like `to_dot` with the default options) if `kind` has the suffix `-dot`, e.g. `const-dot`.
+pub fn analyze_source(src: &str, kind: &str) -> Result<String, AnalyzeError> {
+    let (analysis, dot) = match kind.strip_suffix("-dot") {
+        Some(analysis) => {(analysis, true)}
+        None => {(kind, false)}
+    };
+    let cfg = ast_to_cfg(&parse(src).map_err(|e| format!("Parsing failed. {}", e))?);
+    match analysis {
+        "const" => {render(&mfp::<MultiConstLat>(&cfg), dot)}
+        "avail" => {render(&mfp::<ExpSetLat>(&cfg), dot)}
+        "finite" => {render(&mfp::<MultiFiniteSetLat>(&cfg), dot)}
+        "interval" => {render(&mfp_widening::<MultiIntervalLat>(&cfg), dot)}
+        "sign" => {render(&mfp::<MultiSignLat>(&cfg), dot)}
+        _ => {Err(format!("Unknown kind of analysis {}, expected one of const, avail, finite, interval or sign (optionally with the suffix -dot).", kind))}
+    }
+}
+
+/// Helper function for `analyze_source`: Render the annotated CFG in the Graphviz format if `dot` is `true`, and in JSON otherwise
+fn render<L: Serialize + Display>(cfg_mfp: &Cfg<MfpAnnot<L>>, dot: bool) -> Result<String, AnalyzeError> {
+    if dot {
+        Ok(to_dot(cfg_mfp, &DotOptions::default()))
+    } else {
+        serde_json::to_string(cfg_mfp).map_err(|e| format!("Serialization failed: {}", e))
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use petgraph::{algo::dominators::simple_fast, EdgeDirection::Incoming, EdgeDirection::Outgoing, visit::{Dfs, EdgeRef}};
+
+use crate::{common::VarName, aexp::AExp, bexp::BExp};
+use crate::cfg::{Cfg, RawAnnot, AnnotNode, Node, NodeIdx, Edge};
+
+/// Convert the CFG into static single assignment (SSA) form, where every variable is assigned at most once:
+/// - Phi nodes (see `Node::Phi`) are inserted at the iterated dominance frontiers of the assignments (see `Cfg::dominance_frontiers`), i.e. wherever different assignments to a variable meet. This is the minimal SSA form, so a phi node may assign a variable that is not read afterwards.
+/// - The variables are then renamed along the dominator tree: The `k`-th assignment to `x` assigns `x_k`, and every read refers to the version that reaches it. The initial values (e.g. the input `x`) keep the original names.
+///
+/// Procedures are treated like the rest of the graph, e.g. the `Entry` node of a procedure that is called several times is a join point. Nodes that are not reachable from the init node are left unchanged.
+pub fn to_ssa(cfg: &Cfg<RawAnnot>) -> Cfg<RawAnnot> {
+    let mut cfg = cfg.clone();
+    let phis = insert_phis(&mut cfg);
+    rename(&mut cfg, &phis);
+    cfg
+}
+
+/// Like `to_ssa`, but additionally return the version of every variable that holds its final value, i.e. the version that reaches the end of the program (the node without successors). E.g. the output `z` of `if x <= 0 then z := 1 else z := 2 end; skip` is `z_3`, the variable of the phi node. Variables that are not assigned keep their names and are not listed, and the map is empty if the end of the program is not reachable.
+///
+/// If the program has several ends (e.g. `if x <= 0 then z := 1 else z := 2 end` ends with either assignment), they are first connected to a new `Terminal` node (like with `CfgOptions::always_terminal`), so that phi nodes join their versions.
+pub fn to_ssa_with_exit_versions(cfg: &Cfg<RawAnnot>) -> (Cfg<RawAnnot>, BTreeMap<VarName, VarName>) {
+    let mut cfg = cfg.clone();
+    connect_ends(&mut cfg);
+    let phis = insert_phis(&mut cfg);
+    let exit_versions = rename(&mut cfg, &phis);
+    (cfg, exit_versions)
+}
+
+/// Helper function for `to_ssa_with_exit_versions`: Connect the ends of the program (the nodes without successors that are reachable from the init node) to a new `Terminal` node, if there is more than one
+fn connect_ends(cfg: &mut Cfg<RawAnnot>) {
+    if cfg.graph.node_weight(cfg.init).is_none() {
+        return;
+    }
+    let mut dfs = Dfs::new(&cfg.graph, cfg.init);
+    let mut ends = vec![];
+    while let Some(n) = dfs.next(&cfg.graph) {
+        if cfg.graph.neighbors_directed(n, Outgoing).next().is_none() {
+            ends.push(n);
+        }
+    }
+    if ends.len() > 1 {
+        let terminal = cfg.graph.add_node(AnnotNode::new(Node::Terminal, RawAnnot {}));
+        ends.into_iter().for_each(|n| { cfg.graph.add_edge(n, terminal, Edge::Plain); });
+    }
+}
+
+/// Helper function for `to_ssa`: Insert the phi nodes, and return the original variable of each
+fn insert_phis(cfg: &mut Cfg<RawAnnot>) -> HashMap<NodeIdx, VarName> {
+    let frontiers = cfg.dominance_frontiers();
+    let mut def_sites: BTreeMap<VarName, BTreeSet<NodeIdx>> = BTreeMap::new();
+    cfg.graph.node_indices().filter(|n| frontiers.contains_key(n)).for_each(|n| {
+        if let Node::Assign(x, _) | Node::Havoc(x) = &cfg.graph[n].node {
+            def_sites.entry(x.clone()).or_default().insert(n);
+        }
+    });
+    // The join points that need a phi node for a variable, i.e. the iterated dominance frontier of its assignments
+    let mut joins: BTreeMap<NodeIdx, BTreeSet<VarName>> = BTreeMap::new();
+    for (x, sites) in def_sites {
+        let mut worklist: Vec<NodeIdx> = sites.into_iter().collect();
+        while let Some(n) = worklist.pop() {
+            for m in &frontiers[&n] {
+                if joins.entry(*m).or_default().insert(x.clone()) {
+                    // A phi node is an assignment, too
+                    worklist.push(*m);
+                }
+            }
+        }
+    }
+
+    // Insert the chain of phi nodes in front of every join point, redirecting the incoming edges to its first node
+    let mut phis = HashMap::new();
+    for (m, vars) in joins {
+        let incoming: Vec<(NodeIdx, Edge)> = cfg.graph.edges_directed(m, Incoming).map(|e| (e.source(), e.weight().clone())).collect();
+        let preds: BTreeSet<NodeIdx> = incoming.iter().map(|(p, _)| *p).collect();
+        cfg.graph.retain_edges(|g, e| g.edge_endpoints(e).is_none_or(|(_, target)| target != m));
+        let chain: Vec<NodeIdx> = vars.into_iter().map(|x| {
+            let args = preds.iter().map(|p| (*p, x.clone())).collect();
+            let phi = cfg.graph.add_node(AnnotNode::new(Node::Phi(x.clone(), args), RawAnnot {}));
+            phis.insert(phi, x);
+            phi
+        }).collect();
+        incoming.into_iter().for_each(|(p, e)| { cfg.graph.add_edge(p, chain[0], e); });
+        chain.windows(2).for_each(|w| { cfg.graph.add_edge(w[0], w[1], Edge::Plain); });
+        cfg.graph.add_edge(*chain.last().unwrap(), m, Edge::Plain);
+    }
+    phis
+}
+
+/// Helper function for `to_ssa`: Rename the variables along the dominator tree, where `phis` maps the phi nodes to their original variables. Return the versions that reach the end of the program, see `to_ssa_with_exit_versions`.
+fn rename(cfg: &mut Cfg<RawAnnot>, phis: &HashMap<NodeIdx, VarName>) -> BTreeMap<VarName, VarName> {
+    let mut exit_versions = BTreeMap::new();
+    if cfg.graph.node_weight(cfg.init).is_none() {
+        return exit_versions;
+    }
+    let doms = simple_fast(&cfg.graph, cfg.init);
+    let mut children: HashMap<NodeIdx, Vec<NodeIdx>> = HashMap::new();
+    cfg.graph.node_indices().for_each(|n| {
+        if let Some(d) = doms.immediate_dominator(n) {
+            children.entry(d).or_default().push(n);
+        }
+    });
+
+    // The versions of each variable that are visible at the current node, the current one last
+    let mut versions: HashMap<VarName, Vec<VarName>> = HashMap::new();
+    let mut counters: HashMap<VarName, usize> = HashMap::new();
+    let current = |versions: &HashMap<VarName, Vec<VarName>>, x: &VarName| versions.get(x).and_then(|vs| vs.last()).cloned().unwrap_or_else(|| x.clone());
+
+    // The dominator tree is traversed with an explicit stack (instead of recursion), as it may be as deep as the CFG is long. Leaving a node removes its versions again.
+    enum Task { Enter(NodeIdx), Leave(Vec<VarName>) }
+    let mut tasks = vec![Task::Enter(cfg.init)];
+    while let Some(task) = tasks.pop() {
+        let n = match task {
+            Task::Enter(n) => {n}
+            Task::Leave(defined) => {
+                defined.iter().for_each(|x| { versions.get_mut(x).unwrap().pop(); });
+                continue;
+            }
+        };
+        let mut new_version = |x: &VarName, versions: &mut HashMap<VarName, Vec<VarName>>| {
+            let k = counters.entry(x.clone()).or_insert(0);
+            *k += 1;
+            let v = VarName::new(&format!("{}_{}", x, k));
+            versions.entry(x.clone()).or_default().push(v.clone());
+            v
+        };
+        let mut defined = vec![];
+        let node = match &cfg.graph[n].node {
+            Node::Assign(x, a) => {
+                let a = rename_aexp(a, &|y| current(&versions, y));
+                defined.push(x.clone());
+                Node::Assign(new_version(x, &mut versions), Box::new(a))
+            }
+            Node::Havoc(x) => {
+                defined.push(x.clone());
+                Node::Havoc(new_version(x, &mut versions))
+            }
+            Node::Branch(b) => {Node::Branch(Box::new(rename_bexp(b, &|y| current(&versions, y))))}
+            Node::Phi(_, args) => {
+                let x = &phis[&n];
+                defined.push(x.clone());
+                Node::Phi(new_version(x, &mut versions), args.clone())
+            }
+            node => {node.clone()}
+        };
+        cfg.graph[n].node = node;
+        // The end of the program is the (reachable) node without successors
+        if cfg.graph.neighbors_directed(n, Outgoing).next().is_none() {
+            exit_versions = versions.iter().filter_map(|(x, vs)| vs.last().map(|v| (x.clone(), v.clone()))).collect();
+        }
+
+        // Fill in the arguments of the phi nodes that follow `n`. Within a chain of phi nodes, the arguments refer to the predecessors of the chain.
+        if !phis.contains_key(&n) {
+            let succs: Vec<NodeIdx> = cfg.graph.neighbors_directed(n, Outgoing).collect();
+            for s in succs {
+                let mut m = Some(s).filter(|m| phis.contains_key(m));
+                while let Some(phi) = m {
+                    let version = current(&versions, &phis[&phi]);
+                    if let Node::Phi(_, args) = &mut cfg.graph[phi].node {
+                        args.iter_mut().filter(|(p, _)| *p == n).for_each(|(_, y)| *y = version.clone());
+                    }
+                    m = cfg.graph.neighbors_directed(phi, Outgoing).next().filter(|m| phis.contains_key(m));
+                }
+            }
+        }
+
+        tasks.push(Task::Leave(defined));
+        let mut cs = children.remove(&n).unwrap_or_default();
+        cs.sort();
+        // Rust Expl.: The children are pushed in reverse order, so that they are visited in ascending order
+        cs.into_iter().rev().for_each(|c| tasks.push(Task::Enter(c)));
+    }
+    exit_versions
+}
+
+/// Helper function for `rename`: Replace every variable `x` of `a` by `f(x)`
+fn rename_aexp(a: &AExp, f: &dyn Fn(&VarName) -> VarName) -> AExp {
+    let rename = |a1: &AExp, a2: &AExp| (Box::new(rename_aexp(a1, f)), Box::new(rename_aexp(a2, f)));
+    match a {
+        AExp::Num(_) => {a.clone()}
+        AExp::Var(x) => {AExp::Var(f(x))}
+        AExp::Add(a1, a2) => { let (r1, r2) = rename(a1, a2); AExp::Add(r1, r2) }
+        AExp::Mul(a1, a2) => { let (r1, r2) = rename(a1, a2); AExp::Mul(r1, r2) }
+        AExp::Mod(a1, a2) => { let (r1, r2) = rename(a1, a2); AExp::Mod(r1, r2) }
+        AExp::Pow(a1, a2) => { let (r1, r2) = rename(a1, a2); AExp::Pow(r1, r2) }
+    }
+}
+
+/// Helper function for `rename`: Replace every variable `x` of `b` by `f(x)`
+fn rename_bexp(b: &BExp, f: &dyn Fn(&VarName) -> VarName) -> BExp {
+    b.map_aexps(&|a| rename_aexp(a, f))
+}
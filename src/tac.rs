@@ -0,0 +1,353 @@
+use std::{collections::HashMap, fmt::Display};
+use serde::{Serialize, Deserialize};
+
+use crate::{ast::{Prog, ProgAtom}, aexp::AExp, bexp::BExp, common::{VarName, Int, FreshVars}};
+use crate::interpreter::{MemConfig, eval_aexp};
+
+/// An operand of a three-address instruction: Either a variable (of the program or a temporary) or a number
+#[derive(PartialEq,Clone,Debug,Eq,Hash,Serialize,Deserialize)]
+pub enum Operand {
+    Var(VarName),
+    Num(Int)
+}
+
+/// An arithmetic operation of a three-address instruction
+#[derive(PartialEq,Clone,Copy,Debug,Eq,Hash,Serialize,Deserialize)]
+pub enum TacOp {
+    Add,
+    Mul,
+    Mod,
+    Pow
+}
+
+/// A comparison of a conditional jump
+#[derive(PartialEq,Clone,Copy,Debug,Eq,Hash,Serialize,Deserialize)]
+pub enum CmpOp {
+    LessEq,
+    Less
+}
+
+/// # Three-Address Code
+/// A linear intermediate representation, where every instruction applies at most one operation:
+/// - `Copy(x, o)`:            `x = o`
+/// - `BinOp(x, o1, op, o2)`:  `x = o1 op o2`
+/// - `Label(l)`:              Jump target `Ll:`
+/// - `Goto(l)`:               Unconditional jump to `Ll`
+/// - `If(o1, cmp, o2, b, l)`: Jump to `Ll` if the comparison `o1 cmp o2` evaluates to `b`, otherwise continue with the next instruction
+/// - `Push(x)`:               Push the value of `x` onto the stack of saved values
+/// - `Pop(x)`:                Pop the top of the stack of saved values into `x`
+/// - `Proc(p)`:               Start of the body of procedure `p`
+/// - `Call(p)`:               Jump to the body of procedure `p`, continuing with the next instruction on `Return`
+/// - `Return`:                End of a procedure body
+/// - `Halt`:                  End of the program
+#[derive(PartialEq,Clone,Debug,Eq,Hash,Serialize,Deserialize)]
+pub enum TacInstr {
+    Copy(VarName, Operand),
+    BinOp(VarName, Operand, TacOp, Operand),
+    Label(usize),
+    Goto(usize),
+    If(Operand, CmpOp, Operand, bool, usize),
+    Push(VarName),
+    Pop(VarName),
+    Proc(VarName),
+    Call(VarName),
+    Return,
+    Halt
+}
+
+/// Lower a program to three-address code: Nested expressions are flattened using fresh temporaries `t0`, `t1`, ... (see `FreshVars`), e.g. `z := (x + 1) * 2` becomes `t0 = x + 1; z = t0 * 2`. Boolean expressions are lowered to conditional jumps, evaluating `&&` and `||` from left to right (which does not change the result, as expressions have no side effects).
+/// The code of the main program comes first and ends with `Halt`. It is followed by the bodies of the procedures (in the order of their definitions), each starting with `Proc` and ending with `Return`.
+pub fn to_tac(prog: &Prog) -> Vec<TacInstr> {
+    let mut l = Lowering { code: vec![], temps: FreshVars::new("t"), labels: 0, loops: vec![] };
+    l.prog(prog);
+    l.code.push(TacInstr::Halt);
+    let Prog::Prog(ps) = prog;
+    ps.iter().for_each(|p| if let ProgAtom::Proc(name, body) = p {
+        l.code.push(TacInstr::Proc(name.clone()));
+        l.prog(body);
+        l.code.push(TacInstr::Return);
+    });
+    l.code
+}
+
+/// Intermediate data structure, only needed during `to_tac`
+/// - `code`:   The instructions emitted so far
+/// - `temps`:  The supply of temporaries
+/// - `labels`: The number of labels used so far
+/// - `loops`:  The loops whose bodies are being lowered (innermost last), see `LoopLabels`
+struct Lowering {
+    code: Vec<TacInstr>,
+    temps: FreshVars,
+    labels: usize,
+    loops: Vec<LoopLabels>
+}
+
+/// A loop whose body is being lowered: The labels targeted by `continue` and `break`, and the variables of the `let` blocks entered within the body (innermost last), whose outer values are on the stack of saved values
+type LoopLabels = (usize, usize, Vec<VarName>);
+
+impl Lowering {
+    /// Return a label that has not been used before
+    fn label(&mut self) -> usize {
+        self.labels += 1;
+        self.labels - 1
+    }
+
+    fn prog(&mut self, p: &Prog) {
+        let Prog::Prog(ps) = p;
+        ps.iter().for_each(|p| self.prog_atom(p));
+    }
+
+    fn prog_atom(&mut self, p: &ProgAtom) {
+        match p {
+            // Procedure definitions are lowered after the main program
+            ProgAtom::Skip | ProgAtom::Proc(_, _) => {}
+            ProgAtom::Assign(x, a) => {self.assign(x, a)}
+            ProgAtom::Cond(b, p_tt, p_ff) => {
+                let (l_ff, l_end) = (self.label(), self.label());
+                self.jump(b, false, l_ff);
+                self.prog(p_tt);
+                self.code.push(TacInstr::Goto(l_end));
+                self.code.push(TacInstr::Label(l_ff));
+                self.prog(p_ff);
+                self.code.push(TacInstr::Label(l_end));
+            }
+            ProgAtom::While(b, p) => {
+                let (l_head, l_end) = (self.label(), self.label());
+                self.code.push(TacInstr::Label(l_head));
+                self.jump(b, false, l_end);
+                self.loops.push((l_head, l_end, vec![]));
+                self.prog(p);
+                self.loops.pop();
+                self.code.push(TacInstr::Goto(l_head));
+                self.code.push(TacInstr::Label(l_end));
+            }
+            ProgAtom::Repeat(p, b) => {
+                let l_body = self.label();
+                self.code.push(TacInstr::Label(l_body));
+                if !p.has_jumps() {
+                    self.prog(p);
+                    self.jump(b, false, l_body);
+                    return;
+                }
+                // Only a body with jumps needs labels for the guard and the end of the loop
+                let (l_guard, l_end) = (self.label(), self.label());
+                self.loops.push((l_guard, l_end, vec![]));
+                self.prog(p);
+                self.loops.pop();
+                self.code.push(TacInstr::Label(l_guard));
+                self.jump(b, false, l_body);
+                self.code.push(TacInstr::Label(l_end));
+            }
+            ProgAtom::Break | ProgAtom::Continue => {
+                // As in the CFG, the outer values of the `let` blocks left by the jump are restored first. Outside of loops (which is rejected by the parser), a jump is ignored.
+                if let Some((l_continue, l_break, lets)) = self.loops.last() {
+                    let target = if *p == ProgAtom::Break { *l_break } else { *l_continue };
+                    let restores = lets.iter().rev().map(|x| TacInstr::Pop(x.clone()));
+                    self.code.extend(restores.collect::<Vec<_>>());
+                    self.code.push(TacInstr::Goto(target));
+                }
+            }
+            ProgAtom::Let(x, a, p) => {
+                // The outer value is saved on a stack (rather than in a temporary, as in the CFG), so that every activation of a recursive procedure restores its own value
+                self.code.push(TacInstr::Push(x.clone()));
+                self.assign(x, a);
+                if let Some((_, _, lets)) = self.loops.last_mut() { lets.push(x.clone()) }
+                self.prog(p);
+                if let Some((_, _, lets)) = self.loops.last_mut() { lets.pop(); }
+                self.code.push(TacInstr::Pop(x.clone()));
+            }
+            ProgAtom::Call(name) => {self.code.push(TacInstr::Call(name.clone()))}
+            // As in the interpreter, the arbitrary value is `0`
+            ProgAtom::Havoc(x) => {self.code.push(TacInstr::Copy(x.clone(), Operand::Num(0)))}
+        }
+    }
+
+    /// Emit `x = a`. The outermost operation of `a` is assigned to `x` directly, without a temporary.
+    fn assign(&mut self, x: &VarName, a: &AExp) {
+        match split_bin_op(a) {
+            Some((a1, op, a2)) => {
+                let o1 = self.aexp(a1);
+                let o2 = self.aexp(a2);
+                self.code.push(TacInstr::BinOp(x.clone(), o1, op, o2));
+            }
+            None => {
+                let o = self.aexp(a);
+                self.code.push(TacInstr::Copy(x.clone(), o));
+            }
+        }
+    }
+
+    /// Emit the computation of `a` (left operands first) and return the operand holding its value. Temporaries are numbered in the order in which they are assigned.
+    fn aexp(&mut self, a: &AExp) -> Operand {
+        match a {
+            AExp::Num(n)      => {Operand::Num(*n)}
+            AExp::Var(x)      => {Operand::Var(x.clone())}
+            AExp::Add(a1, a2) => {self.temp(a1, TacOp::Add, a2)}
+            AExp::Mul(a1, a2) => {self.temp(a1, TacOp::Mul, a2)}
+            AExp::Mod(a1, a2) => {self.temp(a1, TacOp::Mod, a2)}
+            AExp::Pow(a1, a2) => {self.temp(a1, TacOp::Pow, a2)}
+        }
+    }
+
+    /// Emit `t = a1 op a2` for a fresh temporary `t` and return it
+    fn temp(&mut self, a1: &AExp, op: TacOp, a2: &AExp) -> Operand {
+        let o1 = self.aexp(a1);
+        let o2 = self.aexp(a2);
+        let t = self.temps.fresh();
+        self.code.push(TacInstr::BinOp(t.clone(), o1, op, o2));
+        Operand::Var(t)
+    }
+
+    /// Emit a jump to `target` that is taken iff `b` evaluates to `jump_if`; otherwise, the code continues with the next instruction
+    fn jump(&mut self, b: &BExp, jump_if: bool, target: usize) {
+        match b {
+            BExp::LessEq(a1, a2) | BExp::Less(a1, a2) => {
+                let cmp = if let BExp::LessEq(_, _) = b { CmpOp::LessEq } else { CmpOp::Less };
+                let o1 = self.aexp(a1);
+                let o2 = self.aexp(a2);
+                self.code.push(TacInstr::If(o1, cmp, o2, jump_if, target));
+            }
+            BExp::Neg(b) => {self.jump(b, !jump_if, target)}
+            // `b1 && b2` is false iff one of them is false, and `b1 || b2` is true iff one of them is true
+            BExp::And(b1, b2) | BExp::Or(b1, b2) if jump_if == matches!(b, BExp::Or(_, _)) => {
+                self.jump(b1, jump_if, target);
+                self.jump(b2, jump_if, target);
+            }
+            // Otherwise, both operands must be tested: If the first one already decides the result the other way, the jump is skipped
+            BExp::And(b1, b2) | BExp::Or(b1, b2) => {
+                let l_skip = self.label();
+                self.jump(b1, !jump_if, l_skip);
+                self.jump(b2, jump_if, target);
+                self.code.push(TacInstr::Label(l_skip));
+            }
+        }
+    }
+}
+
+/// Helper function: Split a binary arithmetic expression into its operands and operation
+fn split_bin_op(a: &AExp) -> Option<(&AExp, TacOp, &AExp)> {
+    match a {
+        AExp::Add(a1, a2) => {Some((a1, TacOp::Add, a2))}
+        AExp::Mul(a1, a2) => {Some((a1, TacOp::Mul, a2))}
+        AExp::Mod(a1, a2) => {Some((a1, TacOp::Mod, a2))}
+        AExp::Pow(a1, a2) => {Some((a1, TacOp::Pow, a2))}
+        AExp::Num(_) | AExp::Var(_) => {None}
+    }
+}
+
+/// Input: Three-address code (see `to_tac`) + Assignment to "x" variable
+/// Output: Like `eval`, the final value of the variable `z`. The arithmetic operations have the same semantics as in the interpreter (e.g. wrapping around on overflow).
+/// Execution starts with the first instruction and stops at `Halt` (or after the last instruction). A jump to an undefined label or a call of an undefined procedure behaves like `skip`, as does a `Return` outside of a procedure or a `Pop` from the empty stack. This function may diverge.
+pub fn eval_tac(code: &[TacInstr], input: Int) -> Int {
+    let mut mem = MemConfig::new();
+    mem.assign(&VarName::new("x"), input);
+    let labels: HashMap<usize, usize> = code.iter().enumerate().filter_map(|(i, instr)| match instr { TacInstr::Label(l) => Some((*l, i)), _ => None }).collect();
+    let procs: HashMap<&VarName, usize> = code.iter().enumerate().filter_map(|(i, instr)| match instr { TacInstr::Proc(p) => Some((p, i)), _ => None }).collect();
+    let value = |o: &Operand, mem: &MemConfig| match o { Operand::Var(x) => mem.lookup(x), Operand::Num(n) => *n };
+    // The positions to continue with after returning from the active procedures
+    let mut returns: Vec<usize> = vec![];
+    // The values saved by `Push`
+    let mut saved: Vec<Int> = vec![];
+    let mut pc = 0;
+    while let Some(instr) = code.get(pc) {
+        pc += 1;
+        match instr {
+            TacInstr::Copy(x, o) => {
+                let n = value(o, &mem);
+                mem.assign(x, n);
+            }
+            TacInstr::BinOp(x, o1, op, o2) => {
+                let n = eval_op(*op, value(o1, &mem), value(o2, &mem));
+                mem.assign(x, n);
+            }
+            TacInstr::Push(x) => {saved.push(mem.lookup(x))}
+            TacInstr::Pop(x) => {
+                if let Some(n) = saved.pop() {
+                    mem.assign(x, n);
+                }
+            }
+            TacInstr::Label(_) | TacInstr::Proc(_) => {}
+            TacInstr::Goto(l) => {pc = labels.get(l).copied().unwrap_or(pc)}
+            TacInstr::If(o1, cmp, o2, jump_if, l) => {
+                let (n1, n2) = (value(o1, &mem), value(o2, &mem));
+                let result = match cmp { CmpOp::LessEq => n1 <= n2, CmpOp::Less => n1 < n2 };
+                if result == *jump_if {
+                    pc = labels.get(l).copied().unwrap_or(pc);
+                }
+            }
+            TacInstr::Call(p) => {
+                if let Some(entry) = procs.get(p) {
+                    returns.push(pc);
+                    pc = *entry;
+                }
+            }
+            TacInstr::Return => {pc = returns.pop().unwrap_or(pc)}
+            TacInstr::Halt => {break}
+        }
+    }
+    mem.lookup(&VarName::new("z"))
+}
+
+/// Helper function for `eval_tac`: Apply an arithmetic operation via the interpreter, so that both agree on its semantics
+fn eval_op(op: TacOp, n1: Int, n2: Int) -> Int {
+    let (a1, a2) = (Box::new(AExp::Num(n1)), Box::new(AExp::Num(n2)));
+    let a = match op {
+        TacOp::Add => {AExp::Add(a1, a2)}
+        TacOp::Mul => {AExp::Mul(a1, a2)}
+        TacOp::Mod => {AExp::Mod(a1, a2)}
+        TacOp::Pow => {AExp::Pow(a1, a2)}
+    };
+    eval_aexp(&a, &MemConfig::new())
+}
+
+/// Pretty-printer
+impl Display for Operand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operand::Var(x) => {write!(f, "{}", x)}
+            Operand::Num(n) => {write!(f, "{}", n)}
+        }
+    }
+}
+
+/// Pretty-printer
+impl Display for TacOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TacOp::Add => {write!(f, "+")}
+            TacOp::Mul => {write!(f, "*")}
+            TacOp::Mod => {write!(f, "%")}
+            TacOp::Pow => {write!(f, "^")}
+        }
+    }
+}
+
+/// Pretty-printer
+impl Display for CmpOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CmpOp::LessEq => {write!(f, "<=")}
+            CmpOp::Less   => {write!(f, "<")}
+        }
+    }
+}
+
+/// Pretty-printer, e.g. `t0 = x + 1`, `if x <= 0 goto L1` or `ifnot x <= 0 goto L1`
+impl Display for TacInstr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TacInstr::Copy(x, o)                 => {write!(f, "{} = {}", x, o)}
+            TacInstr::BinOp(x, o1, op, o2)       => {write!(f, "{} = {} {} {}", x, o1, op, o2)}
+            TacInstr::Label(l)                   => {write!(f, "L{}:", l)}
+            TacInstr::Goto(l)                    => {write!(f, "goto L{}", l)}
+            TacInstr::If(o1, cmp, o2, true, l)   => {write!(f, "if {} {} {} goto L{}", o1, cmp, o2, l)}
+            TacInstr::If(o1, cmp, o2, false, l)  => {write!(f, "ifnot {} {} {} goto L{}", o1, cmp, o2, l)}
+            TacInstr::Push(x)                    => {write!(f, "push {}", x)}
+            TacInstr::Pop(x)                     => {write!(f, "pop {}", x)}
+            TacInstr::Proc(p)                    => {write!(f, "proc {}:", p)}
+            TacInstr::Call(p)                    => {write!(f, "call {}", p)}
+            TacInstr::Return                     => {write!(f, "return")}
+            TacInstr::Halt                       => {write!(f, "halt")}
+        }
+    }
+}
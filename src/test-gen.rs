@@ -1,11 +1,31 @@
+/// CFGs with more nodes than this are stored in the binary format (see `flanelly::binary`) instead of JSON
+const LARGE_FIXTURE_NODES: usize = 1000;
+
 fn generate_test(name: &str) {
     let input: String = read_to_string(format!("test/{:}/prog.while", name)).unwrap();
     let ast = parser::parse(&input).unwrap();
     let cfg = cfg::ast_to_cfg(&ast);
     let cfg_const_prop: Cfg<MfpAnnot<MultiConstLat>> = mfp(&cfg);
     let cfg_avail_exp: Cfg<MfpAnnot<ExpSetLat>> = mfp(&cfg);
-    fs::write(format!("test/{:}/ast.json", name), serde_json::to_string(&ast).unwrap()).unwrap();
-    fs::write(format!("test/{:}/cfg.json", name), serde_json::to_string(&cfg).unwrap()).unwrap();
-    fs::write(format!("test/{:}/cfg_const_prop.json", name), serde_json::to_string(&cfg_const_prop).unwrap()).unwrap();
-    fs::write(format!("test/{:}/cfg_avail_exp.json", name), serde_json::to_string(&cfg_avail_exp).unwrap()).unwrap();
-}
\ No newline at end of file
+    let large = cfg.graph.node_count() > LARGE_FIXTURE_NODES;
+    write_fixture(name, "ast", &ast, large);
+    write_fixture(name, "cfg", &cfg, large);
+    write_fixture(name, "cfg_const_prop", &cfg_const_prop, large);
+    write_fixture(name, "cfg_avail_exp", &cfg_avail_exp, large);
+}
+
+/// Generate the `prog.while` of a test from its `ast.json` (e.g. for an AST produced by a program transformation), then all other files as usual
+fn generate_test_from_ast(name: &str) {
+    let ast: Prog = serde_json::from_str(&read_to_string(format!("test/{:}/ast.json", name)).unwrap()).unwrap();
+    ast::write_while(&ast, format!("test/{:}/prog.while", name)).unwrap();
+    generate_test(name);
+}
+
+/// Write `test/<name>/<file>.json`, or `test/<name>/<file>.bin` for large fixtures
+fn write_fixture<T: Serialize>(name: &str, file: &str, value: &T, large: bool) {
+    if large {
+        fs::write(format!("test/{:}/{:}.bin", name, file), binary::to_bytes(value).unwrap()).unwrap();
+    } else {
+        fs::write(format!("test/{:}/{:}.json", name, file), serde_json::to_string(value).unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,50 @@
+use crate::{ast::{Prog, ProgAtom}, aexp::AExp, bexp::BExp, common::VarName};
+
+/// Compute the weakest precondition of a program `p` w.r.t. a postcondition `post`, i.e. the weakest boolean expression `pre` such that running `p` in a memory configuration satisfying `pre` yields a memory configuration satisfying `post`.
+/// This is possible only for loop-free programs. If `p` contains a loop, a procedure call or a `havoc`, `None` is returned.
+pub fn wp(p: &Prog, post: &BExp) -> Option<BExp> {
+    let Prog::Prog(ps) = p;
+    // The precondition of the last atomic program is the postcondition of the one before, etc.
+    ps.iter().rev().try_fold(post.clone(), |post, p| wp_atom(p, &post))
+}
+
+/// Compute the weakest precondition of an atomic program `p` w.r.t. a postcondition `post`
+pub fn wp_atom(p: &ProgAtom, post: &BExp) -> Option<BExp> {
+    match p {
+        ProgAtom::Skip => {Some(post.clone())}
+        // Procedure definitions do nothing by themselves
+        ProgAtom::Proc(_, _) => {Some(post.clone())}
+        // wp(x := a, post) = post[x := a]
+        ProgAtom::Assign(x, a) => {Some(post.substitute(x, a))}
+        // wp(if b then p1 else p2 end, post) = (b ==> wp(p1, post)) && (!b ==> wp(p2, post))
+        // where the implication `b1 ==> b2` is encoded as `!b1 || b2`
+        ProgAtom::Cond(b, p_tt, p_ff) => {
+            let wp_tt = wp(p_tt, post)?;
+            let wp_ff = wp(p_ff, post)?;
+            let implies_tt = BExp::Or(Box::new(BExp::Neg(b.clone())), Box::new(wp_tt));
+            let implies_ff = BExp::Or(b.clone(), Box::new(wp_ff));
+            Some(BExp::And(Box::new(implies_tt), Box::new(implies_ff)))
+        }
+        // A let block behaves like `x' := x; x := a; p; x := x'` for a fresh variable `x'`, i.e.
+        // wp(let x := a in p end, post) = wp(p, post[x := x'])[x := a][x' := x]
+        ProgAtom::Let(x, a, p) => {
+            let saved = fresh_var(x, post);
+            let pre = wp(p, &post.substitute(x, &AExp::Var(saved.clone())))?;
+            Some(pre.substitute(x, a).substitute(&saved, &AExp::Var(x.clone())))
+        }
+        // Loops would need an invariant, calls would need to deal with recursion, and `havoc x` would need a quantifier over `x`
+        ProgAtom::While(_, _) | ProgAtom::Repeat(_, _) | ProgAtom::Call(_) | ProgAtom::Havoc(_) => {None}
+        // A jump does not reach the end of `p`, but the end of the enclosing loop iteration, which is not known here
+        ProgAtom::Break | ProgAtom::Continue => {None}
+    }
+}
+
+/// Return a variable `x'` (with as many primes as needed) that does not occur in `b`. As the parser does not accept primes in variable names, such a variable cannot occur in a program either.
+fn fresh_var(x: &VarName, b: &BExp) -> VarName {
+    let vars = b.vars();
+    let mut name = format!("{}'", x);
+    while vars.contains(&VarName::new(&name)) {
+        name.push('\'');
+    }
+    VarName::new(&name)
+}
@@ -1,48 +1,2537 @@
-use std::{fs::{read_to_string}};
+use std::{fs::{read_to_string}, collections::{HashMap, BTreeMap}, io::Write, process::{Command, Stdio}};
 
 use test_generator::test_resources;
+use serde::de::DeserializeOwned;
+use petgraph::visit::{Dfs, EdgeRef};
 
-use flanelly::cfg::{Cfg, RawAnnot};
+use flanelly::cfg::{Cfg, CfgStats, RawAnnot, Node, AnnotNode, Edge, NodeIdx, BranchProbability};
+use flanelly::dot::{to_dot, DotOptions, EdgeStyle, DotEngine};
+use flanelly::flow_analysis::def_vars::{undefined_reads, DefVarsLat};
+use flanelly::flow_analysis::uninit::{uninit_reads, UninitLat, MultiUninitLat};
+use flanelly::flow_analysis::live_vars::{LiveVarsLat, dead_stores, remove_dead_stores, remove_dead_assignments, inline_single_use};
+use flanelly::flow_analysis::optimize::{optimize, fold_constants, eliminate_dead_code};
+use flanelly::flow_analysis::mfp::{compact_annots, diff_annots, mop, mfp_backward, mfp_explained, mfp_acyclic, mfp_incremental, mfp_explained_with, MfpOptions, mfp_ascents, mfp_widening, mfp_widening_with, mfp_widening_ascent, mfp_narrowing};
+use flanelly::flow_analysis::common::{SemiLat, FlowSemantics, AnalysisDirection, Unrefined};
+use flanelly::{aexp::{AExp, InternedAExp}, bexp::BExp, common::{VarName, Int}, ast::{ProgAtom, ProgStats, Position, Span, Comments, unroll, write_while, MAX_UNROLL}, wp::wp};
+use flanelly::flow_analysis::const_prop::{ConstLat, AbsorbPolicy, soundness_counterexamples, propagate_constants, consts_after_atoms, highlight_consts, Counterexample, top_origin};
+use flanelly::flow_analysis::finite_set::{FiniteSetLat, MultiFiniteSetLat, FINITE_SET_MAX};
+use flanelly::flow_analysis::interval::{IntervalLat, MultiIntervalLat};
+use flanelly::flow_analysis::sign::{SignLat, MultiSignLat};
+use flanelly::flow_analysis::product::{ProductLat, ConstSignLat};
+use flanelly::flow_analysis::tracked::Tracked;
+use flanelly::flow_analysis::correlation::correlated_branches;
+use flanelly::flow_analysis::affine::{AffineForm, AffineLat, MultiAffineLat};
+use flanelly::playground::analyze_source;
+
+use flanelly::tac::{to_tac, eval_tac, TacInstr};
+use flanelly::ssa::{to_ssa, to_ssa_with_exit_versions};
+use flanelly::interpreter::{MemConfig, Aborted, NonTerminating, OutputStats, eval_random, output_stats, eval_with_callback, eval_detect_loops, eval_prog, eval_bexp, eval_cfg, eval_cfg_mem, AmbiguousSuccessor, eval_aexp_traced, equivalent_loopfree, counterexample_loopfree};
 
 use flanelly::{parser, interpreter::eval, cfg, flow_analysis::mfp::MfpAnnot, flow_analysis::const_prop::MultiConstLat, flow_analysis::mfp::mfp, ast::Prog, flow_analysis::avail_exp::ExpSetLat};
 
 #[test_resources("tests-res/*")]
 fn test_parser(name: &str) {
     let input: String = read_to_string(format!("{:}/prog.while", name)).unwrap();
-    let expected: Prog = serde_json::from_str(&read_to_string(format!("{:}/ast.json", name)).unwrap()).unwrap();
+    let expected: Prog = read_fixture(name, "ast");
     let actual = parser::parse(&input).unwrap();
     assert_eq!(expected, actual);
 }
 
 #[test_resources("tests-res/*")]
 fn test_eval(name: &str) {
-    let prog: Prog = serde_json::from_str(&read_to_string(format!("{:}/ast.json", name)).unwrap()).unwrap();
-    let cases: Vec<(i32, i32)> = serde_json::from_str(&read_to_string(format!("{:}/eval.json", name)).unwrap()).unwrap();
+    let prog: Prog = read_fixture(name, "ast");
+    let cases: Vec<(Int, Int)> = read_fixture(name, "eval");
     cases.iter().for_each(|(x, y)| {
         assert_eq!(eval(&prog, *x), *y);
     });
 }
 
+#[test_resources("tests-res/*")]
+fn test_eval_cfg(name: &str) {
+    let prog: Prog = read_fixture(name, "ast");
+    let cases: Vec<(Int, Int)> = read_fixture(name, "eval");
+    let cfg = cfg::ast_to_cfg(&prog);
+    cases.iter().for_each(|(x, _)| {
+        assert_eq!(eval_cfg(&cfg, *x), Ok(eval(&prog, *x)));
+    });
+}
+
+#[test]
+fn test_eval_cfg_repeated_calls() {
+    // A procedure that is called once returns to its only call
+    let once = parser::parse("proc inc do z := z + 1 end; z := x; call inc").unwrap();
+    (-2..=2).for_each(|x| assert_eq!(eval_cfg(&cfg::ast_to_cfg(&once), x), Ok(eval(&once, x))));
+    // The `Exit` node of a procedure that is called twice has two successors, and the CFG does not tell which one belongs to which call
+    let twice = parser::parse("proc inc do z := z + 1 end; call inc; call inc").unwrap();
+    assert_eq!(eval(&twice, 0), 2);
+    let cfg = cfg::ast_to_cfg(&twice);
+    let exit = cfg.graph.node_indices().find(|n| cfg.graph[*n].node == Node::Exit(VarName::new("inc"))).unwrap();
+    assert_eq!(eval_cfg(&cfg, 0), Err(AmbiguousSuccessor(exit)));
+}
+
+#[test_resources("tests-res/*")]
+fn test_ssa_preserves_semantics(name: &str) {
+    let prog: Prog = read_fixture(name, "ast");
+    let cases: Vec<(Int, Int)> = read_fixture(name, "eval");
+    let (ssa, exit_versions) = to_ssa_with_exit_versions(&cfg::ast_to_cfg(&prog));
+    let z = VarName::new("z");
+    cases.iter().for_each(|(x, _)| {
+        let mut mem = MemConfig::new();
+        mem.assign(&VarName::new("x"), *x);
+        assert_eq!(eval_cfg_mem(&ssa, mem).unwrap().lookup(exit_versions.get(&z).unwrap_or(&z)), eval(&prog, *x), "{} on {}", name, x);
+    });
+}
+
+#[test_resources("tests-res/*")]
+fn test_eval_tac(name: &str) {
+    let prog: Prog = read_fixture(name, "ast");
+    let cases: Vec<(Int, Int)> = read_fixture(name, "eval");
+    let code = to_tac(&prog);
+    cases.iter().for_each(|(x, _)| {
+        assert_eq!(eval_tac(&code, *x), eval(&prog, *x));
+    });
+}
+
+#[test_resources("tests-res/*")]
+fn test_inline_single_use_preserves_semantics(name: &str) {
+    let prog: Prog = read_fixture(name, "ast");
+    let cases: Vec<(Int, Int)> = read_fixture(name, "eval");
+    let inlined = inline_single_use(&prog);
+    cases.iter().for_each(|(x, y)| {
+        assert_eq!(eval(&inlined, *x), *y);
+    });
+}
+
+#[test_resources("tests-res/*")]
+fn test_optimize_preserves_semantics(name: &str) {
+    let prog: Prog = read_fixture(name, "ast");
+    let cases: Vec<(Int, Int)> = read_fixture(name, "eval");
+    let optimized = optimize(&prog);
+    cases.iter().for_each(|(x, y)| {
+        assert_eq!(eval(&optimized, *x), *y);
+    });
+}
+
+#[test_resources("tests-res/*")]
+fn test_uninit_reads_cover_undefined_reads(name: &str) {
+    let prog: Prog = read_fixture(name, "ast");
+    let reads: Vec<(VarName, NodeIdx)> = uninit_reads(&prog).into_iter().map(|(x, n, _)| (x, n)).collect();
+    undefined_reads(&prog).iter().for_each(|read| assert!(reads.contains(read), "{:?}", read));
+}
+
+#[test_resources("tests-res/*")]
+fn test_unroll_preserves_semantics(name: &str) {
+    let prog: Prog = read_fixture(name, "ast");
+    let cases: Vec<(Int, Int)> = read_fixture(name, "eval");
+    (0..3).for_each(|k| {
+        let unrolled = unroll(&prog, k);
+        cases.iter().for_each(|(x, y)| {
+            assert_eq!(eval(&unrolled, *x), *y);
+        });
+    });
+}
+
+#[test_resources("tests-res/*")]
+fn test_canonicalize_preserves_semantics(name: &str) {
+    let prog: Prog = read_fixture(name, "ast");
+    let cases: Vec<(Int, Int)> = read_fixture(name, "eval");
+    let canonical = prog.canonicalize();
+    cases.iter().for_each(|(x, y)| assert_eq!(eval(&canonical, *x), *y));
+    assert_eq!(canonical.canonicalize(), canonical);
+}
+
+#[test_resources("tests-res/*")]
+fn test_comments_round_trip(name: &str) {
+    let source = read_to_string(format!("{}/prog.while", name)).unwrap();
+    let (prog, comments) = parser::parse_with_comments(&source).unwrap();
+    assert_eq!(parser::parse_with_comments(&prog.to_source_with_comments(&comments)).unwrap(), (prog, comments));
+}
+
+#[test_resources("tests-res/*")]
+fn test_write_while(name: &str) {
+    let prog: Prog = read_fixture(name, "ast");
+    let path = std::env::temp_dir().join(format!("flanelly-{}.while", name.replace('/', "-")));
+    write_while(&prog, &path).unwrap();
+    assert_eq!(parser::parse(&read_to_string(&path).unwrap()).unwrap(), prog);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test_resources("tests-res/*")]
+fn test_structurally_eq(name: &str) {
+    let cfg: Cfg<RawAnnot> = read_fixture(name, "cfg");
+    let cfg_const_prop: Cfg<MfpAnnot<MultiConstLat>> = read_fixture(name, "cfg_const_prop");
+    assert!(cfg.structurally_eq(&cfg_const_prop));
+    assert!(cfg_const_prop.structurally_eq(&cfg));
+    assert!(cfg.structurally_eq(&mfp::<ExpSetLat>(&cfg)));
+}
+
+#[test_resources("tests-res/*")]
+fn test_cfg_stats(name: &str) {
+    let prog: Prog = read_fixture(name, "ast");
+    let cfg: Cfg<RawAnnot> = read_fixture(name, "cfg");
+    let stats = cfg.stats();
+    assert_eq!(stats.nodes.values().sum::<usize>(), cfg.graph.node_count());
+    assert_eq!(stats.edges.values().sum::<usize>(), cfg.graph.edge_count());
+    assert!(stats.reducible);
+    if prog.procs().is_empty() {
+        assert_eq!(stats.max_loop_depth, prog.stats().max_loop_depth);
+    }
+}
+
+#[test_resources("tests-res/*")]
+fn test_portable_json(name: &str) {
+    let cfg: Cfg<RawAnnot> = read_fixture(name, "cfg");
+    let json = cfg::to_portable_json(&cfg);
+    assert_eq!(cfg::from_portable_json::<RawAnnot>(&json).unwrap(), cfg);
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["nodes"].as_array().unwrap().len(), cfg.graph.node_count());
+    assert_eq!(value["edges"].as_array().unwrap().len(), cfg.graph.edge_count());
+    assert_eq!(value["init"], cfg.init.index());
+    // Annotations are kept
+    let cfg_const_prop: Cfg<MfpAnnot<MultiConstLat>> = read_fixture(name, "cfg_const_prop");
+    assert_eq!(cfg::from_portable_json::<MfpAnnot<MultiConstLat>>(&cfg::to_portable_json(&cfg_const_prop)).unwrap(), cfg_const_prop);
+}
+
 #[test_resources("tests-res/*")]
 fn test_ast_to_cfg(name: &str) {
-    let input: Prog = serde_json::from_str(&read_to_string(format!("{:}/ast.json", name)).unwrap()).unwrap();
-    let expected: Cfg<RawAnnot> = serde_json::from_str(&read_to_string(format!("{:}/cfg.json", name)).unwrap()).unwrap();
+    let input: Prog = read_fixture(name, "ast");
+    let expected: Cfg<RawAnnot> = read_fixture(name, "cfg");
     let actual = cfg::ast_to_cfg(&input);
     assert_eq!(expected, actual);
 }
 
 #[test_resources("tests-res/*")]
 fn test_const_prop(name: &str) {
-    let input: Cfg<RawAnnot> = serde_json::from_str(&read_to_string(format!("{:}/cfg.json", name)).unwrap()).unwrap();
-    let expected: Cfg<MfpAnnot<MultiConstLat>> = serde_json::from_str(&read_to_string(format!("{:}/cfg_const_prop.json", name)).unwrap()).unwrap();
+    let input: Cfg<RawAnnot> = read_fixture(name, "cfg");
+    let expected: Cfg<MfpAnnot<MultiConstLat>> = read_fixture(name, "cfg_const_prop");
     let actual: Cfg<MfpAnnot<MultiConstLat>> = mfp(&input);
     assert_eq!(expected, actual);
 }
 
+#[test_resources("tests-res/*")]
+fn test_const_prop_sound(name: &str) {
+    let prog: Prog = read_fixture(name, "ast");
+    assert_eq!(soundness_counterexamples(&prog, -20..=20, 10000), vec![]);
+}
+
 #[test_resources("tests-res/*")]
 fn test_avail_exp(name: &str) {
-    let input: Cfg<RawAnnot> = serde_json::from_str(&read_to_string(format!("{:}/cfg.json", name)).unwrap()).unwrap();
-    let expected: Cfg<MfpAnnot<ExpSetLat>> = serde_json::from_str(&read_to_string(format!("{:}/cfg_avail_exp.json", name)).unwrap()).unwrap();
+    let input: Cfg<RawAnnot> = read_fixture(name, "cfg");
+    let expected: Cfg<MfpAnnot<ExpSetLat>> = read_fixture(name, "cfg_avail_exp");
     let actual: Cfg<MfpAnnot<ExpSetLat>> = mfp(&input);
     assert_eq!(expected, actual);
-}
\ No newline at end of file
+}
+
+#[test_resources("tests-res/*")]
+fn test_finite_set(name: &str) {
+    let input: Cfg<RawAnnot> = read_fixture(name, "cfg");
+    let expected: Cfg<MfpAnnot<MultiFiniteSetLat>> = read_fixture(name, "cfg_finite_set");
+    let actual: Cfg<MfpAnnot<MultiFiniteSetLat>> = mfp(&input);
+    assert_eq!(expected, actual);
+}
+
+// The fixtures contain the bounds of 32-bit integers
+#[cfg(not(feature = "i64"))]
+#[test_resources("tests-res/*")]
+fn test_interval(name: &str) {
+    let input: Cfg<RawAnnot> = read_fixture(name, "cfg");
+    let expected: Cfg<MfpAnnot<MultiIntervalLat>> = read_fixture(name, "cfg_interval");
+    let actual: Cfg<MfpAnnot<MultiIntervalLat>> = mfp_widening(&input);
+    assert_eq!(expected, actual);
+}
+
+#[test_resources("tests-res/*")]
+fn test_dead_stores(name: &str) {
+    let input: Cfg<RawAnnot> = read_fixture(name, "cfg");
+    let expected: Vec<String> = read_fixture(name, "dead_stores");
+    let actual: Vec<String> = dead_stores(&input).into_iter().map(|n| input.graph[n].node.to_string()).collect();
+    assert_eq!(expected, actual);
+}
+
+#[test_resources("tests-res/*")]
+fn test_mfp_acyclic(name: &str) {
+    // For cyclic CFGs, `mfp_acyclic` falls back to `mfp`, so the results coincide in either case
+    let input: Cfg<RawAnnot> = read_fixture(name, "cfg");
+    let expected: Cfg<MfpAnnot<MultiConstLat>> = read_fixture(name, "cfg_const_prop");
+    assert_eq!(expected, mfp_acyclic(&input));
+    let expected: Cfg<MfpAnnot<ExpSetLat>> = read_fixture(name, "cfg_avail_exp");
+    assert_eq!(expected, mfp_acyclic(&input));
+}
+
+#[test_resources("tests-res/*")]
+fn test_display_round_trip(name: &str) {
+    let expected: Prog = read_fixture(name, "ast");
+    assert_eq!(parser::parse(&expected.to_string()).unwrap(), expected);
+}
+
+/// Read the fixture `<file>.json` of a test resource. Large fixtures may instead be stored in the binary format as `<file>.bin`.
+fn read_fixture<T: DeserializeOwned>(name: &str, file: &str) -> T {
+    #[cfg(feature = "binary")]
+    if let Ok(bytes) = std::fs::read(format!("{:}/{:}.bin", name, file)) {
+        return flanelly::binary::from_bytes(&bytes).unwrap();
+    }
+    serde_json::from_str(&read_to_string(format!("{:}/{:}.json", name, file)).unwrap()).unwrap()
+}
+
+/// Parse a single arithmetic expression
+fn parse_aexp(s: &str) -> AExp {
+    parser::parse_aexp(s).unwrap()
+}
+
+/// Parse a single boolean expression
+fn parse_bexp(s: &str) -> BExp {
+    parser::parse_bexp(s).unwrap()
+}
+
+#[test]
+fn test_push_negations() {
+    let x = || Box::new(AExp::Var(VarName::new("x")));
+    let y = || Box::new(AExp::Var(VarName::new("y")));
+    let five = || Box::new(AExp::Num(5));
+
+    // !(x <= 5)  ~>  5 < x
+    let b = BExp::Neg(Box::new(BExp::LessEq(x(), five())));
+    assert_eq!(b.push_negations(), BExp::Less(five(), x()));
+
+    // !(x <= 5 && y < x)  ~>  5 < x || x <= y
+    let b = BExp::Neg(Box::new(BExp::And(Box::new(BExp::LessEq(x(), five())), Box::new(BExp::Less(y(), x())))));
+    assert_eq!(b.push_negations(), BExp::Or(Box::new(BExp::Less(five(), x())), Box::new(BExp::LessEq(x(), y()))));
+
+    // !!(x <= 5)  ~>  x <= 5
+    let b = BExp::Neg(Box::new(BExp::Neg(Box::new(BExp::LessEq(x(), five())))));
+    assert_eq!(b.push_negations(), BExp::LessEq(x(), five()));
+}
+
+#[test]
+fn test_try_map() {
+    let p = parser::parse("y := 1; if y <= 2 then skip else z := 3 end").unwrap();
+    let cfg = cfg::ast_to_cfg(&p);
+    // Annotate every node by its index
+    let mut cfg_idx: Cfg<usize> = cfg.map(|_| 0);
+    cfg_idx.graph.node_indices().for_each(|n| { cfg_idx.graph[n].annot = n.index(); });
+
+    let ok: Result<Cfg<String>, String> = cfg_idx.try_map(|i| Ok(i.to_string()));
+    let ok = ok.unwrap();
+    assert_eq!(ok.init, cfg_idx.init);
+    assert_eq!(ok.graph.edge_count(), cfg_idx.graph.edge_count());
+    cfg_idx.graph.node_indices().for_each(|n| {
+        assert_eq!(ok.graph[n].node, cfg_idx.graph[n].node);
+        assert_eq!(ok.graph[n].annot, n.index().to_string());
+    });
+
+    let err: Result<Cfg<usize>, String> = cfg_idx.try_map(|i| if *i == 2 { Err(format!("node {} is malformed", i)) } else { Ok(*i) });
+    assert_eq!(err.unwrap_err(), "node 2 is malformed");
+}
+
+#[test]
+fn test_longest_path_cost() {
+    let weight = |n: &Node| match n { Node::Assign(_, _) => 3, _ => 1 };
+
+    // The `True` branch is more expensive: init + branch + 2 assignments
+    let p = parser::parse("if x <= 1 then y := 1; y := 2 else skip end").unwrap();
+    assert_eq!(cfg::ast_to_cfg(&p).longest_path_cost(weight), Some(8));
+
+    // Loops have no longest path
+    let p = parser::parse("while x <= 1 do x := x + 1 end").unwrap();
+    assert_eq!(cfg::ast_to_cfg(&p).longest_path_cost(weight), None);
+}
+
+#[test]
+fn test_absorb_policy() {
+    let mut mem = MultiConstLat::new(HashMap::new(), ConstLat::Const(0));
+    mem.insert(VarName::new("t"), ConstLat::Top);
+    mem.insert(VarName::new("b"), ConstLat::Bot);
+    let eval = |s: &str, policy| mem.eval_aexp_with(&parse_aexp(s), policy);
+
+    assert_eq!(eval("0 * t", AbsorbPolicy::Never), ConstLat::Top);
+    assert_eq!(eval("0 * b", AbsorbPolicy::Never), ConstLat::Bot);
+    assert_eq!(eval("t * 0", AbsorbPolicy::Never), ConstLat::Top);
+
+    assert_eq!(eval("0 * t", AbsorbPolicy::Zero), ConstLat::Const(0));
+    assert_eq!(eval("t * 0", AbsorbPolicy::Zero), ConstLat::Const(0));
+    // A `Bot` operand makes the result `Bot`, so that the operation is monotone (`b * 0 <= b * t`)
+    assert_eq!(eval("0 * b", AbsorbPolicy::Zero), ConstLat::Bot);
+    assert_eq!(eval("b * 0", AbsorbPolicy::Zero), ConstLat::Bot);
+    assert_eq!(eval("t * b", AbsorbPolicy::Zero), ConstLat::Bot);
+    // Addition has no absorbing element
+    assert_eq!(eval("t + 0", AbsorbPolicy::Zero), ConstLat::Top);
+}
+
+#[test]
+fn test_normalize_branches() {
+    let p = parser::parse("if !(x <= 1) then y := 1 else y := 2 end").unwrap();
+    let mut actual = cfg::ast_to_cfg(&p);
+    cfg::normalize_branches(&mut actual);
+    let p = parser::parse("if x <= 1 then y := 2 else y := 1 end").unwrap();
+    let expected = cfg::ast_to_cfg(&p);
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn test_parser_max_depth() {
+    let nested = |depth: usize| format!("z := {}1{} + 1", "(".repeat(depth), ")".repeat(depth));
+
+    // Just below the maximal depth
+    let p = parser::parse(&nested(parser::MAX_NESTING_DEPTH)).unwrap();
+    assert_eq!(eval(&p, 0), 2);
+
+    // Beyond the maximal depth
+    let err = parser::parse(&nested(parser::MAX_NESTING_DEPTH + 1)).unwrap_err();
+    assert!(err.contains("nesting depth"));
+
+    // Custom depth, also for boolean expressions and sub-programs
+    assert!(parser::parse_with_max_depth("z := ((1))", 2).is_ok());
+    assert!(parser::parse_with_max_depth("z := (((1)))", 2).is_err());
+    assert!(parser::parse_with_max_depth("if !!(1 <= 2) then skip else skip end", 3).is_ok());
+    assert!(parser::parse_with_max_depth("if !!(1 <= 2) then skip else skip end", 2).is_err());
+
+    // Every exponent is nested in the previous one
+    assert!(parser::parse_with_max_depth("z := 2^3^2", 2).is_ok());
+    assert!(parser::parse_with_max_depth("z := 2^3^2^1", 2).is_err());
+    let err = parser::parse(&format!("z := 1{}", "^1".repeat(10000))).unwrap_err();
+    assert!(err.contains("nesting depth"));
+}
+
+#[test]
+fn test_disjoint_union() {
+    let a = cfg::ast_to_cfg(&parser::parse("y := 1; if y <= 2 then skip else z := 3 end").unwrap());
+    let b = cfg::ast_to_cfg(&parser::parse("while x <= 1 do x := x + 1 end").unwrap());
+    let (count_a, count_b) = (a.graph.node_count(), b.graph.node_count());
+    let (edges_a, edges_b) = (a.graph.edge_count(), b.graph.edge_count());
+
+    let (union, init_b) = Cfg::disjoint_union(a, b);
+    assert_eq!(union.graph.node_count(), count_a + count_b);
+    assert_eq!(union.graph.edge_count(), edges_a + edges_b);
+    assert_eq!(union.graph[union.init].node, Node::Init);
+    assert_eq!(union.graph[init_b].node, Node::Init);
+
+    // Each init node reaches exactly the nodes of its own sub-CFG
+    let reachable = |init| {
+        let mut dfs = Dfs::new(&union.graph, init);
+        let mut count = 0;
+        while dfs.next(&union.graph).is_some() { count += 1; }
+        count
+    };
+    assert_eq!(reachable(union.init), count_a);
+    assert_eq!(reachable(init_b), count_b);
+}
+
+#[test]
+fn test_substitute() {
+    let (x, y) = (VarName::new("x"), VarName::new("y"));
+    let var = |v: &VarName| Box::new(AExp::Var(v.clone()));
+    // x <= x*2
+    let b = BExp::LessEq(var(&x), Box::new(AExp::Mul(var(&x), Box::new(AExp::Num(2)))));
+    assert!(b.contains_var(&x));
+    assert!(!b.contains_var(&y));
+
+    // [x := y + 1]  ~>  y + 1 <= (y + 1)*2
+    let y_plus_1 = AExp::Add(var(&y), Box::new(AExp::Num(1)));
+    let actual = b.substitute(&x, &y_plus_1);
+    let expected = BExp::LessEq(Box::new(y_plus_1.clone()), Box::new(AExp::Mul(Box::new(y_plus_1.clone()), Box::new(AExp::Num(2)))));
+    assert_eq!(actual, expected);
+    assert_eq!(actual.to_string(), "y + 1 <= (y + 1)*2");
+    assert!(!actual.contains_var(&x));
+    assert!(actual.contains_var(&y));
+}
+
+#[test]
+fn test_wp() {
+    let post = BExp::LessEq(Box::new(AExp::Var(VarName::new("x"))), Box::new(AExp::Num(10)));
+
+    let p = parser::parse("x := x + 1").unwrap();
+    assert_eq!(wp(&p, &post).unwrap().to_string(), "x + 1 <= 10");
+
+    let p = parser::parse("x := x + 1; x := 2*x").unwrap();
+    assert_eq!(wp(&p, &post).unwrap().to_string(), "2*(x + 1) <= 10");
+
+    // The precondition holds exactly for those inputs for which the postcondition holds afterwards
+    let p = parser::parse("if x <= 0 then x := 0 else x := x + (-5) end").unwrap();
+    let pre = wp(&p, &post).unwrap();
+    (-20..20).for_each(|x| {
+        let mut mem = MemConfig::new();
+        mem.assign(&VarName::new("x"), x);
+        let holds_pre = eval_bexp(&pre, &mem);
+        assert_eq!(holds_pre, eval_bexp(&post, &eval_prog(&p, mem)));
+        assert_eq!(holds_pre, x <= 15);
+    });
+
+    let p = parser::parse("while x <= 0 do x := x + 1 end").unwrap();
+    assert_eq!(wp(&p, &post), None);
+}
+
+#[test]
+fn test_to_dot_edge_styles() {
+    let p = parser::parse("while x <= 1 do x := x + 1 end; if x <= 2 then skip else skip end").unwrap();
+    let cfg = cfg::ast_to_cfg(&p);
+
+    let dot = to_dot(&cfg, &DotOptions { edge_style: EdgeStyle::Colored, ..DotOptions::default() });
+    assert!(dot.starts_with("digraph {\n"));
+    assert!(dot.contains("label = \"\" color = \"black\" style = \"solid\""));
+    assert!(dot.contains("label = \"tt\" color = \"green\" style = \"solid\""));
+    assert!(dot.contains("label = \"ff\" color = \"red\" style = \"solid\""));
+
+    // The init edge comes first
+    let dot = to_dot(&cfg, &DotOptions { edge_style: EdgeStyle::Numbered, ..DotOptions::default() });
+    assert!(dot.contains(&format!("{} -> 1 [ label = \"1\" ]", cfg.init.index())));
+    assert!(dot.contains("label = \"2: tt\""));
+
+    // The default style coincides with petgraph's DOT output
+    let dot = to_dot(&cfg, &DotOptions::default());
+    assert_eq!(dot, format!("{}", petgraph::dot::Dot::new(&cfg.graph)));
+}
+
+#[test]
+fn test_node_labels() {
+    let p = parser::parse("y := 1; while y <= x do y := y * 2 end").unwrap();
+    let cfg_mfp: Cfg<MfpAnnot<MultiConstLat>> = mfp(&cfg::ast_to_cfg(&p));
+    cfg_mfp.graph.node_indices().for_each(|n| {
+        let label = cfg_mfp.node_label(n);
+        assert!(label.starts_with(&format!("{}: {}", n.index(), cfg_mfp.graph[n].node)));
+        // The annotation is kept
+        assert!(label.ends_with(&cfg_mfp.graph[n].annot.to_string()));
+    });
+    assert_eq!(cfg::ast_to_cfg(&p).node_label(cfg_mfp.init), format!("{}: init", cfg_mfp.init.index()));
+
+    let dot = to_dot(&cfg_mfp, &DotOptions { node_indices: true, ..DotOptions::default() });
+    assert!(dot.contains("1 [ label = \"1: y := 1\\l"));
+    // Without the option, the labels stay unchanged
+    assert!(to_dot(&cfg_mfp, &DotOptions::default()).contains("1 [ label = \"y := 1\\l"));
+    assert!(run_cli(&["--node-indices"], "y := 1").contains("[ label = \"1: y := 1"));
+}
+
+#[test]
+fn test_undefined_reads() {
+    let input = read_to_string("tests-res/undefined_read/prog.while").unwrap();
+    let p = parser::parse(&input).unwrap();
+    let reads = undefined_reads(&p);
+    assert_eq!(reads.len(), 1);
+    let (var, node) = &reads[0];
+    assert_eq!(*var, VarName::new("y"));
+    assert_eq!(cfg::ast_to_cfg(&p).graph[*node].node.to_string(), "z := y + x");
+
+    // Reading `x` is fine, as it holds the input
+    let p = parser::parse("y := x; if y <= 0 then z := y else z := x end").unwrap();
+    assert_eq!(undefined_reads(&p), vec![]);
+}
+
+#[test]
+fn test_exp_set_lat() {
+    let a = |s: &str| parse_aexp(s);
+    let s1 = ExpSetLat::new(vec![a("y + 1"), a("x*2"), a("3")].into_iter().collect());
+    let s2 = ExpSetLat::new(vec![a("x*2"), a("y + 1"), a("z")].into_iter().collect());
+
+    // Pretty-printing does not depend on the insertion order
+    let s1_reordered = ExpSetLat::new(vec![a("3"), a("x*2"), a("y + 1")].into_iter().collect());
+    assert_eq!(s1.to_string(), s1_reordered.to_string());
+    assert_eq!(s1.to_string(), "{3, y + 1, x*2}");
+
+    // Join is the intersection
+    let joined = s1.join_bin(&s2);
+    assert_eq!(joined.to_string(), "{y + 1, x*2}");
+
+    // Clearing a variable removes the expressions containing it
+    let mut cleared = joined.clone();
+    cleared.clear_var(&VarName::new("x"));
+    assert!(cleared.contains(&a("y + 1")));
+    assert!(!cleared.contains(&a("x*2")));
+}
+
+#[test]
+fn test_interned_aexp() {
+    let exps: Vec<AExp> = vec!["x + y", "y + x", "(x + y)*(x + y)", "x + y*1", "x + (y*1)", "2^x^y", "(2^x)^y", "3", "x", "x % 2"]
+        .into_iter().map(parse_aexp).collect();
+    // Hash-consed equality matches structural equality, and equal expressions have equal hashes
+    let hash = |a: &InternedAExp| {
+        let mut h = std::collections::hash_map::DefaultHasher::new();
+        std::hash::Hash::hash(a, &mut h);
+        std::hash::Hasher::finish(&h)
+    };
+    exps.iter().for_each(|a| exps.iter().for_each(|b| {
+        assert_eq!(a.interned() == b.interned(), a == b, "{} vs {}", a, b);
+        if a == b { assert_eq!(hash(&a.interned()), hash(&b.interned())) }
+    }));
+    exps.iter().for_each(|a| assert_eq!(a.interned().to_aexp(), *a));
+    // Identical subexpressions are shared, so they are listed once
+    let a = parse_aexp("((x + y)*(x + y))*((x + y)*(x + y))");
+    let subs: std::collections::HashSet<AExp> = a.interned().sub_aexps().iter().map(InternedAExp::to_aexp).collect();
+    assert_eq!(subs, a.sub_aexps());
+    assert_eq!(a.interned().sub_aexps().len(), 5);
+    assert!(a.interned().contains_var(&VarName::new("y")));
+    assert!(!a.interned().contains_var(&VarName::new("z")));
+    assert_eq!(parse_aexp("x*neverinterned").find_interned(), None);
+    // Expressions are freed once they are not used anymore
+    let unused = parse_aexp("neverused * 2");
+    let interned = unused.interned();
+    assert_eq!(unused.find_interned(), Some(interned.clone()));
+    drop(interned);
+    assert_eq!(unused.find_interned(), None);
+}
+
+#[test]
+fn test_interned_bexp() {
+    let exps: Vec<BExp> = vec!["x + y <= 3 && y * 2 < x", "y * 2 < x && x + y <= 3", "x + y <= 3 || y * 2 < x", "!(x < 3)", "x < 3", "x <= 3", "x < 3 && (x < 3 || 2 <= y)"]
+        .into_iter().map(parse_bexp).collect();
+    exps.iter().for_each(|a| exps.iter().for_each(|b| {
+        assert_eq!(a.interned() == b.interned(), a == b, "{} vs {}", a, b);
+        if a == b { assert_eq!(a.interned().hash_value(), b.interned().hash_value()) }
+    }));
+    exps.iter().for_each(|b| assert_eq!(b.interned().to_bexp(), *b));
+    // The arithmetic subexpressions of all comparisons, each listed once
+    let b = parse_bexp("x + y <= 3 && (y * 2 < x || x + y <= y * 2)");
+    let subs: Vec<AExp> = b.interned().sub_aexps().iter().map(InternedAExp::to_aexp).collect();
+    assert_eq!(subs.len(), 6);
+    assert_eq!(subs.into_iter().collect::<std::collections::HashSet<_>>(), b.sub_aexps());
+    assert_eq!(parse_bexp("neverinterned < 1").find_interned(), None);
+}
+
+#[test]
+fn test_exp_set_lat_display_commuted() {
+    let a = |s: &str| parse_aexp(s);
+    assert_eq!(a("(y + x)*(2*z)").normalized(), a("(x + y)*(2*z)"));
+    assert_eq!(a("y % (x + 1)").normalized(), a("y % (1 + x)"));
+
+    // After the second assignment, both `x + y` and `y + x` are available, but only the smaller one is listed
+    let p = parser::parse("a := x + y; b := (y + x)*2").unwrap();
+    let cfg_mfp: Cfg<MfpAnnot<ExpSetLat>> = mfp(&cfg::ast_to_cfg(&p));
+    let assign_b = cfg_mfp.graph.node_indices().find(|n| matches!(&cfg_mfp.graph[*n].node, Node::Assign(v, _) if *v == VarName::new("b"))).unwrap();
+    let post = cfg_mfp.graph[assign_b].annot.post();
+    assert!(post.contains(&a("x + y")) && post.contains(&a("y + x")));
+    // Every other expression is listed as it occurs in the program
+    assert_eq!(post.to_string(), "{2, x, y, x + y, (y + x)*2}");
+    let twice: Vec<String> = (0..2).map(|_| mfp::<ExpSetLat>(&cfg::ast_to_cfg(&p)).graph[assign_b].annot.post().to_string()).collect();
+    assert_eq!(twice, vec![post.to_string(), post.to_string()]);
+}
+
+#[test]
+fn test_exp_order() {
+    let mut aexps: Vec<AExp> = vec!["x*2", "y", "x + 1", "10", "x", "x*1", "2", "x + y"].into_iter().map(parse_aexp).collect();
+    aexps.sort();
+    let sorted: Vec<String> = aexps.iter().map(|a| a.to_string()).collect();
+    assert_eq!(sorted, vec!["2", "10", "x", "y", "x + 1", "x + y", "x*1", "x*2"]);
+
+    let x_le_1 = || Box::new(BExp::LessEq(Box::new(parse_aexp("x")), Box::new(parse_aexp("1"))));
+    let mut bexps = vec![BExp::Neg(x_le_1()), BExp::And(x_le_1(), x_le_1()), *x_le_1()];
+    bexps.sort();
+    assert_eq!(bexps, vec![*x_le_1(), BExp::Neg(x_le_1()), BExp::And(x_le_1(), x_le_1())]);
+}
+
+/// Run the `flanelly` binary with the given arguments and the given program on StdIn, returning its StdOut
+fn run_cli(args: &[&str], program: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_flanelly"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(program.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_cli_sweep() {
+    // Diverges for negative inputs
+    let program = "while x <= -1 do skip end; z := x + x";
+    let expected = "-2 -> diverges\n-1 -> diverges\n0 -> 0\n1 -> 2\n2 -> 4\n";
+    assert_eq!(run_cli(&["--sweep", "-2,2", "--max-steps", "100"], program), expected);
+}
+
+#[test]
+fn test_cli_input_distribution() {
+    // Diverges for negative inputs
+    let program = "while x <= -1 do skip end; z := x * x";
+    let args = ["--input-distribution", "-5,10", "--samples", "50", "--rng-seed", "42", "--max-steps", "100"];
+    let expected = "samples: 50 (diverging: 19)\nmin: 0\nmax: 100\nmean: 35.61\n";
+    assert_eq!(run_cli(&args, program), expected);
+    // The same seed yields the same inputs
+    assert_eq!(run_cli(&args, program), expected);
+    assert_ne!(run_cli(&["--input-distribution", "-5,10", "--samples", "50", "--rng-seed", "43", "--max-steps", "100"], program), expected);
+}
+
+#[test]
+fn test_eval_random() {
+    let prog = parser::parse("while x <= -1 do skip end; z := x * x").unwrap();
+    let results = eval_random(&prog, -5..=10, 200, 7, 100);
+    assert_eq!(results.len(), 200);
+    assert_eq!(results, eval_random(&prog, -5..=10, 200, 7, 100));
+    assert_ne!(results, eval_random(&prog, -5..=10, 200, 8, 100));
+    results.iter().for_each(|(x, z)| {
+        assert!((-5..=10).contains(x));
+        assert_eq!(*z, if *x < 0 { None } else { Some(x * x) });
+    });
+    // Every input of a small range is drawn eventually
+    assert_eq!(results.iter().map(|(x, _)| *x).collect::<std::collections::BTreeSet<_>>().len(), 16);
+    assert!(eval_random(&prog, Int::MIN..=Int::MAX, 20, 0, 10).iter().any(|(x, _)| x.abs() > 1000));
+    assert_eq!(eval_random(&prog, 3..=3, 2, 0, 100), vec![(3, Some(9)), (3, Some(9))]);
+
+    let stats = output_stats(&[(0, Some(4)), (1, None), (2, Some(-3)), (3, Some(2))]);
+    assert_eq!(stats, OutputStats { samples: 4, diverging: 1, min: Some(-3), max: Some(4), mean: Some(1.0) });
+    assert_eq!(stats.to_string(), "samples: 4 (diverging: 1)\nmin: -3\nmax: 4\nmean: 1.00");
+    // The sum of the outputs does not overflow
+    assert_eq!(output_stats(&[(0, Some(Int::MAX)), (1, Some(Int::MAX))]).max, Some(Int::MAX));
+    assert_eq!(output_stats(&[(0, Some(Int::MAX)), (1, Some(Int::MAX))]).mean, Some(Int::MAX as f64));
+    assert_eq!(output_stats(&[(0, None)]).to_string(), "samples: 1 (diverging: 1)");
+}
+
+#[test]
+fn test_cli_seed() {
+    let program = "y := x + 1; z := y * n";
+    let unseeded = run_cli(&["-c"], program);
+    assert!(!unseeded.contains("z = 12"));
+    let seeded = run_cli(&["-c", "--seed", "x=5,n=2"], program);
+    assert!(seeded.contains("y = 6"));
+    assert!(seeded.contains("z = 12"));
+    // An invalid seed is rejected
+    assert_eq!(run_cli(&["-c", "--seed", "x=five"], program), "");
+    // The seed also applies to the constant propagation with sets of values, but is rejected where no constant propagation is run
+    assert!(run_cli(&["-f", "--seed", "x=5,n=2"], program).contains("z = {12}"));
+    let succeeds = |args: &[&str]| {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_flanelly")).args(args).stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::null()).spawn().unwrap();
+        // Rust Expl.: Writing fails if the process has already exited because of the invalid arguments, which is fine here
+        let _ = child.stdin.take().unwrap().write_all(program.as_bytes());
+        child.wait().unwrap().success()
+    };
+    assert!(succeeds(&["-f", "--seed", "x=5"]));
+    assert!(!succeeds(&["-a", "--seed", "x=5"]));
+    assert!(!succeeds(&["--sweep", "0,1", "--seed", "x=5"]));
+}
+
+#[test]
+fn test_const_prop_join_one_sided() {
+    // `y` is assigned in one branch only, so it is `1` or `0` at the end. Joining must not drop the implicit `0` of the other branch.
+    let prog = parser::parse("if x <= 0 then y := 1 else skip end; z := y").unwrap();
+    assert_eq!(soundness_counterexamples(&prog, -2..=2, 100), vec![]);
+    let cfg_mfp: Cfg<MfpAnnot<MultiConstLat>> = mfp(&cfg::ast_to_cfg(&prog));
+    let assign_z = cfg_mfp.graph.node_indices().find(|n| cfg_mfp.graph[*n].node == Node::Assign(VarName::new("z"), Box::new(AExp::Var(VarName::new("y"))))).unwrap();
+    assert_eq!(*cfg_mfp.graph[assign_z].annot.post().lookup(&VarName::new("z")), ConstLat::Top);
+}
+
+#[test]
+fn test_to_source() {
+    let source = |s: &str| parser::parse(s).unwrap().to_source();
+    // Parentheses that `Display` omits are kept
+    assert_eq!(source("z := x + (y + z)"), "z := x + (y + z)");
+    assert_eq!(source("z := x*(y*z) + (x % y)%z + x%(y%z)"), "z := x*(y*z) + x%y%z + x%(y%z)");
+    assert_eq!(source("z := (2^3)^x + 2^3^x + (x + 1)*-2"), "z := (2^3)^x + 2^3^x + (x + 1)*-2");
+    assert_eq!(source("if !x <= 1 && y < 2 then skip else z := 1 end"), "if !x <= 1 && y < 2 then skip else z := 1 end");
+    assert_eq!(source("if ((!(x <= 1)) && y <= 2) || y <= 3 then skip end"), "if ((!x <= 1) && y <= 2) || y <= 3 then skip end");
+    assert_eq!(source("while x <= 1 && (y <= 2 && (z <= 3 || x < y)) do x := x + 1 end"), "while x <= 1 && (y <= 2 && (z <= 3 || x < y)) do x := x + 1 end");
+    // The result parses to the same program and without diagnostics
+    [
+        "z := x + (y + z)",
+        "if x <= 1 || y <= 2 && y <= 3 then skip end",
+        "proc p do let y := (x + 1)^2 in repeat y := y%(3 + x) until (!(x < y)) && x <= y end end end; call p; z := y",
+    ].iter().for_each(|s| {
+        let prog = parser::parse(s).unwrap();
+        let (reparsed, diagnostics) = parser::parse_with_diagnostics(&prog.to_source());
+        assert_eq!(reparsed.unwrap(), prog);
+        assert!(diagnostics.is_empty(), "{}", s);
+    });
+}
+
+#[test]
+fn test_write_only_vars() {
+    let write_only = |s: &str| {
+        let mut vars: Vec<String> = parser::parse(s).unwrap().write_only_vars().iter().map(|x| x.to_string()).collect();
+        vars.sort();
+        vars
+    };
+    // The bookkeeping variable `old` is never read. `steps` counts the iterations and thus reads itself, so it is not reported.
+    assert_eq!(write_only("y := 1; steps := 0; while 0 < x do y := y * 2; x := x + -1; steps := steps + 1; old := x end; z := y"), vec!["old"]);
+    assert_eq!(write_only("y := 1; steps := 0; while 0 < x do y := y * 2; x := x + -1; steps := 1 end; z := y"), vec!["steps"]);
+    // The output variable `z` is never reported, and a read in a guard or a procedure counts
+    assert_eq!(write_only("z := 1; y := 2; if y <= 2 then skip end"), Vec::<String>::new());
+    assert_eq!(write_only("proc p do z := y end; y := 2; let t := 1 in x := 3 end"), vec!["t", "x"]);
+    assert_eq!(write_only("proc p do z := y end; y := 2; call p"), Vec::<String>::new());
+}
+
+#[test]
+fn test_structurally_eq_differences() {
+    let cfg = |s: &str| cfg::ast_to_cfg(&parser::parse(s).unwrap());
+    let analyzed: Cfg<MfpAnnot<MultiConstLat>> = mfp(&cfg("y := 1; z := y"));
+    assert!(cfg("y := 1; z := y").structurally_eq(&analyzed));
+    // Different annotations only differ in the sense of `PartialEq`
+    let other_annots = analyzed.map(|_| MfpAnnot::new(MultiConstLat::init(), MultiConstLat::init()));
+    assert_ne!(other_annots, analyzed);
+    assert!(other_annots.structurally_eq(&analyzed));
+    // Different nodes or edges are detected
+    assert!(!cfg("y := 2; z := y").structurally_eq(&analyzed));
+    assert!(!cfg("if x <= 0 then y := 1 else z := y end").structurally_eq(&cfg("if x <= 0 then z := y else y := 1 end")));
+    // Nodes and edges are counted: Duplicating a node together with its edges leaves the sets of nodes and edges unchanged
+    let mut duplicated = cfg("y := 1; z := y");
+    let assign_y = duplicated.graph.node_indices().find(|n| duplicated.graph[*n].node == Node::Assign(VarName::new("y"), Box::new(AExp::Num(1)))).unwrap();
+    let copy = duplicated.graph.add_node(duplicated.graph[assign_y].clone());
+    let edges: Vec<_> = duplicated.graph.edges_directed(assign_y, petgraph::Incoming).map(|e| (e.source(), copy, e.weight().clone()))
+        .chain(duplicated.graph.edges_directed(assign_y, petgraph::Outgoing).map(|e| (copy, e.target(), e.weight().clone()))).collect();
+    edges.into_iter().for_each(|(from, to, edge)| { duplicated.graph.add_edge(from, to, edge); });
+    assert!(!duplicated.structurally_eq(&analyzed));
+    assert!(!analyzed.structurally_eq(&duplicated));
+}
+
+#[test]
+fn test_compact_annots() {
+    let cfg = cfg::ast_to_cfg(&parser::parse("y := 1; if x <= 0 then y := 2 else skip end; z := y").unwrap());
+    let cfg_mfp: Cfg<MfpAnnot<MultiConstLat>> = mfp(&cfg);
+    let compact = compact_annots(&cfg_mfp);
+    assert!(compact.structurally_eq(&cfg_mfp));
+    let find = |node: &Node| cfg_mfp.graph.node_indices().find(|n| cfg_mfp.graph[*n].node == *node).unwrap();
+    // The `pre` value of `y := 1` is the `post` value of the init node, which is its only predecessor
+    let assign_y = find(&Node::Assign(VarName::new("y"), Box::new(AExp::Num(1))));
+    assert_eq!(compact.graph[assign_y].annot.pre(), None);
+    assert_eq!(compact.graph[assign_y].annot.post(), cfg_mfp.graph[assign_y].annot.post());
+    assert_eq!(compact.graph[compact.init].annot.pre(), None);
+    // The `pre` value of `z := y` joins `y = 1` and `y = 2`, so it is kept
+    let assign_z = find(&Node::Assign(VarName::new("z"), Box::new(AExp::Var(VarName::new("y")))));
+    assert_eq!(compact.graph[assign_z].annot.pre(), Some(cfg_mfp.graph[assign_z].annot.pre()));
+    assert_eq!(compact.graph[assign_y].annot.to_string(), format!("post: {}", cfg_mfp.graph[assign_y].annot.post()));
+    assert_eq!(compact.graph[assign_z].annot.to_string(), cfg_mfp.graph[assign_z].annot.to_string());
+
+    let program = "y := 1; if x <= 0 then y := 2 end; z := y";
+    let pres = |args: &[&str]| run_cli(args, program).matches("pre: ").count();
+    assert!(pres(&["--compact-annots"]) < pres(&[]));
+}
+
+#[test]
+fn test_var_name_interning() {
+    let (x1, x2, y) = (VarName::new("xs"), VarName::new(&format!("x{}", "s")), VarName::new("ys"));
+    assert_eq!(x1, x2);
+    assert_ne!(x1, y);
+    assert_eq!(x1.to_string(), "xs");
+    assert_eq!(x2.as_str(), "xs");
+    assert_eq!(format!("{:?}", y), "VarName(\"ys\")");
+    // Names are ordered alphabetically, regardless of the order in which they were interned
+    let (b, a) = (VarName::new("bthisnameisnew"), VarName::new("athisnameisnew"));
+    assert!(a < b);
+    assert!(a < x1 && x1 < y);
+    // Names interned in another thread are the same
+    let from_thread = std::thread::spawn(|| (VarName::new("xs"), VarName::new("zthisnameisnew"))).join().unwrap();
+    assert_eq!(from_thread.0, x1);
+    assert_eq!(from_thread.1.to_string(), "zthisnameisnew");
+    assert_eq!(serde_json::to_string(&vec![x1.clone(), y.clone()]).unwrap(), "[\"xs\",\"ys\"]");
+    assert_eq!(serde_json::from_str::<VarName>("\"ys\"").unwrap(), y);
+}
+
+#[test]
+fn test_validate_var_kinds() {
+    [
+        "a := 1; b := a + 1",
+        "proc p do z := a end; let a := 2 in call p end",
+        "while x <= a do a := a * 2 end; z := a",
+    ].iter().for_each(|s| assert_eq!(parser::parse(s).unwrap().validate_var_kinds(), Ok(()), "{}", s));
+    // A name that is used both as a procedure and as a scalar is rejected, no matter whether it is assigned, read or only called
+    assert_eq!(parser::parse("proc a do z := 1 end; a := 1").unwrap().validate_var_kinds(), Err("The variable a is used both as a procedure and as a scalar.".to_string()));
+    assert_eq!(parser::parse("proc p do z := 1 end; call p; z := p + b").unwrap().validate_var_kinds(), Err("The variable p is used both as a procedure and as a scalar.".to_string()));
+    assert!(parser::parse("proc p do p := 1 end; call p").unwrap().validate_var_kinds().is_err());
+    // There are no arrays yet, so a program mixing scalars and arrays cannot even be parsed
+    assert!(parser::parse("a := 1; a[0] := 2").is_err());
+}
+
+#[test]
+fn test_eval_with_callback() {
+    // Abort as soon as a loop iteration starts with the same memory configuration as the previous one, which means that the loop diverges
+    let unchanged = |s: &str, x: Int| {
+        let mut last: Option<BTreeMap<VarName, Int>> = None;
+        eval_with_callback(&parser::parse(s).unwrap(), x, |mem| {
+            let current: BTreeMap<VarName, Int> = mem.entries().map(|(x, n)| (x.clone(), n)).collect();
+            let repeated = last.as_ref() == Some(&current);
+            last = Some(current);
+            !repeated
+        })
+    };
+    let prog = "y := 0; while y < x do if 3 <= y then skip else y := y + 1 end end; z := y";
+    assert_eq!(unchanged(prog, 2), Ok(2));
+    assert_eq!(unchanged(prog, 5), Err(Aborted));
+    assert_eq!(unchanged("while 0 <= x do skip end", 1), Err(Aborted));
+    // The callback is invoked once per loop iteration
+    let mut steps = 0;
+    assert_eq!(eval_with_callback(&parser::parse("y := 0; while y < x do y := y + 1 end; z := y").unwrap(), 4, |_| { steps += 1; true }), Ok(4));
+    assert_eq!(steps, 4);
+}
+
+#[test]
+fn test_mem_config_eq_hash() {
+    let hash = |m: &MemConfig| {
+        let mut h = std::collections::hash_map::DefaultHasher::new();
+        std::hash::Hash::hash(m, &mut h);
+        std::hash::Hasher::finish(&h)
+    };
+    let mem = |entries: &[(&str, Int)]| {
+        let mut m = MemConfig::new();
+        entries.iter().for_each(|(x, n)| m.assign(&VarName::new(x), *n));
+        m
+    };
+    // Unassigned variables are `0`, so an explicit `0` makes no difference
+    assert_eq!(mem(&[("x", 0)]), mem(&[]));
+    assert_eq!(hash(&mem(&[("x", 0)])), hash(&mem(&[])));
+    assert_eq!(mem(&[("x", 0), ("y", 2)]), mem(&[("y", 2), ("z", 0)]));
+    assert_eq!(hash(&mem(&[("x", 0), ("y", 2)])), hash(&mem(&[("y", 2), ("z", 0)])));
+    assert_ne!(mem(&[("x", 1)]), mem(&[]));
+    assert_ne!(mem(&[]), mem(&[("x", -1)]));
+    assert_ne!(mem(&[("x", 1)]), mem(&[("y", 1)]));
+    // Overwriting with `0` is the same as never assigning
+    let mut m = mem(&[("x", 5)]);
+    m.assign(&VarName::new("x"), 0);
+    assert_eq!(m, MemConfig::new());
+    let set: std::collections::HashSet<MemConfig> = vec![m, mem(&[]), mem(&[("y", 0)]), mem(&[("y", 3)])].into_iter().collect();
+    assert_eq!(set.len(), 2);
+
+    // A clone is equal, but independent
+    let original = mem(&[("x", 1), ("y", 2)]);
+    let mut clone = original.clone();
+    assert_eq!(clone, original);
+    clone.assign(&VarName::new("y"), 3);
+    assert_ne!(clone, original);
+    assert_eq!(original.lookup(&VarName::new("y")), 2);
+}
+
+#[test]
+fn test_eval_detect_loops() {
+    let detect = |s: &str, x: Int| eval_detect_loops(&parser::parse(s).unwrap(), x);
+    assert_eq!(detect("while 0 <= x do skip end", 3), Err(NonTerminating));
+    assert_eq!(detect("while 0 <= x do skip end; z := 1", -3), Ok(1));
+    assert_eq!(detect("y := 0; while y < x do y := y + 1 end; z := y", 5), Ok(5));
+    assert_eq!(detect("repeat x := (x + 1)%4 until x < 0 end", 0), Err(NonTerminating));
+    // The same memory configuration at different loops, or at the same loop but within different calls, is no repetition
+    assert_eq!(detect("y := 0; while y <= 0 do y := y + 1 end; y := 0; while y <= 0 do y := y + 1 end; z := 2", 0), Ok(2));
+    assert_eq!(detect("proc p do y := 0; while y <= 0 do y := y + 1 end end; call p; call p; z := y", 0), Ok(1));
+    // Nested loops, where the inner loop is entered with the same memory configuration again and again
+    assert_eq!(detect("while x <= 3 do y := 0; while y <= 1 do y := y + 1 end end", 0), Err(NonTerminating));
+    assert_eq!(detect("while x <= 3 do y := 0; while y <= 1 do y := y + 1 end; x := x + 1 end; z := x", 0), Ok(4));
+    // The inner loop is entered with the same memory configuration again and again, but the `let` block restores different outer values of `x`
+    let scoped = "while x <= 2 do let x := 7 in y := 0; while y <= 0 do y := y + 1 end end; x := x + 1 end; z := x";
+    assert_eq!(detect(scoped, 0), Ok(eval(&parser::parse(scoped).unwrap(), 0)));
+    assert_eq!(detect(scoped, 0), Ok(3));
+}
+
+#[test]
+fn test_unroll() {
+    let unrolled = |s: &str, k| unroll(&parser::parse(s).unwrap(), k).to_string();
+    assert_eq!(unrolled("while x <= 3 do x := x + 1 end", 0), "while x <= 3 do x := x + 1 end");
+    assert_eq!(unrolled("while x <= 3 do x := x + 1 end", 2),
+               "if x <= 3 then x := x + 1; if x <= 3 then x := x + 1; while x <= 3 do x := x + 1 end end end");
+    let nested = "while x <= 3 do y := 0; while y <= x do y := y + 1 end; x := x + y + 1 end; z := x";
+    (0..4).for_each(|k| (-3..=5).for_each(|x| {
+        assert_eq!(eval(&unroll(&parser::parse(nested).unwrap(), k), x), eval(&parser::parse(nested).unwrap(), x));
+    }));
+
+    // The loop runs exactly twice, so after unrolling it twice the residual loop is unreachable and `z` is known to be constant
+    let prog = parser::parse("y := 0; while y <= 1 do y := y + 1 end; z := y").unwrap();
+    let post_z = |p: &Prog| {
+        let cfg_mfp: Cfg<MfpAnnot<MultiConstLat>> = mfp(&cfg::ast_to_cfg(p));
+        cfg_mfp.graph[cfg_mfp.exits()[0]].annot.post().lookup(&VarName::new("z")).clone()
+    };
+    assert_eq!(post_z(&prog), ConstLat::Top);
+    assert_eq!(post_z(&unroll(&prog, 1)), ConstLat::Top);
+    assert_eq!(post_z(&unroll(&prog, 2)), ConstLat::Const(2));
+    let loop_prog = "while x <= 3 do x := x + 1 end";
+    assert_eq!(run_cli(&["--max-loop-unroll", "0"], loop_prog), run_cli(&[], loop_prog));
+    assert_ne!(run_cli(&["--max-loop-unroll", "1"], loop_prog), run_cli(&[], loop_prog));
+    // The number of unrollings is bounded
+    assert_ne!(run_cli(&["--max-loop-unroll", &MAX_UNROLL.to_string()], loop_prog), "");
+    assert_eq!(run_cli(&["--max-loop-unroll", &(MAX_UNROLL + 1).to_string()], loop_prog), "");
+    assert_eq!(run_cli(&["--max-loop-unroll", "100000"], loop_prog), "");
+}
+
+#[test]
+fn test_cli_compare() {
+    assert_eq!(run_cli(&["--compare", "-3,3"], "y := 2; z := x * y").trim(), "No counterexamples for the inputs -3 to 3.");
+    // Diverging inputs are skipped
+    assert!(run_cli(&["--compare", "-3,3", "--max-steps", "100"], "while 0 <= x do skip end; z := 1").starts_with("No counterexamples"));
+    let c = Counterexample { input: 4, var: VarName::new("z"), claimed: 1, actual: 0 };
+    assert_eq!(c.to_string(), "x = 4: z is claimed to be 1, but is 0");
+}
+
+#[test]
+fn test_cli_diff_analyses() {
+    // The guard `y <= 0` never holds, which only the refined analysis knows
+    let program = "y := 1; if y <= 0 then z := 5 end";
+    assert_eq!(run_cli(&["--diff-analyses", "const,const"], program).trim(), "The analyses const and const agree on every node.");
+    let expected = "3: z := 5\n- pre: <_ = bb>\n+ pre: <x = tt, y = 1, _ = 0>\n- post: <_ = bb>\n+ post: <x = tt, y = 1, z = 5, _ = 0>\n";
+    assert_eq!(run_cli(&["--diff-analyses", "const,const-unrefined"], program), expected);
+    // The annotations of different property spaces are compared via their pretty-printed form
+    let cfg = cfg::ast_to_cfg(&parser::parse(program).unwrap());
+    let cfg_const_prop: Cfg<MfpAnnot<MultiConstLat>> = mfp(&cfg);
+    let cfg_unrefined: Cfg<MfpAnnot<Unrefined<MultiConstLat>>> = mfp(&cfg);
+    let diffs = diff_annots(&cfg_const_prop, &cfg_unrefined);
+    assert_eq!(diffs.iter().map(|d| d.node).collect::<Vec<_>>(), vec![NodeIdx::new(3)]);
+    assert_eq!(diffs[0].snd, cfg_unrefined.graph[NodeIdx::new(3)].annot.to_strings());
+}
+
+#[test]
+fn test_cli_output_reproducible() {
+    // Every run is a separate process, so a randomly seeded hasher would yield a different order of the variables
+    let program = "a := x + 1; b := a*2; c := 3; d := b + c; if d <= 5 then e := a + b else f := c*3 end; z := a + b";
+    ["-a", "-c"].iter().for_each(|analysis| {
+        let first = run_cli(&[analysis], program);
+        assert!(!first.is_empty());
+        (0..4).for_each(|_| assert_eq!(run_cli(&[analysis], program), first));
+    });
+}
+
+#[test]
+fn test_parser_if_without_else() {
+    let short = parser::parse("if x <= 1 then y := 1 end; z := y").unwrap();
+    let full = parser::parse("if x <= 1 then y := 1 else skip end; z := y").unwrap();
+    assert_eq!(short, full);
+    assert_eq!(short.to_string(), "if x <= 1 then y := 1 end; z := y");
+    assert_eq!(parser::parse(&short.to_string()).unwrap(), short);
+
+    // The full form is kept if the else-branch is not a single `skip`
+    let full = parser::parse("if x <= 1 then y := 1 else skip; skip end").unwrap();
+    assert_eq!(full.to_string(), "if x <= 1 then y := 1 else skip; skip end");
+    assert!(parser::parse("if x <= 1 then y := 1 else end").is_err());
+}
+
+#[test]
+fn test_cfg_diff_remove_skips() {
+    let prog = parser::parse("x := 1; skip; if x <= 1 then skip else y := 2 end; z := x").unwrap();
+    let before = cfg::ast_to_cfg(&prog);
+    let mut after = cfg::ast_to_cfg(&prog);
+    cfg::remove_skips(&mut after);
+    assert!(cfg::diff(&before, &before).is_empty());
+
+    let d = cfg::diff(&before, &after);
+    let skip = AnnotNode::new(Node::Skip, RawAnnot {});
+    assert_eq!(d.removed_nodes, vec![skip.clone(), skip.clone()]);
+    assert!(d.added_nodes.is_empty());
+    assert!(d.removed_edges.iter().all(|(src, tgt, _)| *src == skip || *tgt == skip));
+    assert_eq!(d.removed_edges.len(), 4);
+    // The label of an edge pointing to a removed `Skip` node is kept
+    let branch = after.graph.raw_nodes().iter().find(|n| matches!(n.weight.node, Node::Branch(_))).unwrap().weight.clone();
+    let assign_z = AnnotNode::new(Node::Assign(VarName::new("z"), Box::new(parse_aexp("x"))), RawAnnot {});
+    assert!(d.added_edges.contains(&(branch, assign_z, Edge::True)));
+    assert_eq!(d.added_edges.len(), 2);
+    assert_eq!(eval_cfg(&after, 0), eval_cfg(&before, 0));
+
+    // A final `Skip` node targeted by a labelled edge becomes a `Terminal` node
+    let mut after = cfg::ast_to_cfg(&parser::parse("if x <= 1 then skip else y := 2 end").unwrap());
+    cfg::remove_skips(&mut after);
+    assert!(after.graph.raw_nodes().iter().any(|n| n.weight.node == Node::Terminal));
+    assert!(after.graph.raw_nodes().iter().all(|n| n.weight.node != Node::Skip));
+}
+
+#[test]
+fn test_cfg_omit_skips() {
+    let options = cfg::CfgOptions { omit_skips: true, ..Default::default() };
+    let progs = vec![
+        "x := 1; skip; if x <= 1 then skip else y := 2 end; z := x",
+        "if x <= 1 then skip else y := 2 end",
+        "while x <= 3 do skip end; skip; z := x",
+        "proc p do skip end; skip; call p; z := x",
+        "skip",
+    ];
+    progs.into_iter().for_each(|s| {
+        let prog = parser::parse(s).unwrap();
+        let mut expected = cfg::ast_to_cfg(&prog);
+        cfg::remove_skips(&mut expected);
+        let cfg = cfg::ast_to_cfg_with(&prog, &options);
+        assert!(cfg.graph.raw_nodes().iter().all(|n| n.weight.node != Node::Skip));
+        assert_eq!(cfg, expected, "{}", s);
+    });
+
+    // A `skip` as the sole then-branch passes on the `True` edge
+    let cfg = cfg::ast_to_cfg_with(&parser::parse("if x <= 1 then skip else y := 2 end; z := x").unwrap(), &options);
+    let branch = cfg.graph.node_indices().find(|n| matches!(cfg.graph[*n].node, Node::Branch(_))).unwrap();
+    let assign_z = cfg.graph.node_indices().find(|n| cfg.graph[*n].node == Node::Assign(VarName::new("z"), Box::new(parse_aexp("x")))).unwrap();
+    assert_eq!(cfg.graph.edges_connecting(branch, assign_z).map(|e| e.weight().clone()).collect::<Vec<_>>(), vec![Edge::True]);
+}
+
+#[test]
+fn test_parser_precedence() {
+    let n = |i| Box::new(AExp::Num(i));
+    let add = |a, b| AExp::Add(a, b);
+    let mul = |a, b| AExp::Mul(a, b);
+    // `*` binds stronger than `+`, both are left-associative
+    assert_eq!(parse_aexp("2 + 3 * 4"), add(n(2), Box::new(mul(n(3), n(4)))));
+    assert_eq!(parse_aexp("2 * 3 + 4"), add(Box::new(mul(n(2), n(3))), n(4)));
+    assert_eq!(parse_aexp("(2 + 3) * 4"), mul(Box::new(add(n(2), n(3))), n(4)));
+    assert_eq!(parse_aexp("( 2 + 3 )*4"), mul(Box::new(add(n(2), n(3))), n(4)));
+    assert_eq!(parse_aexp("2 + 3 + 4"), add(Box::new(add(n(2), n(3))), n(4)));
+    assert_eq!(parse_aexp("2 * 3 * 4"), mul(Box::new(mul(n(2), n(3))), n(4)));
+    assert_eq!(parse_aexp("2 * (3 * 4)"), mul(n(2), Box::new(mul(n(3), n(4)))));
+    // A negative number binds stronger than `*`
+    assert_eq!(parse_aexp("-2"), *n(-2));
+    assert_eq!(parse_aexp("-2 * 3"), mul(n(-2), n(3)));
+    assert_eq!(parse_aexp("-2 + 3"), add(n(-2), n(3)));
+    assert_eq!(parse_aexp("2 + -3 * 4"), add(n(2), Box::new(mul(n(-3), n(4)))));
+    assert_eq!(parse_aexp("(-2) * 3"), mul(n(-2), n(3)));
+    assert_eq!(parse_aexp("2 * (-3)"), mul(n(2), n(-3)));
+    assert!(parser::parse("y := - 2").is_err());
+}
+
+#[test]
+fn test_parser_negative_factors() {
+    let n = |i| Box::new(AExp::Num(i));
+    let x = || Box::new(AExp::Var(VarName::new("x")));
+    let add = |a, b| Box::new(AExp::Add(a, b));
+    let mul = |a, b| Box::new(AExp::Mul(a, b));
+    assert_eq!(parse_aexp("2 * -3"), *mul(n(2), n(-3)));
+    assert_eq!(parse_aexp("-2 * -3"), *mul(n(-2), n(-3)));
+    assert_eq!(parse_aexp("2 * -3 * x"), *mul(mul(n(2), n(-3)), x()));
+    assert_eq!(parse_aexp("x + -3 * -4"), *add(x(), mul(n(-3), n(-4))));
+    assert_eq!(parse_aexp("(2 + -3) * -4"), *mul(add(n(2), n(-3)), n(-4)));
+    assert_eq!(parse_aexp("-2 * 3"), *mul(n(-2), n(3)));
+    // Negative factors are printed without parentheses, which must be parsable again
+    let a = parse_aexp("x * -3");
+    assert_eq!(parse_aexp(&a.to_string()), a);
+    let b = parser::parse("if -1 <= x * -2 then y := 1 end").unwrap();
+    assert_eq!(parser::parse(&b.to_string()).unwrap(), b);
+    // There is no subtraction
+    assert!(parser::parse("y := 2 -3").is_err());
+    assert!(parser::parse("y := 2 - 3").is_err());
+    assert!(parser::parse("y := 2 * - 3").is_err());
+}
+
+
+#[test]
+fn test_interval_lat() {
+    let r = IntervalLat::new;
+    assert_eq!(r(0, 2).join_bin(&r(5, 7)), r(0, 7));
+    assert_eq!(r(0, 2).join_bin(&IntervalLat::Bot), r(0, 2));
+    assert_eq!(r(3, 2), IntervalLat::Bot);
+    // Widening moves a growing bound to the extreme value
+    assert_eq!(r(0, 2).widen(&r(0, 3)), r(0, Int::MAX));
+    assert_eq!(r(0, 2).widen(&r(-1, 1)), r(Int::MIN, 2));
+    assert_eq!(r(0, 2).widen(&r(1, 2)), r(0, 2));
+    assert_eq!(IntervalLat::top().to_string(), "[-inf, inf]");
+
+    let mut mem = MultiIntervalLat::new(BTreeMap::new(), IntervalLat::singleton(0));
+    mem.insert(VarName::new("x"), r(-2, 5));
+    let x = VarName::new("x");
+    assert_eq!(mem.eval_aexp(&parse_aexp("x*x + 1")), r(-9, 26));
+    assert_eq!(mem.eval_aexp(&parse_aexp("x % 3")), r(0, 2));
+    assert_eq!(mem.eval_aexp(&parse_aexp(&format!("x + {}", Int::MAX))), IntervalLat::top());
+    // Guards refine the compared variables
+    let b = parse_bexp("x < 3");
+    assert_eq!(*mem.assume(&b, true).lookup(&x), r(-2, 2));
+    assert_eq!(*mem.assume(&b, false).lookup(&x), r(3, 5));
+    assert!(mem.assume(&parse_bexp("5 < x"), true).is_bot());
+    assert_eq!(*mem.assume(&parse_bexp("x <= 0 || 4 <= x"), false).lookup(&x), r(1, 3));
+}
+
+#[test]
+fn test_interval_loop_bound() {
+    // The fixture's counting loop runs while `x <= n` with `n = 10`
+    let cfg: Cfg<MfpAnnot<MultiIntervalLat>> = read_fixture("tests-res/counting_loop", "cfg_interval");
+    let exit = cfg.graph.node_indices().find(|n| cfg.graph[*n].node == Node::Assign(VarName::new("z"), Box::new(parse_aexp("x")))).unwrap();
+    assert_eq!(*cfg.graph[exit].annot.pre().lookup(&VarName::new("x")), IntervalLat::singleton(11));
+    let head = cfg.graph.node_indices().find(|n| matches!(cfg.graph[*n].node, Node::Branch(_))).unwrap();
+    assert_eq!(cfg.loop_heads().into_iter().collect::<Vec<_>>(), vec![head]);
+    assert_eq!(*cfg.graph[head].annot.pre().lookup(&VarName::new("x")), IntervalLat::new(0, 11));
+}
+
+#[test]
+fn test_mfp_narrowing() {
+    let prog = parser::parse("i := 0; while i < 100 do i := i + 1 end; z := i").unwrap();
+    let cfg = cfg::ast_to_cfg(&prog);
+    let i = VarName::new("i");
+    let head = cfg.graph.node_indices().find(|n| matches!(cfg.graph[*n].node, Node::Branch(_))).unwrap();
+    let exit = cfg.graph.node_indices().find(|n| cfg.graph[*n].node == Node::Assign(VarName::new("z"), Box::new(parse_aexp("i")))).unwrap();
+
+    // Widening blows the upper bound up to `inf`
+    let widened: Cfg<MfpAnnot<MultiIntervalLat>> = mfp_widening_ascent(&cfg);
+    assert_eq!(*widened.graph[head].annot.pre().lookup(&i), IntervalLat::new(0, Int::MAX));
+    assert_eq!(*widened.graph[exit].annot.pre().lookup(&i), IntervalLat::new(100, Int::MAX));
+    // Narrowing recovers the precise bounds
+    let narrowed = mfp_narrowing(&widened);
+    assert_eq!(*narrowed.graph[head].annot.pre().lookup(&i), IntervalLat::new(0, 100));
+    assert_eq!(*narrowed.graph[exit].annot.pre().lookup(&i), IntervalLat::singleton(100));
+    assert_eq!(narrowed, mfp_widening(&cfg));
+    // Narrowing an exact solution changes nothing
+    assert_eq!(mfp_narrowing(&narrowed), narrowed);
+    assert_eq!(IntervalLat::new(0, Int::MAX).narrow(&IntervalLat::new(1, 5)), IntervalLat::new(0, 5));
+}
+
+#[test]
+fn test_finite_set_lat() {
+    let values = |vs: &[Int]| FiniteSetLat::new(vs.iter().cloned().collect());
+    assert_eq!(values(&[1]).join_bin(&values(&[2])), values(&[1, 2]));
+    assert_eq!(values(&[1]).join_bin(&FiniteSetLat::bot()), values(&[1]));
+    assert_eq!(values(&[1]).join_bin(&FiniteSetLat::Top), FiniteSetLat::Top);
+    // Collapse to `Top` once there are too many values
+    let many: Vec<Int> = (0..FINITE_SET_MAX as Int).collect();
+    assert_ne!(values(&many), FiniteSetLat::Top);
+    assert_eq!(values(&many).join_bin(&values(&[-1])), FiniteSetLat::Top);
+
+    // Both values of `y` are tracked, where constant propagation only knows `Top`
+    let cfg = cfg::ast_to_cfg(&parser::parse("if x <= 0 then y := 1 else y := 2 end; z := y * 3").unwrap());
+    let fs: Cfg<MfpAnnot<MultiFiniteSetLat>> = mfp(&cfg);
+    let cp: Cfg<MfpAnnot<MultiConstLat>> = mfp(&cfg);
+    let last = cfg.graph.node_indices().find(|n| matches!(cfg.graph[*n].node, Node::Assign(ref v, _) if *v == VarName::new("z"))).unwrap();
+    assert_eq!(*fs.graph[last].annot.post().lookup(&VarName::new("z")), values(&[3, 6]));
+    assert_eq!(*cp.graph[last].annot.post().lookup(&VarName::new("z")), ConstLat::Top);
+}
+
+#[test]
+fn test_always_terminal() {
+    let prog = parser::parse("y := 1; z := y").unwrap();
+    // By default, a straight-line program has no `Terminal` node
+    let cfg_default = cfg::ast_to_cfg(&prog);
+    assert!(!cfg_default.terminal_reachable());
+
+    let cfg = cfg::ast_to_cfg_with(&prog, &cfg::CfgOptions { always_terminal: true, ..Default::default() });
+    let terminals: Vec<_> = cfg.graph.node_indices().filter(|n| cfg.graph[*n].node == Node::Terminal).collect();
+    assert_eq!(terminals.len(), 1);
+    assert_eq!(cfg.exits(), terminals);
+    assert!(cfg.terminal_reachable());
+    let predecs = cfg.predecessors(terminals[0]).unwrap();
+    assert_eq!(predecs.len(), 1);
+    assert_eq!(cfg.graph[*predecs.first()].node, Node::Assign(VarName::new("z"), Box::new(parse_aexp("y"))));
+
+    // Plain and labelled loose ends share the same `Terminal` node
+    let prog = parser::parse("if x <= 0 then y := 1 else skip end; while y <= 3 do y := y + 1 end").unwrap();
+    let cfg = cfg::ast_to_cfg_with(&prog, &cfg::CfgOptions { always_terminal: true, ..Default::default() });
+    assert_eq!(cfg.exits().len(), 1);
+    assert_eq!(cfg.graph[cfg.exits()[0]].node, Node::Terminal);
+    // The default CFG is unchanged if it already has a single `Terminal` exit
+    assert_eq!(cfg, cfg::ast_to_cfg(&prog));
+}
+
+#[test]
+fn test_cfg_display() {
+    let cfg = cfg::ast_to_cfg(&parser::parse("y := 1").unwrap());
+    assert_eq!(cfg.to_string(), "0: init\n    -> 1\n1: y := 1\n");
+
+    let cfg_mfp: Cfg<MfpAnnot<ExpSetLat>> = mfp(&cfg::ast_to_cfg(&parser::parse("while y <= 1 do skip end").unwrap()));
+    let listing = cfg_mfp.to_string();
+    assert!(listing.starts_with("0: init\n    pre: "));
+    assert!(listing.contains("1: y <= 1\n"));
+    assert!(listing.contains("    -tt-> 2\n"));
+    assert!(listing.contains("    -ff-> 3\n"));
+}
+
+#[test]
+fn test_parse_expressions() {
+    let x = || Box::new(AExp::Var(VarName::new("x")));
+    let y = || Box::new(AExp::Var(VarName::new("y")));
+    let n = |i| Box::new(AExp::Num(i));
+    assert_eq!(parser::parse_aexp("x + 2*y"), Ok(AExp::Add(x(), Box::new(AExp::Mul(n(2), y())))));
+    assert_eq!(parser::parse_aexp("  x + 2*y \n"), parser::parse_aexp("x + 2*y"));
+    assert_eq!(parser::parse_bexp("x <= 1 && y <= 2"), Ok(BExp::And(Box::new(BExp::LessEq(x(), n(1))), Box::new(BExp::LessEq(y(), n(2))))));
+    assert!(parser::parse_aexp("x + ").is_err());
+    assert!(parser::parse_aexp("x := 1").is_err());
+    assert!(parser::parse_aexp("").is_err());
+    assert!(parser::parse_bexp("x <= 1 y").is_err());
+    assert!(parser::parse_bexp("x").is_err());
+}
+
+#[test]
+fn test_repl_session() {
+    let script = "\
+:eval 1
+y := x;
+z := y + 1
+:eval 4
+:ast
+if x <= then
+:eval 2
+:cfg
+:unknown
+:quit
+:eval 3
+";
+    let mut child = Command::new(env!("CARGO_BIN_EXE_repl"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(script.as_bytes()).unwrap();
+    let output = String::from_utf8(child.wait_with_output().unwrap().stdout).unwrap();
+    let outputs: Vec<&str> = output.split("\n> ").skip(1).map(|o| o.trim_end()).collect();
+    assert_eq!(outputs[0], "error: No program given yet.");
+    assert_eq!(outputs[1], "5");
+    assert!(outputs[2].contains("\"Assign\""));
+    // A parse error skips the command, but keeps the previous program for later commands
+    assert!(outputs[3].starts_with("error: Parsing failed."));
+    assert!(outputs[4].starts_with("0: init\n    -> 1\n1: y := x\n"));
+    assert!(outputs[5].starts_with("error: Unknown command :unknown."));
+    // Nothing is evaluated after `:quit`
+    assert_eq!(outputs.len(), 7);
+    assert_eq!(outputs[6], "");
+}
+
+#[test]
+fn test_degenerate_inputs() {
+    // Programs that cannot be parsed result in errors
+    let too_large = format!("y := {}", Int::MAX as i128 + 1);
+    let too_small = format!("y := {}", Int::MIN as i128 - 1);
+    ["", "   ", "# only a comment", ";", "y :=", &too_large, &too_small, "call p", "if x <= 1 then"].iter().for_each(|s| {
+        assert!(parser::parse(s).is_err(), "{}", s);
+    });
+    assert_eq!(parse_aexp(&Int::MIN.to_string()), AExp::Num(Int::MIN));
+    // Every procedure is defined at most once
+    assert_eq!(parser::parse("proc p do z := 1 end; proc q do skip end; proc p do z := 2 end; call p").unwrap_err(),
+               "Parsing failed. The procedure p is defined more than once.");
+    assert!(parser::parse("proc p do z := 1 end; proc q do call p end; call q").is_ok());
+
+    // Arithmetic wraps around, consistently in the interpreter and in constant propagation
+    let prog = parser::parse(&format!("y := {} + 1; z := y * 2", Int::MAX)).unwrap();
+    assert_eq!(eval(&prog, 0), 0);
+    let cfg_mfp: Cfg<MfpAnnot<MultiConstLat>> = mfp(&cfg::ast_to_cfg(&prog));
+    let last = cfg_mfp.exits()[0];
+    assert_eq!(*cfg_mfp.graph[last].annot.post().lookup(&VarName::new("y")), ConstLat::Const(Int::MIN));
+
+    // A call of an undefined procedure (only possible for ASTs that are not parsed) behaves like `skip`
+    let prog = Prog::Prog(vec![ProgAtom::Call(VarName::new("p")), ProgAtom::Assign(VarName::new("z"), Box::new(parse_aexp("x + 1")))]);
+    assert_eq!(eval(&prog, 1), 2);
+    assert_eq!(eval_cfg(&cfg::ast_to_cfg(&prog), 1), Ok(2));
+
+    // A single-node CFG
+    let cfg = cfg::ast_to_cfg(&parser::parse("skip").unwrap());
+    let single = Cfg::new(cfg.graph.filter_map(|n, w| if n == cfg.init { Some(w.clone()) } else { None }, |_, e| Some(e.clone())), cfg.init);
+    assert_eq!(single.graph.node_count(), 1);
+    let _: Cfg<MfpAnnot<MultiConstLat>> = mfp(&single);
+    let _: Cfg<MfpAnnot<ExpSetLat>> = mfp(&single);
+    assert_eq!(eval_cfg(&single, 1), Ok(0));
+
+    // Deserialized garbage is either rejected or analyzed without panicking
+    assert!(serde_json::from_str::<Cfg<RawAnnot>>("{\"graph\": 42}").is_err());
+    let mut cfg = cfg::ast_to_cfg(&parser::parse("y := 1; z := y").unwrap());
+    cfg.init = petgraph::graph::NodeIndex::new(17);
+    let json = serde_json::to_string(&cfg).unwrap();
+    let garbage: Cfg<RawAnnot> = serde_json::from_str(&json).unwrap();
+    let _: Cfg<MfpAnnot<MultiConstLat>> = mfp(&garbage);
+    // A node without predecessors that is not the init node
+    let (union, _) = Cfg::disjoint_union(cfg::ast_to_cfg(&parser::parse("y := 1").unwrap()), cfg::ast_to_cfg(&parser::parse("z := 2").unwrap()));
+    let _: Cfg<MfpAnnot<ExpSetLat>> = mfp(&union);
+    // A CFG without init node
+    let empty = cfg::ast_to_cfg(&parser::parse("z := 1").unwrap()).subgraph(&std::collections::HashSet::new());
+    assert_eq!(empty, empty.clone());
+    assert_ne!(empty, cfg::ast_to_cfg(&parser::parse("z := 1").unwrap()));
+    assert_eq!(empty.longest_path_cost(|_| 1), None);
+    assert!(!empty.terminal_reachable());
+    assert_eq!(to_dot(&empty, &DotOptions { edge_style: EdgeStyle::Numbered, ..DotOptions::default() }), "digraph {\n}\n");
+}
+
+#[test]
+fn test_cli_errors() {
+    let output = Command::new(env!("CARGO_BIN_EXE_flanelly"))
+        .args(["--sweep", "1"])
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .and_then(|mut child| { child.stdin.take().unwrap().write_all(b"z := x")?; child.wait_with_output() })
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "The range 1 is not of the form min,max.\n");
+    assert_eq!(run_cli(&["-i", "1"], "z := "), "");
+}
+
+#[test]
+fn test_cli_fail_on_top() {
+    let run = |args: &[&str], program: &str| Command::new(env!("CARGO_BIN_EXE_flanelly"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .and_then(|mut child| { child.stdin.take().unwrap().write_all(program.as_bytes())?; child.wait_with_output() })
+        .unwrap();
+    // The output is constant
+    let output = run(&["--fail-on-top"], "if x <= 0 then y := 1 else y := 1 end; z := y + 1");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stderr.is_empty());
+    // The branches assign different constants, so `y` (and thus `z`) becomes `Top` at the join
+    let output = run(&["--fail-on-top"], "if x <= 0 then y := 1 else y := 2 end; z := y + 1");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "The variable z is tt at the end of the program. It first became tt in node 4: z := y + 1\n");
+    // A different output variable
+    let output = run(&["--fail-on-top", "y"], "y := x; z := 5");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "The variable y is tt at the end of the program. It first became tt in node 1: y := x\n");
+    assert_eq!(run(&["--fail-on-top", "z"], "y := x; z := 5").status.code(), Some(0));
+}
+
+#[test]
+fn test_top_origin() {
+    let cfg = cfg::ast_to_cfg(&parser::parse("y := 1; while y <= x do y := y + 1 end; z := y").unwrap());
+    let (cfg_mfp, origin) = top_origin(&cfg, &MfpOptions::default(), MultiConstLat::init_start(), &VarName::new("z"));
+    assert_eq!(cfg_mfp, mfp(&cfg));
+    // `y` becomes `Top` at the loop head, but `z` only in its assignment
+    assert_eq!(origin.map(|n| cfg.graph[n].node.to_string()), Some("z := y".to_string()));
+    let (_, origin) = top_origin(&cfg, &MfpOptions::default(), MultiConstLat::init_start(), &VarName::new("y"));
+    assert_eq!(origin.map(|n| cfg.graph[n].node.to_string()), Some("y <= x".to_string()));
+    // The input is `Top` from the start
+    let (_, origin) = top_origin(&cfg, &MfpOptions::default(), MultiConstLat::init_start(), &VarName::new("x"));
+    assert_eq!(origin, Some(cfg.init));
+    let (_, origin) = top_origin(&cfg, &MfpOptions::default(), MultiConstLat::seeded(&[(VarName::new("x"), 0)]), &VarName::new("z"));
+    assert_eq!(origin, None);
+}
+
+#[test]
+fn test_cfg_stats_known() {
+    let cfg = cfg::ast_to_cfg(&parser::parse("y := 0; while y <= x do y := 0; repeat y := y + 1 until x <= y end; if y <= 2 then x := x + 1 end end; z := y").unwrap());
+    let stats = cfg.stats();
+    let counts = |v: &[(&'static str, usize)]| v.iter().cloned().collect();
+    assert_eq!(stats, CfgStats {
+        nodes: counts(&[("assign", 5), ("branch", 3), ("init", 1), ("skip", 1)]),
+        edges: counts(&[("false", 3), ("plain", 6), ("true", 3)]),
+        loops: 1,
+        max_loop_depth: 2,
+        reducible: true
+    });
+    assert_eq!(stats.to_string(), "nodes: 10 (assign: 5, branch: 3, init: 1, skip: 1)\nedges: 12 (false: 3, plain: 6, true: 3)\nloops: 1\nmax loop depth: 2\nreducible: yes");
+    assert_eq!(run_cli(&["--graph-stats"], "y := 1; if x <= 0 then y := 2 end; z := y"),
+               "nodes: 6 (assign: 3, branch: 1, init: 1, skip: 1)\nedges: 6 (false: 1, plain: 4, true: 1)\nloops: 0\nmax loop depth: 0\nreducible: yes\n");
+
+    // A cycle that can be entered from both branches is irreducible
+    let (mut graph, init) = cfg::ast_to_cfg(&parser::parse("if x <= 0 then y := 1 else y := 2 end").unwrap()).into_graph();
+    let assign = |graph: &petgraph::Graph<AnnotNode<RawAnnot>, Edge>, n| graph.node_indices().find(|i| graph[*i].node == Node::Assign(VarName::new("y"), Box::new(AExp::Num(n)))).unwrap();
+    let (one, two) = (assign(&graph, 1), assign(&graph, 2));
+    graph.add_edge(one, two, Edge::Plain);
+    graph.add_edge(two, one, Edge::Plain);
+    let irreducible = Cfg::from_graph_checked(graph, init).unwrap();
+    assert_eq!(irreducible.loops().len(), 1);
+    assert!(!irreducible.is_reducible());
+}
+
+#[test]
+fn test_cfg_from_graph_checked() {
+    let cfg = cfg::ast_to_cfg(&parser::parse("y := 1; while y <= 3 do y := y + 1 end").unwrap());
+    assert_eq!(cfg.as_graph().node_count(), cfg.graph.node_count());
+    let orig = cfg.clone();
+
+    // Mutate the graph with petgraph: Append a node after the loop
+    let (mut graph, init) = cfg.into_graph();
+    let branch = graph.node_indices().find(|n| matches!(graph[*n].node, Node::Branch(_))).unwrap();
+    let z = graph.add_node(AnnotNode::new(Node::Assign(VarName::new("z"), Box::new(parse_aexp("y"))), RawAnnot {}));
+    graph.add_edge(branch, z, Edge::False);
+    let cfg = Cfg::from_graph_checked(graph.clone(), init).unwrap();
+    assert_eq!(eval_cfg(&cfg, 0), Ok(4));
+    assert_ne!(cfg, orig);
+
+    // The init node must be valid
+    assert!(Cfg::from_graph_checked(graph.clone(), NodeIdx::new(42)).unwrap_err().contains("not part of the graph"));
+    assert!(Cfg::from_graph_checked(graph.clone(), z).unwrap_err().contains("instead of init"));
+    let mut with_back_edge = graph.clone();
+    with_back_edge.add_edge(z, init, Edge::Plain);
+    assert!(Cfg::from_graph_checked(with_back_edge, init).unwrap_err().contains("incoming edge"));
+    let mut with_second_init = graph;
+    with_second_init.add_node(AnnotNode::new(Node::Init, RawAnnot {}));
+    assert!(Cfg::from_graph_checked(with_second_init, init).unwrap_err().contains("second init node"));
+}
+
+#[test]
+fn test_map_with() {
+    let cfg = cfg::ast_to_cfg(&parser::parse("y := 1; while y <= 3 do y := y + 1 end").unwrap());
+    let mapped: Cfg<String> = cfg.map_with(|n, node, _| format!("{}: {}", n.index(), node));
+    cfg.graph.node_indices().for_each(|n| {
+        assert_eq!(mapped.graph[n].node, cfg.graph[n].node);
+        assert_eq!(mapped.graph[n].annot, format!("{}: {}", n.index(), cfg.graph[n].node));
+    });
+    assert_eq!(mapped.graph.edge_count(), cfg.graph.edge_count());
+    assert_eq!(mapped.init, cfg.init);
+}
+
+#[test]
+fn test_analysis_direction() {
+    assert_eq!(MultiConstLat::DIRECTION, AnalysisDirection::Forward);
+    assert_eq!(ExpSetLat::DIRECTION, AnalysisDirection::Forward);
+    assert_eq!(LiveVarsLat::DIRECTION, AnalysisDirection::Backward);
+}
+
+#[test]
+#[should_panic(expected = "The analysis is a Backward analysis, but is solved as a Forward analysis.")]
+fn test_mfp_rejects_backward_analysis() {
+    let _: Cfg<MfpAnnot<LiveVarsLat>> = mfp(&cfg::ast_to_cfg(&parser::parse("z := x").unwrap()));
+}
+
+#[test]
+#[should_panic(expected = "The analysis is a Forward analysis, but is solved as a Backward analysis.")]
+fn test_mfp_backward_rejects_forward_analysis() {
+    let _: Cfg<MfpAnnot<MultiConstLat>> = mfp_backward(&cfg::ast_to_cfg(&parser::parse("z := x").unwrap()));
+}
+
+#[test]
+#[should_panic(expected = "The components of the product are a Forward and a Backward analysis.")]
+fn test_product_rejects_mixed_directions() {
+    let _: Cfg<MfpAnnot<ProductLat<MultiConstLat, LiveVarsLat>>> = mfp(&cfg::ast_to_cfg(&parser::parse("z := x").unwrap()));
+}
+
+#[test]
+fn test_remove_dead_stores() {
+    let prog = parser::parse("y := x + 1; a := y; b := a; z := x; if z <= 0 then z := 1 end").unwrap();
+    let mut cfg = cfg::ast_to_cfg(&prog);
+    let cfg_live: Cfg<MfpAnnot<LiveVarsLat>> = mfp_backward(&cfg);
+    assert!(cfg_live.graph[cfg_live.init].annot.post().contains(&VarName::new("x")));
+    assert!(!cfg_live.graph[cfg_live.init].annot.post().contains(&VarName::new("z")));
+    // Only `b := a` is dead at first, which renders `a := y` and `y := x + 1` dead
+    assert_eq!(dead_stores(&cfg).into_iter().map(|n| n.index()).collect::<Vec<_>>(), vec![3]);
+    remove_dead_stores(&mut cfg);
+    assert!(dead_stores(&cfg).is_empty());
+    let assigned: Vec<String> = cfg.graph.raw_nodes().iter().filter_map(|n| match &n.weight.node {
+        Node::Assign(x, _) => Some(x.to_string()),
+        _ => None
+    }).collect();
+    assert_eq!(assigned, vec!["z", "z"]);
+    (-2..=2).for_each(|x| assert_eq!(eval_cfg(&cfg, x), Ok(eval(&prog, x))));
+}
+
+#[test]
+fn test_mfp_explained() {
+    let cfg = cfg::ast_to_cfg(&parser::parse("y := 1; while y <= 3 do z := y end").unwrap());
+    let (cfg_mfp, steps) = mfp_explained::<DefVarsLat>(&cfg);
+    let cfg_plain: Cfg<MfpAnnot<DefVarsLat>> = mfp(&cfg);
+    cfg.graph.node_indices().for_each(|n| assert_eq!(cfg_mfp.graph[n].annot, cfg_plain.graph[n].annot));
+    let vars = |xs: &[&str]| DefVarsLat::Vars(xs.iter().map(|x| VarName::new(x)).collect());
+    // Node 1: `y := 1`, node 2: `y <= 3`, node 3: `z := y`, node 4: terminal
+    let expected = vec![
+        (1, vars(&["x"]), vars(&["x", "y"]), true),
+        (2, vars(&["x", "y"]), vars(&["x", "y"]), true),
+        (3, vars(&["x", "y"]), vars(&["x", "y", "z"]), true),
+        (2, vars(&["x", "y"]), vars(&["x", "y"]), false),
+        (4, vars(&["x", "y"]), vars(&["x", "y"]), true),
+    ];
+    let actual: Vec<(usize, DefVarsLat, DefVarsLat, bool)> = steps.into_iter().map(|s| (s.node.index(), s.pre, s.post, s.changed)).collect();
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_literals() {
+    let prog = parser::parse("proc p do y := y * 7 end; y := 1; if x <= -2 then while y < 10 do call p end else z := x + 1 end").unwrap();
+    let expected: std::collections::HashSet<Int> = vec![7, 1, -2, 10].into_iter().collect();
+    assert_eq!(prog.literals(), expected);
+    assert!(parser::parse("y := x; skip").unwrap().literals().is_empty());
+}
+
+#[cfg(feature = "i64")]
+#[test]
+fn test_i64() {
+    // 20! exceeds the range of 32-bit integers
+    let prog = parser::parse("y := 1; z := 1; while y <= 20 do z := z * y; y := y + 1 end").unwrap();
+    assert_eq!(eval(&prog, 0), 2432902008176640000);
+    let cfg_mfp: Cfg<MfpAnnot<MultiConstLat>> = mfp(&cfg::ast_to_cfg(&parser::parse("z := 3000000000 * 2").unwrap()));
+    assert_eq!(*cfg_mfp.graph[cfg_mfp.exits()[0]].annot.post().lookup(&VarName::new("z")), ConstLat::Const(6000000000));
+}
+
+#[test]
+fn test_subgraph() {
+    let cfg = cfg::ast_to_cfg(&parser::parse("y := 1; while y <= 3 do y := y + 1; skip end; z := y").unwrap());
+    // The only non-trivial SCC is the loop
+    let scc: std::collections::HashSet<_> = petgraph::algo::tarjan_scc(&cfg.graph).into_iter().find(|c| c.len() > 1).unwrap().into_iter().collect();
+    let sub = cfg.subgraph(&scc);
+    assert_eq!(sub.graph.node_count(), 3);
+    // The guard is the entry of the loop
+    assert!(matches!(sub.graph[sub.init].node, Node::Branch(_)));
+    // Besides the `True` edge into the body, the back-edge to the guard is present
+    assert_eq!(sub.graph.edge_count(), 3);
+    let back_edges: Vec<_> = sub.graph.raw_edges().iter().filter(|e| e.target() == sub.init).collect();
+    assert_eq!(back_edges.len(), 1);
+    assert_eq!(sub.graph[back_edges[0].source()].node, Node::Skip);
+
+    assert_eq!(cfg.subgraph(&cfg.graph.node_indices().collect()), cfg);
+    assert_eq!(cfg.subgraph(&std::collections::HashSet::new()).graph.node_count(), 0);
+}
+
+#[test]
+fn test_edge_refinement() {
+    // The negation of the guard is false, so only the `True` edge can be taken
+    let cfg = cfg::ast_to_cfg(&parser::parse("y := 1; if !(y <= 0) then z := 5 else z := 7 end; y := z").unwrap());
+    let cfg_mfp: Cfg<MfpAnnot<MultiConstLat>> = mfp(&cfg);
+    let find = |node: &Node| cfg_mfp.graph.node_indices().find(|n| cfg_mfp.graph[*n].node == *node).unwrap();
+    let assign = |x: &str, n: Int| Node::Assign(VarName::new(x), Box::new(AExp::Num(n)));
+    assert_eq!(cfg_mfp.graph[find(&assign("z", 5))].annot.pre().lookup(&VarName::new("y")), &ConstLat::Const(1));
+    assert!(cfg_mfp.graph[find(&assign("z", 7))].annot.pre().is_bot());
+    let last = Node::Assign(VarName::new("y"), Box::new(AExp::Var(VarName::new("z"))));
+    assert_eq!(cfg_mfp.graph[find(&last)].annot.post().lookup(&VarName::new("y")), &ConstLat::Const(5));
+
+    // Both edges remain possible if the guard is not constant
+    let branch = Node::Branch(Box::new(parser::parse_bexp("x <= 0").unwrap()));
+    let mem = MultiConstLat::init_start();
+    assert_eq!(MultiConstLat::eval_edge(&branch, &mem, &Edge::True), mem);
+    assert_eq!(MultiConstLat::eval_edge(&branch, &mem, &Edge::False), mem);
+    let mut mem = MultiConstLat::init_start();
+    mem.insert(VarName::new("x"), ConstLat::Const(3));
+    assert!(MultiConstLat::eval_edge(&branch, &mem, &Edge::True).is_bot());
+    assert_eq!(MultiConstLat::eval_edge(&branch, &mem, &Edge::False), mem);
+}
+
+
+#[cfg(feature = "binary")]
+#[test]
+fn test_binary_round_trip() {
+    use flanelly::binary::{to_bytes, from_bytes};
+    let name = "tests-res/test1";
+    let prog: Prog = read_fixture(name, "ast");
+    assert_eq!(from_bytes::<Prog>(&to_bytes(&prog).unwrap()).unwrap(), prog);
+    let cfg: Cfg<RawAnnot> = read_fixture(name, "cfg");
+    assert_eq!(from_bytes::<Cfg<RawAnnot>>(&to_bytes(&cfg).unwrap()).unwrap(), cfg);
+    let cfg_const_prop: Cfg<MfpAnnot<MultiConstLat>> = read_fixture(name, "cfg_const_prop");
+    assert_eq!(from_bytes::<Cfg<MfpAnnot<MultiConstLat>>>(&to_bytes(&cfg_const_prop).unwrap()).unwrap(), cfg_const_prop);
+    assert!(from_bytes::<Prog>(&[0xff]).is_err());
+}
+
+#[test]
+fn test_topo_order() {
+    let cfg: Cfg<RawAnnot> = read_fixture("tests-res/two_values", "cfg");
+    let order = cfg.topo_order().unwrap();
+    assert_eq!(order.len(), cfg.graph.node_count());
+    let position = |n| order.iter().position(|m| *m == n).unwrap();
+    cfg.graph.edge_indices().for_each(|e| {
+        let (source, target) = cfg.graph.edge_endpoints(e).unwrap();
+        assert!(position(source) < position(target));
+    });
+    let cfg_const_prop: Cfg<MfpAnnot<MultiConstLat>> = mfp(&cfg);
+    assert_eq!(mfp_acyclic(&cfg), cfg_const_prop);
+
+    let cfg = cfg::ast_to_cfg(&parser::parse("y := 1; while y <= 3 do y := y + 1 end").unwrap());
+    assert_eq!(cfg.topo_order(), None);
+}
+
+#[test]
+fn test_dot_cluster_loops() {
+    // Node 1: `y := 1`, node 2: `y <= 3`, node 3: `y := y + 1`, node 4: terminal
+    let cfg = cfg::ast_to_cfg(&parser::parse("y := 1; while y <= 3 do y := y + 1 end").unwrap());
+    assert_eq!(cfg.loops(), vec![vec![2.into(), 3.into()]]);
+    let dot = to_dot(&cfg, &DotOptions { cluster_loops: true, engine: Some(DotEngine::Dot), ..DotOptions::default() });
+    assert!(dot.contains("    layout = \"dot\"\n"));
+    let start = dot.find("    subgraph cluster_0 {\n").unwrap();
+    let end = start + dot[start..].find("\n    }\n").unwrap();
+    let cluster = &dot[start..end];
+    assert!(cluster.contains("        2 [ label = \"y <= 3\" ]"));
+    assert!(cluster.contains("        3 [ label = \"y := y + 1\" ]"));
+    assert!(!cluster.contains("1 [") && !cluster.contains("4 ["));
+    // Without the option, there are no clusters
+    assert!(!to_dot(&cfg, &DotOptions::default()).contains("cluster_"));
+}
+
+#[test]
+fn test_mfp_incremental() {
+    // Node 1: `y := x`
+    let cfg = cfg::ast_to_cfg(&parser::parse("y := x; w := 2; while y <= 3 do y := y + 1 end; z := y").unwrap());
+    let prev: Cfg<MfpAnnot<MultiConstLat>> = mfp(&cfg);
+    // Change the assignment into `y := 5`, so that `y` (and thus `z`) becomes constant
+    let n = NodeIdx::new(1);
+    let mut cfg_changed = cfg.clone();
+    cfg_changed.graph[n].node = Node::Assign(VarName::new("y"), Box::new(AExp::Num(5)));
+    let mut prev_changed = prev.clone();
+    prev_changed.graph[n].node = cfg_changed.graph[n].node.clone();
+    let changed = vec![n].into_iter().collect();
+    let expected: Cfg<MfpAnnot<MultiConstLat>> = mfp(&cfg_changed);
+    assert_eq!(mfp_incremental(&prev_changed, &changed), expected);
+    assert_ne!(expected, prev);
+    // Without changes, the previous solution is kept
+    assert_eq!(mfp_incremental(&prev, &std::collections::HashSet::new()), prev);
+}
+
+#[test]
+fn test_eval_aexp_traced() {
+    let mut mem = MemConfig::new();
+    mem.assign(&VarName::new("x"), 4);
+    let (n, steps) = eval_aexp_traced(&parse_aexp("2 + 3 * x"), &mem);
+    assert_eq!(n, 14);
+    let expected: Vec<(AExp, Int)> = vec![("2", 2), ("3", 3), ("x", 4), ("3 * x", 12), ("2 + 3 * x", 14)].into_iter()
+        .map(|(a, n)| (parse_aexp(a), n)).collect();
+    assert_eq!(steps, expected);
+}
+
+#[test]
+fn test_prog_stats() {
+    let prog = parser::parse("proc p do a := 1 end; y := 0; while y <= x do w := 0; while w < y do if w <= 2 then w := w + 1 else call p end end; y := y + 1 end; while 0 < 0 do skip end; z := y").unwrap();
+    let expected = ProgStats { assignments: 6, conditionals: 1, loops: 3, max_loop_depth: 2, vars: 5 };
+    assert_eq!(prog.stats(), expected);
+    assert_eq!(parser::parse("skip").unwrap().stats(), ProgStats::default());
+}
+
+#[test]
+fn test_prog_map_exps() {
+    let prog = parser::parse("proc p do y := y + x end; y := x; if !(x <= 2) then call p else skip end; while y < x*2 do y := y + 1 end; z := y").unwrap();
+    // Rewriting all arithmetic expressions reaches assignments, comparisons and procedure bodies
+    let substituted = prog.map_aexps(|a| a.substitute(&VarName::new("x"), &parse_aexp("3")));
+    let expected = parser::parse("proc p do y := y + 3 end; y := 3; if !(3 <= 2) then call p else skip end; while y < 3*2 do y := y + 1 end; z := y").unwrap();
+    assert_eq!(substituted, expected);
+    // Rewriting all guards keeps the assignments
+    let pushed = prog.map_bexps(|b| b.push_negations());
+    let expected = parser::parse("proc p do y := y + x end; y := x; if 2 < x then call p else skip end; while y < x*2 do y := y + 1 end; z := y").unwrap();
+    assert_eq!(pushed, expected);
+    assert_eq!(prog.map_aexps(|a| a.clone()).map_bexps(|b| b.clone()), prog);
+}
+
+#[test]
+fn test_canonicalize() {
+    let canonical = |s: &str| parser::parse(s).unwrap().canonicalize();
+    assert_eq!(canonical("skip; x := 1; skip"), parser::parse("x := 1").unwrap());
+    // A block keeps a single `skip` if it would become empty otherwise
+    let cond = parser::parse("if x <= 0 then skip else skip end").unwrap();
+    assert_eq!(cond.canonicalize(), cond);
+    assert_eq!(canonical("skip; skip"), parser::parse("skip").unwrap());
+    assert_eq!(canonical("while x < 3 do skip; x := x + 1; skip end; if 0 <= x then skip; skip else skip; z := 1 end"),
+               parser::parse("while x < 3 do x := x + 1 end; if 0 <= x then skip else z := 1 end").unwrap());
+    assert_eq!(canonical("proc p do skip; let y := 1 in skip end end; repeat skip; call p until 0 <= x end"),
+               parser::parse("proc p do let y := 1 in skip end end; repeat call p until 0 <= x end").unwrap());
+}
+
+#[test]
+fn test_repeat() {
+    let repeat = parser::parse("repeat y := y + 1 until 3 <= y end; z := y").unwrap();
+    assert_eq!(repeat.to_string(), "repeat y := y + 1 until 3 <= y end; z := y");
+    // The body is executed once even if the guard holds from the start
+    assert_eq!(eval(&parser::parse("y := 5; repeat y := y + 1 until 3 <= y end; z := y").unwrap(), 0), 6);
+    assert_eq!(eval(&repeat, 0), 3);
+
+    // The body comes before the branch node, which loops back via its `False` edge and leaves via its `True` edge
+    let cfg = cfg::ast_to_cfg(&repeat);
+    let body = cfg.successors(cfg.init)[0];
+    assert_eq!(cfg.graph[body].node, Node::Assign(VarName::new("y"), Box::new(parse_aexp("y + 1"))));
+    let branch = cfg.successors(body)[0];
+    assert!(matches!(cfg.graph[branch].node, Node::Branch(_)));
+    let targets = |e: Edge| cfg.graph.edges_directed(branch, petgraph::Direction::Outgoing).filter(|edge| *edge.weight() == e).map(|edge| edge.target()).collect::<Vec<_>>();
+    assert_eq!(targets(Edge::False), vec![body]);
+    assert_eq!(cfg.graph[targets(Edge::True)[0]].node, Node::Assign(VarName::new("z"), Box::new(parse_aexp("y"))));
+    // The corresponding `while` loop starts with the branch node instead
+    let cfg_while = cfg::ast_to_cfg(&parser::parse("while !(3 <= y) do y := y + 1 end; z := y").unwrap());
+    assert!(matches!(cfg_while.graph[cfg_while.successors(cfg_while.init)[0]].node, Node::Branch(_)));
+
+    // Without a node for the body, the branch node loops to itself
+    let cfg = cfg::ast_to_cfg_with(&parser::parse("repeat skip until 0 <= x end").unwrap(), &cfg::CfgOptions { omit_skips: true, ..Default::default() });
+    let branch = cfg.successors(cfg.init)[0];
+    assert!(cfg.successors(branch).contains(&branch));
+}
+
+#[test]
+fn test_let() {
+    let prog = parser::parse("y := 7; let y := x + 1 in z := y * 2 end; z := z + y").unwrap();
+    assert_eq!(prog.to_string(), "y := 7; let y := x + 1 in z := y*2 end; z := z + y");
+    assert_eq!(eval(&prog, 3), 15);
+    // The scoped variable does not leak out of the block
+    let mut mem = MemConfig::new();
+    mem.assign(&VarName::new("x"), 3);
+    let mem = eval_prog(&parser::parse("let t := x * x in z := t + 1 end").unwrap(), mem);
+    assert_eq!(mem.lookup(&VarName::new("z")), 10);
+    assert!(mem.entries().all(|(v, _)| *v != VarName::new("t")));
+    // Nested blocks restore the binding of the enclosing block, and procedures see the scoped value
+    let nested = parser::parse("proc p do z := z + y end; let y := 1 in let y := 10 in call p end; call p end; call p").unwrap();
+    assert_eq!(eval(&nested, 0), 11);
+
+    // The CFG saves and restores the outer value, so that it agrees with the interpreter and constant propagation stays sound
+    let cfg = cfg::ast_to_cfg(&prog);
+    (-3..=3).for_each(|x| assert_eq!(eval_cfg(&cfg, x), Ok(eval(&prog, x))));
+    assert_eq!(soundness_counterexamples(&prog, -3..=3, 100), vec![]);
+    assert_eq!(soundness_counterexamples(&nested, -3..=3, 100), vec![]);
+    let cfg_mfp: Cfg<MfpAnnot<MultiConstLat>> = mfp(&cfg::ast_to_cfg(&parser::parse("y := 7; let y := x in y := y + 1 end; z := y").unwrap()));
+    let assign_z = cfg_mfp.graph.node_indices().find(|n| cfg_mfp.graph[*n].node == Node::Assign(VarName::new("z"), Box::new(parse_aexp("y")))).unwrap();
+    assert_eq!(*cfg_mfp.graph[assign_z].annot.post().lookup(&VarName::new("z")), ConstLat::Const(7));
+    assert_eq!(*cfg_mfp.graph[assign_z].annot.post().lookup(&VarName::new("y'0")), ConstLat::Const(0));
+
+    // wp(let y := x in y := y + 1 end, y <= 0) = y <= 0
+    let pre = wp(&parser::parse("let y := x in y := y + 1 end").unwrap(), &parse_bexp("y <= 0")).unwrap();
+    assert_eq!(pre, parse_bexp("y <= 0"));
+    let pre = wp(&parser::parse("let y := x in z := y + 1 end").unwrap(), &parse_bexp("z <= y")).unwrap();
+    assert_eq!(pre, parse_bexp("x + 1 <= y"));
+}
+
+#[test]
+fn test_break_continue() {
+    let prog = parser::parse("while z < 10 do z := z + 1; if x <= z then break end end").unwrap();
+    assert_eq!(prog.to_string(), "while z < 10 do z := z + 1; if x <= z then break end end");
+    assert_eq!(eval(&prog, 4), 4);
+    assert_eq!(eval(&prog, 20), 10);
+    // A `continue` skips the rest of the body, also in `repeat` loops, where the guard is tested next
+    let repeat = parser::parse("repeat y := y + 1; if y % 2 <= 0 then continue end; z := z + y until x <= y end").unwrap();
+    assert_eq!(eval(&repeat, 5), 1 + 3 + 5);
+    // A jump out of a `let` block restores the outer value, and only leaves the innermost loop
+    let nested = parser::parse("y := 7; while 0 <= x do let y := x in x := x + -1; if y <= 2 then break end; continue end end; z := y").unwrap();
+    assert_eq!(eval(&nested, 5), 7);
+    let inner = parser::parse("while y < 3 do y := y + 1; while 0 <= 1 do z := z + 1; break end end").unwrap();
+    assert_eq!(eval(&inner, 0), 3);
+
+    // Jumps are only allowed inside loops, but not inside procedure bodies, which cannot leave a loop around the call
+    assert!(parser::parse("break").is_err());
+    assert!(parser::parse("if x <= 0 then continue end").is_err());
+    assert!(parser::parse("while 0 <= x do proc p do break end; call p end").is_err());
+    assert!(parser::parse("breaks := 1").is_ok());
+
+    // The CFG, the three-address code and constant propagation agree with the interpreter
+    let cfg = cfg::ast_to_cfg(&nested);
+    let cfg_mfp: Cfg<MfpAnnot<MultiConstLat>> = mfp(&cfg);
+    let assign_z = cfg_mfp.graph.node_indices().find(|n| cfg_mfp.graph[*n].node == Node::Assign(VarName::new("z"), Box::new(parse_aexp("y")))).unwrap();
+    assert_eq!(*cfg_mfp.graph[assign_z].annot.post().lookup(&VarName::new("z")), ConstLat::Const(7));
+    [&prog, &repeat, &nested, &inner].iter().for_each(|p| {
+        let cfg = cfg::ast_to_cfg(p);
+        let code = to_tac(p);
+        (-3..=8).for_each(|x| {
+            assert_eq!(eval_cfg(&cfg, x), Ok(eval(p, x)), "{} on {}", p, x);
+            assert_eq!(eval_tac(&code, x), eval(p, x), "{} on {}", p, x);
+        });
+        assert_eq!(soundness_counterexamples(p, -3..=8, 100), vec![]);
+        assert_eq!(eliminate_dead_code(p).to_string(), p.to_string());
+    });
+    assert_eq!(itertools::join(to_tac(&inner), "; "),
+               "L0:; ifnot y < 3 goto L1; y = y + 1; L2:; ifnot 0 <= 1 goto L3; z = z + 1; goto L3; goto L2; L3:; goto L0; L1:; halt");
+}
+
+#[test]
+fn test_tac() {
+    let tac = |s: &str| itertools::join(to_tac(&parser::parse(s).unwrap()), "; ");
+    assert_eq!(tac("z := (x + 1) * 2"), "t0 = x + 1; z = t0 * 2; halt");
+    assert_eq!(tac("y := x; z := y + x*x^2"), "y = x; t0 = x ^ 2; t1 = x * t0; z = y + t1; halt");
+    // Boolean expressions become conditional jumps
+    assert_eq!(tac("while x < 3 && (!(2*x <= 4)) do x := x + 1 end"),
+               "L0:; ifnot x < 3 goto L1; t0 = 2 * x; if t0 <= 4 goto L1; x = x + 1; goto L0; L1:; halt");
+    assert_eq!(tac("if x <= 0 || 5 <= x then z := 1 end"),
+               "if x <= 0 goto L2; ifnot 5 <= x goto L0; L2:; z = 1; goto L1; L0:; L1:; halt");
+    assert_eq!(tac("proc p do z := z + 1 end; call p; call p"), "call p; call p; halt; proc p:; z = z + 1; return");
+
+    // The evaluator agrees with the interpreter
+    [
+        "if x <= 0 || 5 <= x then z := 1 else z := 2 end",
+        "if !(x <= 0 && x < 5) then z := 1 else z := 2 end",
+        "y := 7; let y := x + 1 in z := y * 2 end; z := z + y",
+        "repeat x := x + 2; z := z + x % 3 until 10 <= x end",
+        "proc f do if 1 <= x then x := x + -1; z := z + 2; call f end end; call f",
+        // Every activation of the recursive procedure restores its own outer value of `y`
+        "proc f do let y := y + 1 in if y <= 3 then call f end end end; call f; z := y",
+        "proc f do let y := y + 1 in z := z + y; if y <= 3 then call f end end; z := z * y end; call f",
+    ].iter().for_each(|s| {
+        let prog = parser::parse(s).unwrap();
+        let code = to_tac(&prog);
+        (-3..=8).for_each(|x| assert_eq!(eval_tac(&code, x), eval(&prog, x), "{} on {}", s, x));
+    });
+    assert_eq!(to_tac(&parser::parse("skip").unwrap()), vec![TacInstr::Halt]);
+    assert_eq!(tac("let y := x + 1 in z := y end"), "push y; y = x + 1; z = y; pop y; halt");
+}
+
+#[test]
+fn test_inline_single_use() {
+    let inline = |s: &str| inline_single_use(&parser::parse(s).unwrap()).to_string();
+    assert_eq!(inline("t := x + 1; z := t * 2"), "z := (x + 1)*2");
+    let prog = parser::parse(&read_to_string("tests-res/single_use/prog.while").unwrap()).unwrap();
+    let expected = read_to_string("tests-res/single_use/inlined.while").unwrap();
+    assert_eq!(inline_single_use(&prog).to_string(), expected.trim());
+    // Chains of temporaries are inlined one after the other, also within sub-programs
+    assert_eq!(inline("if 0 <= x then a := x + 1; b := a*a; z := b + 1 end"), "if 0 <= x then a := x + 1; z := a*a + 1 end");
+    assert_eq!(inline("while x <= 3 do a := x + 1; x := a end; z := x"), "while x <= 3 do x := x + 1 end; z := x");
+    // `t` is still live after `z := t * 2`, but the first `z` can be inlined into the second one
+    assert_eq!(inline("t := x + 1; z := t * 2; z := z + t"), "t := x + 1; z := t*2 + t");
+
+    // Nothing is inlined if ...
+    [
+        // ... an operand is redefined in between
+        "t := x + 1; x := 5; z := t * 2",
+        // ... the variable is read twice
+        "t := x + 1; z := t * t",
+        // ... the variable is still live afterwards
+        "t := x + 1; y := t; while y <= 3 do y := y + t end; z := y",
+        // ... the use is not the next assignment that mentions the variable
+        "t := x + 1; if x <= 0 then z := t end",
+        "t := x + 1; let y := 2 in z := t * y end",
+        // ... a procedure may read the variable
+        "proc p do z := t end; t := x + 1; call p; y := t",
+    ].iter().for_each(|s| assert_eq!(inline(s), parser::parse(s).unwrap().to_string(), "{}", s));
+}
+
+#[test]
+fn test_optimize() {
+    let p = |s: &str| parser::parse(s).unwrap();
+    let optimized = |s: &str| optimize(&p(s)).to_string();
+    // Folding `x % 1` exposes the constant `y`, which only the second round propagates into `z`
+    let prog = p("y := x % 1; z := y + 1");
+    assert_eq!(eliminate_dead_code(&fold_constants(&propagate_constants(&prog))).to_string(), "y := 0; z := y + 1");
+    assert_eq!(optimize(&prog).to_string(), "z := 1");
+    // Propagating into a guard resolves the conditional, so that `w` becomes constant afterwards
+    assert_eq!(optimized("y := x % 1; if y <= 0 then w := 2 else w := x end; z := w * w"), "z := 4");
+    assert_eq!(optimized("y := x^0; while y < 1 do y := x end; repeat x := x + y until 0 < 1 end; z := x + 0"), "x := x + 1; z := x");
+    // Loops with a changing guard and procedures are kept
+    assert_eq!(optimized("y := 1; while x <= 3 do x := x + y end; z := x*1"), "while x <= 3 do x := x + 1 end; z := x");
+    assert_eq!(optimized("proc p do y := y + 1; z := y*2 + 0 end; y := 1; call p; w := 3"), "proc p do y := y + 1; z := y*2 end; y := 1; call p");
+
+    // The single transformations
+    assert_eq!(propagate_constants(&p("y := 2; if x <= y then y := 3 end; z := y + x")).to_string(), "y := 2; if x <= 2 then y := 3 end; z := y + x");
+    assert_eq!(propagate_constants(&p("y := 2; while x <= 3 do x := x + y end; z := y")).to_string(), "y := 2; while x <= 3 do x := x + 2 end; z := 2");
+    assert_eq!(propagate_constants(&p("y := 2; let y := x in z := y end; z := z + y")).to_string(), "y := 2; let y := x in z := y end; z := z + 2");
+    assert_eq!(fold_constants(&p("z := (x + 2*0)*(1 + 2); if x^1 <= 2^3 then z := 5 % 0 end")).to_string(), "z := x*3; if x <= 8 then z := 5%0 end");
+    assert_eq!(eliminate_dead_code(&p("y := 1; if 0 <= 1 then skip; z := 2 else z := y end; while 1 < 0 do z := 3 end")).to_string(), "z := 2");
+    assert_eq!(remove_dead_assignments(&p("a := 1; b := a; y := x; z := y")).to_string(), "y := x; z := y");
+    assert_eq!(remove_dead_assignments(&p("if x <= 0 then y := 1 end")).to_string(), "if x <= 0 then skip end");
+}
+
+#[test]
+fn test_aexp_folded() {
+    let a = |s: &str| parser::parse_aexp(s).unwrap();
+    assert_eq!(a("2*3 + 4^2 % 5").folded(), AExp::Num(7));
+    assert_eq!(a("(x + 0)*1 + y*(0*z)").folded(), a("x"));
+    assert_eq!(a("(x*y)^0 + x % 1 + x^1").folded(), a("1 + x"));
+    // Undefined operations are kept
+    assert_eq!(a("x + 2 % 0").folded(), a("x + 2 % 0"));
+    assert_eq!(a("2^-1").folded(), a("2^-1"));
+}
+
+#[test]
+fn test_havoc() {
+    let prog = parser::parse(&read_to_string("tests-res/havoc/prog.while").unwrap()).unwrap();
+    let x = VarName::new("x");
+    let Prog::Prog(ps) = &prog;
+    assert_eq!(ps[3], ProgAtom::Havoc(x.clone()));
+    assert_eq!(ps[3].to_string(), "havoc x");
+    assert_eq!(parser::parse(&prog.to_source()).unwrap(), prog);
+    // The interpreter picks `0`
+    assert_eq!(eval(&prog, 7), 5);
+
+    // Const prop loses the value of `x` at the `havoc`, but keeps the value of `y`
+    let cfg = cfg::ast_to_cfg(&prog);
+    let havoc = cfg.graph.node_indices().find(|n| cfg.graph[*n].node == Node::Havoc(x.clone())).unwrap();
+    let cfg_const_prop: Cfg<MfpAnnot<MultiConstLat>> = mfp(&cfg);
+    assert_eq!(*cfg_const_prop.graph[havoc].annot.pre().lookup(&x), ConstLat::Const(3));
+    assert_eq!(*cfg_const_prop.graph[havoc].annot.post().lookup(&x), ConstLat::Top);
+    assert_eq!(*cfg_const_prop.graph[havoc].annot.post().lookup(&VarName::new("y")), ConstLat::Const(4));
+    // Available expressions containing `x` are killed
+    let cfg_avail_exp: Cfg<MfpAnnot<ExpSetLat>> = mfp(&cfg);
+    assert!(cfg_avail_exp.graph[havoc].annot.pre().contains(&parse_aexp("x + 1")));
+    assert!(!cfg_avail_exp.graph[havoc].annot.post().contains(&parse_aexp("x + 1")));
+    assert!(cfg_avail_exp.graph[havoc].annot.post().contains(&parse_aexp("y + 1")));
+    // `havoc x` kills `x`, so `x := 3` is needed only for `y`
+    let cfg_live: Cfg<MfpAnnot<LiveVarsLat>> = mfp_backward(&cfg);
+    assert!(!cfg_live.graph[havoc].annot.pre().contains(&x));
+    assert!(cfg_live.graph[havoc].annot.post().contains(&x));
+    assert_eq!(optimize(&prog).to_string(), "havoc x; z := x + 4 + 1");
+
+    // There is no weakest precondition without quantifiers
+    assert_eq!(wp(&prog, &parser::parse_bexp("z <= 5").unwrap()), None);
+    let json = cfg::to_portable_json(&cfg);
+    assert!(json.contains("\"kind\": \"havoc\""));
+    assert_eq!(cfg::from_portable_json::<RawAnnot>(&json).unwrap(), cfg);
+}
+
+#[test]
+fn test_to_mermaid() {
+    let cfg = cfg::ast_to_cfg(&parser::parse("if x <= 0 then z := 1 else call p end; proc p do z := 2 end").unwrap());
+    let mermaid = cfg::to_mermaid(&cfg);
+    let lines: Vec<&str> = mermaid.lines().collect();
+    assert_eq!(lines[0], "flowchart TD");
+    [
+        "    n0([\"init\"])",
+        "    n1{\"x #60;= 0\"}",
+        "    n2[\"z := 1\"]",
+        "    n3[[\"call p\"]]",
+        "    n0 --> n1",
+        "    n1 -->|tt| n2",
+        "    n1 -->|ff| n3",
+        "    n3 -.->|call| n4",
+    ].iter().for_each(|line| assert!(lines.contains(line), "{} is missing in {}", line, mermaid));
+    assert_eq!(lines.len(), 1 + cfg.graph.node_count() + cfg.graph.edge_count());
+    // Multi-line annotations become line breaks, and the brackets of the memory are escaped
+    let cfg_const_prop: Cfg<MfpAnnot<MultiConstLat>> = mfp(&cfg::ast_to_cfg(&parser::parse("z := 1").unwrap()));
+    assert!(cfg::to_mermaid(&cfg_const_prop).contains("    n1[\"z := 1<br>pre: #60;x = tt, _ = 0#62;<br>post: #60;x = tt, z = 1, _ = 0#62;\"]"));
+}
+
+#[test]
+fn test_correlated_branches() {
+    let cfg: Cfg<RawAnnot> = read_fixture("tests-res/branch_correlation", "cfg");
+    let branches: Vec<NodeIdx> = cfg.graph.node_indices().filter(|n| matches!(cfg.graph[*n].node, Node::Branch(_))).collect();
+    assert_eq!(correlated_branches(&cfg), vec![(branches[0], branches[1])]);
+    let pairs = |s: &str| {
+        let cfg = cfg::ast_to_cfg(&parser::parse(s).unwrap());
+        correlated_branches(&cfg).into_iter().map(|(b1, b2)| (cfg.graph[b1].node.to_string(), cfg.graph[b2].node.to_string())).collect::<Vec<_>>()
+    };
+    let pair = |b1: &str, b2: &str| vec![(b1.to_string(), b2.to_string())];
+    // Implied guards are correlated, too (here only if the first guard holds)
+    assert_eq!(pairs("if x <= 0 then y := 1 end; if x < 5 then z := y end"), pair("x <= 0", "x < 5"));
+    assert_eq!(pairs("if x <= 0 then y := 1 end; if 0 < x then z := y end"), pair("x <= 0", "0 < x"));
+    // Assigning to `x` (on every path) or calling a procedure in between invalidates the correlation
+    assert_eq!(pairs("if x <= 0 then y := 1 end; x := y; if x <= 0 then z := y end"), vec![]);
+    assert_eq!(pairs("if x <= 0 then x := 1 end; if x <= 0 then z := y end"), pair("x <= 0", "x <= 0"));
+    assert_eq!(pairs("if x <= 0 then call p end; if x <= 0 then z := y end; proc p do skip end"), pair("x <= 0", "x <= 0"));
+    // Uncorrelated and constant guards
+    assert_eq!(pairs("if x <= 0 then y := 1 end; if y <= 0 then z := y end"), vec![]);
+    assert_eq!(pairs("if x <= 0 then y := 1 end; if 1 <= 2 then z := y end"), vec![]);
+    // A loop guard is not correlated with itself
+    assert_eq!(pairs("while x <= 0 do y := 1 end"), vec![]);
+}
+
+#[test_resources("tests-res/*")]
+fn test_ssa_single_assignment(name: &str) {
+    let cfg: Cfg<RawAnnot> = read_fixture(name, "cfg");
+    let ssa = to_ssa(&cfg);
+    let mut assigned = std::collections::HashSet::new();
+    for n in ssa.graph.node_indices() {
+        if let Node::Assign(x, _) | Node::Havoc(x) | Node::Phi(x, _) = &ssa.graph[n].node {
+            assert!(assigned.insert(x.clone()), "{} is assigned twice", x);
+        }
+    }
+    // Only phi nodes are added
+    assert_eq!(ssa.graph.node_count() - cfg.graph.node_count(), ssa.graph.node_indices().filter(|n| matches!(ssa.graph[*n].node, Node::Phi(_, _))).count());
+    assert_eq!(to_ssa(&cfg), ssa);
+}
+
+#[test]
+fn test_to_ssa() {
+    let ssa = |s: &str| to_ssa(&cfg::ast_to_cfg(&parser::parse(s).unwrap()));
+    let nodes = |cfg: &Cfg<RawAnnot>| cfg.graph.node_indices().map(|n| cfg.graph[n].node.to_string()).collect::<Vec<_>>();
+    // A diamond: `y` is assigned differently on both branches, so a phi node is inserted at the join point
+    let cfg = cfg::ast_to_cfg(&parser::parse("if x <= 0 then y := 1 else y := 2 end; z := y").unwrap());
+    let diamond = to_ssa(&cfg);
+    assert_eq!(nodes(&diamond), vec!["init", "x <= 0", "y_1 := 1", "y_2 := 2", "z_1 := y_3", "y_3 := phi(y_1, y_2)"]);
+    let phi = diamond.graph.node_indices().find(|n| matches!(diamond.graph[*n].node, Node::Phi(_, _))).unwrap();
+    let find = |s: &str| diamond.graph.node_indices().find(|n| diamond.graph[*n].node.to_string() == s).unwrap();
+    assert_eq!(diamond.graph[phi].node, Node::Phi(VarName::new("y_3"), vec![(find("y_1 := 1"), VarName::new("y_1")), (find("y_2 := 2"), VarName::new("y_2"))]));
+    assert_eq!(diamond.graph.neighbors_directed(phi, petgraph::Direction::Incoming).count(), 2);
+    assert_eq!(diamond.graph.neighbors(phi).collect::<Vec<_>>(), vec![find("z_1 := y_3")]);
+    // The dominance frontier of both branches is the join point
+    let frontiers = cfg.dominance_frontiers();
+    assert_eq!(frontiers[&find("y_1 := 1")], vec![find("z_1 := y_3")].into_iter().collect());
+    assert!(frontiers[&find("x <= 0")].is_empty());
+    // The phi node selects the version of the branch that was taken, which the final version of `z` holds
+    let (_, exit_versions) = to_ssa_with_exit_versions(&cfg);
+    assert_eq!(exit_versions.get(&VarName::new("z")), Some(&VarName::new("z_1")));
+    assert_eq!(exit_versions.get(&VarName::new("x")), None);
+    let output = |x: Int| {
+        let mut mem = MemConfig::new();
+        mem.assign(&VarName::new("x"), x);
+        eval_cfg_mem(&diamond, mem).unwrap().lookup(&exit_versions[&VarName::new("z")])
+    };
+    assert_eq!(output(-1), 1);
+    assert_eq!(output(1), 2);
+    assert_eq!(cfg::from_portable_json::<RawAnnot>(&cfg::to_portable_json(&diamond)).unwrap(), diamond);
+
+    // A loop: The phi node at the loop head joins the value before the loop with the one of the previous iteration
+    assert_eq!(nodes(&ssa("y := 0; while y <= x do y := y + 1 end; z := y")),
+        vec!["init", "y_1 := 0", "y_2 <= x", "y_3 := y_2 + 1", "z_1 := y_2", "y_2 := phi(y_1, y_3)"]);
+    // Variables that are not assigned keep their names, and a straight-line program needs no phi nodes
+    assert_eq!(nodes(&ssa("y := x; y := y + x")), vec!["init", "y_1 := x", "y_2 := y_1 + x"]);
+    // Several phi nodes form a chain
+    let chain = ssa("if x <= 0 then y := 1; z := 1 else y := 2 end; w := y + z");
+    assert_eq!(nodes(&chain).iter().filter(|n| n.contains("phi")).cloned().collect::<Vec<_>>(), vec!["y_3 := phi(y_1, y_2)", "z_2 := phi(z_1, z)"]);
+}
+
+#[test]
+fn test_reduced_product() {
+    let prog = parser::parse(&read_to_string("tests-res/reduced_product/prog.while").unwrap()).unwrap();
+    let cfg = cfg::ast_to_cfg(&prog);
+    let z = VarName::new("z");
+    let last = cfg.graph.node_indices().next_back().unwrap();
+    // Neither component proves `z = 5` on its own
+    let cfg_const_prop: Cfg<MfpAnnot<MultiConstLat>> = mfp(&cfg);
+    assert_eq!(*cfg_const_prop.graph[last].annot.post().lookup(&z), ConstLat::Top);
+    let cfg_sign: Cfg<MfpAnnot<MultiSignLat>> = mfp(&cfg);
+    assert_eq!(*cfg_sign.graph[last].annot.post().lookup(&z), SignLat::Pos);
+    // Without a reduction, the product is just as precise as its components
+    let cfg_product: Cfg<MfpAnnot<ProductLat<MultiConstLat, MultiSignLat>>> = mfp(&cfg);
+    assert_eq!(*cfg_product.graph[last].annot.post().fst.lookup(&z), ConstLat::Top);
+    assert_eq!(*cfg_product.graph[last].annot.post().snd.lookup(&z), SignLat::Pos);
+    // The signs of both guards imply `x = 0`, which is propagated as a constant
+    let cfg_reduced: Cfg<MfpAnnot<ConstSignLat>> = mfp(&cfg);
+    let post = cfg_reduced.graph[last].annot.post();
+    assert_eq!(*post.fst.lookup(&z), ConstLat::Const(5));
+    assert_eq!(*post.snd.lookup(&z), SignLat::Pos);
+    // A constant of the wrong sign is contradictory
+    let p = ConstSignLat::init_start();
+    assert_eq!(ConstSignLat::new(p.fst.clone(), p.snd.assume(&parser::parse_bexp("y < 0").unwrap(), true)), ConstSignLat::init());
+}
+
+#[test]
+fn test_tracked_lat() {
+    let cfg = cfg::ast_to_cfg(&parser::parse("if x <= 0 then y := 1 else y := 2 end; skip; z := y + 1").unwrap());
+    let node = |label: &str| cfg.graph.node_indices().find(|n| cfg.graph[*n].node.to_string() == label).unwrap();
+    let cfg_tracked: Cfg<MfpAnnot<Tracked<MultiConstLat>>> = mfp(&cfg);
+    // The values are those of the wrapped analysis
+    let cfg_const_prop: Cfg<MfpAnnot<MultiConstLat>> = mfp(&cfg);
+    assert_eq!(cfg_tracked.map(|a| MfpAnnot::new(a.pre().value.clone(), a.post().value.clone())), cfg_const_prop);
+    // The joined value's provenance includes both branches, but not the `skip`, which does not change the value
+    let joined = cfg_tracked.graph[node("skip")].annot.pre();
+    assert_eq!(*joined.value.lookup(&VarName::new("y")), ConstLat::Top);
+    assert_eq!(joined.provenance, vec![node("y := 1"), node("y := 2")].into_iter().collect());
+    let post = cfg_tracked.graph[node("z := y + 1")].annot.post();
+    assert_eq!(post.provenance, vec![node("y := 1"), node("y := 2"), node("z := y + 1")].into_iter().collect());
+    assert_eq!(Tracked::new(ConstLat::Const(1)).join_bin(&Tracked::new(ConstLat::Bot)), Tracked::new(ConstLat::Const(1)));
+    assert_eq!(post.to_string(), format!("{} from {{{}, {}, {}}}", post.value, node("y := 1").index(), node("y := 2").index(), node("z := y + 1").index()));
+}
+
+#[test]
+fn test_sign_lat() {
+    assert_eq!(SignLat::of(-3), SignLat::Neg);
+    assert_eq!(SignLat::Neg.join_bin(&SignLat::Zero), SignLat::NonPos);
+    assert_eq!(SignLat::NonPos.meet(&SignLat::NonNeg), SignLat::Zero);
+    assert!(SignLat::NonZero.contains(5) && !SignLat::NonZero.contains(0));
+    let mut m = MultiSignLat::init_start();
+    m.insert(VarName::new("y"), SignLat::Pos);
+    let eval = |s: &str| m.eval_aexp(&parser::parse_aexp(s).unwrap());
+    // Sums and products may wrap around
+    assert_eq!(eval("y + 1"), SignLat::NonZero);
+    assert_eq!(eval("y * z"), SignLat::Zero);
+    assert_eq!(eval("y * y"), SignLat::Top);
+    assert_eq!(eval("x % y"), SignLat::NonNeg);
+    assert_eq!(eval("x ^ 0"), SignLat::Pos);
+    let assume = |s: &str, truth| m.assume(&parser::parse_bexp(s).unwrap(), truth);
+    assert_eq!(*assume("x < 0", false).lookup(&VarName::new("x")), SignLat::NonNeg);
+    assert_eq!(*assume("x <= 0 && 0 <= x", true).lookup(&VarName::new("x")), SignLat::Zero);
+    assert_eq!(*assume("x <= y", false).lookup(&VarName::new("x")), SignLat::Pos);
+    assert!(assume("y <= 0", true).is_bot());
+    assert!(assume("1 <= -1", true).is_bot());
+    assert_eq!(m.to_string(), "<x = tt, y = >0, _ = 0>");
+}
+
+#[test]
+fn test_affine() {
+    let prog = parser::parse(&read_to_string("tests-res/affine/prog.while").unwrap()).unwrap();
+    let cfg = cfg::ast_to_cfg(&prog);
+    let (x, y, z) = (VarName::new("x"), VarName::new("y"), VarName::new("z"));
+    let last = cfg.graph.node_indices().next_back().unwrap();
+    // Constant propagation only knows that `y` and `z` depend on the input
+    let cfg_const_prop: Cfg<MfpAnnot<MultiConstLat>> = mfp(&cfg);
+    assert_eq!(*cfg_const_prop.graph[last].annot.post().lookup(&y), ConstLat::Top);
+    let cfg_affine: Cfg<MfpAnnot<MultiAffineLat>> = mfp(&cfg);
+    let post = cfg_affine.graph[last].annot.post();
+    assert_eq!(*post.lookup(&y), AffineLat::Affine(AffineForm::var(&x).scale(2)));
+    assert_eq!(*post.lookup(&z), AffineLat::Affine(AffineForm::var(&x).scale(2).add(&AffineForm::constant(1))));
+    assert_eq!(post.to_string(), "<x = x, y = 2*x, z = 2*x + 1, _ = 0>");
+    let mut m = MultiAffineLat::init_start();
+    m.insert(y.clone(), AffineLat::Affine(AffineForm::var(&x).scale(-1)));
+    let eval = |s: &str| m.eval_aexp(&parser::parse_aexp(s).unwrap()).to_string();
+    assert_eq!(eval("x + y"), "0");
+    assert_eq!(eval("3 * (y + -1)"), "-3*x - 3");
+    assert_eq!(eval("x * y"), "tt");
+    assert_eq!(eval("(z + 7) % 4"), "3");
+    // Different forms are incomparable
+    assert_eq!(AffineLat::Affine(AffineForm::var(&x)).join_bin(&AffineLat::constant(0)), AffineLat::Top);
+}
+
+#[test_resources("tests-res/*")]
+fn test_parse_with_spans_covers_atoms(name: &str) {
+    let input: String = read_to_string(format!("{:}/prog.while", name)).unwrap();
+    let (prog, spans) = parser::parse_with_spans(&input).unwrap();
+    assert_eq!(spans.len(), prog.atoms().len());
+    // The atoms are in source order
+    assert!(spans.windows(2).all(|w| w[0].start < w[1].start));
+    assert!(spans.iter().all(|span| span.start <= span.end));
+}
+
+#[test]
+fn test_parse_with_spans() {
+    let pos = |line, column| Position { line, column };
+    let span = |start, end| Span { start, end };
+    let source = "# comment\n  y := 1;  # y\nif x <= 0 then\n  havoc y\nend";
+    let (prog, spans) = parser::parse_with_spans(source).unwrap();
+    assert_eq!(prog, parser::parse(source).unwrap());
+    assert_eq!(spans, vec![
+        span(pos(2, 3), pos(2, 9)),
+        span(pos(3, 1), pos(5, 4)),
+        span(pos(4, 3), pos(4, 10)),
+        // The implicit else-branch
+        span(pos(5, 1), pos(5, 1))
+    ]);
+    let atoms: Vec<String> = prog.atoms().iter().map(|p| p.to_source()).collect();
+    assert_eq!(atoms, vec!["y := 1", "if x <= 0 then havoc y end", "havoc y", "skip"]);
+    assert!(parser::parse_with_spans("y := ").is_err());
+}
+
+#[test]
+fn test_parse_with_comments() {
+    let source = "# Doubling\ny := 1; # start\nwhile y <= x do # grow\n  # double y\n  y := y + y\nend;\nif x <= 0 then\n  z := y\n  # no else\nend # done\n# the end";
+    let (prog, comments) = parser::parse_with_comments(source).unwrap();
+    assert_eq!(prog, parser::parse(source).unwrap());
+    let text = |s: &str| s.to_string();
+    // The atoms are `y := 1`, the loop, `y := y + y`, the conditional, `z := y` and the implicit else-branch
+    let expected = Comments {
+        leading: vec![(0, vec![text(" Doubling")]), (2, vec![text(" grow"), text(" double y")]), (5, vec![text(" no else")])].into_iter().collect(),
+        trailing: vec![(0, text(" start")), (3, text(" done"))].into_iter().collect(),
+        end: vec![text(" the end")]
+    };
+    assert_eq!(comments, expected);
+    // Reformatting keeps the comments, and the implicit else-branch becomes explicit to keep its comment
+    let reformatted = prog.to_source_with_comments(&comments);
+    assert_eq!(reformatted, "# Doubling\ny := 1; # start\nwhile y <= x do\n  # grow\n  # double y\n  y := y + y end; if x <= 0 then z := y else\n  # no else\n  skip end # done\n# the end");
+    assert_eq!(parser::parse_with_comments(&reformatted).unwrap(), (prog.clone(), comments));
+    // Comments are indented like the statements of their block, and a block ending with a comment puts `end` on a line of its own
+    let nested = "while 0 <= x do\n  # loop\n  if x <= 0 then\n    # inner\n    y := 1 # one\n  end; x := x + -1 # dec\nend";
+    let (prog_nested, comments_nested) = parser::parse_with_comments(nested).unwrap();
+    assert_eq!(prog_nested.to_source_with_comments(&comments_nested), nested);
+    // Without comments, the source is the usual one
+    assert_eq!(prog.to_source_with_comments(&Comments::default()), prog.to_source());
+    assert!(parser::parse_with_comments("y := # 1").is_err());
+}
+
+#[test]
+fn test_highlight_consts() {
+    let source = "y := 1;\nif x <= 0 then\n  y := 2;\n  w := 3\nend;\nw := y + 1; y := 5";
+    let expected = "y := 1; # const = 1\nif x <= 0 then\n  y := 2; # const = 2\n  w := 3 # const = 2\nend;\nw := y + 1; y := 5 # const = 5";
+    assert_eq!(highlight_consts(source, &VarName::new("y")).unwrap(), expected);
+    assert_eq!(run_cli(&["--highlight", "y"], source), format!("{}\n", expected));
+    // Only the outermost statement ending on a line counts
+    let source = "while x <= 0 do y := 1; x := x + 1 end";
+    assert_eq!(highlight_consts(source, &VarName::new("x")).unwrap(), source);
+    assert_eq!(highlight_consts(source, &VarName::new("y")).unwrap(), source);
+    let after = consts_after_atoms(&parser::parse(source).unwrap());
+    assert_eq!(*after[1].lookup(&VarName::new("y")), ConstLat::Const(1));
+}
+
+#[test]
+fn test_uninit_reads() {
+    let prog = parser::parse(&read_to_string("tests-res/uninit_branch/prog.while").unwrap()).unwrap();
+    let cfg = cfg::ast_to_cfg(&prog);
+    let expected: Vec<(VarName, String, UninitLat)> = read_fixture("tests-res/uninit_branch", "uninit_reads");
+    let actual: Vec<(VarName, String, UninitLat)> = uninit_reads(&prog).into_iter().map(|(x, n, v)| (x, cfg.graph[n].node.to_string(), v)).collect();
+    assert_eq!(actual, expected);
+    let p = |s: &str| parser::parse(s).unwrap();
+    assert_eq!(uninit_reads(&p("y := 1; z := y + x")), vec![]);
+    // Unlike the defined variables analysis, reading `y` is reported as well, as it holds a value computed from `w`
+    assert_eq!(undefined_reads(&p("y := w; z := y")).len(), 1);
+    assert_eq!(uninit_reads(&p("y := w; z := y")).len(), 2);
+    // `havoc` initializes
+    assert_eq!(uninit_reads(&p("havoc y; z := y")), vec![]);
+    // The nodes saving and restoring the outer value of a `let` variable are not part of the program
+    assert_eq!(uninit_reads(&p("let y := 1 in z := y end")), vec![]);
+    assert_eq!(undefined_reads(&p("let y := 1 in z := y end")), vec![]);
+    let reads: Vec<String> = uninit_reads(&p("let y := w in z := y end")).into_iter().map(|(x, _, _)| x.to_string()).collect();
+    assert_eq!(reads, vec!["w", "y"]);
+
+    // Uninitialized values propagate through arithmetic, unlike in constant propagation, where they are `0`
+    let cfg_uninit: Cfg<MfpAnnot<MultiUninitLat>> = mfp(&cfg);
+    let last = cfg_uninit.graph.node_indices().next_back().unwrap();
+    let post = cfg_uninit.graph[last].annot.post();
+    assert_eq!(*post.lookup(&VarName::new("v")), UninitLat::Uninit);
+    assert_eq!(*post.lookup(&VarName::new("z")), UninitLat::Uninit);
+    assert_eq!(*post.lookup(&VarName::new("y")), UninitLat::Top);
+    assert_eq!(*post.lookup(&VarName::new("x")), UninitLat::Init);
+    let cfg_const_prop: Cfg<MfpAnnot<MultiConstLat>> = mfp(&cfg);
+    assert_eq!(*cfg_const_prop.graph[last].annot.post().lookup(&VarName::new("v")), ConstLat::Const(1));
+
+    // The lattice
+    let (c1, c2) = (UninitLat::Const(1), UninitLat::Const(2));
+    assert_eq!(UninitLat::Bot.join_bin(&UninitLat::Uninit), UninitLat::Uninit);
+    assert_eq!(c1.join_bin(&c1), c1);
+    assert_eq!(c1.join_bin(&c2), UninitLat::Init);
+    assert_eq!(c1.join_bin(&UninitLat::Uninit), UninitLat::Top);
+    assert_eq!(UninitLat::Init.join_bin(&UninitLat::Uninit), UninitLat::Top);
+    assert_eq!(MultiUninitLat::init_start().eval_aexp(&parse_aexp("(x + 1)*2")), UninitLat::Init);
+    assert_eq!(MultiUninitLat::init_start().eval_aexp(&parse_aexp("x*0 + y")), UninitLat::Uninit);
+    assert_eq!(MultiUninitLat::init_start().eval_aexp(&parse_aexp("2^3 % 5")), UninitLat::Const(3));
+    assert_eq!(MultiUninitLat::init_start().to_string(), "<x = init, _ = uninit>");
+}
+
+#[test]
+fn test_portable_json_format() {
+    let cfg = cfg::ast_to_cfg(&parser::parse("y := 1; if y <= x then call p end; proc p do skip end").unwrap());
+    let value: serde_json::Value = serde_json::from_str(&cfg::to_portable_json(&cfg)).unwrap();
+    let node = |i: usize| &value["nodes"][i];
+    assert_eq!(node(0)["kind"], "init");
+    assert_eq!(node(1)["kind"], "assign");
+    assert_eq!(node(1)["var"], "y");
+    assert_eq!(node(1)["text"], "y := 1");
+    assert_eq!(node(2)["kind"], "branch");
+    assert!(node(2).get("var").is_none());
+    assert_eq!(node(3)["kind"], "call");
+    assert_eq!(node(3)["name"], "p");
+    assert_eq!(value["edges"][0], serde_json::json!({ "from": 0, "to": 1, "label": "plain" }));
+    assert_eq!(value["edges"][2]["label"], "true");
+
+    // Malformed input is rejected
+    let parse = |s: &str| cfg::from_portable_json::<RawAnnot>(s);
+    assert!(parse("{}").unwrap_err().starts_with("The portable JSON is malformed"));
+    let init = r#"{ "id": 0, "kind": "init", "text": "", "annot": {} }"#;
+    assert_eq!(parse(&format!(r#"{{ "init": 0, "nodes": [{}, {{ "id": 1, "kind": "assign", "var": "y", "text": "", "annot": {{}} }}], "edges": [] }}"#, init)).unwrap_err(), "The assign node 1 has no expr.");
+    assert_eq!(parse(&format!(r#"{{ "init": 0, "nodes": [{}, {{ "id": 2, "kind": "skip", "text": "", "annot": {{}} }}], "edges": [] }}"#, init)).unwrap_err(), "The node ids are not consecutive: 1 is missing.");
+    assert_eq!(parse(&format!(r#"{{ "init": 0, "nodes": [{}], "edges": [{{ "from": 0, "to": 1, "label": "plain" }}] }}"#, init)).unwrap_err(), "The edge 0 -> 1 refers to a node that does not exist.");
+    assert_eq!(parse(&format!(r#"{{ "init": 0, "nodes": [{}], "edges": [{{ "from": 0, "to": 0, "label": "maybe" }}] }}"#, init)).unwrap_err(), "The edge 0 -> 0 has the unknown label maybe.");
+    assert!(parse(&format!(r#"{{ "init": 1, "nodes": [{}], "edges": [] }}"#, init)).is_err());
+}
+
+#[test]
+fn test_modulo() {
+    let x = || Box::new(AExp::Var(VarName::new("x")));
+    let n = |n| Box::new(AExp::Num(n));
+    // `%` binds like `*` and associates to the left
+    assert_eq!(parse_aexp("x * 3 % 2 + 1"), AExp::Add(Box::new(AExp::Mod(Box::new(AExp::Mul(x(), n(3))), n(2))), n(1)));
+    // Pretty-printing keeps the structure
+    ["x*(3%2)", "x%(3*2)", "x%(3%2)", "x%3*2", "(x + 1)%-2"].iter().for_each(|a| {
+        assert_eq!(parse_aexp(a).to_string(), *a);
+        assert_eq!(parse_aexp(&parse_aexp(a).to_string()), parse_aexp(a));
+    });
+    let mut mem = MemConfig::new();
+    mem.assign(&VarName::new("x"), -7);
+    assert_eq!(flanelly::interpreter::eval_aexp(&parse_aexp("x % 3"), &mem), 2);
+    assert_eq!(flanelly::interpreter::eval_aexp(&parse_aexp("x % 0"), &mem), -7);
+    let cp = MultiConstLat::init_start();
+    assert_eq!(cp.eval_aexp_with(&parse_aexp("7 % 0"), AbsorbPolicy::Never), ConstLat::Top);
+}
+
+#[test]
+fn test_pow() {
+    let n = |n| Box::new(AExp::Num(n));
+    let x = || Box::new(AExp::Var(VarName::new("x")));
+    // `^` binds stronger than `*` and associates to the right
+    assert_eq!(parse_aexp("2 * x ^ 3"), AExp::Mul(n(2), Box::new(AExp::Pow(x(), n(3)))));
+    assert_eq!(parse_aexp("2^3^2"), AExp::Pow(n(2), Box::new(AExp::Pow(n(3), n(2)))));
+    ["(2^3)^2", "2^3^2", "(x + 1)^2", "x^(2*3)", "x*2^3", "(x*2)^3", "-2^2"].iter().for_each(|a| {
+        assert_eq!(parse_aexp(a).to_string(), *a);
+        assert_eq!(parse_aexp(&parse_aexp(a).to_string()), parse_aexp(a));
+    });
+
+    let eval = |a: &str| flanelly::interpreter::eval_aexp(&parse_aexp(a), &MemConfig::new());
+    assert_eq!(eval("2^3^2"), 512);
+    assert_eq!(eval("(2^3)^2"), 64);
+    assert_eq!(eval("0^0"), 1);
+    // Overflow wraps around like multiplication, and a negative exponent yields `0`
+    assert_eq!(eval("3^41"), (0..41).fold(1 as Int, |acc, _| acc.wrapping_mul(3)));
+    assert_eq!(eval("2^-1"), 0);
+    let cp = MultiConstLat::init_start();
+    assert_eq!(cp.eval_aexp_with(&parse_aexp("2^10"), AbsorbPolicy::Never), ConstLat::Const(1024));
+    assert_eq!(cp.eval_aexp_with(&parse_aexp("2^-1"), AbsorbPolicy::Never), ConstLat::Top);
+    assert_eq!(cp.eval_aexp_with(&parse_aexp("x^2"), AbsorbPolicy::Never), ConstLat::Top);
+}
+
+#[test]
+fn test_fingerprint() {
+    let prog = "y := 1; while y <= x do if y <= 3 then y := y * 2 else y := y + 1 end end; z := y";
+    let cfg = || cfg::ast_to_cfg(&parser::parse(prog).unwrap());
+    assert_eq!(cfg().fingerprint(), cfg().fingerprint());
+    // The fingerprint does not depend on the node indices
+    let fixture: Cfg<RawAnnot> = read_fixture("tests-res/test1", "cfg");
+    let fresh = cfg::ast_to_cfg(&read_fixture("tests-res/test1", "ast"));
+    assert_eq!(fixture.fingerprint(), fresh.fingerprint());
+    // Changing a single constant changes the fingerprint
+    let modified = cfg::ast_to_cfg(&parser::parse(&prog.replace("y * 2", "y * 3")).unwrap());
+    assert_ne!(cfg().fingerprint(), modified.fingerprint());
+    // Annotations are taken into account
+    let cfg_const_prop: Cfg<MfpAnnot<MultiConstLat>> = mfp(&cfg());
+    let cfg_avail_exp: Cfg<MfpAnnot<ExpSetLat>> = mfp(&cfg());
+    assert_ne!(cfg_const_prop.fingerprint(), cfg_avail_exp.map(|_| MfpAnnot::new(MultiConstLat::init(), MultiConstLat::init())).fingerprint());
+    // Without init node
+    let empty = cfg().subgraph(&std::collections::HashSet::new());
+    assert_eq!(empty.fingerprint(), cfg::ast_to_cfg(&parser::parse("z := 1").unwrap()).subgraph(&std::collections::HashSet::new()).fingerprint());
+}
+
+#[test]
+fn test_only_reachable() {
+    let mut cfg = cfg::ast_to_cfg(&parser::parse("y := 1; z := y").unwrap());
+    // An orphan node with an edge into the reachable part of the CFG
+    let orphan = cfg.graph.add_node(AnnotNode::new(Node::Assign(VarName::new("w"), Box::new(AExp::Num(2))), RawAnnot {}));
+    let last = cfg.exits()[0];
+    cfg.graph.add_edge(orphan, last, Edge::Plain);
+    let options = MfpOptions { only_reachable: true };
+    let (cfg_mfp, steps) = mfp_explained_with::<MultiFiniteSetLat>(&cfg, &options);
+    assert!(steps.iter().all(|step| step.node != orphan));
+    assert_eq!(cfg_mfp.graph[orphan].annot, MfpAnnot::new(MultiFiniteSetLat::init(), MultiFiniteSetLat::init()));
+    // Thus, the assignment to `w` does not flow into the reachable nodes
+    let w = VarName::new("w");
+    assert_eq!(cfg_mfp.graph[last].annot.pre().lookup(&w), &FiniteSetLat::singleton(0));
+    // By default, the orphan is analyzed as well
+    let (cfg_mfp, steps) = mfp_explained::<MultiFiniteSetLat>(&cfg);
+    assert!(steps.iter().any(|step| step.node == orphan));
+    assert_eq!(cfg_mfp.graph[last].annot.pre().lookup(&w), &FiniteSetLat::new(vec![0, 2].into_iter().collect()));
+    // The same holds with widening
+    let widened: Cfg<MfpAnnot<MultiFiniteSetLat>> = mfp_widening_with(&cfg, &options);
+    assert_eq!(widened.graph[orphan].annot, MfpAnnot::new(MultiFiniteSetLat::init(), MultiFiniteSetLat::init()));
+    assert_eq!(widened.graph[last].annot.pre().lookup(&w), &FiniteSetLat::singleton(0));
+    let widened: Cfg<MfpAnnot<MultiFiniteSetLat>> = mfp_widening(&cfg);
+    assert_eq!(widened.graph[last].annot.pre().lookup(&w), &FiniteSetLat::new(vec![0, 2].into_iter().collect()));
+}
+
+#[test]
+fn test_truth_table() {
+    let b = parser::parse_bexp("!(x <= 0 && y < x)").unwrap();
+    let vars = vec![VarName::new("x"), VarName::new("y")];
+    let table = b.truth_table(&vars, -1..=1);
+    let expected: Vec<(Vec<Int>, bool)> = vec![
+        (vec![-1, -1], true), (vec![-1, 0], true), (vec![-1, 1], true),
+        (vec![0, -1], false), (vec![0, 0], true), (vec![0, 1], true),
+        (vec![1, -1], true), (vec![1, 0], true), (vec![1, 1], true),
+    ];
+    assert_eq!(table, expected);
+    // Pushing the negations down preserves the meaning
+    assert_eq!(b.push_negations().truth_table(&vars, -2..=2), b.truth_table(&vars, -2..=2));
+    assert_eq!(b.truth_table(&[], 0..=1), vec![(vec![], true)]);
+}
+
+#[test]
+fn test_parse_diagnostics() {
+    let (p, diagnostics) = parser::parse_with_diagnostics("if x<=1 && y<=1 || z<=1 then skip end");
+    assert_eq!(p, parser::parse("if (x<=1 && y<=1) || z<=1 then skip end"));
+    assert_eq!(diagnostics.len(), 1);
+    assert!(diagnostics[0].message.contains("add parentheses"));
+    assert!(diagnostics[0].message.contains("`(x <= 1 && y <= 1) || z <= 1`"));
+    ["if (x<=1 && y<=1) || z<=1 then skip end", "if x<=1 && (y<=1 || z<=1) then skip end", "while x<=1 || y<=1 || z<=1 do skip end"].iter().for_each(|s| {
+        let (p, diagnostics) = parser::parse_with_diagnostics(s);
+        assert!(p.is_ok());
+        assert_eq!(diagnostics, vec![]);
+    });
+    // Plain parses do not collect diagnostics, so that they are not reported by a later `parse_with_diagnostics` either
+    (0..3).for_each(|_| { parser::parse("if x<=1 && y<=1 || z<=1 then skip end").unwrap(); });
+    assert_eq!(parser::parse_with_diagnostics("skip").1, vec![]);
+    assert_eq!(parser::parse_with_diagnostics("if x<=1 && y<=1 || z<=1 then skip end").1.len(), 1);
+}
+
+#[test]
+fn test_mem_config_eq_on() {
+    let original = parser::parse("y := 2 * 3; w := y + x; if y <= 5 then z := w else z := w * (1 + 1) end").unwrap();
+    // Folded by hand, using the constants known from constant propagation
+    let folded = parser::parse("y := 6; w := 6 + x; z := w * 2").unwrap();
+    (-3..=3).for_each(|x| {
+        let run = |p: &Prog| { let mut mem = MemConfig::new(); mem.assign(&VarName::new("x"), x); eval_prog(p, mem) };
+        let (mem_original, mem_folded) = (run(&original), run(&folded));
+        let mut vars: Vec<VarName> = mem_original.entries().chain(mem_folded.entries()).map(|(x, _)| x.clone()).collect();
+        vars.sort();
+        vars.dedup();
+        assert_eq!(vars, vec![VarName::new("w"), VarName::new("x"), VarName::new("y"), VarName::new("z")]);
+        assert!(mem_original.eq_on(&mem_folded, &vars));
+    });
+    let mut mem = MemConfig::new();
+    mem.assign(&VarName::new("y"), 1);
+    // Unassigned variables are `0`
+    assert!(mem.eq_on(&MemConfig::new(), &[VarName::new("x")]));
+    assert!(!mem.eq_on(&MemConfig::new(), &[VarName::new("x"), VarName::new("y")]));
+    assert_eq!(mem.entries().collect::<Vec<_>>(), vec![(&VarName::new("y"), 1)]);
+}
+
+#[test]
+fn test_mfp_ascents() {
+    // `y` oscillates between `0` and `1`. Node 1: `y := 0`, node 2: `x <= 5`, node 3: `y := y * -1 + 1`, node 4: `z := y`
+    let cfg = cfg::ast_to_cfg(&parser::parse("y := 0; while x <= 5 do y := y * -1 + 1 end; z := y").unwrap());
+    let (cfg_mfp, ascents) = mfp_ascents::<MultiConstLat>(&cfg);
+    let cfg_plain: Cfg<MfpAnnot<MultiConstLat>> = mfp(&cfg);
+    assert_eq!(cfg_mfp, cfg_plain);
+    let y = VarName::new("y");
+    let header = NodeIdx::new(2);
+    assert_eq!(cfg_mfp.graph[header].annot.pre().lookup(&y), &ConstLat::Top);
+    // At the loop header, `y` ascends from `0` to `Top` in a single step
+    assert_eq!(ascents[&header], 2);
+    assert_eq!(ascents[&NodeIdx::new(1)], 1);
+    assert!(!ascents.contains_key(&cfg.init));
+}
+
+#[test]
+fn test_assume() {
+    let mut mem = MultiConstLat::init_start();
+    mem.insert(VarName::new("y"), ConstLat::Const(3));
+    let b = |s: &str| parser::parse_bexp(s).unwrap();
+    // Contradictory assumptions
+    assert_eq!(mem.assume(&b("y <= 5"), false), MultiConstLat::init());
+    assert_eq!(mem.assume(&b("!(y <= 5)"), true), MultiConstLat::init());
+    assert_eq!(mem.assume(&b("y <= 5 && w < 0"), true), MultiConstLat::init());
+    // Consistent assumptions leave the memory unchanged
+    assert_eq!(mem.assume(&b("y <= 5"), true), mem);
+    assert_eq!(mem.assume(&b("x <= 5"), false), mem);
+    assert_eq!(mem.assume(&b("x <= 5 || y < 3"), true), mem);
+    assert_eq!(mem.assume(&b("x <= 5 && y < 3"), false), mem);
+    // The bottom element stays the bottom element
+    assert_eq!(MultiConstLat::init().assume(&b("y <= 5"), true), MultiConstLat::init());
+}
+
+#[test]
+fn test_equivalent_loopfree() {
+    let original = parser::parse("y := 2 * 3; if x <= y then z := x + y * 1 else z := y * (x + 0) end").unwrap();
+    let folded = parser::parse("y := 6; if x <= 6 then z := x + 6 else z := 6 * x end").unwrap();
+    let vars = vec![VarName::new("y"), VarName::new("z")];
+    assert!(equivalent_loopfree(&original, &folded, &vars, -20..=20));
+    // Broken folding: The guard is folded the wrong way
+    let broken = parser::parse("y := 6; if x < 6 then z := x + 6 else z := 6 * x end").unwrap();
+    assert!(!equivalent_loopfree(&original, &broken, &vars, -20..=20));
+    assert_eq!(counterexample_loopfree(&original, &broken, &vars, -20..=20), Some(6));
+    // Both agree on `y` nevertheless
+    assert!(equivalent_loopfree(&original, &broken, &vars[..1], -20..=20));
+}
+
+#[test]
+fn test_most_likely_path() {
+    // Node 1: `x <= 0`, node 2: `y := 1`, node 3: `y := 2`, node 4: `z := y`
+    let cfg = cfg::ast_to_cfg(&parser::parse("if x <= 0 then y := 1 else y := 2 end; z := y").unwrap());
+    let with_prob = |p: BranchProbability| -> Cfg<BranchProbability> { cfg.map(|_| p) };
+    let path = |ns: &[usize]| ns.iter().map(|n| NodeIdx::new(*n)).collect::<Vec<_>>();
+    assert_eq!(with_prob(0.1).most_likely_path(), path(&[0, 1, 3, 4]));
+    assert_eq!(with_prob(0.9).most_likely_path(), path(&[0, 1, 2, 4]));
+    // A likely loop is followed once around
+    let cfg = cfg::ast_to_cfg(&parser::parse("while x <= 0 do x := x + 1 end; z := x").unwrap());
+    assert_eq!(cfg.map(|_| 0.9).most_likely_path(), path(&[0, 1, 2]));
+    assert_eq!(cfg.map(|_| 0.1).most_likely_path(), path(&[0, 1, 3]));
+    // Without init node
+    assert_eq!(cfg.map(|_| 0.9).subgraph(&std::collections::HashSet::new()).most_likely_path(), vec![]);
+}
+
+#[test]
+fn test_paths_up_to() {
+    // Node 1: `x <= 0`, nodes 2, 3: `y := 1; z := 2`, nodes 4, 5: `y := 3; z := 2`
+    let cfg = cfg::ast_to_cfg(&parser::parse("if x <= 0 then y := 1; z := 2 else y := 3; z := 2 end").unwrap());
+    let path = |ns: &[usize]| ns.iter().map(|n| NodeIdx::new(*n)).collect::<Vec<_>>();
+    let paths: Vec<_> = cfg.paths_up_to(10).collect();
+    assert_eq!(paths, vec![path(&[0, 1, 2, 3]), path(&[0, 1, 4, 5])]);
+    // Shorter paths are not found
+    assert_eq!(cfg.paths_up_to(3).count(), 0);
+    assert_eq!(cfg.paths_up_to(0).count(), 0);
+
+    // The meet over these paths: The transfer functions are composed along each path, and the results are joined
+    let along = |p: &Vec<NodeIdx>| p.iter().enumerate().fold(MultiConstLat::init_start(), |x, (i, n)| {
+        let out = MultiConstLat::eval_transfer_function(&cfg.graph[*n].node, &x);
+        match p.get(i + 1) {
+            Some(next) => {MultiConstLat::eval_edge(&cfg.graph[*n].node, &out, &cfg.graph[cfg.graph.find_edge(*n, *next).unwrap()])}
+            None => {out}
+        }
+    });
+    let mop = paths.iter().map(along).fold(MultiConstLat::init(), |acc, x| acc.join_bin(&x));
+    // For this program, it agrees with the MFP solution at the exits
+    let cfg_mfp: Cfg<MfpAnnot<MultiConstLat>> = mfp(&cfg);
+    let mfp_exit = cfg.exits().iter().fold(MultiConstLat::init(), |acc, n| acc.join_bin(cfg_mfp.graph[*n].annot.post()));
+    assert_eq!(mop, mfp_exit);
+    assert_eq!(*mop.lookup(&VarName::new("z")), ConstLat::Const(2));
+
+    // Loops are followed a bounded number of times: The branch node is visited once more than the body
+    let cfg = cfg::ast_to_cfg(&parser::parse("while x <= 0 do x := x + 1 end; z := x").unwrap());
+    assert_eq!(cfg.paths_up_to(3).collect::<Vec<_>>(), vec![path(&[0, 1, 3])]);
+    assert_eq!(cfg.paths_up_to(7).collect::<Vec<_>>(), vec![path(&[0, 1, 2, 1, 2, 1, 3]), path(&[0, 1, 2, 1, 3]), path(&[0, 1, 3])]);
+}
+
+#[test]
+fn test_mop() {
+    // Both branches yield `y + z = 3`, which constant propagation only finds by considering them separately
+    let cfg = cfg::ast_to_cfg(&parser::parse("if x <= 0 then y := 1; z := 2 else y := 2; z := 1 end; w := y + z").unwrap());
+    let w = VarName::new("w");
+    let exit = cfg.exits()[0];
+    let cfg_mop: Cfg<MfpAnnot<MultiConstLat>> = mop(&cfg, 0);
+    let cfg_mfp: Cfg<MfpAnnot<MultiConstLat>> = mfp(&cfg);
+    assert_eq!(*cfg_mop.graph[exit].annot.post().lookup(&w), ConstLat::Const(3));
+    assert_eq!(*cfg_mfp.graph[exit].annot.post().lookup(&w), ConstLat::Top);
+    // Before the join, both solutions agree
+    assert_eq!(cfg_mop.graph[NodeIdx::new(2)], cfg_mfp.graph[NodeIdx::new(2)]);
+    assert_eq!(diff_annots(&cfg_mop, &cfg_mfp).len(), 1);
+
+    // Available expressions are distributive, so the solutions agree on acyclic CFGs
+    let cfg = cfg::ast_to_cfg(&parser::parse("y := x + 1; if x <= 0 then z := x + 1 else y := 2; z := x * 2 end; w := x * 2").unwrap());
+    assert_eq!(mop::<ExpSetLat>(&cfg, 0), mfp::<ExpSetLat>(&cfg));
+
+    // Loops are followed a bounded number of times: Following the loop once more finds another value of `y`
+    let cfg = cfg::ast_to_cfg(&parser::parse("y := 1; while y <= x do y := y + y end; z := y").unwrap());
+    let exit = cfg.exits()[0];
+    let y_at_exit = |k: usize| mop::<MultiFiniteSetLat>(&cfg, k).graph[exit].annot.post().lookup(&VarName::new("y")).to_string();
+    assert_eq!(y_at_exit(0), "{1}");
+    assert_eq!(y_at_exit(2), "{1, 2, 4}");
+    // The exit of an infinite loop is unreachable
+    let cfg = cfg::ast_to_cfg(&parser::parse("while 0 <= 1 do skip end; z := 1").unwrap());
+    let cfg_mop: Cfg<MfpAnnot<MultiConstLat>> = mop(&cfg, 3);
+    assert_eq!(*cfg_mop.graph[cfg.exits()[0]].annot.pre(), MultiConstLat::init());
+}
+
+#[test]
+fn test_no_std_build() {
+    // Build the library without the `std` feature in a separate target directory, which must not use `std` at all
+    let status = Command::new(env!("CARGO"))
+        .args(["build", "--quiet", "--lib", "--no-default-features", "--manifest-path", concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml")])
+        .env("CARGO_TARGET_DIR", concat!(env!("CARGO_TARGET_TMPDIR"), "/no_std"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_analyze_source() {
+    let src = "y := 1; while y <= x do y := y + y end; z := y";
+    let cfg = cfg::ast_to_cfg(&parser::parse(src).unwrap());
+    let options = DotOptions::default();
+    let const_prop: Cfg<MfpAnnot<MultiConstLat>> = mfp(&cfg);
+    assert_eq!(serde_json::from_str::<Cfg<MfpAnnot<MultiConstLat>>>(&analyze_source(src, "const").unwrap()).unwrap(), const_prop);
+    assert_eq!(analyze_source(src, "const-dot").unwrap(), to_dot(&const_prop, &options));
+    let avail_exp: Cfg<MfpAnnot<ExpSetLat>> = mfp(&cfg);
+    assert_eq!(serde_json::from_str::<Cfg<MfpAnnot<ExpSetLat>>>(&analyze_source(src, "avail").unwrap()).unwrap(), avail_exp);
+    assert_eq!(analyze_source(src, "avail-dot").unwrap(), to_dot(&avail_exp, &options));
+    let finite_set: Cfg<MfpAnnot<MultiFiniteSetLat>> = mfp(&cfg);
+    assert_eq!(serde_json::from_str::<Cfg<MfpAnnot<MultiFiniteSetLat>>>(&analyze_source(src, "finite").unwrap()).unwrap(), finite_set);
+    assert_eq!(analyze_source(src, "finite-dot").unwrap(), to_dot(&finite_set, &options));
+    let interval: Cfg<MfpAnnot<MultiIntervalLat>> = mfp_widening(&cfg);
+    assert_eq!(serde_json::from_str::<Cfg<MfpAnnot<MultiIntervalLat>>>(&analyze_source(src, "interval").unwrap()).unwrap(), interval);
+    assert_eq!(analyze_source(src, "interval-dot").unwrap(), to_dot(&interval, &options));
+    let sign: Cfg<MfpAnnot<MultiSignLat>> = mfp(&cfg);
+    assert_eq!(serde_json::from_str::<Cfg<MfpAnnot<MultiSignLat>>>(&analyze_source(src, "sign").unwrap()).unwrap(), sign);
+    assert_eq!(analyze_source(src, "sign-dot").unwrap(), to_dot(&sign, &options));
+
+    assert!(analyze_source("z :=", "const").unwrap_err().starts_with("Parsing failed."));
+    assert!(analyze_source(src, "live").unwrap_err().starts_with("Unknown kind of analysis live"));
+    assert!(analyze_source(src, "-dot").is_err());
+}